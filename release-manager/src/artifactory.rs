@@ -0,0 +1,342 @@
+//! `--debian-publisher artifactory`: publish debians to a JFrog Artifactory
+//! Debian repository instead of an S3 bucket through deb-s3. Artifactory
+//! indexes a deployed `.deb` from the `deb.*` matrix parameters of its PUT.
+
+use crate::command_timeout::{DEFAULT_TIMEOUT, QUICK_TIMEOUT};
+use crate::errors::{ManagerError, ManagerResult};
+use crate::local_mirror::{pool_dir, read_deb_control, DebControl};
+use crate::output::textln;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
+
+/// Environment variable holding the Artifactory access token
+pub const TOKEN_ENV: &str = "ARTIFACTORY_TOKEN";
+
+/// Where and as whom to deploy
+#[derive(Debug, Clone)]
+pub struct ArtifactoryConfig {
+    /// Base URL, e.g. `https://example.jfrog.io/artifactory`
+    pub url: String,
+    /// Debian repository key
+    pub repo: String,
+    /// Access token, sent as a bearer token
+    pub token: String,
+}
+
+impl ArtifactoryConfig {
+    /// `url` and `repo` from the flags, the token from [`TOKEN_ENV`]
+    pub fn from_args(url: Option<&str>, repo: Option<&str>) -> ManagerResult<Self> {
+        let url = url.ok_or_else(|| ManagerError::MissingParameter("artifactory-url".into()))?;
+        let repo = repo.ok_or_else(|| ManagerError::MissingParameter("artifactory-repo".into()))?;
+        let token = std::env::var(TOKEN_ENV)
+            .ok()
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| ManagerError::MissingParameter(TOKEN_ENV.to_string()))?;
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            repo: repo.trim_matches('/').to_string(),
+            token,
+        })
+    }
+}
+
+/// A `.deb` deployed to Artifactory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    /// Folder of the package in the repository, e.g. `pool/unstable/m/mina-devnet`
+    pub path: String,
+    pub file_name: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AqlResponse {
+    results: Vec<AqlItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AqlItem {
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+/// Debian publisher for an Artifactory repository
+pub struct ArtifactoryPublisher {
+    config: ArtifactoryConfig,
+    http: reqwest::Client,
+}
+
+impl ArtifactoryPublisher {
+    /// Requests that stall fail instead of hanging the release: connecting
+    /// is bounded like a quick command, a whole upload like a registry one
+    pub fn new(config: ArtifactoryConfig) -> ManagerResult<Self> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(QUICK_TIMEOUT)
+            .timeout(DEFAULT_TIMEOUT)
+            .build()?;
+        Ok(Self { config, http })
+    }
+
+    /// Deploy URL of `file_name` in the pool, with the matrix parameters
+    /// that place it in `codename`/`component` for its architecture
+    pub fn deploy_url(
+        &self,
+        control: &DebControl,
+        file_name: &str,
+        codename: &str,
+        component: &str,
+    ) -> String {
+        format!(
+            "{}/{}/{}/{};deb.distribution={};deb.component={};deb.architecture={}",
+            self.config.url,
+            self.config.repo,
+            pool_dir(component, &control.package).display(),
+            file_name,
+            codename,
+            component,
+            control.architecture
+        )
+    }
+
+    /// AQL query finding `deployment` indexed under `codename`/`component`
+    pub fn aql_query(&self, deployment: &Deployment, codename: &str, component: &str) -> String {
+        let criteria = serde_json::json!({
+            "repo": self.config.repo,
+            "path": deployment.path,
+            "name": deployment.file_name,
+            "@deb.distribution": codename,
+            "@deb.component": component,
+        });
+        format!(r#"items.find({}).include("sha256")"#, criteria)
+    }
+
+    /// Read the control fields of the `.deb` at `deb_path` and deploy it
+    pub async fn publish(
+        &self,
+        deb_path: &Path,
+        codename: &str,
+        component: &str,
+    ) -> ManagerResult<Deployment> {
+        let control = read_deb_control(deb_path).await?;
+        self.deploy(deb_path, &control, codename, component).await
+    }
+
+    /// PUT the `.deb` at `deb_path` with checksum headers, so Artifactory
+    /// rejects a corrupted upload
+    pub async fn deploy(
+        &self,
+        deb_path: &Path,
+        control: &DebControl,
+        codename: &str,
+        component: &str,
+    ) -> ManagerResult<Deployment> {
+        let file_name = deb_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                ManagerError::ValidationError(format!("Not a file: {}", deb_path.display()))
+            })?;
+        // The checksum header goes first, so the file is read twice rather
+        // than held in memory
+        let sha256 = sha256_file(deb_path).await?;
+        let file = tokio::fs::File::open(deb_path).await?;
+        let length = file.metadata().await?.len();
+        let url = self.deploy_url(control, &file_name, codename, component);
+
+        let response = self
+            .http
+            .put(&url)
+            .bearer_auth(&self.config.token)
+            .header("X-Checksum-Sha256", &sha256)
+            .header(reqwest::header::CONTENT_LENGTH, length)
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ManagerError::CommandFailed(format!(
+                "Artifactory upload of {} failed with {}: {}",
+                file_name,
+                status,
+                body.trim()
+            )));
+        }
+
+        textln!(
+            "    🐸 Published {} {} to Artifactory {}/{}",
+            control.package,
+            control.version,
+            self.config.url,
+            self.config.repo
+        );
+        Ok(Deployment {
+            path: pool_dir(component, &control.package)
+                .to_string_lossy()
+                .into_owned(),
+            file_name,
+            sha256,
+        })
+    }
+
+    /// Check through AQL that `deployment` is indexed under
+    /// `codename`/`component` with the uploaded checksum
+    pub async fn verify(
+        &self,
+        deployment: &Deployment,
+        codename: &str,
+        component: &str,
+    ) -> ManagerResult<()> {
+        let url = format!("{}/api/search/aql", self.config.url);
+        let response = self
+            .http
+            .post(&url)
+            .bearer_auth(&self.config.token)
+            .header("Content-Type", "text/plain")
+            .body(self.aql_query(deployment, codename, component))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ManagerError::CommandFailed(format!(
+                "Artifactory AQL search failed with {}: {}",
+                status,
+                body.trim()
+            )));
+        }
+
+        let found: AqlResponse = serde_json::from_str(&response.text().await?)?;
+        match found.results.first() {
+            None => Err(ManagerError::ArtifactNotFound(format!(
+                "{}/{} is not in Artifactory {} under {}/{}",
+                deployment.path, deployment.file_name, self.config.repo, codename, component
+            ))),
+            Some(item) if item.sha256.as_deref() != Some(deployment.sha256.as_str()) => {
+                Err(ManagerError::ValidationError(format!(
+                    "{} in Artifactory has SHA256 {}, expected {}",
+                    deployment.file_name,
+                    item.sha256.as_deref().unwrap_or("<none>"),
+                    deployment.sha256
+                )))
+            }
+            Some(_) => {
+                textln!(
+                    "    ✅ {} is indexed in {}/{}",
+                    deployment.file_name,
+                    codename,
+                    component
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Hex SHA256 of the file at `path`, read in chunks
+async fn sha256_file(path: &Path) -> ManagerResult<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_bytes, body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn control() -> DebControl {
+        DebControl::parse("Package: mina-devnet\nVersion: 3.0.1\nArchitecture: amd64\n").unwrap()
+    }
+
+    fn publisher(url: &str) -> ArtifactoryPublisher {
+        ArtifactoryPublisher::new(ArtifactoryConfig {
+            url: url.to_string(),
+            repo: "mina-debian".to_string(),
+            token: "secret".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_deploy_url_and_aql_query() {
+        let publisher = publisher("https://example.jfrog.io/artifactory");
+        assert_eq!(
+            publisher.deploy_url(&control(), "mina-devnet_3.0.1.deb", "bullseye", "unstable"),
+            "https://example.jfrog.io/artifactory/mina-debian/pool/unstable/m/mina-devnet/mina-devnet_3.0.1.deb;deb.distribution=bullseye;deb.component=unstable;deb.architecture=amd64"
+        );
+
+        let deployment = Deployment {
+            path: "pool/unstable/m/mina-devnet".to_string(),
+            file_name: "mina-devnet_3.0.1.deb".to_string(),
+            sha256: "abc".to_string(),
+        };
+        assert_eq!(
+            publisher.aql_query(&deployment, "bullseye", "unstable"),
+            r#"items.find({"@deb.component":"unstable","@deb.distribution":"bullseye","name":"mina-devnet_3.0.1.deb","path":"pool/unstable/m/mina-devnet","repo":"mina-debian"}).include("sha256")"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deploy_and_verify() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("mina-devnet_3.0.1.deb");
+        std::fs::write(&deb, b"not really a deb").unwrap();
+        let sha256 = hex::encode(Sha256::digest(b"not really a deb"));
+
+        Mock::given(method("PUT"))
+            .and(path(
+                "/mina-debian/pool/unstable/m/mina-devnet/mina-devnet_3.0.1.deb;deb.distribution=bullseye;deb.component=unstable;deb.architecture=amd64",
+            ))
+            .and(header("Authorization", "Bearer secret"))
+            .and(header("X-Checksum-Sha256", sha256.as_str()))
+            .and(header("Content-Length", "16"))
+            .and(body_bytes(b"not really a deb".to_vec()))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/search/aql"))
+            .and(body_string_contains(r#""@deb.distribution":"bullseye""#))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!(r#"{{"results":[{{"sha256":"{}"}}]}}"#, sha256)),
+            )
+            .mount(&server)
+            .await;
+
+        let publisher = publisher(&server.uri());
+        let deployment = publisher
+            .deploy(&deb, &control(), "bullseye", "unstable")
+            .await
+            .unwrap();
+        assert_eq!(deployment.sha256, sha256);
+        publisher
+            .verify(&deployment, "bullseye", "unstable")
+            .await
+            .unwrap();
+
+        let stale = Deployment {
+            sha256: "0".repeat(64),
+            ..deployment.clone()
+        };
+        assert!(matches!(
+            publisher.verify(&stale, "bullseye", "unstable").await,
+            Err(ManagerError::ValidationError(_))
+        ));
+    }
+}
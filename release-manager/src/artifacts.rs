@@ -1,5 +1,10 @@
 use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::utils::resolve_version_arg;
 use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Artifact {
@@ -55,6 +60,38 @@ impl Artifact {
             Artifact::Minimina => "minimina",
         }
     }
+
+    /// Whether CI builds a docker image for this artifact (as opposed to a
+    /// debian package only). Every command consults this before touching
+    /// docker; the match is exhaustive so a new artifact has to pick a side.
+    pub fn has_docker_image(&self) -> bool {
+        match self {
+            Artifact::MinaDaemon
+            | Artifact::MinaArchive
+            | Artifact::MinaRosetta
+            | Artifact::MinaGeneric
+            | Artifact::RosettaGeneric => true,
+            Artifact::MinaLogproc
+            | Artifact::MinaConfig
+            | Artifact::MinaAutomode
+            | Artifact::MinaPrefork
+            | Artifact::MinaPostfork
+            | Artifact::MinaPostforkMesa
+            | Artifact::MinaPreforkMesa
+            | Artifact::Minimina => false,
+        }
+    }
+}
+
+/// Whether CI builds a docker image for the artifact named `artifact`; see
+/// [`Artifact::has_docker_image`]. Unknown names count as having one.
+pub fn artifact_has_docker(artifact: &str) -> bool {
+    Artifact::from_str(artifact).map_or(true, |a| a.has_docker_image())
+}
+
+/// Notice printed in place of the docker step for a debian-only artifact
+pub fn print_no_docker_image(artifact: &str) {
+    textln!("   ℹ️  There is no {} docker image. skipping", artifact);
 }
 
 /// Build profiles understood by manager.sh (`lightnet`, `instrumented`).
@@ -90,6 +127,11 @@ pub fn get_suffix(artifact: &str, network: Option<&str>, profile: Option<&str>)
 /// Resolve an artifact name + network + profile to the actual package name
 /// that ends up in the Debian repo / Docker registry. Matches manager.sh's
 /// `get_artifact_with_suffix()`.
+///
+/// Note that `mina-daemon` is intentionally published as `mina-<network>`
+/// (e.g. `mina-devnet`), not `mina-daemon-<network>` like archive/rosetta.
+/// Repos using the latter convention go through [`get_debian_package_name`]
+/// with a `--daemon-debian-name` override.
 pub fn get_artifact_with_suffix(
     artifact: &str,
     network: Option<&str>,
@@ -120,6 +162,60 @@ pub fn get_artifact_with_suffix(
     }
 }
 
+/// Placeholder substituted with the network in `--daemon-debian-name`.
+pub const NETWORK_PLACEHOLDER: &str = "{network}";
+
+/// Debian package name for an artifact, honouring a `--daemon-debian-name`
+/// override (e.g. `mina-daemon-{network}`) for `mina-daemon`. Every other
+/// artifact, and `mina-daemon` without an override, resolves through
+/// [`get_artifact_with_suffix`].
+pub fn get_debian_package_name(
+    artifact: &str,
+    network: Option<&str>,
+    profile: Option<&str>,
+    daemon_debian_name: Option<&str>,
+) -> String {
+    match (artifact, daemon_debian_name) {
+        ("mina-daemon", Some(template)) => {
+            template.replace(NETWORK_PLACEHOLDER, network.unwrap_or(""))
+        }
+        _ => get_artifact_with_suffix(artifact, network, profile),
+    }
+}
+
+/// Name of the daemon binary, `mina` unless packaging renames it. A
+/// `daemon_binary_name` template (e.g. `mina-{network}`) gets the network
+/// substituted, as with `--daemon-debian-name`.
+pub fn get_daemon_binary_name(network: Option<&str>, daemon_binary_name: Option<&str>) -> String {
+    match daemon_binary_name {
+        Some(template) => template.replace(NETWORK_PLACEHOLDER, network.unwrap_or("")),
+        None => "mina".to_string(),
+    }
+}
+
+/// Binaries an artifact's debian or docker image ships, in the order they
+/// are smoke tested. `artifact` may be the bare artifact or its suffixed
+/// package / image name (`mina-archive-devnet`, `mina-devnet`), and the
+/// daemon binary resolves through [`get_daemon_binary_name`].
+pub fn get_artifact_binaries(
+    artifact: &str,
+    network: Option<&str>,
+    daemon_binary_name: Option<&str>,
+) -> Vec<String> {
+    let daemon = get_daemon_binary_name(network, daemon_binary_name);
+    match artifact {
+        a if a.starts_with("mina-archive") => vec!["mina-archive".to_string()],
+        "mina-logproc" => vec![],
+        a if a.starts_with("mina-rosetta") || a == "rosetta-generic" => vec![
+            daemon,
+            "mina-archive".to_string(),
+            "mina-rosetta".to_string(),
+        ],
+        a if a.starts_with("mina-") => vec![daemon],
+        other => vec![other.to_string()],
+    }
+}
+
 /// CI builds some artifacts under a different Docker image name than the
 /// artifact identifier. Mirrors manager.sh's `get_docker_image_name()`.
 pub fn get_docker_image_name(artifact: &str) -> &str {
@@ -164,6 +260,82 @@ pub fn extract_version_from_deb(deb_file: &str) -> ManagerResult<String> {
     )))
 }
 
+/// Compare two Debian version strings (`[epoch:]upstream[-revision]`) the
+/// way `dpkg --compare-versions` does, so `~` sorts before everything and
+/// digit runs compare numerically (`1.10.0` > `1.9.0`).
+pub fn debian_version_cmp(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (u64, &str, &str) {
+        let (epoch, rest) = match version.split_once(':') {
+            Some((e, rest)) if !e.is_empty() && e.chars().all(|c| c.is_ascii_digit()) => {
+                (e.parse().unwrap_or(0), rest)
+            }
+            _ => (0, version),
+        };
+        match rest.rsplit_once('-') {
+            Some((upstream, revision)) => (epoch, upstream, revision),
+            None => (epoch, rest, ""),
+        }
+    }
+
+    fn order(c: Option<u8>) -> i32 {
+        match c {
+            None => 0,
+            Some(b'~') => -1,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => c as i32,
+            Some(c) => c as i32 + 256,
+        }
+    }
+
+    fn verrevcmp(a: &[u8], b: &[u8]) -> Ordering {
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            // Non-digit prefix, character by character
+            while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit())
+            {
+                let (ac, bc) = (order(a.get(i).copied()), order(b.get(j).copied()));
+                if ac != bc {
+                    return ac.cmp(&bc);
+                }
+                i += 1;
+                j += 1;
+            }
+            // Digit run, numerically (leading zeros ignored)
+            while i < a.len() && a[i] == b'0' {
+                i += 1;
+            }
+            while j < b.len() && b[j] == b'0' {
+                j += 1;
+            }
+            let mut first_diff = Ordering::Equal;
+            while i < a.len() && a[i].is_ascii_digit() && j < b.len() && b[j].is_ascii_digit() {
+                if first_diff == Ordering::Equal {
+                    first_diff = a[i].cmp(&b[j]);
+                }
+                i += 1;
+                j += 1;
+            }
+            if i < a.len() && a[i].is_ascii_digit() {
+                return Ordering::Greater;
+            }
+            if j < b.len() && b[j].is_ascii_digit() {
+                return Ordering::Less;
+            }
+            if first_diff != Ordering::Equal {
+                return first_diff;
+            }
+        }
+        Ordering::Equal
+    }
+
+    let (a_epoch, a_upstream, a_revision) = split(a);
+    let (b_epoch, b_upstream, b_revision) = split(b);
+    a_epoch
+        .cmp(&b_epoch)
+        .then_with(|| verrevcmp(a_upstream.as_bytes(), b_upstream.as_bytes()))
+        .then_with(|| verrevcmp(a_revision.as_bytes(), b_revision.as_bytes()))
+}
+
 pub fn get_arch_suffix(arch: &str) -> String {
     if arch.is_empty() || arch == "amd64" {
         String::new()
@@ -181,16 +353,106 @@ pub fn calculate_docker_tag(
     profile: Option<&str>,
     arch: Option<&str>,
 ) -> String {
-    let docker_name = get_docker_image_name(artifact);
+    calculate_docker_tag_in(
+        get_repo(publish_to_docker_io),
+        artifact,
+        target_version,
+        codename,
+        network,
+        profile,
+        arch,
+    )
+}
+
+/// [`calculate_docker_tag`] in an arbitrary registry `repo`, e.g. `ghcr.io/minaprotocol`
+pub fn calculate_docker_tag_in(
+    repo: &str,
+    artifact: &str,
+    target_version: &str,
+    codename: &str,
+    network: Option<&str>,
+    profile: Option<&str>,
+    arch: Option<&str>,
+) -> String {
     let network_suffix = get_suffix(artifact, network, profile);
     let arch_suffix = arch.map(get_arch_suffix).unwrap_or_default();
-    let repo = get_repo(publish_to_docker_io);
+    docker_image_tag(
+        repo,
+        artifact,
+        target_version,
+        codename,
+        &format!("{}{}", network_suffix, arch_suffix),
+    )
+}
+
+/// `<repo>/<image>:<version>-<codename segment><suffix>`, for callers that
+/// build the suffix themselves (e.g. verify's `--docker-suffix`)
+pub fn docker_image_tag(
+    repo: &str,
+    artifact: &str,
+    version: &str,
+    codename: &str,
+    suffix: &str,
+) -> String {
     format!(
-        "{}/{}:{}-{}{}{}",
-        repo, docker_name, target_version, codename, network_suffix, arch_suffix
+        "{}/{}:{}-{}{}",
+        repo,
+        get_docker_image_name(artifact),
+        version,
+        docker_codename(codename),
+        suffix
     )
 }
 
+/// `--docker-codename-tag codename=segment`: what docker tags say in place of
+/// a debian codename, for images tagged by distro (`focal=ubuntu2004`).
+/// Unmapped codenames are used as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodenameTags(HashMap<String, String>);
+
+impl CodenameTags {
+    /// Parse `codename=segment` specs
+    pub fn parse(specs: &[String]) -> ManagerResult<Self> {
+        let mut tags = HashMap::new();
+        for spec in specs {
+            match spec.split_once('=') {
+                Some((codename, segment))
+                    if !codename.trim().is_empty() && !segment.trim().is_empty() =>
+                {
+                    tags.insert(codename.trim().to_string(), segment.trim().to_string());
+                }
+                _ => {
+                    return Err(ManagerError::ValidationError(format!(
+                        "Invalid --docker-codename-tag '{}', expected codename=segment",
+                        spec
+                    )))
+                }
+            }
+        }
+        Ok(Self(tags))
+    }
+
+    /// Tag segment of `codename`
+    pub fn segment<'a>(&'a self, codename: &'a str) -> &'a str {
+        self.0.get(codename).map(String::as_str).unwrap_or(codename)
+    }
+}
+
+static CODENAME_TAGS: OnceLock<CodenameTags> = OnceLock::new();
+
+/// Set the process-wide codename mapping; only the first call takes effect
+pub fn configure_codename_tags(tags: CodenameTags) {
+    let _ = CODENAME_TAGS.set(tags);
+}
+
+/// Docker tag segment of a debian `codename` under the configured mapping
+pub fn docker_codename(codename: &str) -> &str {
+    match CODENAME_TAGS.get() {
+        Some(tags) => tags.segment(codename),
+        None => codename,
+    }
+}
+
 pub fn get_repo(publish_to_docker_io: bool) -> &'static str {
     if publish_to_docker_io {
         "docker.io/minaprotocol"
@@ -199,6 +461,75 @@ pub fn get_repo(publish_to_docker_io: bool) -> &'static str {
     }
 }
 
+/// Registry images are published/promoted to: `--target-registry` when
+/// given, else docker.io or gcr.io per `--publish-to-docker-io`
+pub fn target_repo(target_registry: Option<&str>, publish_to_docker_io: bool) -> String {
+    match target_registry {
+        Some(registry) => registry.trim_end_matches('/').to_string(),
+        None => get_repo(publish_to_docker_io).to_string(),
+    }
+}
+
+/// Docker registry accepted by `--docker-registries`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockerRegistry {
+    Gcr,
+    DockerIo,
+}
+
+impl DockerRegistry {
+    pub fn from_str(s: &str) -> ManagerResult<Self> {
+        match s {
+            "gcr" | "gcr.io" => Ok(DockerRegistry::Gcr),
+            "dockerio" | "docker.io" => Ok(DockerRegistry::DockerIo),
+            _ => Err(ManagerError::ValidationError(format!(
+                "Unknown docker registry '{}', expected gcr or dockerio",
+                s
+            ))),
+        }
+    }
+
+    pub fn from_docker_io(docker_io: bool) -> Self {
+        if docker_io {
+            DockerRegistry::DockerIo
+        } else {
+            DockerRegistry::Gcr
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DockerRegistry::Gcr => "gcr",
+            DockerRegistry::DockerIo => "dockerio",
+        }
+    }
+
+    pub fn is_docker_io(&self) -> bool {
+        *self == DockerRegistry::DockerIo
+    }
+
+    pub fn repo(&self) -> &'static str {
+        get_repo(self.is_docker_io())
+    }
+}
+
+/// Parse a comma separated registry list, dropping duplicates
+pub fn parse_docker_registries(list: &str) -> ManagerResult<Vec<DockerRegistry>> {
+    let mut registries = Vec::new();
+    for name in parse_string_list(list) {
+        let registry = DockerRegistry::from_str(&name)?;
+        if !registries.contains(&registry) {
+            registries.push(registry);
+        }
+    }
+    if registries.is_empty() {
+        return Err(ManagerError::ValidationError(
+            "--docker-registries needs at least one registry".to_string(),
+        ));
+    }
+    Ok(registries)
+}
+
 pub fn combine_docker_suffixes(network: &str, docker_suffix: Option<&str>) -> String {
     if let Some(suffix) = docker_suffix {
         format!("-{}-{}", network, suffix)
@@ -207,6 +538,56 @@ pub fn combine_docker_suffixes(network: &str, docker_suffix: Option<&str>) -> St
     }
 }
 
+/// Networks out of `known` that appear as a whole `-`/`_`/`:`/`/`/`.`
+/// separated token of `name`.
+pub fn networks_in_name<'a>(name: &str, known: &'a [String]) -> Vec<&'a str> {
+    let tokens: Vec<&str> = name
+        .split(['-', '_', ':', '/', '.'])
+        .filter(|t| !t.is_empty())
+        .collect();
+    known
+        .iter()
+        .map(String::as_str)
+        .filter(|network| tokens.contains(network))
+        .collect()
+}
+
+/// Guard against cross-network bleed in the publish matrix: `name` (a debian
+/// package name, or a docker tag without its version) must not encode a
+/// network other than the one being processed, and must encode that one when
+/// `network_expected` (i.e. no override like `--strip-network-from-archive`
+/// dropped it).
+pub fn check_network_in_name(
+    kind: &str,
+    name: &str,
+    network: Option<&str>,
+    known: &[String],
+    network_expected: bool,
+) -> ManagerResult<()> {
+    let encoded = networks_in_name(name, known);
+
+    if let Some(other) = encoded.iter().find(|n| Some(**n) != network) {
+        return Err(ManagerError::ValidationError(format!(
+            "{} '{}' encodes the {} network while processing {}",
+            kind,
+            name,
+            other,
+            network.unwrap_or("no network")
+        )));
+    }
+
+    if let Some(network) = network.filter(|_| network_expected) {
+        if !encoded.contains(&network) {
+            return Err(ManagerError::ValidationError(format!(
+                "{} '{}' does not encode the {} network being processed",
+                kind, name, network
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 pub fn parse_artifact_list(artifacts: &str) -> ManagerResult<Vec<Artifact>> {
     artifacts
         .split(',')
@@ -216,6 +597,59 @@ pub fn parse_artifact_list(artifacts: &str) -> ManagerResult<Vec<Artifact>> {
         .collect()
 }
 
+/// Parse `artifact=version` overrides (`--artifact-versions`). Versions go
+/// through [`resolve_version_arg`], so `@<path>` works too; each overridden
+/// artifact must be one of `artifacts` and appear once.
+pub fn parse_artifact_versions(
+    list: &str,
+    artifacts: &[Artifact],
+) -> ManagerResult<Vec<(Artifact, String)>> {
+    let mut overrides: Vec<(Artifact, String)> = Vec::new();
+    for entry in parse_string_list(list) {
+        let (name, version) = entry.split_once('=').ok_or_else(|| {
+            ManagerError::ValidationError(format!(
+                "Invalid artifact version '{}': expected artifact=version",
+                entry
+            ))
+        })?;
+        let artifact = Artifact::from_str(name.trim())?;
+        if !artifacts.contains(&artifact) {
+            return Err(ManagerError::ValidationError(format!(
+                "Version override for {}, which is not being published",
+                artifact.as_str()
+            )));
+        }
+        if overrides.iter().any(|(a, _)| *a == artifact) {
+            return Err(ManagerError::ValidationError(format!(
+                "Duplicate version override for {}",
+                artifact.as_str()
+            )));
+        }
+        let version =
+            resolve_version_arg(&format!("{} version", artifact.as_str()), version.trim())?;
+        if version.is_empty() {
+            return Err(ManagerError::ValidationError(format!(
+                "Empty version override for {}",
+                artifact.as_str()
+            )));
+        }
+        overrides.push((artifact, version));
+    }
+    Ok(overrides)
+}
+
+/// Version to publish `artifact` at: its override, or `default`
+pub fn version_for<'a>(
+    overrides: &'a [(Artifact, String)],
+    artifact: Artifact,
+    default: &'a str,
+) -> &'a str {
+    overrides
+        .iter()
+        .find(|(a, _)| *a == artifact)
+        .map_or(default, |(_, version)| version.as_str())
+}
+
 pub fn parse_string_list(input: &str) -> Vec<String> {
     input
         .split(',')
@@ -228,6 +662,153 @@ pub fn parse_string_list(input: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_artifact_versions() {
+        let artifacts = parse_artifact_list("mina-daemon,mina-archive,mina-logproc").unwrap();
+        let overrides =
+            parse_artifact_versions("mina-archive=1.2.3-1, mina-logproc = 1.2.4", &artifacts)
+                .unwrap();
+        assert_eq!(
+            overrides,
+            vec![
+                (Artifact::MinaArchive, "1.2.3-1".to_string()),
+                (Artifact::MinaLogproc, "1.2.4".to_string()),
+            ]
+        );
+        assert_eq!(
+            version_for(&overrides, Artifact::MinaArchive, "1.2.3"),
+            "1.2.3-1"
+        );
+        assert_eq!(
+            version_for(&overrides, Artifact::MinaDaemon, "1.2.3"),
+            "1.2.3"
+        );
+        assert!(parse_artifact_versions("", &artifacts).unwrap().is_empty());
+
+        for bad in [
+            "mina-archive",
+            "mina-archive=",
+            "mina-archive=v1.2.3",
+            "mina-archive=1.2.3 beta",
+            "mina-archive=1.2.3,mina-archive=1.2.4",
+            "mina-rosetta=1.2.3",
+            "not-an-artifact=1.2.3",
+        ] {
+            assert!(
+                parse_artifact_versions(bad, &artifacts).is_err(),
+                "{} should be rejected",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_docker_registries() {
+        assert_eq!(
+            parse_docker_registries("gcr,dockerio").unwrap(),
+            vec![DockerRegistry::Gcr, DockerRegistry::DockerIo]
+        );
+        assert_eq!(
+            parse_docker_registries("docker.io, gcr, dockerio").unwrap(),
+            vec![DockerRegistry::DockerIo, DockerRegistry::Gcr]
+        );
+        assert!(parse_docker_registries("quay").is_err());
+        assert!(parse_docker_registries("").is_err());
+    }
+
+    #[test]
+    fn test_docker_tag_per_registry() {
+        let tags: Vec<String> = parse_docker_registries("gcr,dockerio")
+            .unwrap()
+            .iter()
+            .map(|registry| {
+                calculate_docker_tag(
+                    registry.is_docker_io(),
+                    "mina-generic",
+                    "1.0.0",
+                    "bullseye",
+                    Some("devnet"),
+                    None,
+                    None,
+                )
+            })
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                "gcr.io/o1labs-192920/mina-daemon:1.0.0-bullseye-devnet",
+                "docker.io/minaprotocol/mina-daemon:1.0.0-bullseye-devnet",
+            ]
+        );
+        assert_eq!(DockerRegistry::DockerIo.repo(), get_repo(true));
+        assert_eq!(DockerRegistry::from_docker_io(false), DockerRegistry::Gcr);
+    }
+
+    fn known() -> Vec<String> {
+        vec!["devnet".to_string(), "mainnet".to_string()]
+    }
+
+    #[test]
+    fn test_networks_in_name() {
+        assert_eq!(
+            networks_in_name("mina-archive-devnet", &known()),
+            vec!["devnet"]
+        );
+        assert_eq!(
+            networks_in_name("mina-devnet-generic", &known()),
+            vec!["devnet"]
+        );
+        assert!(networks_in_name("mina-archive", &known()).is_empty());
+        // substrings don't count
+        assert!(networks_in_name("mina-devnetx", &known()).is_empty());
+        assert_eq!(
+            networks_in_name("-bullseye-mainnet-arm64", &known()),
+            vec!["mainnet"]
+        );
+    }
+
+    #[test]
+    fn test_check_network_in_name() {
+        let known = known();
+        assert!(check_network_in_name(
+            "debian",
+            "mina-archive-devnet",
+            Some("devnet"),
+            &known,
+            true
+        )
+        .is_ok());
+        assert!(check_network_in_name("debian", "mina-logproc", None, &known, false).is_ok());
+        // stripped names carry no network, which is fine when not expected
+        assert!(
+            check_network_in_name("debian", "mina-archive", Some("mainnet"), &known, false).is_ok()
+        );
+
+        let bleed = check_network_in_name(
+            "debian",
+            "mina-archive-devnet",
+            Some("mainnet"),
+            &known,
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(bleed.contains("encodes the devnet network while processing mainnet"));
+
+        assert!(
+            check_network_in_name("debian", "mina-archive", Some("mainnet"), &known, true).is_err()
+        );
+        assert!(check_network_in_name(
+            "docker tag",
+            "-bullseye-devnet",
+            Some("mainnet"),
+            &known,
+            true
+        )
+        .is_err());
+        assert!(check_network_in_name("debian", "mina-devnet", None, &known, false).is_err());
+    }
+
     #[test]
     fn test_get_suffix() {
         assert_eq!(get_suffix("mina-daemon", Some("devnet"), None), "-devnet");
@@ -297,6 +878,83 @@ mod tests {
         assert_eq!(get_artifact_with_suffix("minimina", None, None), "minimina");
     }
 
+    #[test]
+    fn test_mina_daemon_debian_name_omits_daemon() {
+        // Intentional: the daemon package is `mina-<network>`, unlike
+        // `mina-archive-<network>` / `mina-rosetta-<network>`.
+        assert_eq!(
+            get_artifact_with_suffix("mina-daemon", Some("devnet"), None),
+            "mina-devnet"
+        );
+        assert_eq!(
+            get_artifact_with_suffix("mina-daemon", Some("mainnet"), None),
+            "mina-mainnet"
+        );
+        assert_eq!(
+            get_debian_package_name("mina-daemon", Some("devnet"), None, None),
+            "mina-devnet"
+        );
+    }
+
+    #[test]
+    fn test_get_artifact_binaries() {
+        assert_eq!(
+            get_artifact_binaries("mina-daemon", Some("devnet"), None),
+            vec!["mina"]
+        );
+        assert_eq!(
+            get_artifact_binaries("mina-devnet", Some("devnet"), None),
+            vec!["mina"]
+        );
+        assert_eq!(
+            get_artifact_binaries(
+                "mina-archive-mainnet",
+                Some("mainnet"),
+                Some("mina-{network}")
+            ),
+            vec!["mina-archive"]
+        );
+        assert_eq!(
+            get_artifact_binaries("mina-rosetta", Some("mainnet"), Some("mina-{network}")),
+            vec!["mina-mainnet", "mina-archive", "mina-rosetta"]
+        );
+        assert_eq!(
+            get_artifact_binaries("rosetta-generic", Some("devnet"), None),
+            vec!["mina", "mina-archive", "mina-rosetta"]
+        );
+        assert_eq!(
+            get_artifact_binaries("mina-daemon", Some("mainnet"), Some("mina-{network}")),
+            vec!["mina-mainnet"]
+        );
+        assert_eq!(
+            get_artifact_binaries("mina-generic", Some("devnet"), Some("mina-node")),
+            vec!["mina-node"]
+        );
+        assert!(get_artifact_binaries("mina-logproc", None, None).is_empty());
+        assert_eq!(
+            get_artifact_binaries("minimina", None, Some("mina-{network}")),
+            vec!["minimina"]
+        );
+    }
+
+    #[test]
+    fn test_get_debian_package_name_with_daemon_override() {
+        let template = Some("mina-daemon-{network}");
+        assert_eq!(
+            get_debian_package_name("mina-daemon", Some("devnet"), None, template),
+            "mina-daemon-devnet"
+        );
+        // Override only applies to mina-daemon
+        assert_eq!(
+            get_debian_package_name("mina-archive", Some("devnet"), None, template),
+            "mina-archive-devnet"
+        );
+        assert_eq!(
+            get_debian_package_name("mina-logproc", None, None, template),
+            "mina-logproc"
+        );
+    }
+
     #[test]
     fn test_get_docker_image_name() {
         assert_eq!(get_docker_image_name("mina-generic"), "mina-daemon");
@@ -324,6 +982,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_target_repo_overrides_docker_io() {
+        assert_eq!(target_repo(None, false), "gcr.io/o1labs-192920");
+        assert_eq!(target_repo(None, true), "docker.io/minaprotocol");
+        assert_eq!(
+            target_repo(Some("ghcr.io/minaprotocol/"), true),
+            "ghcr.io/minaprotocol"
+        );
+        assert_eq!(
+            calculate_docker_tag_in(
+                &target_repo(
+                    Some("123456789012.dkr.ecr.us-west-2.amazonaws.com/mina"),
+                    false
+                ),
+                "mina-generic",
+                "1.0.0",
+                "bullseye",
+                Some("devnet"),
+                None,
+                None
+            ),
+            "123456789012.dkr.ecr.us-west-2.amazonaws.com/mina/mina-daemon:1.0.0-bullseye-devnet"
+        );
+    }
+
+    #[test]
+    fn test_codename_tags() {
+        let tags = CodenameTags::parse(&[
+            "focal=ubuntu2004".to_string(),
+            " noble = ubuntu2404 ".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(tags.segment("focal"), "ubuntu2004");
+        assert_eq!(tags.segment("noble"), "ubuntu2404");
+        assert_eq!(tags.segment("bullseye"), "bullseye");
+        assert_eq!(CodenameTags::default().segment("focal"), "focal");
+        for spec in ["focal", "=ubuntu2004", "focal="] {
+            assert!(
+                CodenameTags::parse(&[spec.to_string()]).is_err(),
+                "{}",
+                spec
+            );
+        }
+
+        // Nothing configured in tests: tags keep the raw codename
+        assert_eq!(
+            calculate_docker_tag(
+                false,
+                "mina-daemon",
+                "3.0.1",
+                "focal",
+                Some("devnet"),
+                None,
+                None
+            ),
+            "gcr.io/o1labs-192920/mina-daemon:3.0.1-focal-devnet"
+        );
+        assert_eq!(
+            docker_image_tag(
+                "gcr.io/o1labs-192920",
+                "mina-daemon",
+                "3.0.1",
+                "focal",
+                "-devnet-instrumented"
+            ),
+            "gcr.io/o1labs-192920/mina-daemon:3.0.1-focal-devnet-instrumented"
+        );
+    }
+
     #[test]
     fn test_calculate_docker_tag_with_docker_name_mapping() {
         // mina-generic's docker image is published as mina-daemon
@@ -379,6 +1106,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_debian_version_cmp() {
+        use std::cmp::Ordering::*;
+        assert_eq!(debian_version_cmp("1.0.0", "1.0.0"), Equal);
+        assert_eq!(debian_version_cmp("1.10.0", "1.9.0"), Greater);
+        assert_eq!(debian_version_cmp("1.0.0~rc1", "1.0.0"), Less);
+        assert_eq!(debian_version_cmp("1.0.0~alpha", "1.0.0~beta"), Less);
+        assert_eq!(debian_version_cmp("1.0.0a", "1.0.0"), Greater);
+        assert_eq!(debian_version_cmp("1.0.0+b1", "1.0.0a"), Greater);
+        assert_eq!(debian_version_cmp("1:0.1", "2.0"), Greater);
+        assert_eq!(debian_version_cmp("1.0-2", "1.0-10"), Less);
+        assert_eq!(debian_version_cmp("1.01", "1.1"), Equal);
+        assert_eq!(
+            debian_version_cmp("3.0.1-bullseye-devnet", "3.0.0-bullseye-devnet"),
+            Greater
+        );
+    }
+
     #[test]
     fn test_extract_version_from_deb() {
         assert_eq!(
@@ -397,4 +1142,18 @@ mod tests {
         assert_eq!(artifacts[2], Artifact::MinaGeneric);
         assert_eq!(artifacts[3], Artifact::Minimina);
     }
+
+    #[test]
+    fn test_artifact_has_docker() {
+        assert!(!artifact_has_docker("mina-logproc"));
+        assert!(!artifact_has_docker("minimina"));
+        assert!(!artifact_has_docker("mina-config"));
+        assert!(!artifact_has_docker("mina-automode"));
+        assert!(!artifact_has_docker("mina-prefork-mesa"));
+        assert!(artifact_has_docker("mina-daemon"));
+        assert!(artifact_has_docker("mina-generic"));
+        assert!(artifact_has_docker("some-new-artifact"));
+        assert!(Artifact::RosettaGeneric.has_docker_image());
+        assert!(!Artifact::MinaPostfork.has_docker_image());
+    }
 }
@@ -0,0 +1,297 @@
+//! Verification baselines: the binaries each verified debian installed,
+//! saved as JSON by `verify --save-baseline` and diffed against a later run
+//! by `verify --compare-baseline`.
+//!
+//! ```json
+//! { "packages": [ { "package": "mina-devnet", "codename": "bullseye",
+//!   "version": "3.0.0", "binaries": [ { "name": "mina", "size": 184320 } ] } ] }
+//! ```
+
+use crate::errors::ManagerResult;
+use crate::output::textln;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Size change, in percent of the baseline size, reported unless
+/// `--baseline-size-threshold` is set
+pub const DEFAULT_SIZE_THRESHOLD_PERCENT: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedBinary {
+    pub name: String,
+    /// Bytes, as `stat` reported inside the verification container
+    pub size: u64,
+}
+
+/// One debian verified on one codename
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedPackage {
+    pub package: String,
+    pub codename: String,
+    pub version: String,
+    #[serde(default)]
+    pub binaries: Vec<VerifiedBinary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    #[serde(default)]
+    pub packages: Vec<VerifiedPackage>,
+}
+
+/// A difference between a baseline and the current run. Versions are
+/// expected to move between releases and aren't compared.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BaselineChange {
+    PackageAdded {
+        package: String,
+        codename: String,
+    },
+    PackageRemoved {
+        package: String,
+        codename: String,
+    },
+    BinaryAdded {
+        package: String,
+        codename: String,
+        binary: String,
+    },
+    BinaryRemoved {
+        package: String,
+        codename: String,
+        binary: String,
+    },
+    SizeChanged {
+        package: String,
+        codename: String,
+        binary: String,
+        before: u64,
+        after: u64,
+    },
+}
+
+impl fmt::Display for BaselineChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaselineChange::PackageAdded { package, codename } => {
+                write!(f, "{}/{}: new package", codename, package)
+            }
+            BaselineChange::PackageRemoved { package, codename } => {
+                write!(f, "{}/{}: package no longer verified", codename, package)
+            }
+            BaselineChange::BinaryAdded {
+                package,
+                codename,
+                binary,
+            } => write!(f, "{}/{}: new binary {}", codename, package, binary),
+            BaselineChange::BinaryRemoved {
+                package,
+                codename,
+                binary,
+            } => write!(f, "{}/{}: binary {} removed", codename, package, binary),
+            BaselineChange::SizeChanged {
+                package,
+                codename,
+                binary,
+                before,
+                after,
+            } => write!(
+                f,
+                "{}/{}: {} is {} bytes, was {} ({:+.1}%)",
+                codename,
+                package,
+                binary,
+                after,
+                before,
+                percent_change(*before, *after)
+            ),
+        }
+    }
+}
+
+impl Baseline {
+    pub fn load(path: &str) -> ManagerResult<Self> {
+        let content = std::fs::read_to_string(Path::new(path))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &str) -> ManagerResult<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        textln!(" 🧾 Baseline written to {}", path);
+        Ok(())
+    }
+
+    fn find(&self, package: &str, codename: &str) -> Option<&VerifiedPackage> {
+        self.packages
+            .iter()
+            .find(|p| p.package == package && p.codename == codename)
+    }
+
+    /// Differences from this baseline to `current`, in `current`'s order
+    /// followed by anything it no longer has. A binary whose size moved by
+    /// more than `threshold_percent` of its baseline size counts as changed.
+    pub fn compare(&self, current: &Baseline, threshold_percent: f64) -> Vec<BaselineChange> {
+        let mut changes = Vec::new();
+        for now in &current.packages {
+            let Some(before) = self.find(&now.package, &now.codename) else {
+                changes.push(BaselineChange::PackageAdded {
+                    package: now.package.clone(),
+                    codename: now.codename.clone(),
+                });
+                continue;
+            };
+
+            for binary in &now.binaries {
+                match before.binaries.iter().find(|b| b.name == binary.name) {
+                    None => changes.push(BaselineChange::BinaryAdded {
+                        package: now.package.clone(),
+                        codename: now.codename.clone(),
+                        binary: binary.name.clone(),
+                    }),
+                    Some(old)
+                        if percent_change(old.size, binary.size).abs() > threshold_percent =>
+                    {
+                        changes.push(BaselineChange::SizeChanged {
+                            package: now.package.clone(),
+                            codename: now.codename.clone(),
+                            binary: binary.name.clone(),
+                            before: old.size,
+                            after: binary.size,
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+            for old in &before.binaries {
+                if !now.binaries.iter().any(|b| b.name == old.name) {
+                    changes.push(BaselineChange::BinaryRemoved {
+                        package: now.package.clone(),
+                        codename: now.codename.clone(),
+                        binary: old.name.clone(),
+                    });
+                }
+            }
+        }
+
+        for old in &self.packages {
+            if current.find(&old.package, &old.codename).is_none() {
+                changes.push(BaselineChange::PackageRemoved {
+                    package: old.package.clone(),
+                    codename: old.codename.clone(),
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// Change from `before` to `after` in percent of `before`; any growth from
+/// an empty file counts as infinite
+fn percent_change(before: u64, after: u64) -> f64 {
+    if before == 0 {
+        return if after == 0 { 0.0 } else { f64::INFINITY };
+    }
+    (after as f64 - before as f64) / before as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, binaries: &[(&str, u64)]) -> VerifiedPackage {
+        VerifiedPackage {
+            package: name.to_string(),
+            codename: "bullseye".to_string(),
+            version: "3.0.0".to_string(),
+            binaries: binaries
+                .iter()
+                .map(|(name, size)| VerifiedBinary {
+                    name: name.to_string(),
+                    size: *size,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let path = path.to_str().unwrap();
+        let baseline = Baseline {
+            packages: vec![
+                package("mina-devnet", &[("mina", 1000)]),
+                package("mina-logproc", &[]),
+            ],
+        };
+
+        baseline.save(path).unwrap();
+        let loaded = Baseline::load(path).unwrap();
+
+        assert_eq!(loaded, baseline);
+        assert!(loaded.compare(&baseline, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_baseline_compare_reports_changes() {
+        let before = Baseline {
+            packages: vec![
+                package(
+                    "mina-rosetta-devnet",
+                    &[("mina", 1000), ("mina-archive", 500), ("mina-rosetta", 800)],
+                ),
+                package("mina-archive-devnet", &[("mina-archive", 500)]),
+            ],
+        };
+        let after = Baseline {
+            packages: vec![
+                // mina grew 5%, under the threshold; mina-archive grew 40%
+                package(
+                    "mina-rosetta-devnet",
+                    &[("mina", 1050), ("mina-archive", 700), ("mina-ocaml", 10)],
+                ),
+                package("mina-devnet", &[("mina", 1000)]),
+            ],
+        };
+
+        let changes = before.compare(&after, DEFAULT_SIZE_THRESHOLD_PERCENT);
+
+        let rosetta = "mina-rosetta-devnet".to_string();
+        let bullseye = "bullseye".to_string();
+        assert_eq!(
+            changes,
+            vec![
+                BaselineChange::SizeChanged {
+                    package: rosetta.clone(),
+                    codename: bullseye.clone(),
+                    binary: "mina-archive".to_string(),
+                    before: 500,
+                    after: 700,
+                },
+                BaselineChange::BinaryAdded {
+                    package: rosetta.clone(),
+                    codename: bullseye.clone(),
+                    binary: "mina-ocaml".to_string(),
+                },
+                BaselineChange::BinaryRemoved {
+                    package: rosetta.clone(),
+                    codename: bullseye.clone(),
+                    binary: "mina-rosetta".to_string(),
+                },
+                BaselineChange::PackageAdded {
+                    package: "mina-devnet".to_string(),
+                    codename: bullseye.clone(),
+                },
+                BaselineChange::PackageRemoved {
+                    package: "mina-archive-devnet".to_string(),
+                    codename: bullseye,
+                },
+            ]
+        );
+        assert_eq!(
+            changes[0].to_string(),
+            "bullseye/mina-rosetta-devnet: mina-archive is 700 bytes, was 500 (+40.0%)"
+        );
+    }
+}
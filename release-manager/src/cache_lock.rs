@@ -0,0 +1,147 @@
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+
+/// How long publish waits for another run to release a package's cache
+/// unless `--cache-lock-timeout` is set
+pub const DEFAULT_CACHE_LOCK_TIMEOUT_SECS: u64 = 1800;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Exclusive advisory lock on one package's files in a codename's debian
+/// cache directory, held until dropped. Runs on the same host serialize on
+/// it instead of racing on downloads and reversioned output files, while
+/// other packages of the codename go ahead.
+#[derive(Debug)]
+pub struct CacheLock {
+    file: File,
+}
+
+impl CacheLock {
+    /// Lock `<cache_folder>/<codename>/<package>.lock`, waiting at most
+    /// `timeout` for another holder to let go
+    pub async fn acquire(
+        cache_folder: &Path,
+        codename: &str,
+        package: &str,
+        timeout: Duration,
+    ) -> ManagerResult<Self> {
+        let codename_cache = cache_folder.join(codename);
+        std::fs::create_dir_all(&codename_cache)?;
+        let path = codename_cache.join(format!("{}.lock", package));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let started = Instant::now();
+        let mut waiting = false;
+        while file.try_lock_exclusive().is_err() {
+            let holder = lock_holder(&mut file);
+            if started.elapsed() >= timeout {
+                return Err(ManagerError::Timeout {
+                    operation: format!(
+                        "Waiting for the {} {} debian cache lock {}{}",
+                        codename,
+                        package,
+                        path.display(),
+                        holder
+                            .map(|pid| format!(" (held by release-manager pid {})", pid))
+                            .unwrap_or_default()
+                    ),
+                    seconds: timeout.as_secs(),
+                });
+            }
+            if !waiting {
+                textln!(
+                    " ⏳ Waiting for the {} {} debian cache lock{}",
+                    codename,
+                    package,
+                    holder
+                        .map(|pid| format!(" held by pid {}", pid))
+                        .unwrap_or_default()
+                );
+                waiting = true;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        // Record the holder for anyone left waiting
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+fn lock_holder(file: &mut File) -> Option<u32> {
+    let mut pid = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut pid).ok()?;
+    pid.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each acquire opens its own file description, which flock treats like
+    /// a separate process
+    #[tokio::test]
+    async fn test_cache_lock_contention() {
+        let cache = tempfile::tempdir().unwrap();
+        let lock = |codename: &'static str, package: &'static str, timeout: Duration| {
+            let cache = cache.path().to_path_buf();
+            async move { CacheLock::acquire(&cache, codename, package, timeout).await }
+        };
+        let first = lock("bullseye", "mina-devnet", Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(cache.path().join("bullseye/mina-devnet.lock").exists());
+
+        // Other codenames and other packages of the codename aren't blocked
+        lock("focal", "mina-devnet", Duration::from_millis(100))
+            .await
+            .unwrap();
+        lock(
+            "bullseye",
+            "mina-archive-devnet",
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap();
+
+        let err = lock("bullseye", "mina-devnet", Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ManagerError::Timeout { .. }), "{}", err);
+        let err = err.to_string();
+        assert!(err.contains("timed out after"), "{}", err);
+        assert!(
+            err.contains(&format!("pid {}", std::process::id())),
+            "{}",
+            err
+        );
+
+        // A waiter gets the lock once the holder is done
+        let waiter = tokio::spawn(lock("bullseye", "mina-devnet", Duration::from_secs(5)));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!waiter.is_finished());
+        drop(first);
+        assert!(waiter.await.unwrap().is_ok());
+    }
+}
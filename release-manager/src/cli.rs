@@ -1,9 +1,17 @@
+use clap::builder::RangedU64ValueParser;
 use clap::Args;
 
+use crate::baseline::DEFAULT_SIZE_THRESHOLD_PERCENT;
+use crate::cache_lock::DEFAULT_CACHE_LOCK_TIMEOUT_SECS;
+use crate::commands::names::NamesFormat;
+use crate::debian_publish::{DebianPublisherKind, DEFAULT_LOCKFILE_MAX_AGE_SECS};
+use crate::verification::{InstallMethod, RemovalCheck, SourcesFormat, DEFAULT_VERIFY_CONCURRENCY};
+
 pub const DEFAULT_ARTIFACTS: &str = "mina-logproc,mina-archive,mina-rosetta,mina-daemon";
 pub const DEFAULT_NETWORKS: &str = "devnet,mainnet";
 pub const DEFAULT_CODENAMES: &str = "bullseye,focal";
 pub const DEFAULT_DEBIAN_REPO: &str = "packages.o1test.net";
+pub const DEFAULT_S3_REGION: &str = "us-west-2";
 pub const DEFAULT_ARCHITECTURES: &str = "amd64";
 
 #[derive(Args)]
@@ -16,7 +24,8 @@ pub struct PublishArgs {
     #[arg(long, default_value = DEFAULT_NETWORKS)]
     pub networks: String,
 
-    /// Buildkite build id of release build to publish
+    /// Buildkite build id of release build to publish. Comma separated to merge
+    /// artifacts from several builds; each debian is taken from the first that has it
     #[arg(long)]
     pub buildkite_build_id: String,
 
@@ -28,6 +37,10 @@ pub struct PublishArgs {
     #[arg(long)]
     pub target_version: String,
 
+    /// Comma separated `artifact=version` overrides of --target-version
+    #[arg(long)]
+    pub artifact_versions: Option<String>,
+
     /// Comma separated list of debian codenames to publish
     #[arg(long, default_value = DEFAULT_CODENAMES)]
     pub codenames: String,
@@ -40,6 +53,22 @@ pub struct PublishArgs {
     #[arg(long)]
     pub publish_to_docker_io: bool,
 
+    /// Registry to publish docker images to, e.g. `ghcr.io/minaprotocol`; overrides --publish-to-docker-io
+    #[arg(long)]
+    pub target_registry: Option<String>,
+
+    /// Copy docker images registry-to-registry with skopeo instead of through the local Docker daemon
+    #[arg(long)]
+    pub use_skopeo: bool,
+
+    /// Comma-separated platforms (os/arch[/variant]) of multi-arch docker images to copy; all of them by default
+    #[arg(long)]
+    pub platforms: Option<String>,
+
+    /// Resolve each source docker tag to its digest first, copy that digest, and fail if the tag moves meanwhile
+    #[arg(long)]
+    pub pin_digest: bool,
+
     /// Publish only docker images
     #[arg(long)]
     pub only_dockers: bool,
@@ -52,6 +81,14 @@ pub struct PublishArgs {
     #[arg(long)]
     pub verify: bool,
 
+    /// When verifying, check the installed debian changelog's top entry is the published version
+    #[arg(long)]
+    pub verify_changelog: bool,
+
+    /// When verifying, substring each debian binary's `--version` output must contain (default: the version, when the output names one)
+    #[arg(long)]
+    pub expected_version: Option<String>,
+
     /// Don't publish anything, just print what would be published
     #[arg(long)]
     pub dry_run: bool,
@@ -64,14 +101,94 @@ pub struct PublishArgs {
     #[arg(long, default_value = DEFAULT_DEBIAN_REPO)]
     pub debian_repo: String,
 
+    /// AWS region of the debian repository bucket, passed to deb-s3 as --s3-region
+    #[arg(long, env = "DEB_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+
     /// Debian signing key to use
     #[arg(long)]
     pub debian_sign_key: Option<String>,
 
+    /// Suite for the Release file when it differs from the channel (e.g. `edge`); the codename is unchanged
+    #[arg(long)]
+    pub debian_suite: Option<String>,
+
+    /// Don't pass --lock to deb-s3. Only safe if uploads are serialized externally
+    #[arg(long, conflicts_with = "lock_timeout")]
+    pub no_lock: bool,
+
+    /// Fail a locked deb-s3 upload that takes longer than this many seconds
+    #[arg(long)]
+    pub lock_timeout: Option<u64>,
+
+    /// Seconds a deb-s3 lockfile must be old before a failed upload removes it as stale
+    #[arg(long = "lockfile-max-age", default_value_t = DEFAULT_LOCKFILE_MAX_AGE_SECS)]
+    pub lockfile_max_age_secs: u64,
+
+    /// Seconds to wait for another run on this host to release a codename's debian cache
+    #[arg(long, default_value_t = DEFAULT_CACHE_LOCK_TIMEOUT_SECS)]
+    pub cache_lock_timeout: u64,
+
+    /// Run up to this many independent debian uploads and docker promotions at once
+    #[arg(long, default_value_t = 1)]
+    pub parallel: usize,
+
+    /// Skip each debian and docker already in the target channel or registry at the target version
+    #[arg(long)]
+    pub only_missing: bool,
+
+    /// Publish debians to `<channel>-staging`, verify there, then copy into the live channel
+    #[arg(long)]
+    pub staged: bool,
+
+    /// When deb-s3 verify finds the manifest inconsistent after an upload, run --fix-manifests and verify once more
+    #[arg(long)]
+    pub auto_fix_manifests: bool,
+
+    /// Refuse to download cached debians larger than this many bytes
+    #[arg(long)]
+    pub max_download_size: Option<u64>,
+
+    /// After publishing, prune each package in the channel down to this many most recent versions (at least 1)
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub keep_versions: Option<usize>,
+
+    /// Publish debians into this local `file://` repository directory instead of S3
+    #[arg(long)]
+    pub local_mirror: Option<String>,
+
+    /// How debians are published: `deb-s3` (S3 bucket) or `artifactory` (token in ARTIFACTORY_TOKEN)
+    #[arg(long, value_enum, default_value_t = DebianPublisherKind::DebS3)]
+    pub debian_publisher: DebianPublisherKind,
+
+    /// Artifactory base URL for `--debian-publisher artifactory`, e.g. https://example.jfrog.io/artifactory
+    #[arg(long, env = "ARTIFACTORY_URL")]
+    pub artifactory_url: Option<String>,
+
+    /// Artifactory Debian repository key for `--debian-publisher artifactory`
+    #[arg(long, env = "ARTIFACTORY_REPO")]
+    pub artifactory_repo: Option<String>,
+
+    /// Publish debians straight from storage, skipping the cache, when no reversion is needed
+    #[arg(long)]
+    pub stream: bool,
+
     /// Strip network from archive package name
     #[arg(long)]
     pub strip_network_from_archive: bool,
 
+    /// Write a JSON manifest of every debian and docker coordinate the run targets to this path
+    #[arg(long)]
+    pub emit_manifest: Option<String>,
+
+    /// Directory of `<artifact>_<version>.spdx.json` SBOMs to publish alongside packages
+    #[arg(long)]
+    pub sbom_dir: Option<String>,
+
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
     /// Enable debug mode to show external command execution
     #[arg(long)]
     pub debug: bool,
@@ -111,6 +228,22 @@ pub struct PromoteArgs {
     #[arg(long)]
     pub publish_to_docker_io: bool,
 
+    /// Registry to publish docker images to, e.g. `ghcr.io/minaprotocol`; overrides --publish-to-docker-io
+    #[arg(long)]
+    pub target_registry: Option<String>,
+
+    /// Copy docker images registry-to-registry with skopeo instead of through the local Docker daemon
+    #[arg(long)]
+    pub use_skopeo: bool,
+
+    /// Comma-separated platforms (os/arch[/variant]) of multi-arch docker images to copy; all of them by default
+    #[arg(long)]
+    pub platforms: Option<String>,
+
+    /// Resolve each source docker tag to its digest first, copy that digest, and fail if the tag moves meanwhile
+    #[arg(long)]
+    pub pin_digest: bool,
+
     /// Promote only docker images
     #[arg(long)]
     pub only_dockers: bool,
@@ -123,6 +256,18 @@ pub struct PromoteArgs {
     #[arg(long)]
     pub verify: bool,
 
+    /// When verifying, substring each debian binary's `--version` output must contain (default: the version, when the output names one)
+    #[arg(long)]
+    pub expected_version: Option<String>,
+
+    /// Push docker images to a temporary tag, verify it, and only then move the final tag to it
+    #[arg(long)]
+    pub verify_before_commit: bool,
+
+    /// Move each docker image's latest-<codename>[-<network>] tag to it, only if it's a newer version
+    #[arg(long, conflicts_with = "only_debians")]
+    pub update_latest: bool,
+
     /// Don't promote anything, just print what would be promoted
     #[arg(long)]
     pub dry_run: bool,
@@ -131,6 +276,14 @@ pub struct PromoteArgs {
     #[arg(long, default_value = DEFAULT_DEBIAN_REPO)]
     pub debian_repo: String,
 
+    /// AWS region of the debian repository bucket, passed to deb-s3 as --s3-region
+    #[arg(long, env = "DEB_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+
+    /// Seconds a deb-s3 lockfile must be old before a failed upload removes it as stale
+    #[arg(long = "lockfile-max-age", default_value_t = DEFAULT_LOCKFILE_MAX_AGE_SECS)]
+    pub lockfile_max_age_secs: u64,
+
     /// Debian signing key to use
     #[arg(long)]
     pub debian_sign_key: Option<String>,
@@ -139,6 +292,30 @@ pub struct PromoteArgs {
     #[arg(long)]
     pub strip_network_from_archive: bool,
 
+    /// Write a JSON manifest of every debian and docker coordinate the run targets to this path
+    #[arg(long)]
+    pub emit_manifest: Option<String>,
+
+    /// OCI annotation `key=value` to set on promoted docker images (repeatable)
+    #[arg(long = "annotation")]
+    pub annotations: Vec<String>,
+
+    /// Label `key=value` the source docker image must carry to be promoted (repeatable)
+    #[arg(long = "require-label")]
+    pub required_labels: Vec<String>,
+
+    /// Promote exactly the artifacts this build stored, restricted to --artifacts/--networks/--codenames
+    #[arg(long)]
+    pub from_build_id: Option<String>,
+
+    /// Storage backend to list --from-build-id artifacts from
+    #[arg(long, default_value = "gs")]
+    pub backend: String,
+
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
     /// Enable debug mode to show external command execution
     #[arg(long)]
     pub debug: bool,
@@ -174,6 +351,10 @@ pub struct VerifyArgs {
     #[arg(long)]
     pub docker_io: bool,
 
+    /// Comma separated docker registries to verify in (`gcr`, `dockerio`); overrides --docker-io
+    #[arg(long)]
+    pub docker_registries: Option<String>,
+
     /// Verify only docker images
     #[arg(long)]
     pub only_dockers: bool,
@@ -190,6 +371,114 @@ pub struct VerifyArgs {
     #[arg(long)]
     pub docker_suffix: Option<String>,
 
+    /// Check that every network's docker image shares a common base layer
+    #[arg(long)]
+    pub check_layer_consistency: bool,
+
+    /// Check that each artifact's docker image and debian report the same, requested, version
+    #[arg(long, conflicts_with_all = ["only_debians", "only_dockers"])]
+    pub verify_cross_consistency: bool,
+
+    /// Docker platform to verify debians on (e.g. `linux/arm64`), emulated via qemu if foreign
+    #[arg(long)]
+    pub platform: Option<String>,
+
+    /// Expected Release file suite when it differs from the codename (e.g. `edge`)
+    #[arg(long)]
+    pub debian_suite: Option<String>,
+
+    /// Fail if a debian's dependencies don't all resolve from the target channel alone
+    #[arg(long)]
+    pub strict_deps: bool,
+
+    /// Check the installed debian changelog's top entry is the verified version
+    #[arg(long)]
+    pub verify_changelog: bool,
+
+    /// Times to retry a debian install apt can't find yet (index still propagating)
+    #[arg(long, default_value_t = 3)]
+    pub apt_retries: u32,
+
+    /// Seconds before the first apt retry; doubles after each attempt
+    #[arg(long, default_value_t = 10)]
+    pub apt_retry_delay: u64,
+
+    /// JSON verify manifest overriding the arguments each docker binary is tested with
+    #[arg(long)]
+    pub manifest: Option<String>,
+
+    /// Only run each binary's `--version` check, skipping `--help`
+    #[arg(long)]
+    pub quick_verify: bool,
+
+    /// Also start each rosetta docker image's server and check `/network/list` answers (slow)
+    #[arg(long)]
+    pub rosetta_smoke: bool,
+
+    /// Stop at the first failed verification instead of verifying the rest of the matrix
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// After smoke-testing each debian, uninstall it with `remove` (the default) or `purge` and check it's gone
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "remove")]
+    pub verify_removal: Option<RemovalCheck>,
+
+    /// How many test containers to run at once when verifying a docker image (at least 1)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_VERIFY_CONCURRENCY,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub verify_concurrency: usize,
+
+    /// Substring each debian binary's `--version` output must contain (default: the version, when the output names one)
+    #[arg(long)]
+    pub expected_version: Option<String>,
+
+    /// Daemon binary name with `{network}` substituted, e.g. `mina-{network}` (default: `mina`)
+    #[arg(long)]
+    pub daemon_binary_name: Option<String>,
+
+    /// How debians are installed for verification: `repo` (apt-get install) or `download` (apt-get download + dpkg -i)
+    #[arg(long, value_enum, default_value_t = InstallMethod::Repo)]
+    pub install_method: InstallMethod,
+
+    /// apt source format for the repository when verifying debians: `list` (one-line mina.list) or `deb822` (mina.sources)
+    #[arg(long, value_enum, default_value_t = SourcesFormat::List)]
+    pub sources_format: SourcesFormat,
+
+    /// Verify debians against the repository snapshot at this UTC timestamp (YYYYMMDDTHHMMSSZ, served at snapshot.<repo>/<timestamp>) or snapshot URL
+    #[arg(long)]
+    pub repo_snapshot: Option<String>,
+
+    /// Verify the docker images listed in a publish/promote `--emit-manifest` file by their recorded digests
+    #[arg(long)]
+    pub from_manifest: Option<String>,
+
+    /// Give up on a single artifact's verification after this many seconds and move on
+    #[arg(long)]
+    pub verify_timeout: Option<u64>,
+
+    /// Write the verified debians' binaries and their sizes to this JSON baseline
+    #[arg(long, conflicts_with = "from_manifest")]
+    pub save_baseline: Option<String>,
+
+    /// Fail if the verified debians' binaries differ from this JSON baseline
+    #[arg(long, conflicts_with = "from_manifest")]
+    pub compare_baseline: Option<String>,
+
+    /// Print a JSON line per finished check (artifact, kind, codename, network, result, duration) instead of the human summary
+    #[arg(long)]
+    pub json_stream: bool,
+
+    /// Percent a binary's size may move from the baseline before it counts as changed
+    #[arg(long, default_value_t = DEFAULT_SIZE_THRESHOLD_PERCENT, requires = "compare_baseline")]
+    pub baseline_size_threshold: f64,
+
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
     /// Enable debug mode to show external command execution
     #[arg(long)]
     pub debug: bool,
@@ -205,11 +494,122 @@ pub struct FixArgs {
     #[arg(long)]
     pub channel: String,
 
+    /// AWS region of the debian repository bucket, passed to deb-s3 as --s3-region
+    #[arg(long, env = "DEB_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+
+    /// Fix up to this many codenames concurrently and print a pass/fail
+    /// summary. Codenames are fixed one at a time by default
+    #[arg(long)]
+    pub parallel_codenames: Option<usize>,
+
+    /// Enable debug mode to show external command execution
+    #[arg(long)]
+    pub debug: bool,
+}
+
+#[derive(Args)]
+pub struct DeleteArgs {
+    /// Comma separated list of artifacts
+    #[arg(long, default_value = DEFAULT_ARTIFACTS)]
+    pub artifacts: String,
+
+    /// Version to delete
+    #[arg(long)]
+    pub version: String,
+
+    /// Comma separated list of debian codenames
+    #[arg(long, default_value = DEFAULT_CODENAMES)]
+    pub codenames: String,
+
+    /// Debian channel to delete from
+    #[arg(long)]
+    pub channel: String,
+
+    /// Debian repository bucket
+    #[arg(long, default_value = DEFAULT_DEBIAN_REPO)]
+    pub debian_repo: String,
+
+    /// AWS region of the debian repository bucket, passed to deb-s3 as --s3-region
+    #[arg(long, env = "DEB_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+
+    /// Comma separated list of networks
+    #[arg(long, default_value = DEFAULT_NETWORKS)]
+    pub networks: String,
+
+    /// Architecture of the packages to delete
+    #[arg(long, default_value = DEFAULT_ARCHITECTURES)]
+    pub arch: String,
+
+    /// GPG key ID used to re-sign the Release file after deleting
+    #[arg(long)]
+    pub debian_sign_key: Option<String>,
+
+    /// Seconds a deb-s3 lockfile must be old before a failed delete removes it as stale
+    #[arg(long = "lockfile-max-age", default_value_t = DEFAULT_LOCKFILE_MAX_AGE_SECS)]
+    pub lockfile_max_age_secs: u64,
+
+    /// Print the packages that would be deleted without deleting them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
     /// Enable debug mode to show external command execution
     #[arg(long)]
     pub debug: bool,
 }
 
+#[derive(Args)]
+pub struct ListArgs {
+    /// Comma separated list of artifacts
+    #[arg(long, default_value = DEFAULT_ARTIFACTS)]
+    pub artifacts: String,
+
+    /// Comma separated list of networks
+    #[arg(long, default_value = DEFAULT_NETWORKS)]
+    pub networks: String,
+
+    /// Comma separated list of debian codenames
+    #[arg(long, default_value = DEFAULT_CODENAMES)]
+    pub codenames: String,
+
+    /// Debian channel to list
+    #[arg(long, default_value = "unstable")]
+    pub channel: String,
+
+    /// Debian repository bucket
+    #[arg(long, default_value = DEFAULT_DEBIAN_REPO)]
+    pub debian_repo: String,
+
+    /// Architecture whose Packages index is read
+    #[arg(long, default_value = DEFAULT_ARCHITECTURES)]
+    pub arch: String,
+
+    /// List docker tags on docker.io instead of gcr.io
+    #[arg(long)]
+    pub docker_io: bool,
+
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
+    /// Only list debian packages
+    #[arg(long, conflicts_with = "only_dockers")]
+    pub only_debians: bool,
+
+    /// Only list docker tags
+    #[arg(long)]
+    pub only_dockers: bool,
+
+    /// Print a JSON array of what was found instead of text
+    #[arg(long)]
+    pub json: bool,
+}
+
 #[derive(Args)]
 pub struct PersistArgs {
     /// Backend to persist artifacts
@@ -240,6 +640,10 @@ pub struct PersistArgs {
     #[arg(long, default_value = "unstable")]
     pub suite: String,
 
+    /// Re-hash each persisted file in storage and fail if it differs from the local copy
+    #[arg(long)]
+    pub verify: bool,
+
     /// Enable debug mode to show external command execution
     #[arg(long)]
     pub debug: bool,
@@ -263,6 +667,10 @@ pub struct ProgressArgs {
     #[arg(long, default_value = "bullseye,focal,jammy,noble,bookworm")]
     pub codenames: String,
 
+    /// AWS region of the debian repository bucket, passed to deb-s3 as --s3-region
+    #[arg(long, env = "DEB_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+
     /// Build profile (e.g. lightnet, instrumented)
     #[arg(long)]
     pub profile: Option<String>,
@@ -280,6 +688,49 @@ pub struct ProgressArgs {
     pub skip_mina_public: bool,
 }
 
+#[derive(Args)]
+pub struct NamesArgs {
+    /// Comma separated list of artifacts
+    #[arg(long, default_value = DEFAULT_ARTIFACTS)]
+    pub artifacts: String,
+
+    /// Comma separated list of networks
+    #[arg(long, default_value = DEFAULT_NETWORKS)]
+    pub networks: String,
+
+    /// Comma separated list of debian codenames
+    #[arg(long, default_value = DEFAULT_CODENAMES)]
+    pub codenames: String,
+
+    /// Version to resolve names for
+    #[arg(long)]
+    pub version: String,
+
+    /// Debian channel
+    #[arg(long, default_value = "unstable")]
+    pub channel: String,
+
+    /// Build profile (e.g. lightnet, instrumented)
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Architecture (e.g. amd64, arm64)
+    #[arg(long)]
+    pub arch: Option<String>,
+
+    /// Resolve docker tags for docker.io instead of gcr.io
+    #[arg(long)]
+    pub docker_io: bool,
+
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = NamesFormat::Text)]
+    pub format: NamesFormat,
+}
+
 #[derive(Args)]
 pub struct ReversionArgs {
     /// Folder with `{codename}/*.deb` structure (typically the output of `pull`)
@@ -294,13 +745,25 @@ pub struct ReversionArgs {
     #[arg(long)]
     pub new_version: String,
 
-    /// Replace the suite in the control file (e.g. stable, unstable)
+    /// Suite for the new changelog entry (e.g. stable, unstable); binary control files have no suite
     #[arg(long)]
     pub suite: Option<String>,
 
     /// Rename the package (e.g. mina-devnet-hardfork)
     #[arg(long)]
     pub name: Option<String>,
+
+    /// `debian-binary` format version to build with (e.g. 2.0); defaults to each source package's
+    #[arg(long)]
+    pub deb_format_version: Option<String>,
+
+    /// Replace existing output packages even when their contents differ
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// Only show the control file changes each package would get; build and write nothing
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 #[derive(Args)]
@@ -321,6 +784,10 @@ pub struct ValidateArgs {
     #[arg(long, default_value = DEFAULT_DEBIAN_REPO)]
     pub debian_repo: String,
 
+    /// AWS region of the debian repository bucket, passed to deb-s3 as --s3-region
+    #[arg(long, env = "DEB_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+
     /// GPG key ID used to re-sign InRelease when fixing
     #[arg(long)]
     pub debian_sign_key: Option<String>,
@@ -332,6 +799,18 @@ pub struct ValidateArgs {
     /// Only list packages; skip SHA256 verification
     #[arg(long)]
     pub list_only: bool,
+
+    /// Check each codename's InRelease signature and how long its signing key stays valid
+    #[arg(long)]
+    pub check_signing_key: bool,
+
+    /// Warn when the Release signing key expires within this many days
+    #[arg(long, default_value_t = 30, requires = "check_signing_key")]
+    pub expiry_warn_days: i64,
+
+    /// Fail, rather than warn, when the Release signing key is about to expire
+    #[arg(long, requires = "check_signing_key")]
+    pub fail_on_expiring: bool,
 }
 
 #[derive(Args)]
@@ -360,7 +839,18 @@ pub struct PullArgs {
     #[arg(long, default_value = DEFAULT_NETWORKS)]
     pub networks: String,
 
+    /// Debian package name for mina-daemon with `{network}` substituted, e.g. `mina-daemon-{network}` (default: `mina-<network>`)
+    #[arg(long)]
+    pub daemon_debian_name: Option<String>,
+
     /// Enable debug mode to show external command execution
     #[arg(long)]
     pub debug: bool,
 }
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
@@ -0,0 +1,105 @@
+//! Upper bounds on how long an external command may run, so a hung
+//! `docker pull` or `gsutil` fails the release instead of blocking it.
+
+use std::time::Duration;
+
+/// Overrides every class's limit, in seconds; `0` disables the limits
+pub const TIMEOUT_ENV: &str = "RELEASE_MANAGER_CMD_TIMEOUT";
+
+/// Lookups and local checks that answer within seconds
+pub const QUICK_TIMEOUT: Duration = Duration::from_secs(60);
+/// Registry, storage and packaging commands
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// deb-s3 rewrites whole manifests; uploads to large repositories take 8+ minutes
+pub const DEB_S3_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Limit for `program` run with `args`, by command class. Verification
+/// containers (`docker run`) have none: `verify --verify-timeout` bounds
+/// them, and emulated runs can legitimately take longer than
+/// [`DEFAULT_TIMEOUT`].
+pub fn class_timeout(program: &str, args: &[String]) -> Option<Duration> {
+    let subcommand = args.first().map(String::as_str);
+    match (program, subcommand) {
+        ("which" | "md5sum" | "dig" | "gpg", _) => Some(QUICK_TIMEOUT),
+        ("docker" | "podman", Some("tag" | "rm")) => Some(QUICK_TIMEOUT),
+        ("docker" | "podman", Some("run")) => None,
+        ("deb-s3", _) => Some(DEB_S3_TIMEOUT),
+        _ => Some(DEFAULT_TIMEOUT),
+    }
+}
+
+/// Limit for `program` run with `args`, honouring [`TIMEOUT_ENV`]; `None`
+/// when disabled
+pub fn command_timeout(program: &str, args: &[String]) -> Option<Duration> {
+    limit_with_override(program, args, std::env::var(TIMEOUT_ENV).ok().as_deref())
+}
+
+fn limit_with_override(program: &str, args: &[String], env: Option<&str>) -> Option<Duration> {
+    match env.and_then(|value| value.trim().parse::<u64>().ok()) {
+        Some(0) => None,
+        Some(secs) => Some(Duration::from_secs(secs)),
+        None => class_timeout(program, args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_command_timeout_classes_and_override() {
+        assert_eq!(
+            limit_with_override("docker", &args(&["pull", "mina"]), None),
+            Some(DEFAULT_TIMEOUT)
+        );
+        assert_eq!(
+            limit_with_override("docker", &args(&["tag", "a", "b"]), None),
+            Some(QUICK_TIMEOUT)
+        );
+        assert_eq!(
+            limit_with_override("gsutil", &args(&["cp"]), None),
+            Some(DEFAULT_TIMEOUT)
+        );
+        assert_eq!(
+            limit_with_override("deb-s3", &args(&["upload"]), None),
+            Some(DEB_S3_TIMEOUT)
+        );
+        assert_eq!(
+            limit_with_override("which", &args(&["docker"]), None),
+            Some(QUICK_TIMEOUT)
+        );
+
+        assert_eq!(
+            limit_with_override("deb-s3", &args(&["upload"]), Some("90")),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(limit_with_override("docker", &[], Some("0")), None);
+        // An unparsable override is ignored
+        assert_eq!(
+            limit_with_override("docker", &[], Some("ten")),
+            Some(DEFAULT_TIMEOUT)
+        );
+    }
+
+    #[test]
+    fn test_verification_containers_are_bounded_by_verify_timeout_only() {
+        // `--verify-timeout 1800` on an emulated arm64 image must not be
+        // cut short by the 10 minute class default
+        let verify_timeout = Duration::from_secs(1800);
+        let run = args(&["run", "--rm", "--platform", "linux/arm64", "mina"]);
+        for runtime in ["docker", "podman"] {
+            let limit = limit_with_override(runtime, &run, None);
+            assert_eq!(limit, None);
+            assert!(limit.is_none_or(|limit| limit >= verify_timeout));
+        }
+        // An explicit override still applies
+        assert_eq!(
+            limit_with_override("docker", &run, Some("90")),
+            Some(Duration::from_secs(90))
+        );
+    }
+}
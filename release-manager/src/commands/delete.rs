@@ -0,0 +1,159 @@
+use crate::artifacts::{
+    get_debian_package_name, get_suffix, parse_artifact_list, parse_string_list, Artifact,
+};
+use crate::cli::DeleteArgs;
+use crate::debian_publish::delete_debian_package;
+use crate::errors::ManagerResult;
+use crate::output::{textln, Reporter};
+use crate::utils::{print_operation_info, validate_required_args};
+use colored::*;
+
+/// One debian package to remove from a codename's channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteSpec {
+    pub package: String,
+    pub codename: String,
+}
+
+pub async fn execute(args: DeleteArgs, reporter: &Reporter) -> ManagerResult<()> {
+    validate_required_args(&[
+        ("version", Some(&args.version)),
+        ("channel", Some(&args.channel)),
+    ])?;
+
+    let artifacts = parse_artifact_list(&args.artifacts)?;
+    let networks = parse_string_list(&args.networks);
+    let codenames = parse_string_list(&args.codenames);
+
+    let dry_run_str = args.dry_run.to_string();
+    let params = vec![
+        ("Artifacts", args.artifacts.as_str()),
+        ("Networks", args.networks.as_str()),
+        ("Codenames", args.codenames.as_str()),
+        ("Version", args.version.as_str()),
+        ("Channel", args.channel.as_str()),
+        ("Arch", args.arch.as_str()),
+        ("Debian repo", args.debian_repo.as_str()),
+        ("S3 region", args.s3_region.as_str()),
+        (
+            "Daemon debian name",
+            args.daemon_debian_name.as_deref().unwrap_or(""),
+        ),
+        ("Dry run", dry_run_str.as_str()),
+    ];
+    print_operation_info("Deleting mina debian packages", &params);
+
+    for spec in delete_specs(
+        &artifacts,
+        &networks,
+        &codenames,
+        args.daemon_debian_name.as_deref(),
+    ) {
+        let target = format!(
+            "{}={} ({}) from {}/{}",
+            spec.package, args.version, args.arch, spec.codename, args.channel
+        );
+        if args.dry_run {
+            textln!(" 🗑️  Would delete {}", target);
+            continue;
+        }
+
+        textln!(" 🗑️  Deleting {}", target);
+        let delete = delete_debian_package(
+            &spec.package,
+            &args.version,
+            &args.arch,
+            &args.debian_repo,
+            &args.s3_region,
+            &spec.codename,
+            &args.channel,
+            args.debian_sign_key.as_deref(),
+            args.debug,
+            args.lockfile_max_age_secs,
+        );
+        reporter
+            .track(
+                &spec.package,
+                &format!("debian {} {}", spec.codename, args.channel),
+                delete,
+            )
+            .await?;
+    }
+
+    textln!("{}", " ✅  Done.".green());
+    Ok(())
+}
+
+/// The debian package of every artifact/network/codename combination, named
+/// as [`get_debian_package_name`] does. Artifacts that don't carry a
+/// network in their names are deleted once per codename.
+pub fn delete_specs(
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    daemon_debian_name: Option<&str>,
+) -> Vec<DeleteSpec> {
+    let mut specs = Vec::new();
+
+    for artifact in artifacts {
+        let name = artifact.as_str();
+        let network_dependent = !get_suffix(name, Some("network"), None).is_empty();
+        let artifact_networks: Vec<Option<&str>> = if network_dependent {
+            networks.iter().map(|n| Some(n.as_str())).collect()
+        } else {
+            vec![None]
+        };
+
+        for codename in codenames {
+            for network in &artifact_networks {
+                specs.push(DeleteSpec {
+                    package: get_debian_package_name(name, *network, None, daemon_debian_name),
+                    codename: codename.clone(),
+                });
+            }
+        }
+    }
+
+    specs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delete_specs() {
+        let artifacts = parse_artifact_list("mina-logproc,mina-daemon,mina-archive").unwrap();
+        let networks = parse_string_list("devnet,mainnet");
+        let codenames = parse_string_list("bullseye,focal");
+
+        let specs: Vec<(String, String)> = delete_specs(&artifacts, &networks, &codenames, None)
+            .into_iter()
+            .map(|spec| (spec.package, spec.codename))
+            .collect();
+        let expected: Vec<(String, String)> = [
+            ("mina-logproc", "bullseye"),
+            ("mina-logproc", "focal"),
+            ("mina-devnet", "bullseye"),
+            ("mina-mainnet", "bullseye"),
+            ("mina-devnet", "focal"),
+            ("mina-mainnet", "focal"),
+            ("mina-archive-devnet", "bullseye"),
+            ("mina-archive-mainnet", "bullseye"),
+            ("mina-archive-devnet", "focal"),
+            ("mina-archive-mainnet", "focal"),
+        ]
+        .iter()
+        .map(|(package, codename)| (package.to_string(), codename.to_string()))
+        .collect();
+        assert_eq!(specs, expected);
+
+        let renamed = delete_specs(
+            &parse_artifact_list("mina-daemon").unwrap(),
+            &parse_string_list("devnet"),
+            &parse_string_list("bullseye"),
+            Some("mina-daemon-{network}"),
+        );
+        assert_eq!(renamed[0].package, "mina-daemon-devnet");
+    }
+}
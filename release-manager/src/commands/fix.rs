@@ -1,50 +1,181 @@
 use crate::artifacts::parse_string_list;
 use crate::cli::FixArgs;
-use crate::errors::ManagerResult;
+use crate::debian_publish::detect_broken_deb_s3;
+use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::trace::{failure_excerpt, TracedCommand};
 use crate::utils::{print_operation_info, run_command_with_debug};
 use colored::*;
+use std::future::Future;
+use std::sync::Arc;
 use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+const BUCKET_ARG: &str = "--bucket=packages.o1test.net";
 
 pub async fn execute(args: FixArgs) -> ManagerResult<()> {
     // Parse lists
     let codenames = parse_string_list(&args.codenames);
+    let parallel_str = args.parallel_codenames.map(|n| n.to_string());
 
     // Print operation info
-    let params = vec![
+    let mut params = vec![
         ("Codenames", args.codenames.as_str()),
         ("Channel", args.channel.as_str()),
+        ("S3 region", args.s3_region.as_str()),
     ];
+    if let Some(parallel) = &parallel_str {
+        params.push(("Parallel codenames", parallel.as_str()));
+    }
 
     print_operation_info("Fixing debian repository", &params);
 
-    let bucket_arg = "--bucket=packages.o1test.net";
-    let s3_region_arg = "--s3-region=us-west-2";
-
-    // Fix manifests for each codename
-    for codename in &codenames {
-        let mut cmd = Command::new("deb-s3");
-        cmd.arg("verify")
-            .arg("--fix-manifests")
-            .arg(bucket_arg)
-            .arg(s3_region_arg)
-            .arg(format!("--codename={}", codename))
-            .arg(format!("--component={}", args.channel));
-
-        if args.debug {
-            run_command_with_debug(cmd, true).await?;
-        } else {
-            let output = cmd.output().await?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                eprintln!("Failed to fix manifests for {}: {}", codename, stderr);
-            } else {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                println!("Fixed manifests for {}: {}", codename, stdout);
+    // Fix manifests for each codename, a bounded number at a time
+    let channel = args.channel.clone();
+    let s3_region = args.s3_region.clone();
+    let debug = args.debug;
+    let results = fix_codenames(
+        &codenames,
+        args.parallel_codenames.unwrap_or(1),
+        |codename| fix_codename(codename, channel.clone(), s3_region.clone(), debug),
+    )
+    .await;
+
+    for (codename, result) in &results {
+        match result {
+            Ok(stdout) => textln!("Fixed manifests for {}: {}", codename, stdout),
+            Err(e) => eprintln!("Failed to fix manifests for {}: {}", codename, e),
+        }
+    }
+
+    if args.parallel_codenames.is_some() {
+        textln!("📋 Summary:");
+        for (codename, result) in &results {
+            match result {
+                Ok(_) => textln!("  {} {}", "✅".green(), codename),
+                Err(_) => textln!("  {} {}", "❌".red(), codename),
             }
         }
     }
 
-    println!("{}", " ✅  Done.".green());
+    // A broken deb-s3 fails every codename, so report it as such. Other
+    // failures are only fatal in debug mode, as before.
+    for (_, result) in results {
+        match result {
+            Err(ManagerError::DebS3Broken(cause)) => return Err(ManagerError::DebS3Broken(cause)),
+            Err(e) if debug => return Err(e),
+            _ => {}
+        }
+    }
+
+    textln!("{}", " ✅  Done.".green());
     Ok(())
 }
+
+/// Run `fix` for every codename, at most `limit` at a time, and return the
+/// results in `codenames` order
+pub async fn fix_codenames<F, Fut>(
+    codenames: &[String],
+    limit: usize,
+    fix: F,
+) -> Vec<(String, ManagerResult<String>)>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = ManagerResult<String>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(limit.max(1)));
+    let handles: Vec<_> = codenames
+        .iter()
+        .map(|codename| {
+            let semaphore = semaphore.clone();
+            let fix = fix(codename.clone());
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                fix.await
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (codename, handle) in codenames.iter().zip(handles) {
+        let result = handle.await.unwrap_or_else(|e| {
+            Err(ManagerError::CommandFailed(format!(
+                "Fix for {} panicked: {}",
+                codename, e
+            )))
+        });
+        results.push((codename.clone(), result));
+    }
+    results
+}
+
+/// `deb-s3 verify --fix-manifests` for one codename's channel
+async fn fix_codename(
+    codename: String,
+    channel: String,
+    s3_region: String,
+    debug: bool,
+) -> ManagerResult<String> {
+    let mut cmd = Command::new("deb-s3");
+    cmd.arg("verify")
+        .arg("--fix-manifests")
+        .arg(BUCKET_ARG)
+        .arg(format!("--s3-region={}", s3_region))
+        .arg(format!("--codename={}", codename))
+        .arg(format!("--component={}", channel));
+
+    if debug {
+        return run_command_with_debug(cmd, true).await;
+    }
+
+    let output = cmd.traced_output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(cause) = detect_broken_deb_s3(&stderr) {
+            return Err(ManagerError::DebS3Broken(cause));
+        }
+        return Err(ManagerError::CommandFailed(failure_excerpt(&output)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_fix_codenames_bounded_and_ordered() {
+        let codenames: Vec<String> = ["bullseye", "focal", "noble", "jammy", "bookworm"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let results = fix_codenames(&codenames, 2, |codename| {
+            let running = running.clone();
+            let peak = peak.clone();
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                if codename == "noble" {
+                    Err(ManagerError::CommandFailed("boom".to_string()))
+                } else {
+                    Ok(format!("fixed {}", codename))
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        let order: Vec<&str> = results.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(order, ["bullseye", "focal", "noble", "jammy", "bookworm"]);
+        assert_eq!(results[0].1.as_deref().unwrap(), "fixed bullseye");
+        assert!(matches!(results[2].1, Err(ManagerError::CommandFailed(_))));
+        assert_eq!(results.iter().filter(|(_, r)| r.is_ok()).count(), 4);
+    }
+}
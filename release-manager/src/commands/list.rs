@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+
+use crate::artifacts::{
+    artifact_has_docker, docker_codename, get_docker_image_name, get_repo, get_suffix,
+    parse_artifact_list, parse_string_list,
+};
+use crate::cli::ListArgs;
+use crate::commands::names::{resolve_names, NamesOptions};
+use crate::commands::validate::{parse_packages_file, repo_base};
+use crate::errors::{ManagerError, ManagerResult};
+use crate::utils::print_operation_info;
+use serde::{Deserialize, Serialize};
+
+/// A debian package version or docker tag found published
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListedArtifact {
+    /// `debian` or `docker`
+    pub kind: &'static str,
+    pub artifact: String,
+    /// `None` for artifacts whose names don't depend on the network
+    pub network: Option<String>,
+    pub codename: String,
+    /// Debian package name, or docker image without the tag
+    pub name: String,
+    /// Debian version, or docker tag
+    pub version: String,
+}
+
+pub async fn execute(args: ListArgs) -> ManagerResult<()> {
+    let artifacts = parse_artifact_list(&args.artifacts)?;
+    let networks = parse_string_list(&args.networks);
+    let codenames = parse_string_list(&args.codenames);
+
+    if !args.json {
+        let docker_io_str = args.docker_io.to_string();
+        let params = vec![
+            ("Artifacts", args.artifacts.as_str()),
+            ("Networks", args.networks.as_str()),
+            ("Codenames", args.codenames.as_str()),
+            ("Channel", args.channel.as_str()),
+            ("Arch", args.arch.as_str()),
+            ("Debian repo", args.debian_repo.as_str()),
+            ("Docker.io", docker_io_str.as_str()),
+        ];
+        print_operation_info("Listing published mina artifacts", &params);
+    }
+
+    // Only the package names and image names are used, so no version
+    let options = NamesOptions {
+        version: "",
+        channel: &args.channel,
+        profile: None,
+        arch: None,
+        docker_io: args.docker_io,
+        target_registry: None,
+        daemon_debian_name: args.daemon_debian_name.as_deref(),
+    };
+    let names = resolve_names(&artifacts, &networks, &codenames, &options);
+    let http = reqwest::Client::new();
+    let mut listed = Vec::new();
+
+    if !args.only_dockers {
+        let mut indexes: HashMap<&str, String> = HashMap::new();
+        for entry in &names {
+            if !indexes.contains_key(entry.codename.as_str()) {
+                let index = read_packages_index(
+                    &http,
+                    &args.debian_repo,
+                    &entry.codename,
+                    &args.channel,
+                    &args.arch,
+                )
+                .await?;
+                indexes.insert(&entry.codename, index);
+            }
+            for version in
+                package_versions(&indexes[entry.codename.as_str()], &entry.debian_package)
+            {
+                listed.push(ListedArtifact {
+                    kind: "debian",
+                    artifact: entry.artifact.clone(),
+                    network: entry.network.clone(),
+                    codename: entry.codename.clone(),
+                    name: entry.debian_package.clone(),
+                    version,
+                });
+            }
+        }
+    }
+
+    if !args.only_debians {
+        let repo = get_repo(args.docker_io);
+        let mut tags: HashMap<&str, Vec<String>> = HashMap::new();
+        for entry in names.iter().filter(|e| artifact_has_docker(&e.artifact)) {
+            let image = get_docker_image_name(&entry.artifact);
+            if !tags.contains_key(image) {
+                tags.insert(image, list_registry_tags(&http, repo, image).await?);
+            }
+            let suffix = get_suffix(&entry.artifact, entry.network.as_deref(), None);
+            for tag in tags[image]
+                .iter()
+                .filter(|tag| tag_matches(tag, &entry.codename, &suffix))
+            {
+                listed.push(ListedArtifact {
+                    kind: "docker",
+                    artifact: entry.artifact.clone(),
+                    network: entry.network.clone(),
+                    codename: entry.codename.clone(),
+                    name: format!("{}/{}", repo, image),
+                    version: tag.clone(),
+                });
+            }
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(());
+    }
+
+    if listed.is_empty() {
+        println!(" ℹ️  Nothing published for the requested artifacts");
+    }
+    for item in &listed {
+        let icon = if item.kind == "debian" {
+            "📦"
+        } else {
+            "🐋"
+        };
+        println!(
+            " {}  {} {} ({} codename)",
+            icon, item.name, item.version, item.codename
+        );
+    }
+    Ok(())
+}
+
+/// The `channel` Packages index of `codename` for `arch`
+async fn read_packages_index(
+    http: &reqwest::Client,
+    debian_repo: &str,
+    codename: &str,
+    channel: &str,
+    arch: &str,
+) -> ManagerResult<String> {
+    let index_url = format!(
+        "{}/dists/{}/{}/binary-{}/Packages",
+        repo_base(debian_repo),
+        codename,
+        channel,
+        arch
+    );
+    let resp = http.get(&index_url).send().await?;
+    if !resp.status().is_success() {
+        return Err(ManagerError::ArtifactNotFound(format!(
+            "Could not read the {} channel index {} ({})",
+            channel,
+            index_url,
+            resp.status()
+        )));
+    }
+    Ok(resp.text().await?)
+}
+
+/// Every version of `package` a Packages index lists, in index order
+fn package_versions(index: &str, package: &str) -> Vec<String> {
+    parse_packages_file(index)
+        .into_iter()
+        .filter(|entry| entry.package.as_deref() == Some(package))
+        .filter_map(|entry| entry.version)
+        .collect()
+}
+
+/// Whether a docker tag (`<version>-<codename><suffix>[-<profile|arch>]`)
+/// was built for `codename` and the network `suffix`
+fn tag_matches(tag: &str, codename: &str, suffix: &str) -> bool {
+    let marker = format!("-{}{}", docker_codename(codename), suffix);
+    tag.match_indices(&marker).any(|(at, _)| {
+        let rest = &tag[at + marker.len()..];
+        rest.is_empty() || rest.starts_with('-')
+    })
+}
+
+/// Registry API base URL and repository namespace of a docker `repo` such as
+/// `gcr.io/o1labs-192920`. A repo with a scheme (a test registry) keeps its
+/// host as the base.
+fn registry_endpoint(repo: &str) -> (String, String) {
+    let (scheme, rest) = match repo.split_once("://") {
+        Some((scheme, rest)) => (scheme, rest),
+        None => ("https", repo),
+    };
+    let (host, namespace) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = if host == "docker.io" {
+        "registry-1.docker.io"
+    } else {
+        host
+    };
+    (format!("{}://{}", scheme, host), namespace.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct TagList {
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+/// Docker Hub sends both fields, other token servers one of them
+#[derive(Debug, Deserialize)]
+struct RegistryToken {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+/// Every tag of `repo/image`, from the registry's `/v2/<name>/tags/list`,
+/// following `Link` pagination. Registries answering 401 get an anonymous
+/// pull token from the realm their `WWW-Authenticate` challenge names.
+async fn list_registry_tags(
+    http: &reqwest::Client,
+    repo: &str,
+    image: &str,
+) -> ManagerResult<Vec<String>> {
+    let (base, namespace) = registry_endpoint(repo);
+    let name = if namespace.is_empty() {
+        image.to_string()
+    } else {
+        format!("{}/{}", namespace, image)
+    };
+
+    let mut token: Option<String> = None;
+    let mut tags = Vec::new();
+    let mut next = Some(format!("{}/v2/{}/tags/list", base, name));
+    while let Some(url) = next.take() {
+        let mut request = http.get(&url);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        let resp = request.send().await?;
+
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED && token.is_none() {
+            let challenge = resp
+                .headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
+            token = Some(fetch_registry_token(http, challenge, &name).await?);
+            next = Some(url);
+            continue;
+        }
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !resp.status().is_success() {
+            return Err(ManagerError::ArtifactNotFound(format!(
+                "Listing tags of {}/{} failed: {}",
+                repo,
+                image,
+                resp.status()
+            )));
+        }
+
+        next = resp
+            .headers()
+            .get(reqwest::header::LINK)
+            .and_then(|v| v.to_str().ok())
+            .and_then(next_link)
+            .map(|path| format!("{}{}", base, path));
+        let page: TagList = resp.json().await?;
+        tags.extend(page.tags.unwrap_or_default());
+    }
+
+    Ok(tags)
+}
+
+/// Anonymous pull token for repository `name` from a
+/// `Bearer realm="…",service="…"` challenge
+async fn fetch_registry_token(
+    http: &reqwest::Client,
+    challenge: &str,
+    name: &str,
+) -> ManagerResult<String> {
+    let params = parse_bearer_challenge(challenge);
+    let realm = params.get("realm").ok_or_else(|| {
+        ManagerError::ArtifactNotFound(format!(
+            "Registry asked for credentials without a token realm: {}",
+            challenge
+        ))
+    })?;
+    let scope = params
+        .get("scope")
+        .cloned()
+        .unwrap_or_else(|| format!("repository:{}:pull", name));
+    let mut query = vec![("scope", scope)];
+    if let Some(service) = params.get("service") {
+        query.push(("service", service.clone()));
+    }
+
+    let resp = http.get(realm).query(&query).send().await?;
+    if !resp.status().is_success() {
+        return Err(ManagerError::ArtifactNotFound(format!(
+            "Fetching a registry token from {} failed: {}",
+            realm,
+            resp.status()
+        )));
+    }
+    let token = resp.json::<RegistryToken>().await?;
+    token.token.or(token.access_token).ok_or_else(|| {
+        ManagerError::ArtifactNotFound(format!("{} returned no registry token", realm))
+    })
+}
+
+/// Key/value pairs of a `WWW-Authenticate: Bearer k="v",…` header
+fn parse_bearer_challenge(challenge: &str) -> HashMap<String, String> {
+    let Some(params) = challenge.strip_prefix("Bearer ") else {
+        return HashMap::new();
+    };
+    params
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Path of the `rel="next"` page in a registry `Link` header
+fn next_link(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (target, rel) = part.split_once(';')?;
+        rel.contains("rel=\"next\"").then(|| {
+            target
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_package_versions() {
+        let index = "Package: mina-devnet\nVersion: 3.0.0-bullseye-devnet\nFilename: pool/a.deb\n\n\
+                     Package: mina-archive-devnet\nVersion: 3.0.0-bullseye-devnet\nFilename: pool/b.deb\n\n\
+                     Package: mina-devnet\nVersion: 3.0.1-bullseye-devnet\nFilename: pool/c.deb\n";
+        assert_eq!(
+            package_versions(index, "mina-devnet"),
+            vec!["3.0.0-bullseye-devnet", "3.0.1-bullseye-devnet"]
+        );
+        assert!(package_versions(index, "mina-mainnet").is_empty());
+    }
+
+    #[test]
+    fn test_tag_matches() {
+        assert!(tag_matches("3.0.0-bullseye-devnet", "bullseye", "-devnet"));
+        assert!(tag_matches(
+            "3.0.0-bullseye-devnet-arm64",
+            "bullseye",
+            "-devnet"
+        ));
+        assert!(!tag_matches(
+            "3.0.0-bullseye-mainnet",
+            "bullseye",
+            "-devnet"
+        ));
+        assert!(!tag_matches("3.0.0-focal-devnet", "bullseye", "-devnet"));
+        assert!(!tag_matches(
+            "3.0.0-bullseye-devnet2",
+            "bullseye",
+            "-devnet"
+        ));
+    }
+
+    #[test]
+    fn test_registry_endpoint() {
+        assert_eq!(
+            registry_endpoint("gcr.io/o1labs-192920"),
+            ("https://gcr.io".to_string(), "o1labs-192920".to_string())
+        );
+        assert_eq!(
+            registry_endpoint("docker.io/minaprotocol"),
+            (
+                "https://registry-1.docker.io".to_string(),
+                "minaprotocol".to_string()
+            )
+        );
+        assert_eq!(
+            registry_endpoint("http://127.0.0.1:5000/mina"),
+            ("http://127.0.0.1:5000".to_string(), "mina".to_string())
+        );
+    }
+
+    #[test]
+    fn test_next_link() {
+        assert_eq!(
+            next_link("</v2/mina/mina-daemon/tags/list?last=b&n=2>; rel=\"next\"").as_deref(),
+            Some("/v2/mina/mina-daemon/tags/list?last=b&n=2")
+        );
+        assert_eq!(next_link("</v2/x>; rel=\"prev\""), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_registry_tags_with_token_and_pages() {
+        let server = MockServer::start().await;
+        let challenge = format!(
+            "Bearer realm=\"{}/token\",service=\"test-registry\"",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"access_token": "anon"}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/mina/mina-daemon/tags/list"))
+            .and(header("authorization", "Bearer anon"))
+            .and(wiremock::matchers::query_param("last", "a"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"tags": ["b"]}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/mina/mina-daemon/tags/list"))
+            .and(header("authorization", "Bearer anon"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "Link",
+                        "</v2/mina/mina-daemon/tags/list?last=a>; rel=\"next\"",
+                    )
+                    .set_body_string(r#"{"tags": ["a"]}"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/mina/mina-daemon/tags/list"))
+            .respond_with(ResponseTemplate::new(401).insert_header("WWW-Authenticate", challenge))
+            .mount(&server)
+            .await;
+
+        let repo = format!("{}/mina", server.uri());
+        let tags = list_registry_tags(&reqwest::Client::new(), &repo, "mina-daemon")
+            .await
+            .unwrap();
+        assert_eq!(tags, vec!["a", "b"]);
+    }
+}
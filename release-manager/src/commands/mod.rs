@@ -1,4 +1,7 @@
+pub mod delete;
 pub mod fix;
+pub mod list;
+pub mod names;
 pub mod persist;
 pub mod progress;
 pub mod promote;
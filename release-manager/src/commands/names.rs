@@ -0,0 +1,271 @@
+use crate::artifacts::{
+    calculate_debian_version, calculate_docker_tag_in, get_debian_package_name, get_suffix,
+    parse_artifact_list, parse_string_list, target_repo, Artifact,
+};
+use crate::cli::NamesArgs;
+use crate::errors::ManagerResult;
+use crate::utils::{print_operation_info, validate_required_args};
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How `names` prints the resolved names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NamesFormat {
+    /// One block per combination, after the parameter listing
+    #[default]
+    Text,
+    /// A JSON array of the resolved names, and nothing else
+    Json,
+}
+
+/// Canonical names of one artifact/network/codename combination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedNames {
+    pub artifact: String,
+    /// `None` for artifacts whose names don't depend on the network
+    pub network: Option<String>,
+    pub codename: String,
+    pub channel: String,
+    pub debian_package: String,
+    pub debian_version: String,
+    /// `None` for artifacts without a docker image
+    pub docker_tag: Option<String>,
+}
+
+/// Inputs shared by every combination passed to [`resolve_names`].
+pub struct NamesOptions<'a> {
+    pub version: &'a str,
+    pub channel: &'a str,
+    pub profile: Option<&'a str>,
+    pub arch: Option<&'a str>,
+    pub docker_io: bool,
+    /// Registry overriding `docker_io`, as `--target-registry`
+    pub target_registry: Option<&'a str>,
+    pub daemon_debian_name: Option<&'a str>,
+}
+
+pub async fn execute(args: NamesArgs) -> ManagerResult<()> {
+    validate_required_args(&[("version", Some(&args.version))])?;
+
+    let artifacts = parse_artifact_list(&args.artifacts)?;
+    let networks = parse_string_list(&args.networks);
+    let codenames = parse_string_list(&args.codenames);
+
+    let options = NamesOptions {
+        version: &args.version,
+        channel: &args.channel,
+        profile: args.profile.as_deref(),
+        arch: args.arch.as_deref(),
+        docker_io: args.docker_io,
+        target_registry: None,
+        daemon_debian_name: args.daemon_debian_name.as_deref(),
+    };
+    let names = resolve_names(&artifacts, &networks, &codenames, &options);
+
+    if args.format == NamesFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&names)?);
+        return Ok(());
+    }
+
+    let docker_io_str = args.docker_io.to_string();
+    let params = vec![
+        ("Artifacts", args.artifacts.as_str()),
+        ("Networks", args.networks.as_str()),
+        ("Codenames", args.codenames.as_str()),
+        ("Version", args.version.as_str()),
+        ("Channel", args.channel.as_str()),
+        ("Profile", args.profile.as_deref().unwrap_or("")),
+        ("Arch", args.arch.as_deref().unwrap_or("")),
+        ("Docker.io", docker_io_str.as_str()),
+    ];
+    print_operation_info("Resolving mina artifact names", &params);
+
+    for entry in &names {
+        match &entry.network {
+            Some(network) => println!(
+                " 📦  {} ({} network, {} codename)",
+                entry.artifact, network, entry.codename
+            ),
+            None => println!(" 📦  {} ({} codename)", entry.artifact, entry.codename),
+        }
+        println!("     Debian package: {}", entry.debian_package);
+        println!("     Debian version: {}", entry.debian_version);
+        println!(
+            "     Docker tag:     {}",
+            entry.docker_tag.as_deref().unwrap_or("-")
+        );
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Resolve debian package name, debian version and docker tag for every
+/// artifact/network/codename combination. Artifacts that don't carry a
+/// network in their names are resolved once per codename.
+pub fn resolve_names(
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    options: &NamesOptions,
+) -> Vec<ResolvedNames> {
+    let mut names = Vec::new();
+
+    for artifact in artifacts {
+        let name = artifact.as_str();
+        let network_dependent = !get_suffix(name, Some("network"), None).is_empty();
+        let artifact_networks: Vec<Option<&str>> = if network_dependent {
+            networks.iter().map(|n| Some(n.as_str())).collect()
+        } else {
+            vec![None]
+        };
+
+        for codename in codenames {
+            for network in &artifact_networks {
+                let docker_tag = artifact.has_docker_image().then(|| {
+                    calculate_docker_tag_in(
+                        &target_repo(options.target_registry, options.docker_io),
+                        name,
+                        options.version,
+                        codename,
+                        *network,
+                        options.profile,
+                        options.arch,
+                    )
+                });
+
+                names.push(ResolvedNames {
+                    artifact: name.to_string(),
+                    network: network.map(|n| n.to_string()),
+                    codename: codename.clone(),
+                    channel: options.channel.to_string(),
+                    debian_package: get_debian_package_name(
+                        name,
+                        *network,
+                        options.profile,
+                        options.daemon_debian_name,
+                    ),
+                    debian_version: calculate_debian_version(
+                        name,
+                        options.version,
+                        codename,
+                        *network,
+                        options.arch,
+                    ),
+                    docker_tag,
+                });
+            }
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(version: &str) -> NamesOptions<'_> {
+        NamesOptions {
+            version,
+            channel: "unstable",
+            profile: None,
+            arch: None,
+            docker_io: false,
+            target_registry: None,
+            daemon_debian_name: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_names_archive_devnet_bullseye() {
+        let names = resolve_names(
+            &[Artifact::MinaArchive],
+            &["devnet".to_string()],
+            &["bullseye".to_string()],
+            &options("1.2.3"),
+        );
+        assert_eq!(
+            names,
+            vec![ResolvedNames {
+                artifact: "mina-archive".to_string(),
+                network: Some("devnet".to_string()),
+                codename: "bullseye".to_string(),
+                channel: "unstable".to_string(),
+                debian_package: "mina-archive-devnet".to_string(),
+                debian_version: "mina-archive:1.2.3-bullseye-devnet".to_string(),
+                docker_tag: Some(
+                    "gcr.io/o1labs-192920/mina-archive:1.2.3-bullseye-devnet".to_string()
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resolve_names_networkless_artifact_without_docker() {
+        let names = resolve_names(
+            &[Artifact::MinaLogproc],
+            &["devnet".to_string(), "mainnet".to_string()],
+            &["bullseye".to_string(), "focal".to_string()],
+            &options("1.2.3"),
+        );
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|n| n.network.is_none()));
+        assert!(names.iter().all(|n| n.docker_tag.is_none()));
+        assert_eq!(names[1].debian_package, "mina-logproc");
+        assert_eq!(names[1].debian_version, "mina-logproc:1.2.3-focal");
+    }
+
+    #[test]
+    fn test_resolve_names_daemon_override_and_docker_io() {
+        let mut opts = options("1.2.3");
+        opts.docker_io = true;
+        opts.daemon_debian_name = Some("mina-daemon-{network}");
+        let names = resolve_names(
+            &[Artifact::MinaDaemon],
+            &["mainnet".to_string()],
+            &["focal".to_string()],
+            &opts,
+        );
+        assert_eq!(names[0].debian_package, "mina-daemon-mainnet");
+        assert_eq!(
+            names[0].docker_tag.as_deref(),
+            Some("docker.io/minaprotocol/mina-daemon:1.2.3-focal-mainnet")
+        );
+    }
+
+    #[test]
+    fn test_resolved_names_json() {
+        let names = resolve_names(
+            &[Artifact::Minimina],
+            &[],
+            &["bullseye".to_string()],
+            &options("1.2.3"),
+        );
+        let json = serde_json::to_value(&names).unwrap();
+        assert_eq!(json[0]["debian_package"], "minimina");
+        assert_eq!(json[0]["network"], serde_json::Value::Null);
+        assert_eq!(json[0]["docker_tag"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_names_format_is_validated_by_clap() {
+        use clap::Parser;
+
+        let parse = |format: &str| {
+            crate::Cli::try_parse_from([
+                "release-manager",
+                "names",
+                "--version",
+                "3.0.1",
+                "--format",
+                format,
+            ])
+        };
+        match parse("json").unwrap().command {
+            crate::Commands::Names(args) => assert_eq!(args.format, NamesFormat::Json),
+            _ => unreachable!(),
+        }
+        assert!(parse("yaml").is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use crate::artifacts::{extract_version_from_deb, get_artifact_with_suffix, parse_string_list};
 use crate::cli::PersistArgs;
-use crate::errors::ManagerResult;
-use crate::storage::{StorageBackend, StorageClient, StorageOperations};
+use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::storage::{expand_local_glob, StorageBackend, StorageClient, StorageOperations};
 use crate::utils::{
-    format_subcommand_tab, print_operation_info, run_command_with_debug, validate_required_args,
+    format_mismatch, format_subcommand_tab, print_operation_info, run_command_with_debug,
+    validate_required_args,
 };
 use colored::*;
+use std::path::PathBuf;
 use tempfile::TempDir;
 use tokio::process::Command;
 
@@ -35,6 +38,9 @@ pub async fn execute(args: PersistArgs) -> ManagerResult<()> {
         params.push(("New version", new_version));
     }
 
+    let verify_str = args.verify.to_string();
+    params.push(("Verify", verify_str.as_str()));
+
     print_operation_info("Persisting mina artifacts", &params);
 
     // Set up storage
@@ -43,18 +49,13 @@ pub async fn execute(args: PersistArgs) -> ManagerResult<()> {
 
     // Create temporary directory
     let tmp_dir = TempDir::new()?;
-    println!(" - Using temporary directory: {}", tmp_dir.path().display());
-    println!();
+    textln!(" - Using temporary directory: {}", tmp_dir.path().display());
+    textln!();
 
     // Process each artifact
     for artifact in &artifacts {
-        let remote_path = format!(
-            "{}/{}/debians/{}/{}_*",
-            storage.backend.root_path(),
-            args.buildkite_build_id,
-            args.codename,
-            artifact
-        );
+        let remote_path =
+            storage.debian_glob(&args.buildkite_build_id, &args.codename, artifact)?;
 
         // Download artifacts to temp directory
         storage
@@ -83,9 +84,11 @@ pub async fn execute(args: PersistArgs) -> ManagerResult<()> {
                     extract_version_from_deb(&deb_path.file_name().unwrap().to_string_lossy())?;
                 let artifact_full_name = get_artifact_with_suffix(artifact, None, None);
 
-                println!(
+                textln!(
                     " 🗃️  Rebuilding {} debian from {} to {}",
-                    artifact, source_version, new_version
+                    artifact,
+                    source_version,
+                    new_version
                 );
 
                 // Use reversion command
@@ -110,17 +113,94 @@ pub async fn execute(args: PersistArgs) -> ManagerResult<()> {
         }
 
         // Upload to target location
-        let target_path = format!(
-            "{}/{}/debians/{}/",
-            storage.backend.root_path(),
-            args.target,
-            args.codename
-        );
+        let target_path = storage.debian_directory(&args.target, &args.codename)?;
 
         let local_pattern = format!("{}/*{}*", tmp_dir.path().display(), artifact);
         storage.upload(&local_pattern, &target_path).await?;
+
+        if args.verify {
+            verify_persisted(&storage, &expand_local_glob(&local_pattern)?, &target_path).await?;
+        }
+    }
+
+    textln!("{}", " ✅  Done.".green());
+    Ok(())
+}
+
+/// Compare the md5 of every uploaded file with its copy under `target_path`
+/// (a directory path ending in `/`), failing on the first mismatch.
+pub async fn verify_persisted<S: StorageOperations + Sync + ?Sized>(
+    storage: &S,
+    local_files: &[PathBuf],
+    target_path: &str,
+) -> ManagerResult<()> {
+    let local = StorageClient::new(StorageBackend::Local);
+
+    for file in local_files {
+        let Some(file_name) = file.file_name() else {
+            continue;
+        };
+        let remote_file = format!("{}{}", target_path, file_name.to_string_lossy());
+
+        let expected = local.md5(&file.to_string_lossy()).await?;
+        let actual = storage.md5(&remote_file).await?;
+        if expected != actual {
+            return Err(ManagerError::ValidationError(format!(
+                "Persisted {} is corrupted, md5 differs from the local copy:{}",
+                remote_file,
+                format_mismatch(&expected, &actual)
+            )));
+        }
+        textln!(" ✅  Verified {} ({})", remote_file, actual);
     }
 
-    println!("{}", " ✅  Done.".green());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_verify_persisted_detects_corruption() {
+        let tmp = TempDir::new().unwrap();
+        let local_dir = tmp.path().join("local");
+        let target_dir = tmp.path().join("archive");
+        std::fs::create_dir_all(&local_dir).unwrap();
+        std::fs::create_dir_all(&target_dir).unwrap();
+
+        let deb = local_dir.join("mina-logproc_1.0.0.deb");
+        std::fs::write(&deb, "package bytes").unwrap();
+
+        let storage = StorageClient::new(StorageBackend::Local);
+        let target_path = format!("{}/", target_dir.display());
+        storage
+            .upload(&deb.to_string_lossy(), &target_path)
+            .await
+            .unwrap();
+
+        let files = vec![deb.clone()];
+        verify_persisted(&storage, &files, &target_path)
+            .await
+            .unwrap();
+
+        std::fs::write(target_dir.join("mina-logproc_1.0.0.deb"), "package byte5").unwrap();
+        let err = verify_persisted(&storage, &files, &target_path)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("is corrupted"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_persisted_missing_remote_fails() {
+        let tmp = TempDir::new().unwrap();
+        let deb = tmp.path().join("mina-logproc_1.0.0.deb");
+        std::fs::write(&deb, "package bytes").unwrap();
+
+        let storage = StorageClient::new(StorageBackend::Local);
+        let target_path = format!("{}/archive/", tmp.path().display());
+        assert!(verify_persisted(&storage, &[deb], &target_path)
+            .await
+            .is_err());
+    }
+}
@@ -1,10 +1,15 @@
 use std::process::Command;
 
-use crate::artifacts::{get_arch_suffix, get_artifact_with_suffix, get_suffix, parse_string_list};
+use crate::artifacts::{
+    artifact_has_docker, docker_codename, get_arch_suffix, get_artifact_with_suffix, get_suffix,
+    parse_string_list,
+};
 use crate::cli::ProgressArgs;
+use crate::container_runtime::container_runtime;
 use crate::errors::ManagerResult;
+use crate::output::textln;
+use crate::trace::TracedStdCommand;
 
-const S3_REGION: &str = "us-west-2";
 const GCR_REPO: &str = "gcr.io/o1labs-192920";
 const DOCKER_IO_REPO: &str = "docker.io/minaprotocol";
 
@@ -18,31 +23,32 @@ pub async fn execute(args: ProgressArgs) -> ManagerResult<()> {
         .filter(|b| !(args.skip_mina_public && b.contains("packages.minaprotocol.com")))
         .collect();
 
-    println!();
+    textln!();
     print_header();
-    println!(" 📦  Version: {}", args.version);
-    println!(" 🏷️   Release: {}", args.release);
-    println!(" 🌐  Network: {}", network);
-    println!(" 📚  Artifacts: {}", args.artifacts);
-    println!(" 🖥️   Codenames: {}", args.codenames);
+    textln!(" 📦  Version: {}", args.version);
+    textln!(" 🏷️   Release: {}", args.release);
+    textln!(" 🌐  Network: {}", network);
+    textln!(" 📚  Artifacts: {}", args.artifacts);
+    textln!(" 🖥️   Codenames: {}", args.codenames);
     print_header();
-    println!();
+    textln!();
 
     let mut totals = Totals::default();
 
     if !args.only_dockers {
-        println!("📦 DEBIAN PACKAGES");
+        textln!("📦 DEBIAN PACKAGES");
         print_header();
-        println!();
+        textln!();
         for bucket in &buckets {
-            println!("  🗄️  Repository: {}", bucket);
-            println!("  ─────────────────────────────────────────────────────────────");
-            println!();
+            textln!("  🗄️  Repository: {}", bucket);
+            textln!("  ─────────────────────────────────────────────────────────────");
+            textln!();
 
             for codename in &codenames {
                 for arch in archs_for_codename(codename) {
-                    println!("    📋  Checking {}/{}...", codename, arch);
-                    let available = deb_s3_list(bucket, &args.release, codename, arch);
+                    textln!("    📋  Checking {}/{}...", codename, arch);
+                    let available =
+                        deb_s3_list(bucket, &args.s3_region, &args.release, codename, arch);
 
                     for artifact in &artifacts {
                         check_artifact_in_debs3(
@@ -57,22 +63,22 @@ pub async fn execute(args: ProgressArgs) -> ManagerResult<()> {
                     }
                 }
             }
-            println!();
+            textln!();
         }
     }
 
     if !args.only_debians {
-        println!("🐋 DOCKER IMAGES");
+        textln!("🐋 DOCKER IMAGES");
         print_header();
-        println!();
+        textln!();
         let docker_repo = if args.release == "stable" {
             DOCKER_IO_REPO
         } else {
             GCR_REPO
         };
-        println!("  🐳  Registry: {}", docker_repo);
-        println!("  ─────────────────────────────────────────────────────────────");
-        println!();
+        textln!("  🐳  Registry: {}", docker_repo);
+        textln!("  ─────────────────────────────────────────────────────────────");
+        textln!();
 
         for artifact in &artifacts {
             if !artifact_has_docker(artifact) {
@@ -82,19 +88,25 @@ pub async fn execute(args: ProgressArgs) -> ManagerResult<()> {
                 for arch in archs_for_codename(codename) {
                     let net_suffix = get_suffix(artifact, Some(&network), args.profile.as_deref());
                     let arch_suffix = get_arch_suffix(arch);
-                    let tag = format!("{}-{}{}{}", args.version, codename, net_suffix, arch_suffix);
+                    let tag = format!(
+                        "{}-{}{}{}",
+                        args.version,
+                        docker_codename(codename),
+                        net_suffix,
+                        arch_suffix
+                    );
 
                     totals.docker_total += 1;
                     if check_docker_manifest(docker_repo, artifact, &tag) {
-                        println!("    ✅  {}:{}", artifact, tag);
+                        textln!("    ✅  {}:{}", artifact, tag);
                         totals.docker_passed += 1;
                     } else {
-                        println!("    ❌  {}:{} - MISSING", artifact, tag);
+                        textln!("    ❌  {}:{} - MISSING", artifact, tag);
                     }
                 }
             }
         }
-        println!();
+        textln!();
     }
 
     print_summary(&args, &totals);
@@ -110,7 +122,7 @@ struct Totals {
 }
 
 fn print_header() {
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    textln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 }
 
 fn network_for_channel(channel: &str) -> String {
@@ -143,26 +155,18 @@ fn archs_for_codename(codename: &str) -> &'static [&'static str] {
     }
 }
 
-fn artifact_has_docker(artifact: &str) -> bool {
-    !matches!(
-        artifact,
-        "mina-logproc"
-            | "minimina"
-            | "mina-config"
-            | "mina-automode"
-            | "mina-prefork"
-            | "mina-postfork"
-            | "mina-postfork-mesa"
-            | "mina-prefork-mesa"
-    )
-}
-
-fn deb_s3_list(bucket: &str, component: &str, codename: &str, arch: &str) -> String {
+fn deb_s3_list(
+    bucket: &str,
+    s3_region: &str,
+    component: &str,
+    codename: &str,
+    arch: &str,
+) -> String {
     let output = Command::new("deb-s3")
         .args([
             "list",
             &format!("--bucket={}", bucket),
-            &format!("--s3-region={}", S3_REGION),
+            &format!("--s3-region={}", s3_region),
             "--component",
             component,
             "--codename",
@@ -170,7 +174,7 @@ fn deb_s3_list(bucket: &str, component: &str, codename: &str, arch: &str) -> Str
             "--arch",
             arch,
         ])
-        .output();
+        .traced_output();
     match output {
         Ok(out) => String::from_utf8_lossy(&out.stdout).to_string(),
         Err(_) => String::new(),
@@ -230,20 +234,20 @@ fn check_artifact_in_debs3(
 fn check_one(name: &str, version: &str, arch: &str, available: &str, totals: &mut Totals) {
     totals.debian_total += 1;
     if package_present(available, name, version, arch) {
-        println!("      ✅  {}", name);
+        textln!("      ✅  {}", name);
         totals.debian_passed += 1;
     } else {
-        println!("      ❌  {} - MISSING", name);
+        textln!("      ❌  {} - MISSING", name);
     }
 }
 
 fn check_docker_manifest(repo: &str, artifact: &str, tag: &str) -> bool {
     let target = format!("{}/{}:{}", repo, artifact, tag);
-    Command::new("docker")
+    Command::new(container_runtime())
         .args(["manifest", "inspect", &target])
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
-        .status()
+        .traced_status()
         .map(|s| s.success())
         .unwrap_or(false)
 }
@@ -262,48 +266,51 @@ fn progress_bar(passed: usize, total: usize) -> String {
 
 fn print_summary(args: &ProgressArgs, t: &Totals) {
     print_header();
-    println!("📊 SUMMARY");
+    textln!("📊 SUMMARY");
     print_header();
-    println!();
+    textln!();
 
     if !args.only_dockers {
         let pct = t.debian_passed * 100 / t.debian_total.max(1);
-        println!(
+        textln!(
             "  📦  Debian Packages: {} / {} ({}%)",
-            t.debian_passed, t.debian_total, pct
+            t.debian_passed,
+            t.debian_total,
+            pct
         );
-        println!("{}", progress_bar(t.debian_passed, t.debian_total));
-        println!();
+        textln!("{}", progress_bar(t.debian_passed, t.debian_total));
+        textln!();
     }
 
     if !args.only_debians {
         let pct = t.docker_passed * 100 / t.docker_total.max(1);
-        println!(
+        textln!(
             "  🐋  Docker Images: {} / {} ({}%)",
-            t.docker_passed, t.docker_total, pct
+            t.docker_passed,
+            t.docker_total,
+            pct
         );
-        println!("{}", progress_bar(t.docker_passed, t.docker_total));
-        println!();
+        textln!("{}", progress_bar(t.docker_passed, t.docker_total));
+        textln!();
     }
 
     let total = t.debian_total + t.docker_total;
     let passed = t.debian_passed + t.docker_passed;
-    if total > 0 {
-        let pct = passed * 100 / total;
-        println!("  🎯  Overall Progress: {} / {} ({}%)", passed, total, pct);
-        println!("{}", progress_bar(passed, total));
-        println!();
+    if let Some(pct) = (passed * 100).checked_div(total) {
+        textln!("  🎯  Overall Progress: {} / {} ({}%)", passed, total, pct);
+        textln!("{}", progress_bar(passed, total));
+        textln!();
     }
 
     if passed == total && total > 0 {
-        println!("  🎉  Congratulations! All artifacts are published!");
+        textln!("  🎉  Congratulations! All artifacts are published!");
     } else {
-        println!("  ⚠️   There are missing artifacts. Please review the list above.");
+        textln!("  ⚠️   There are missing artifacts. Please review the list above.");
     }
 
-    println!();
+    textln!();
     print_header();
-    println!();
+    textln!();
 }
 
 #[cfg(test)]
@@ -359,15 +366,4 @@ mod tests {
             "arm64"
         ));
     }
-
-    #[test]
-    fn artifacts_without_docker() {
-        assert!(!artifact_has_docker("mina-logproc"));
-        assert!(!artifact_has_docker("minimina"));
-        assert!(!artifact_has_docker("mina-config"));
-        assert!(!artifact_has_docker("mina-automode"));
-        assert!(!artifact_has_docker("mina-prefork-mesa"));
-        assert!(artifact_has_docker("mina-daemon"));
-        assert!(artifact_has_docker("mina-generic"));
-    }
 }
@@ -1,16 +1,39 @@
 use crate::artifacts::{
-    calculate_debian_version, calculate_docker_tag, get_artifact_with_suffix, get_suffix,
-    parse_artifact_list, parse_string_list, Artifact,
+    calculate_debian_version, calculate_docker_tag_in, docker_codename, get_debian_package_name,
+    get_suffix, parse_artifact_list, parse_string_list, print_no_docker_image, target_repo,
+    Artifact,
 };
 use crate::cli::PromoteArgs;
-use crate::docker_promote::promote_docker_image;
-use crate::errors::ManagerResult;
+use crate::commands::names::{resolve_names, NamesOptions};
+use crate::commands::validate::{
+    find_package, parse_packages_file, repo_base, PRESENCE_INDEX_ARCH,
+};
+use crate::debian_publish::{publish_debian_package, LockMode};
+use crate::docker_promote::{
+    check_source_labels, delete_docker_tag, latest_tag, parse_annotation, parse_platforms,
+    parse_required_label, promote_docker_image, promote_verified, retag_docker_image,
+    staging_suffix, update_latest_tag, StagedPromotion,
+};
+use crate::errors::{ManagerError, ManagerResult};
+use crate::metrics::{self, Counter};
+use crate::output::{textln, Reporter};
 use crate::reversion;
-use crate::utils::{print_operation_info, validate_required_args};
-use crate::verification::{verify_debian_package, verify_docker_image};
+use crate::run_manifest::RunManifest;
+use crate::storage::{StorageBackend, StorageClient, StorageOperations};
+use crate::utils::{print_operation_info, resolve_version_arg, validate_required_args};
+use crate::verification::{
+    verify_debian_package, verify_docker_image, DebianVerifyConfig, VerifyManifest,
+};
+use async_trait::async_trait;
 use colored::*;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+pub async fn execute(mut args: PromoteArgs, reporter: &Reporter) -> ManagerResult<()> {
+    args.source_version = resolve_version_arg("source-version", &args.source_version)?;
+    args.target_version = resolve_version_arg("target-version", &args.target_version)?;
 
-pub async fn execute(args: PromoteArgs) -> ManagerResult<()> {
     // Validate required arguments
     validate_required_args(&[
         ("target-version", Some(&args.target_version)),
@@ -25,6 +48,22 @@ pub async fn execute(args: PromoteArgs) -> ManagerResult<()> {
     }
 
     // Parse lists
+    let annotations = args
+        .annotations
+        .iter()
+        .map(|a| parse_annotation(a))
+        .collect::<ManagerResult<Vec<_>>>()?;
+    let required_labels = args
+        .required_labels
+        .iter()
+        .map(|l| parse_required_label(l))
+        .collect::<ManagerResult<Vec<_>>>()?;
+    let platforms = args
+        .platforms
+        .as_deref()
+        .map(parse_platforms)
+        .transpose()?
+        .unwrap_or_default();
     let artifacts = parse_artifact_list(&args.artifacts)?;
     let networks = parse_string_list(&args.networks);
     let codenames = parse_string_list(&args.codenames);
@@ -34,21 +73,53 @@ pub async fn execute(args: PromoteArgs) -> ManagerResult<()> {
     let only_dockers_str = args.only_dockers.to_string();
     let only_debians_str = args.only_debians.to_string();
     let verify_str = args.verify.to_string();
+    let verify_before_commit_str = args.verify_before_commit.to_string();
+    let update_latest_str = args.update_latest.to_string();
+    let use_skopeo_str = args.use_skopeo.to_string();
+    let pin_digest_str = args.pin_digest.to_string();
     let dry_run_str = args.dry_run.to_string();
     let strip_network_str = args.strip_network_from_archive.to_string();
+    let annotations_str = args.annotations.join(", ");
+    let required_labels_str = args.required_labels.join(", ");
+    let emit_manifest_str = args.emit_manifest.as_deref().unwrap_or("");
 
     let mut params = vec![
         ("Promoting artifacts", args.artifacts.as_str()),
         ("Networks", args.networks.as_str()),
         ("Promoting codenames", args.codenames.as_str()),
         ("Publish to docker.io", publish_to_docker_io_str.as_str()),
+        (
+            "Target registry",
+            args.target_registry.as_deref().unwrap_or(""),
+        ),
         ("Only dockers", only_dockers_str.as_str()),
         ("Only debians", only_debians_str.as_str()),
         ("Verify", verify_str.as_str()),
+        (
+            "Expected version",
+            args.expected_version.as_deref().unwrap_or(""),
+        ),
+        ("Verify before commit", verify_before_commit_str.as_str()),
+        ("Update latest", update_latest_str.as_str()),
+        ("Use skopeo", use_skopeo_str.as_str()),
+        ("Platforms", args.platforms.as_deref().unwrap_or("")),
+        ("Pin digest", pin_digest_str.as_str()),
+        (
+            "Daemon debian name",
+            args.daemon_debian_name.as_deref().unwrap_or(""),
+        ),
         ("Dry run", dry_run_str.as_str()),
         ("Strip network from archive", strip_network_str.as_str()),
+        ("Annotations", annotations_str.as_str()),
+        ("Required labels", required_labels_str.as_str()),
+        ("Emit manifest", emit_manifest_str),
     ];
 
+    if let Some(ref build_id) = args.from_build_id {
+        params.push(("From build id", build_id.as_str()));
+        params.push(("Backend", args.backend.as_str()));
+    }
+
     if !args.only_dockers {
         if let Some(ref source_channel) = args.source_channel {
             params.push(("Source channel", source_channel.as_str()));
@@ -64,39 +135,139 @@ pub async fn execute(args: PromoteArgs) -> ManagerResult<()> {
 
     // Warning if source and target versions are the same
     if args.source_version == args.target_version {
-        println!(" ⚠️  Warning: Source version and target version are the same.");
-        println!("    Script will do promotion but it won't have an effect at the end unless you are publishing dockers from gcr.io to docker.io ...");
-        println!();
+        textln!(" ⚠️  Warning: Source version and target version are the same.");
+        textln!("    Script will do promotion but it won't have an effect at the end unless you are publishing dockers from gcr.io to docker.io ...");
+        textln!();
+    }
+
+    let mut promoted = Vec::new();
+    let mut manifest = RunManifest::new("promote", args.dry_run);
+
+    if let Some(ref build_id) = args.from_build_id {
+        let storage = StorageClient::new(StorageBackend::from_str(&args.backend)?);
+        let targets = list_build_targets(
+            &storage,
+            build_id,
+            &artifacts,
+            &networks,
+            &codenames,
+            args.daemon_debian_name.as_deref(),
+        )
+        .await?;
+        if targets.is_empty() {
+            return Err(ManagerError::ArtifactNotFound(format!(
+                "No debians of the requested artifacts found for build {}",
+                build_id
+            )));
+        }
+
+        for target in &targets {
+            add_to_manifest(
+                &mut manifest,
+                &args,
+                &[target.artifact],
+                &target.networks,
+                std::slice::from_ref(&target.codename),
+            );
+            promote_matrix(
+                &args,
+                &[target.artifact],
+                &target.networks,
+                std::slice::from_ref(&target.codename),
+                &annotations,
+                &platforms,
+                &required_labels,
+                &mut promoted,
+                reporter,
+            )
+            .await?;
+        }
+    } else {
+        add_to_manifest(&mut manifest, &args, &artifacts, &networks, &codenames);
+        promote_matrix(
+            &args,
+            &artifacts,
+            &networks,
+            &codenames,
+            &annotations,
+            &platforms,
+            &required_labels,
+            &mut promoted,
+            reporter,
+        )
+        .await?;
+    }
+
+    if !promoted.is_empty() {
+        textln!();
+        textln!(" 🧾 Promoted docker images:");
+        textln!("{}", format_digest_table(&promoted));
     }
 
-    // Process each artifact
-    for artifact in &artifacts {
-        for codename in &codenames {
+    if let Some(path) = &args.emit_manifest {
+        manifest.record_digests(
+            promoted
+                .iter()
+                .filter_map(|image| Some((image.tag.as_str(), image.digest.as_deref()?))),
+        );
+        manifest.write(path)?;
+    }
+
+    textln!("{}", " ✅  Promoting done.".green());
+    Ok(())
+}
+
+/// Record the target coordinates of the given lists for `--emit-manifest`
+fn add_to_manifest(
+    manifest: &mut RunManifest,
+    args: &PromoteArgs,
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+) {
+    let options = NamesOptions {
+        version: &args.target_version,
+        channel: args.target_channel.as_deref().unwrap_or(""),
+        profile: None,
+        arch: None,
+        docker_io: args.publish_to_docker_io,
+        target_registry: args.target_registry.as_deref(),
+        daemon_debian_name: args.daemon_debian_name.as_deref(),
+    };
+    let names = resolve_names(artifacts, networks, codenames, &options);
+    manifest.add(&names, &options, !args.only_dockers, !args.only_debians);
+}
+
+/// Promote every artifact/codename/network combination of the given lists
+#[allow(clippy::too_many_arguments)]
+async fn promote_matrix(
+    args: &PromoteArgs,
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    annotations: &[(String, String)],
+    platforms: &[String],
+    required_labels: &[(String, String)],
+    promoted: &mut Vec<PromotedImage>,
+    reporter: &Reporter,
+) -> ManagerResult<()> {
+    let target_repo = target_repo(args.target_registry.as_deref(), args.publish_to_docker_io);
+    for artifact in artifacts {
+        let has_docker = artifact.has_docker_image();
+        if !args.only_debians && !has_docker {
+            print_no_docker_image(artifact.as_str());
+        }
+        for codename in codenames {
             match artifact {
                 Artifact::MinaLogproc | Artifact::Minimina => {
                     if !args.only_dockers {
-                        promote_debian(
-                            artifact.as_str(),
-                            codename,
-                            &args.source_version,
-                            &args.target_version,
-                            args.source_channel.as_deref().unwrap(),
-                            args.target_channel.as_deref().unwrap(),
-                            None,
-                            args.verify,
-                            args.dry_run,
-                            &args.debian_repo,
-                            args.debian_sign_key.as_deref(),
-                            args.debug,
-                        )
-                        .await?;
-                    }
-
-                    if !args.only_debians {
-                        println!(
-                            "   ℹ️  There is no {} docker image to promote. skipping",
-                            artifact.as_str()
-                        );
+                        reporter
+                            .track(
+                                artifact.as_str(),
+                                &format!("debian {}", codename),
+                                promote_debian(args, artifact.as_str(), codename, None),
+                            )
+                            .await?;
                     }
                 }
 
@@ -106,143 +277,164 @@ pub async fn execute(args: PromoteArgs) -> ManagerResult<()> {
                 | Artifact::MinaPostfork
                 | Artifact::MinaPostforkMesa
                 | Artifact::MinaPreforkMesa => {
-                    for network in &networks {
+                    for network in networks {
                         if !args.only_dockers {
-                            promote_debian(
-                                artifact.as_str(),
-                                codename,
-                                &args.source_version,
-                                &args.target_version,
-                                args.source_channel.as_deref().unwrap(),
-                                args.target_channel.as_deref().unwrap(),
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                args.debug,
-                            )
-                            .await?;
-                        }
-                        if !args.only_debians {
-                            println!(
-                                "   ℹ️  There is no {} docker image to promote. skipping",
-                                artifact.as_str()
-                            );
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("debian {} {}", codename, network),
+                                    promote_debian(
+                                        args,
+                                        artifact.as_str(),
+                                        codename,
+                                        Some(network),
+                                    ),
+                                )
+                                .await?;
                         }
                     }
                 }
 
                 Artifact::MinaGeneric | Artifact::RosettaGeneric => {
-                    for network in &networks {
+                    for network in networks {
                         if !args.only_dockers {
-                            promote_debian(
-                                artifact.as_str(),
-                                codename,
-                                &args.source_version,
-                                &args.target_version,
-                                args.source_channel.as_deref().unwrap(),
-                                args.target_channel.as_deref().unwrap(),
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                args.debug,
-                            )
-                            .await?;
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("debian {} {}", codename, network),
+                                    promote_debian(
+                                        args,
+                                        artifact.as_str(),
+                                        codename,
+                                        Some(network),
+                                    ),
+                                )
+                                .await?;
                         }
 
-                        if !args.only_debians {
+                        if !args.only_debians && has_docker {
                             // calculate_docker_tag inside promote_and_verify_docker
                             // applies the docker-name mapping for the *-generic
                             // artifacts.
-                            promote_and_verify_docker(
-                                artifact.as_str(),
-                                &args.source_version,
-                                &args.target_version,
-                                codename,
-                                network,
-                                args.publish_to_docker_io,
-                                args.verify,
-                                args.dry_run,
-                                args.debug,
-                            )
-                            .await?;
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("docker {} {}", codename, network),
+                                    promote_and_verify_docker(
+                                        artifact.as_str(),
+                                        &args.source_version,
+                                        &args.target_version,
+                                        codename,
+                                        network,
+                                        &target_repo,
+                                        args.verify,
+                                        args.verify_before_commit,
+                                        args.update_latest,
+                                        args.use_skopeo,
+                                        args.pin_digest,
+                                        args.dry_run,
+                                        annotations,
+                                        platforms,
+                                        required_labels,
+                                        promoted,
+                                        args.debug,
+                                    ),
+                                )
+                                .await?;
                         }
                     }
                 }
 
                 Artifact::MinaArchive => {
-                    for network in &networks {
+                    for network in networks {
                         if !args.only_dockers {
-                            promote_debian(
-                                artifact.as_str(),
-                                codename,
-                                &args.source_version,
-                                &args.target_version,
-                                args.source_channel.as_deref().unwrap(),
-                                args.target_channel.as_deref().unwrap(),
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                args.debug,
-                            )
-                            .await?;
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("debian {} {}", codename, network),
+                                    promote_debian(
+                                        args,
+                                        artifact.as_str(),
+                                        codename,
+                                        Some(network),
+                                    ),
+                                )
+                                .await?;
                         }
 
-                        if !args.only_debians {
-                            promote_and_verify_docker(
-                                artifact.as_str(),
-                                &args.source_version,
-                                &args.target_version,
-                                codename,
-                                network,
-                                args.publish_to_docker_io,
-                                args.verify,
-                                args.dry_run,
-                                args.debug,
-                            )
-                            .await?;
+                        if !args.only_debians && has_docker {
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("docker {} {}", codename, network),
+                                    promote_and_verify_docker(
+                                        artifact.as_str(),
+                                        &args.source_version,
+                                        &args.target_version,
+                                        codename,
+                                        network,
+                                        &target_repo,
+                                        args.verify,
+                                        args.verify_before_commit,
+                                        args.update_latest,
+                                        args.use_skopeo,
+                                        args.pin_digest,
+                                        args.dry_run,
+                                        annotations,
+                                        platforms,
+                                        required_labels,
+                                        promoted,
+                                        args.debug,
+                                    ),
+                                )
+                                .await?;
                         }
                     }
                 }
 
                 Artifact::MinaRosetta | Artifact::MinaDaemon => {
-                    for network in &networks {
+                    for network in networks {
                         if !args.only_dockers {
-                            promote_debian(
-                                artifact.as_str(),
-                                codename,
-                                &args.source_version,
-                                &args.target_version,
-                                args.source_channel.as_deref().unwrap(),
-                                args.target_channel.as_deref().unwrap(),
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                args.debug,
-                            )
-                            .await?;
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("debian {} {}", codename, network),
+                                    promote_debian(
+                                        args,
+                                        artifact.as_str(),
+                                        codename,
+                                        Some(network),
+                                    ),
+                                )
+                                .await?;
                         }
 
-                        if !args.only_debians {
-                            promote_and_verify_docker(
-                                artifact.as_str(),
-                                &args.source_version,
-                                &args.target_version,
-                                codename,
-                                network,
-                                args.publish_to_docker_io,
-                                args.verify,
-                                args.dry_run,
-                                args.debug,
-                            )
-                            .await?;
+                        if !args.only_debians && has_docker {
+                            reporter
+                                .track(
+                                    artifact.as_str(),
+                                    &format!("docker {} {}", codename, network),
+                                    promote_and_verify_docker(
+                                        artifact.as_str(),
+                                        &args.source_version,
+                                        &args.target_version,
+                                        codename,
+                                        network,
+                                        &target_repo,
+                                        args.verify,
+                                        args.verify_before_commit,
+                                        args.update_latest,
+                                        args.use_skopeo,
+                                        args.pin_digest,
+                                        args.dry_run,
+                                        annotations,
+                                        platforms,
+                                        required_labels,
+                                        promoted,
+                                        args.debug,
+                                    ),
+                                )
+                                .await?;
                         }
                     }
                 }
@@ -250,70 +442,330 @@ pub async fn execute(args: PromoteArgs) -> ManagerResult<()> {
         }
     }
 
-    println!("{}", " ✅  Promoting done.".green());
     Ok(())
 }
 
+/// An artifact found in a build's storage for one codename, with the
+/// networks it was built for (empty for network-less artifacts).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildTarget {
+    pub artifact: Artifact,
+    pub codename: String,
+    pub networks: Vec<String>,
+}
+
+/// List the debians stored for `build_id` under each candidate codename and
+/// derive what to promote from them.
+async fn list_build_targets(
+    storage: &StorageClient,
+    build_id: &str,
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    daemon_debian_name: Option<&str>,
+) -> ManagerResult<Vec<BuildTarget>> {
+    let mut files = Vec::new();
+    for codename in codenames {
+        let pattern = format!("{}*.deb", storage.debian_directory(build_id, codename)?);
+        // Listing a codename the build didn't produce fails on most backends,
+        // so check for it first and let any other storage error through.
+        if !storage.exists(&pattern).await? {
+            textln!(" ℹ️  No debians found for {} codename", codename);
+            continue;
+        }
+        // The layout may not nest files under their codename, so key them by
+        // the codename they were listed for.
+        files.extend(storage.list(&pattern).await?.iter().filter_map(|file| {
+            let file_name = file.rsplit('/').next()?;
+            Some(format!("{}/{}", codename, file_name))
+        }));
+    }
+
+    Ok(derive_build_targets(
+        &files,
+        artifacts,
+        networks,
+        codenames,
+        daemon_debian_name,
+    ))
+}
+
+/// Match stored debian paths (`.../<codename>/<package>_<version>.deb`)
+/// against the candidate artifacts, networks and codenames. Each package
+/// name is claimed by the first candidate resolving to it, so aliases like
+/// `mina-prefork`/`mina-prefork-mesa` are promoted once.
+pub fn derive_build_targets(
+    files: &[String],
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    daemon_debian_name: Option<&str>,
+) -> Vec<BuildTarget> {
+    let stored: Vec<(&str, &str)> = files
+        .iter()
+        .filter_map(|file| {
+            let mut components = file.trim_end_matches('/').rsplit('/');
+            let file_name = components.next()?;
+            let codename = components.next()?;
+            let package = file_name.strip_suffix(".deb")?.split('_').next()?;
+            Some((codename, package))
+        })
+        .collect();
+
+    let mut targets = Vec::new();
+    for codename in codenames {
+        let mut claimed: Vec<String> = Vec::new();
+        let mut present = |package: String| {
+            let found = !claimed.contains(&package)
+                && stored
+                    .iter()
+                    .any(|(c, p)| *c == codename.as_str() && *p == package);
+            if found {
+                claimed.push(package);
+            }
+            found
+        };
+
+        for artifact in artifacts {
+            let name = artifact.as_str();
+            if get_suffix(name, Some("network"), None).is_empty() {
+                if present(get_debian_package_name(
+                    name,
+                    None,
+                    None,
+                    daemon_debian_name,
+                )) {
+                    targets.push(BuildTarget {
+                        artifact: *artifact,
+                        codename: codename.clone(),
+                        networks: Vec::new(),
+                    });
+                }
+                continue;
+            }
+
+            let found: Vec<String> = networks
+                .iter()
+                .filter(|network| {
+                    present(get_debian_package_name(
+                        name,
+                        Some(network),
+                        None,
+                        daemon_debian_name,
+                    ))
+                })
+                .cloned()
+                .collect();
+            if !found.is_empty() {
+                targets.push(BuildTarget {
+                    artifact: *artifact,
+                    codename: codename.clone(),
+                    networks: found,
+                });
+            }
+        }
+    }
+
+    targets
+}
+
+/// Download `package` at `version` from `component` of the repository into
+/// `dest_dir`, locating it in the pool through the component's Packages
+/// index. Fails with [`ManagerError::ArtifactNotFound`] when the index
+/// doesn't list it, so a mistyped source version stops here instead of
+/// somewhere inside the reversion.
+async fn fetch_deb_from_channel(
+    http: &reqwest::Client,
+    debian_repo: &str,
+    codename: &str,
+    component: &str,
+    package: &str,
+    version: &str,
+    dest_dir: &Path,
+) -> ManagerResult<PathBuf> {
+    let index_url = format!(
+        "{}/dists/{}/{}/binary-{}/Packages",
+        repo_base(debian_repo),
+        codename,
+        component,
+        PRESENCE_INDEX_ARCH
+    );
+
+    let index = match http.get(&index_url).send().await {
+        Ok(resp) if resp.status().is_success() => resp.text().await?,
+        Ok(resp) => {
+            return Err(ManagerError::ArtifactNotFound(format!(
+                "Could not read the {} channel index {} ({}) to look up {}={}",
+                component,
+                index_url,
+                resp.status(),
+                package,
+                version
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let entries = parse_packages_file(&index);
+    let entry = find_package(&entries, package, version);
+    let Some(filename) = entry.and_then(|entry| entry.filename.as_deref()) else {
+        return Err(ManagerError::ArtifactNotFound(format!(
+            "{}={} is not in the {} channel for {}; check --source-version and --source-channel",
+            package, version, component, codename
+        )));
+    };
+
+    let url = format!("{}/{}", repo_base(debian_repo), filename);
+    textln!("    ⬇️  Downloading {}", url);
+    let mut resp = http.get(&url).send().await?;
+    if !resp.status().is_success() {
+        return Err(ManagerError::ArtifactNotFound(format!(
+            "Could not download {}={} from {} ({})",
+            package,
+            version,
+            url,
+            resp.status()
+        )));
+    }
+
+    // Hash while writing, so the package is never held in memory whole
+    let name = filename.rsplit('/').next().unwrap_or(filename);
+    let path = dest_dir.join(name);
+    let mut file = tokio::fs::File::create(&path).await?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = resp.chunk().await? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+
+    if let Some(expected) = entry.and_then(|entry| entry.sha256.as_deref()) {
+        let actual = hex::encode(hasher.finalize());
+        if !actual.eq_ignore_ascii_case(expected) {
+            tokio::fs::remove_file(&path).await?;
+            return Err(ManagerError::ValidationError(format!(
+                "SHA256 of {} is {}, but the {} index lists {}",
+                url, actual, component, expected
+            )));
+        }
+    }
+    textln!(
+        "    ✅ Fetched {}={} from the {} channel",
+        package,
+        version,
+        component
+    );
+    Ok(path)
+}
+
 async fn promote_debian(
+    args: &PromoteArgs,
     artifact: &str,
     codename: &str,
-    source_version: &str,
-    target_version: &str,
-    source_channel: &str,
-    target_channel: &str,
     network: Option<&str>,
-    verify: bool,
-    dry_run: bool,
-    debian_repo: &str,
-    debian_sign_key: Option<&str>,
-    _debug: bool,
 ) -> ManagerResult<()> {
-    println!(
+    let source_version = args.source_version.as_str();
+    let target_version = args.target_version.as_str();
+    let source_channel = args.source_channel.as_deref().unwrap();
+    let target_channel = args.target_channel.as_deref().unwrap();
+    let debian_repo = args.debian_repo.as_str();
+    let debian_sign_key = args.debian_sign_key.as_deref();
+    textln!(
         " 🍥 Promoting {} debian from {} to {}, from {} to {}",
-        artifact, source_channel, target_channel, source_version, target_version
+        artifact,
+        source_channel,
+        target_channel,
+        source_version,
+        target_version
     );
-    println!(
+    textln!(
         "    📦 Target debian version: {}",
         calculate_debian_version(artifact, target_version, codename, network, None)
     );
 
-    let artifact_full_name = get_artifact_with_suffix(artifact, network, None);
+    let artifact_full_name =
+        get_debian_package_name(artifact, network, None, args.daemon_debian_name.as_deref());
 
-    if !dry_run {
-        println!(
+    if !args.dry_run {
+        textln!(
             "    🗃️  Promoting {} debian from {}/{} to {}/{}",
-            artifact, codename, source_version, codename, target_version
+            artifact,
+            codename,
+            source_version,
+            codename,
+            target_version
         );
 
-        // Use Rust reversion instead of shell script
-        let deb_path =
-            std::path::Path::new(debian_repo).join(&format!("{}.deb", artifact_full_name));
-
-        reversion::reversion_debian_package(
-            &deb_path,
+        let workdir = tempfile::tempdir()?;
+        let source_deb = fetch_deb_from_channel(
+            &reqwest::Client::new(),
+            debian_repo,
+            codename,
+            source_channel,
             &artifact_full_name,
             source_version,
+            workdir.path(),
+        )
+        .await?;
+
+        let deb_path = if source_version == target_version {
+            source_deb
+        } else {
+            reversion::reversion_debian_package(
+                &source_deb,
+                &artifact_full_name,
+                source_version,
+                target_version,
+                source_channel,
+                target_channel,
+                Some(&artifact_full_name),
+                Some(&workdir.path().join("reversioned")),
+                None,
+                true,
+                false,
+            )
+            .await?
+        };
+
+        publish_debian_package(
+            &deb_path.to_string_lossy(),
             target_version,
-            source_channel,
+            debian_repo,
+            &args.s3_region,
+            codename,
             target_channel,
-            Some(&artifact_full_name),
+            debian_sign_key,
+            args.debug,
+            LockMode::default(),
+            args.lockfile_max_age_secs,
+            None,
+            false,
         )
         .await?;
+        metrics::record(Counter::Promoted);
 
-        if verify {
-            println!(
+        if args.verify {
+            textln!(
                 "     📋 Verifying: {} debian to {} channel with {} version",
-                artifact, target_channel, target_version
+                artifact,
+                target_channel,
+                target_version
             );
 
-            verify_debian_package(
-                &artifact_full_name,
-                target_version,
-                debian_repo,
-                codename,
-                target_channel,
-                debian_sign_key.is_some(),
-            )
+            verify_debian_package(DebianVerifyConfig {
+                signed: debian_sign_key.is_some(),
+                manifest: VerifyManifest {
+                    expected_version: args.expected_version.clone(),
+                    ..VerifyManifest::default()
+                },
+                ..DebianVerifyConfig::new(
+                    &artifact_full_name,
+                    target_version,
+                    debian_repo,
+                    codename,
+                    target_channel,
+                )
+            })
             .await?;
         }
     }
@@ -321,68 +773,506 @@ async fn promote_debian(
     Ok(())
 }
 
+/// A docker image promoted by this run, for the closing digest table
+#[derive(Debug, Clone, PartialEq)]
+struct PromotedImage {
+    artifact: String,
+    network: String,
+    codename: String,
+    tag: String,
+    /// `None` when the registry didn't report one
+    digest: Option<String>,
+}
+
+/// Column-aligned (artifact, network, codename, tag, digest) table
+fn format_digest_table(images: &[PromotedImage]) -> String {
+    let header = ["ARTIFACT", "NETWORK", "CODENAME", "TAG", "DIGEST"];
+    let rows: Vec<[&str; 5]> = images
+        .iter()
+        .map(|image| {
+            [
+                image.artifact.as_str(),
+                image.network.as_str(),
+                image.codename.as_str(),
+                image.tag.as_str(),
+                image.digest.as_deref().unwrap_or("-"),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    std::iter::once(header)
+        .chain(rows)
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            format!("    {}", cells.join("  ").trim_end())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One docker promotion in verify-before-commit mode: the source tag is
+/// copied to a staging tag in the target registry and verified there
+struct StagedDockerPromotion<'a> {
+    artifact: &'a str,
+    source_tag: &'a str,
+    target_tag: &'a str,
+    target_version: &'a str,
+    codename: &'a str,
+    network_suffix: &'a str,
+    target_repo: &'a str,
+    annotations: &'a [(String, String)],
+    use_skopeo: bool,
+    platforms: &'a [String],
+    pin_digest: bool,
+}
+
+#[async_trait]
+impl StagedPromotion for StagedDockerPromotion<'_> {
+    async fn push(&self, staging_tag: &str) -> ManagerResult<()> {
+        promote_docker_image(
+            self.artifact,
+            self.source_tag,
+            staging_tag,
+            self.target_repo,
+            true,
+            self.annotations,
+            self.use_skopeo,
+            self.platforms,
+            self.pin_digest,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn verify(&self, _staging_tag: &str) -> ManagerResult<()> {
+        // DockerVerifier tags images `<version>-<codename><suffix>`, and the
+        // staging tag is the final tag plus the staging suffix
+        verify_docker_image(
+            self.artifact,
+            self.target_version,
+            self.target_repo,
+            self.codename,
+            &format!("{}{}", self.network_suffix, staging_suffix()),
+            None,
+        )
+        .await
+    }
+
+    async fn commit(&self, staging_tag: &str) -> ManagerResult<Option<String>> {
+        retag_docker_image(
+            self.target_repo,
+            self.artifact,
+            staging_tag,
+            self.target_tag,
+            self.annotations,
+            self.use_skopeo,
+        )
+        .await
+    }
+
+    async fn cleanup(&self, staging_tag: &str) -> ManagerResult<()> {
+        delete_docker_tag(self.target_repo, self.artifact, staging_tag).await
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn promote_and_verify_docker(
     artifact: &str,
     source_version: &str,
     target_version: &str,
     codename: &str,
     network: &str,
-    publish_to_docker_io: bool,
+    target_repo: &str,
     verify: bool,
+    verify_before_commit: bool,
+    update_latest: bool,
+    use_skopeo: bool,
+    pin_digest: bool,
     dry_run: bool,
+    annotations: &[(String, String)],
+    platforms: &[String],
+    required_labels: &[(String, String)],
+    promoted: &mut Vec<PromotedImage>,
     _debug: bool,
 ) -> ManagerResult<()> {
     let network_suffix = get_suffix(artifact, Some(network), None);
-    let artifact_full_source_version = format!("{}-{}{}", source_version, codename, network_suffix);
-    let artifact_full_target_version = format!("{}-{}{}", target_version, codename, network_suffix);
+    let artifact_full_source_version = format!(
+        "{}-{}{}",
+        source_version,
+        docker_codename(codename),
+        network_suffix
+    );
+    let artifact_full_target_version = format!(
+        "{}-{}{}",
+        target_version,
+        docker_codename(codename),
+        network_suffix
+    );
 
-    println!(
+    textln!(
         " 🐋 Publishing {} docker for '{}' network and '{}' codename with '{}' version",
-        artifact, network, codename, target_version
+        artifact,
+        network,
+        codename,
+        target_version
     );
-    println!(
-        "    📦 Target version: {}",
-        calculate_docker_tag(
-            publish_to_docker_io,
-            artifact,
-            target_version,
-            codename,
-            Some(network),
-            None,
-            None,
-        )
+    let target_tag = calculate_docker_tag_in(
+        target_repo,
+        artifact,
+        target_version,
+        codename,
+        Some(network),
+        None,
+        None,
     );
-    println!();
+    textln!("    📦 Target version: {}", target_tag);
+    textln!();
 
     if !dry_run {
-        promote_docker_image(
-            artifact,
-            &artifact_full_source_version,
-            &artifact_full_target_version,
-            publish_to_docker_io,
-            true, // quiet mode (equivalent to -q flag)
-        )
-        .await?;
-        println!();
+        check_source_labels(artifact, &artifact_full_source_version, required_labels).await?;
+        let digest = if verify_before_commit {
+            let staged = StagedDockerPromotion {
+                artifact,
+                source_tag: &artifact_full_source_version,
+                target_tag: &artifact_full_target_version,
+                target_version,
+                codename,
+                network_suffix: &network_suffix,
+                target_repo,
+                annotations,
+                use_skopeo,
+                platforms,
+                pin_digest,
+            };
+            promote_verified(&staged, &artifact_full_target_version).await?
+        } else {
+            promote_docker_image(
+                artifact,
+                &artifact_full_source_version,
+                &artifact_full_target_version,
+                target_repo,
+                true, // quiet mode (equivalent to -q flag)
+                annotations,
+                use_skopeo,
+                platforms,
+                pin_digest,
+            )
+            .await?
+        };
+        metrics::record(Counter::Promoted);
+        promoted.push(PromotedImage {
+            artifact: artifact.to_string(),
+            network: network.to_string(),
+            codename: codename.to_string(),
+            tag: target_tag,
+            digest,
+        });
+        textln!();
 
-        if verify {
-            println!(
+        // Already verified under the staging tag
+        if verify && !verify_before_commit {
+            textln!(
                 "    📋 Verifying: {} docker for '{}' network and '{}' codename with '{}' version",
-                artifact, network, codename, target_version
+                artifact,
+                network,
+                codename,
+                target_version
             );
-            println!();
+            textln!();
 
-            let repo = if publish_to_docker_io {
-                "docker.io/minaprotocol"
-            } else {
-                "gcr.io/o1labs-192920"
-            };
+            verify_docker_image(
+                artifact,
+                target_version,
+                target_repo,
+                codename,
+                &network_suffix,
+                None,
+            )
+            .await?;
 
-            verify_docker_image(artifact, target_version, repo, codename, &network_suffix).await?;
+            textln!();
+        }
 
-            println!();
+        // Last, so `latest` never points at an image that failed verification
+        if update_latest {
+            update_latest_tag(
+                target_repo,
+                artifact,
+                &artifact_full_target_version,
+                &latest_tag(codename, &network_suffix),
+                target_version,
+                annotations,
+            )
+            .await?;
+            textln!();
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE_INDEX: &str = "\
+Package: mina-devnet
+Version: 3.0.1-bullseye-devnet
+Architecture: amd64
+Filename: pool/unstable/m/mi/mina-devnet_3.0.1-bullseye-devnet_amd64.deb
+SHA256: abc123
+
+Package: mina-devnet
+Version: 3.0.0-bullseye-devnet
+Architecture: amd64
+Filename: pool/unstable/m/mi/mina-devnet_3.0.0-bullseye-devnet_amd64.deb
+SHA256: def456
+
+Package: mina-logproc
+Version: 1.0.0-ab12cd3
+Architecture: all
+Filename: pool/unstable/m/mi/mina-logproc_1.0.0-ab12cd3_all.deb
+SHA256: 789abc
+";
+
+    #[test]
+    fn test_format_digest_table() {
+        let images = vec![
+            PromotedImage {
+                artifact: "mina-daemon".to_string(),
+                network: "devnet".to_string(),
+                codename: "bullseye".to_string(),
+                tag: "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet".to_string(),
+                digest: Some("sha256:4c1b".to_string()),
+            },
+            PromotedImage {
+                artifact: "mina-archive".to_string(),
+                network: "mainnet".to_string(),
+                codename: "focal".to_string(),
+                tag: "gcr.io/o1labs-192920/mina-archive:3.0.1-focal-mainnet".to_string(),
+                digest: None,
+            },
+        ];
+        assert_eq!(
+            format_digest_table(&images),
+            "    ARTIFACT      NETWORK  CODENAME  TAG                                                     DIGEST\n    \
+             mina-daemon   devnet   bullseye  gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet  sha256:4c1b\n    \
+             mina-archive  mainnet  focal     gcr.io/o1labs-192920/mina-archive:3.0.1-focal-mainnet   -"
+        );
+    }
+
+    #[test]
+    fn test_find_package_pool_path() {
+        let entries = parse_packages_file(SOURCE_INDEX);
+        let pool = |package, version| {
+            find_package(&entries, package, version).and_then(|entry| entry.filename.clone())
+        };
+        assert_eq!(
+            pool("mina-devnet", "3.0.0-bullseye-devnet").as_deref(),
+            Some("pool/unstable/m/mi/mina-devnet_3.0.0-bullseye-devnet_amd64.deb")
+        );
+        assert_eq!(
+            pool("mina-logproc", "1.0.0-ab12cd3").as_deref(),
+            Some("pool/unstable/m/mi/mina-logproc_1.0.0-ab12cd3_all.deb")
+        );
+        // typo'd version, wrong package, and a version only another package has
+        assert_eq!(pool("mina-devnet", "3.0.01-bullseye-devnet"), None);
+        assert_eq!(pool("mina-mainnet", "3.0.1-bullseye-devnet"), None);
+        assert_eq!(pool("mina-logproc", "3.0.1-bullseye-devnet"), None);
+        assert!(find_package(&[], "mina-devnet", "3.0.1-bullseye-devnet").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_deb_from_channel_through_the_pool() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let deb = b"!<arch>\nnot really a deb".to_vec();
+        let pool = "pool/unstable/m/mi/mina-devnet_3.0.1-bullseye-devnet_amd64.deb";
+        let index = |sha256: &str| {
+            format!(
+                "Package: mina-devnet\nVersion: 3.0.1-bullseye-devnet\n\
+                 Filename: {}\nSHA256: {}\n",
+                pool, sha256
+            )
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/dists/bullseye/unstable/binary-amd64/Packages"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(index(&hex::encode(Sha256::digest(&deb)))),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/dists/bullseye/beta/binary-amd64/Packages"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(index("abc123")))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/{}", pool)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(deb.clone()))
+            .mount(&server)
+            .await;
+        let http = reqwest::Client::new();
+        let repo = server.uri();
+        let dest = tempfile::tempdir().unwrap();
+        let fetch = |component, version| {
+            fetch_deb_from_channel(
+                &http,
+                &repo,
+                "bullseye",
+                component,
+                "mina-devnet",
+                version,
+                dest.path(),
+            )
+        };
+
+        let fetched = fetch("unstable", "3.0.1-bullseye-devnet").await.unwrap();
+        assert_eq!(
+            fetched,
+            dest.path()
+                .join("mina-devnet_3.0.1-bullseye-devnet_amd64.deb")
+        );
+        assert_eq!(std::fs::read(&fetched).unwrap(), deb);
+
+        let absent = fetch("unstable", "3.0.2-bullseye-devnet")
+            .await
+            .unwrap_err();
+        assert!(matches!(absent, ManagerError::ArtifactNotFound(_)));
+        assert!(absent
+            .to_string()
+            .contains("mina-devnet=3.0.2-bullseye-devnet is not in the unstable channel"));
+
+        // No index at all for the channel
+        let missing_channel = fetch("alpha", "3.0.1-bullseye-devnet").await.unwrap_err();
+        assert!(matches!(missing_channel, ManagerError::ArtifactNotFound(_)));
+
+        let corrupt = fetch("beta", "3.0.1-bullseye-devnet").await.unwrap_err();
+        assert!(corrupt.to_string().contains("SHA256"), "{}", corrupt);
+        // The mismatching download isn't left behind
+        assert!(!fetched.exists());
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_derive_build_targets_from_listing() {
+        let listing = strings(&[
+            "gs://buildkite_k8s/coda/shared/123/debians/bullseye/mina-devnet_3.0.0-abc.deb",
+            "gs://buildkite_k8s/coda/shared/123/debians/bullseye/mina-mainnet_3.0.0-abc.deb",
+            "gs://buildkite_k8s/coda/shared/123/debians/bullseye/mina-archive-devnet_3.0.0-abc.deb",
+            "gs://buildkite_k8s/coda/shared/123/debians/bullseye/mina-logproc_3.0.0-abc.deb",
+            "gs://buildkite_k8s/coda/shared/123/debians/focal/mina-devnet_3.0.0-abc.deb",
+            "gs://buildkite_k8s/coda/shared/123/debians/focal/mina-rosetta-mainnet_3.0.0-abc.deb",
+            "gs://buildkite_k8s/coda/shared/123/debians/jammy/mina-devnet_3.0.0-abc.deb",
+        ]);
+
+        let targets = derive_build_targets(
+            &listing,
+            &[
+                Artifact::MinaDaemon,
+                Artifact::MinaArchive,
+                Artifact::MinaRosetta,
+                Artifact::MinaLogproc,
+            ],
+            &strings(&["devnet", "mainnet"]),
+            &strings(&["bullseye", "focal"]),
+            None,
+        );
+
+        assert_eq!(
+            targets,
+            vec![
+                BuildTarget {
+                    artifact: Artifact::MinaDaemon,
+                    codename: "bullseye".to_string(),
+                    networks: strings(&["devnet", "mainnet"]),
+                },
+                BuildTarget {
+                    artifact: Artifact::MinaArchive,
+                    codename: "bullseye".to_string(),
+                    networks: strings(&["devnet"]),
+                },
+                BuildTarget {
+                    artifact: Artifact::MinaLogproc,
+                    codename: "bullseye".to_string(),
+                    networks: vec![],
+                },
+                BuildTarget {
+                    artifact: Artifact::MinaDaemon,
+                    codename: "focal".to_string(),
+                    networks: strings(&["devnet"]),
+                },
+                BuildTarget {
+                    artifact: Artifact::MinaRosetta,
+                    codename: "focal".to_string(),
+                    networks: strings(&["mainnet"]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_derive_build_targets_aliases_claimed_once() {
+        let listing =
+            strings(&["/var/storagebox/123/debians/bullseye/mina-devnet-prefork-mesa_3.0.0.deb"]);
+        let targets = derive_build_targets(
+            &listing,
+            &[Artifact::MinaPrefork, Artifact::MinaPreforkMesa],
+            &strings(&["devnet"]),
+            &strings(&["bullseye"]),
+            None,
+        );
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].artifact, Artifact::MinaPrefork);
+    }
+
+    #[test]
+    fn test_derive_build_targets_renamed_daemon() {
+        let listing =
+            strings(&["/var/storagebox/123/debians/bullseye/mina-daemon-devnet_3.0.0.deb"]);
+        let targets = derive_build_targets(
+            &listing,
+            &[Artifact::MinaDaemon],
+            &strings(&["devnet"]),
+            &strings(&["bullseye"]),
+            Some("mina-daemon-{network}"),
+        );
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].networks, strings(&["devnet"]));
+    }
+
+    #[test]
+    fn test_derive_build_targets_ignores_unrelated_files() {
+        let listing = strings(&[
+            "/var/storagebox/123/debians/bullseye/mina-devnet_3.0.0.tar.gz",
+            "/var/storagebox/123/debians/bullseye/mina-test-executive_3.0.0.deb",
+            "mina-devnet_3.0.0.deb",
+        ]);
+        assert!(derive_build_targets(
+            &listing,
+            &[Artifact::MinaDaemon],
+            &strings(&["devnet"]),
+            &strings(&["bullseye"]),
+            None,
+        )
+        .is_empty());
+    }
+}
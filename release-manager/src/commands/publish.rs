@@ -1,21 +1,52 @@
+use crate::artifactory::{ArtifactoryConfig, ArtifactoryPublisher};
 use crate::artifacts::{
-    calculate_debian_version, calculate_docker_tag, get_artifact_with_suffix, parse_artifact_list,
-    parse_string_list, Artifact,
+    calculate_debian_version, calculate_docker_tag_in, check_network_in_name, docker_codename,
+    get_debian_package_name, get_docker_image_name, get_suffix, parse_artifact_list,
+    parse_artifact_versions, parse_string_list, print_no_docker_image, target_repo, version_for,
+    Artifact,
 };
-use crate::cli::PublishArgs;
-use crate::debian_publish::publish_debian_package;
-use crate::docker_promote::promote_docker_image;
-use crate::errors::ManagerResult;
+use crate::cache_lock::CacheLock;
+use crate::cli::{PublishArgs, DEFAULT_NETWORKS};
+use crate::commands::names::{resolve_names, NamesOptions};
+use crate::commands::validate::{parse_packages_file, repo_base, PRESENCE_INDEX_ARCH};
+use crate::debian_publish::{
+    prune_debian_package, publish_debian_package, run_staged, staging_component,
+    swap_staged_debian_package, DebianPublisherKind, LockMode,
+};
+use crate::docker_promote::{parse_platforms, promote_docker_image, resolve_digest};
+use crate::errors::{ManagerError, ManagerResult};
+use crate::local_mirror::{
+    prune_local_mirror_package, publish_to_local_mirror, read_component_indexes,
+    verify_local_mirror_package,
+};
+use crate::metrics::{self, Counter};
+use crate::output::{textln, Reporter};
 use crate::reversion::reversion_debian_package;
-use crate::storage::{get_cached_debian_or_download, StorageBackend, StorageClient};
+use crate::run_manifest::RunManifest;
+use crate::sbom::{attach_docker_sbom, find_sbom, publish_debian_sbom};
+use crate::storage::{
+    find_stored_debian, get_cached_debian_or_download, StorageBackend, StorageClient,
+};
 use crate::utils::{
-    get_debian_cache_folder, print_operation_info, validate_backend, validate_required_args,
+    get_debian_cache_folder, print_operation_info, resolve_version_arg, validate_backend,
+    validate_required_args,
+};
+use crate::verification::{
+    verify_debian_package, verify_docker_image, DebianVerifyConfig, VerifyManifest,
 };
-use crate::verification::{verify_debian_package, verify_docker_image};
 use colored::*;
+use futures::stream::{self, StreamExt};
+use std::cell::Cell;
+use std::collections::HashSet;
 use std::env;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub async fn execute(mut args: PublishArgs, reporter: &Reporter) -> ManagerResult<()> {
+    args.source_version = resolve_version_arg("source-version", &args.source_version)?;
+    args.target_version = resolve_version_arg("target-version", &args.target_version)?;
 
-pub async fn execute(args: PublishArgs) -> ManagerResult<()> {
     // Validate required arguments
     validate_required_args(&[
         ("target-version", Some(&args.target_version)),
@@ -25,20 +56,56 @@ pub async fn execute(args: PublishArgs) -> ManagerResult<()> {
     ])?;
 
     validate_backend(&args.backend)?;
+    if !args.only_dockers {
+        check_unlocked_parallelism(args.parallel, args.no_lock)?;
+    }
 
     // Parse lists
     let artifacts = parse_artifact_list(&args.artifacts)?;
+    let artifact_versions =
+        parse_artifact_versions(args.artifact_versions.as_deref().unwrap_or(""), &artifacts)?;
     let networks = parse_string_list(&args.networks);
+    let platforms = args
+        .platforms
+        .as_deref()
+        .map(parse_platforms)
+        .transpose()?
+        .unwrap_or_default();
     let codenames = parse_string_list(&args.codenames);
 
     // Print operation info
     let publish_to_docker_io_str = args.publish_to_docker_io.to_string();
+    let use_skopeo_str = args.use_skopeo.to_string();
+    let pin_digest_str = args.pin_digest.to_string();
     let only_dockers_str = args.only_dockers.to_string();
     let only_debians_str = args.only_debians.to_string();
     let verify_str = args.verify.to_string();
+    let verify_changelog_str = args.verify_changelog.to_string();
     let dry_run_str = args.dry_run.to_string();
     let strip_network_str = args.strip_network_from_archive.to_string();
     let debian_sign_key_str = args.debian_sign_key.as_deref().unwrap_or("");
+    let no_lock_str = args.no_lock.to_string();
+    let staged_str = args.staged.to_string();
+    let auto_fix_manifests_str = args.auto_fix_manifests.to_string();
+    let max_download_size_str = args
+        .max_download_size
+        .map(|bytes| format!("{} bytes", bytes))
+        .unwrap_or_default();
+    let keep_versions_str = args
+        .keep_versions
+        .map(|n| n.to_string())
+        .unwrap_or_default();
+    let lock_timeout_str = args
+        .lock_timeout
+        .map(|secs| format!("{}s", secs))
+        .unwrap_or_default();
+    let cache_lock_timeout_str = format!("{}s", args.cache_lock_timeout);
+    let parallel_str = args.parallel.to_string();
+    let only_missing_str = args.only_missing.to_string();
+    let sbom_dir_str = args.sbom_dir.as_deref().unwrap_or("");
+    let stream_str = args.stream.to_string();
+    let daemon_debian_name_str = args.daemon_debian_name.as_deref().unwrap_or("");
+    let emit_manifest_str = args.emit_manifest.as_deref().unwrap_or("");
 
     let params = vec![
         ("Publishing artifacts", args.artifacts.as_str()),
@@ -46,17 +113,50 @@ pub async fn execute(args: PublishArgs) -> ManagerResult<()> {
         ("Buildkite build id", args.buildkite_build_id.as_str()),
         ("Source version", args.source_version.as_str()),
         ("Target version", args.target_version.as_str()),
+        (
+            "Artifact versions",
+            args.artifact_versions.as_deref().unwrap_or(""),
+        ),
         ("Publishing codenames", args.codenames.as_str()),
         ("Target channel", args.channel.as_str()),
         ("Publish to docker.io", publish_to_docker_io_str.as_str()),
+        (
+            "Target registry",
+            args.target_registry.as_deref().unwrap_or(""),
+        ),
+        ("Use skopeo", use_skopeo_str.as_str()),
+        ("Platforms", args.platforms.as_deref().unwrap_or("")),
+        ("Pin digest", pin_digest_str.as_str()),
         ("Only dockers", only_dockers_str.as_str()),
         ("Only debians", only_debians_str.as_str()),
         ("Verify", verify_str.as_str()),
+        ("Verify changelog", verify_changelog_str.as_str()),
+        (
+            "Expected version",
+            args.expected_version.as_deref().unwrap_or(""),
+        ),
         ("Dry run", dry_run_str.as_str()),
         ("Backend", args.backend.as_str()),
         ("Debian repo", args.debian_repo.as_str()),
+        ("S3 region", args.s3_region.as_str()),
         ("Debian sign key", debian_sign_key_str),
+        ("Debian suite", args.debian_suite.as_deref().unwrap_or("")),
+        ("No lock", no_lock_str.as_str()),
+        ("Lock timeout", lock_timeout_str.as_str()),
+        ("Cache lock timeout", cache_lock_timeout_str.as_str()),
+        ("Parallel", parallel_str.as_str()),
+        ("Only missing", only_missing_str.as_str()),
+        ("Staged", staged_str.as_str()),
+        ("Auto fix manifests", auto_fix_manifests_str.as_str()),
+        ("Max download size", max_download_size_str.as_str()),
+        ("Keep versions", keep_versions_str.as_str()),
+        ("Local mirror", args.local_mirror.as_deref().unwrap_or("")),
+        ("Debian publisher", args.debian_publisher.as_str()),
+        ("Stream", stream_str.as_str()),
         ("Strip network from archive", strip_network_str.as_str()),
+        ("SBOM dir", sbom_dir_str),
+        ("Daemon debian name", daemon_debian_name_str),
+        ("Emit manifest", emit_manifest_str),
     ];
 
     print_operation_info("Publishing mina artifacts", &params);
@@ -65,42 +165,174 @@ pub async fn execute(args: PublishArgs) -> ManagerResult<()> {
     let backend = StorageBackend::from_str(&args.backend)?;
     let storage = StorageClient::new(backend);
 
+    // Builds are searched in order for each debian; the first is the release build
+    let build_ids = parse_string_list(&args.buildkite_build_id);
+    if build_ids.is_empty() {
+        return Err(ManagerError::MissingParameter(
+            "buildkite-build-id".to_string(),
+        ));
+    }
+    let mut sources: Vec<DebianBuildSource> = Vec::new();
+    let mut digests: Vec<(String, String)> = Vec::new();
+
     // Set environment variable for buildkite build id
-    env::set_var("BUILDKITE_BUILD_ID", &args.buildkite_build_id);
+    env::set_var("BUILDKITE_BUILD_ID", &build_ids[0]);
 
     let cache_folder = get_debian_cache_folder();
     tokio::fs::create_dir_all(&cache_folder).await?;
 
-    // Process each artifact
+    let sbom_dir = args.sbom_dir.as_deref().map(Path::new);
+    let debian_options = DebianOptions {
+        lock: LockMode::from_args(args.no_lock, args.lock_timeout),
+        staged: args.staged,
+        max_download_size: args.max_download_size,
+        keep_versions: args.keep_versions,
+        local_mirror: local_mirror_dir(&args)?,
+        artifactory: artifactory_config(&args)?,
+        stream: args.stream,
+        suite: args.debian_suite.clone(),
+        known_networks: known_networks(&networks),
+        verify_changelog: args.verify_changelog,
+        expected_version: args.expected_version.clone(),
+        auto_fix_manifests: args.auto_fix_manifests,
+        cache_lock_timeout: Duration::from_secs(args.cache_lock_timeout),
+        s3_region: args.s3_region.clone(),
+        lockfile_max_age_secs: args.lockfile_max_age_secs,
+    };
+
+    // Created up front so concurrent units never race on creating them
+    for codename in &codenames {
+        tokio::fs::create_dir_all(cache_folder.join(codename)).await?;
+    }
+
     for artifact in &artifacts {
-        for codename in &codenames {
+        if !args.only_debians && !artifact.has_docker_image() {
+            print_no_docker_image(artifact.as_str());
+        }
+    }
+
+    let mut units = publish_units(
+        &artifacts,
+        &codenames,
+        &networks,
+        args.only_debians,
+        args.only_dockers,
+        args.strip_network_from_archive,
+    );
+    if args.only_missing {
+        let targets: Vec<UnitTarget> = units
+            .iter()
+            .map(|unit| {
+                unit_target(
+                    unit,
+                    &artifact_versions,
+                    &args.target_version,
+                    &target_repo(args.target_registry.as_deref(), args.publish_to_docker_io),
+                    args.daemon_debian_name.as_deref(),
+                )
+            })
+            .collect();
+        let inventory = Inventory::load(&args, &codenames, &targets).await?;
+        let mut targets = targets.into_iter();
+        units.retain(|_| {
+            let target = targets.next().expect("one target per unit");
+            let published = inventory.has(&target);
+            if published {
+                textln!(" ⏭️  Skipping {}: already published", target);
+            }
+            !published
+        });
+        textln!();
+    }
+    let context = PublishContext {
+        storage: &storage,
+        args: &args,
+        artifact_versions: &artifact_versions,
+        build_ids: &build_ids,
+        sbom_dir,
+        debian_options: &debian_options,
+        platforms: &platforms,
+        reporter,
+    };
+    let outputs = run_units(units, args.parallel, |unit| run_unit(unit, &context)).await?;
+    for output in outputs {
+        sources.extend(output.sources);
+        digests.extend(output.digests);
+    }
+
+    if build_ids.len() > 1 && !sources.is_empty() {
+        textln!(" 🏗️  Debian packages by source build:");
+        for source in &sources {
+            textln!(
+                "    {}/{}: {}",
+                source.codename,
+                source.package,
+                source.build_id
+            );
+        }
+    }
+
+    if !digests.is_empty() {
+        textln!(" 🔏 Promoted docker digests:");
+        for (reference, digest) in &digests {
+            textln!("    {} {}", reference, digest);
+        }
+    }
+
+    if let Some(path) = &args.emit_manifest {
+        let mut manifest =
+            publish_manifest(&args, &artifacts, &artifact_versions, &networks, &codenames);
+        manifest.record_digests(
+            digests
+                .iter()
+                .map(|(reference, digest)| (reference.as_str(), digest.as_str())),
+        );
+        manifest.write(path)?;
+    }
+
+    textln!("{}", " ✅  Publishing done.".green());
+    Ok(())
+}
+
+/// One debian upload or docker promotion. No unit depends on another's
+/// outcome, which is what lets `--parallel` run several at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PublishUnit<'a> {
+    Debian {
+        artifact: Artifact,
+        codename: &'a str,
+        network: Option<&'a str>,
+        new_name: Option<&'static str>,
+    },
+    Docker {
+        artifact: Artifact,
+        codename: &'a str,
+        network: &'a str,
+    },
+}
+
+/// Every unit of the run, in the order they run sequentially
+fn publish_units<'a>(
+    artifacts: &[Artifact],
+    codenames: &'a [String],
+    networks: &'a [String],
+    only_debians: bool,
+    only_dockers: bool,
+    strip_network_from_archive: bool,
+) -> Vec<PublishUnit<'a>> {
+    let mut units = Vec::new();
+    for &artifact in artifacts {
+        let has_docker = artifact.has_docker_image();
+        for codename in codenames {
             match artifact {
                 Artifact::MinaLogproc | Artifact::Minimina => {
-                    if !args.only_dockers {
-                        publish_debian(
-                            &storage,
-                            artifact.as_str(),
+                    if !only_dockers {
+                        units.push(PublishUnit::Debian {
+                            artifact,
                             codename,
-                            &args.source_version,
-                            &args.target_version,
-                            &args.channel,
-                            None,
-                            args.verify,
-                            args.dry_run,
-                            &args.debian_repo,
-                            args.debian_sign_key.as_deref(),
-                            None,
-                            &args.buildkite_build_id,
-                            args.debug,
-                        )
-                        .await?;
-                    }
-
-                    if !args.only_debians {
-                        println!(
-                            "ℹ️  There is no {} docker image to publish. skipping",
-                            artifact.as_str()
-                        );
+                            network: None,
+                            new_name: None,
+                        });
                     }
                 }
 
@@ -110,165 +342,603 @@ pub async fn execute(args: PublishArgs) -> ManagerResult<()> {
                 | Artifact::MinaPostfork
                 | Artifact::MinaPostforkMesa
                 | Artifact::MinaPreforkMesa => {
-                    for network in &networks {
-                        if !args.only_dockers {
-                            publish_debian(
-                                &storage,
-                                artifact.as_str(),
+                    if !only_dockers {
+                        for network in networks {
+                            units.push(PublishUnit::Debian {
+                                artifact,
                                 codename,
-                                &args.source_version,
-                                &args.target_version,
-                                &args.channel,
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                None,
-                                &args.buildkite_build_id,
-                                args.debug,
-                            )
-                            .await?;
-                        }
-                        if !args.only_debians {
-                            println!(
-                                "ℹ️  There is no {} docker image to publish. skipping",
-                                artifact.as_str()
-                            );
-                        }
-                    }
-                }
-
-                Artifact::MinaGeneric | Artifact::RosettaGeneric => {
-                    for network in &networks {
-                        if !args.only_dockers {
-                            publish_debian(
-                                &storage,
-                                artifact.as_str(),
-                                codename,
-                                &args.source_version,
-                                &args.target_version,
-                                &args.channel,
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                None,
-                                &args.buildkite_build_id,
-                                args.debug,
-                            )
-                            .await?;
-                        }
-
-                        if !args.only_debians {
-                            // promote_and_verify_docker uses calculate_docker_tag,
-                            // which applies the get_docker_image_name mapping for
-                            // mina-generic / rosetta-generic.
-                            promote_and_verify_docker(
-                                artifact.as_str(),
-                                &args.source_version,
-                                &args.target_version,
-                                codename,
-                                network,
-                                args.publish_to_docker_io,
-                                args.verify,
-                                args.dry_run,
-                            )
-                            .await?;
+                                network: Some(network),
+                                new_name: None,
+                            });
                         }
                     }
                 }
 
-                Artifact::MinaArchive => {
-                    for network in &networks {
-                        let new_name = if args.strip_network_from_archive {
+                // Dockers for mina-generic / rosetta-generic are tagged through
+                // calculate_docker_tag, which applies the get_docker_image_name mapping
+                Artifact::MinaGeneric
+                | Artifact::RosettaGeneric
+                | Artifact::MinaArchive
+                | Artifact::MinaRosetta
+                | Artifact::MinaDaemon => {
+                    let new_name =
+                        if artifact == Artifact::MinaArchive && strip_network_from_archive {
                             Some("mina-archive")
                         } else {
                             None
                         };
-
-                        if !args.only_dockers {
-                            publish_debian(
-                                &storage,
-                                artifact.as_str(),
+                    for network in networks {
+                        if !only_dockers {
+                            units.push(PublishUnit::Debian {
+                                artifact,
                                 codename,
-                                &args.source_version,
-                                &args.target_version,
-                                &args.channel,
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
+                                network: Some(network),
                                 new_name,
-                                &args.buildkite_build_id,
-                                args.debug,
-                            )
-                            .await?;
+                            });
                         }
-
-                        if !args.only_debians {
-                            promote_and_verify_docker(
-                                artifact.as_str(),
-                                &args.source_version,
-                                &args.target_version,
+                        if !only_debians && has_docker {
+                            units.push(PublishUnit::Docker {
+                                artifact,
                                 codename,
                                 network,
-                                args.publish_to_docker_io,
-                                args.verify,
-                                args.dry_run,
-                            )
-                            .await?;
+                            });
                         }
                     }
                 }
+            }
+        }
+    }
+    units
+}
 
-                Artifact::MinaRosetta | Artifact::MinaDaemon => {
-                    for network in &networks {
-                        if !args.only_dockers {
-                            publish_debian(
-                                &storage,
-                                artifact.as_str(),
-                                codename,
-                                &args.source_version,
-                                &args.target_version,
-                                &args.channel,
-                                Some(network),
-                                args.verify,
-                                args.dry_run,
-                                &args.debian_repo,
-                                args.debian_sign_key.as_deref(),
-                                None,
-                                &args.buildkite_build_id,
-                                args.debug,
-                            )
-                            .await?;
-                        }
+/// What a unit publishes, as `--only-missing` looks for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UnitTarget {
+    Debian {
+        codename: String,
+        package: String,
+        version: String,
+    },
+    Docker {
+        tag: String,
+    },
+}
 
-                        if !args.only_debians {
-                            promote_and_verify_docker(
-                                artifact.as_str(),
-                                &args.source_version,
-                                &args.target_version,
-                                codename,
-                                network,
-                                args.publish_to_docker_io,
-                                args.verify,
-                                args.dry_run,
-                            )
-                            .await?;
-                        }
-                    }
+impl std::fmt::Display for UnitTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitTarget::Debian {
+                codename,
+                package,
+                version,
+            } => write!(f, "{}={} debian for {}", package, version, codename),
+            UnitTarget::Docker { tag } => write!(f, "{} docker", tag),
+        }
+    }
+}
+
+/// The debian package and version or docker tag `unit` publishes, named
+/// the way [`publish_debian`] and [`promote_and_verify_docker`] name them
+fn unit_target(
+    unit: &PublishUnit<'_>,
+    artifact_versions: &[(Artifact, String)],
+    target_version: &str,
+    target_repo: &str,
+    daemon_debian_name: Option<&str>,
+) -> UnitTarget {
+    match *unit {
+        PublishUnit::Debian {
+            artifact,
+            codename,
+            network,
+            new_name,
+        } => UnitTarget::Debian {
+            codename: codename.to_string(),
+            package: new_name.map(str::to_string).unwrap_or_else(|| {
+                get_debian_package_name(artifact.as_str(), network, None, daemon_debian_name)
+            }),
+            version: version_for(artifact_versions, artifact, target_version).to_string(),
+        },
+        PublishUnit::Docker {
+            artifact,
+            codename,
+            network,
+        } => UnitTarget::Docker {
+            tag: calculate_docker_tag_in(
+                target_repo,
+                artifact.as_str(),
+                version_for(artifact_versions, artifact, target_version),
+                codename,
+                Some(network),
+                None,
+                None,
+            ),
+        },
+    }
+}
+
+/// What the target channel and registry already have, for `--only-missing`
+#[derive(Debug, Default)]
+struct Inventory {
+    /// (codename, package, version) listed in the target channel
+    debians: HashSet<(String, String, String)>,
+    /// Docker tags that resolve in their registry
+    docker_tags: HashSet<String>,
+}
+
+impl Inventory {
+    /// Read the target channel's Packages index of every codename with a
+    /// debian unit, and look up every docker unit's tag. An index or tag
+    /// that can't be read counts as empty, so its units get published.
+    async fn load(
+        args: &PublishArgs,
+        codenames: &[String],
+        targets: &[UnitTarget],
+    ) -> ManagerResult<Self> {
+        let mut inventory = Inventory::default();
+        let http = reqwest::Client::new();
+        for codename in codenames {
+            let has_debians = targets
+                .iter()
+                .any(|t| matches!(t, UnitTarget::Debian { codename: c, .. } if c == codename));
+            if !has_debians {
+                continue;
+            }
+            let index = match &args.local_mirror {
+                Some(mirror) => read_component_indexes(Path::new(mirror), codename, &args.channel)?,
+                None => read_channel_index(&http, &args.debian_repo, codename, &args.channel).await,
+            };
+            inventory.add_index(codename, &index);
+        }
+        for target in targets {
+            if let UnitTarget::Docker { tag } = target {
+                if resolve_digest(tag).await.is_some() {
+                    inventory.docker_tags.insert(tag.clone());
                 }
             }
         }
+        Ok(inventory)
+    }
+
+    fn add_index(&mut self, codename: &str, index: &str) {
+        for entry in parse_packages_file(index) {
+            if let (Some(package), Some(version)) = (entry.package, entry.version) {
+                self.debians
+                    .insert((codename.to_string(), package, version));
+            }
+        }
     }
 
-    println!("{}", " ✅  Publishing done.".green());
+    /// Whether `target` is already published
+    fn has(&self, target: &UnitTarget) -> bool {
+        match target {
+            UnitTarget::Debian {
+                codename,
+                package,
+                version,
+            } => self
+                .debians
+                .contains(&(codename.clone(), package.clone(), version.clone())),
+            UnitTarget::Docker { tag } => self.docker_tags.contains(tag),
+        }
+    }
+}
+
+/// The `channel` Packages index of `codename`, empty when it can't be read
+async fn read_channel_index(
+    http: &reqwest::Client,
+    debian_repo: &str,
+    codename: &str,
+    channel: &str,
+) -> String {
+    let index_url = format!(
+        "{}/dists/{}/{}/binary-{}/Packages",
+        repo_base(debian_repo),
+        codename,
+        channel,
+        PRESENCE_INDEX_ARCH
+    );
+    let index = match http.get(&index_url).send().await {
+        Ok(resp) if resp.status().is_success() => resp.text().await.map_err(|e| e.to_string()),
+        Ok(resp) => Err(format!("HTTP {}", resp.status())),
+        Err(e) => Err(e.to_string()),
+    };
+    index.unwrap_or_else(|reason| {
+        textln!(
+            "    ⚠️  Warning: could not read {} ({}), treating it as empty and publishing every {} debian",
+            index_url, reason, codename
+        );
+        String::new()
+    })
+}
+
+/// What every unit of a run shares
+struct PublishContext<'a> {
+    storage: &'a StorageClient,
+    args: &'a PublishArgs,
+    artifact_versions: &'a [(Artifact, String)],
+    build_ids: &'a [String],
+    sbom_dir: Option<&'a Path>,
+    debian_options: &'a DebianOptions,
+    /// Platforms of multi-arch docker images to copy; all when empty
+    platforms: &'a [String],
+    reporter: &'a Reporter,
+}
+
+/// What a unit reports back for the end-of-run summary and manifest
+#[derive(Debug, Default)]
+struct UnitOutput {
+    sources: Vec<DebianBuildSource>,
+    digests: Vec<(String, String)>,
+}
+
+async fn run_unit(
+    unit: PublishUnit<'_>,
+    context: &PublishContext<'_>,
+) -> ManagerResult<UnitOutput> {
+    let args = context.args;
+    let mut output = UnitOutput::default();
+    match unit {
+        PublishUnit::Debian {
+            artifact,
+            codename,
+            network,
+            new_name,
+        } => {
+            let target = match network {
+                Some(network) => format!("debian {} {}", codename, network),
+                None => format!("debian {}", codename),
+            };
+            let publish = publish_debian(
+                context.storage,
+                artifact.as_str(),
+                codename,
+                &args.source_version,
+                version_for(context.artifact_versions, artifact, &args.target_version),
+                &args.channel,
+                network,
+                args.verify,
+                args.dry_run,
+                &args.debian_repo,
+                args.debian_sign_key.as_deref(),
+                new_name,
+                context.build_ids,
+                args.debug,
+                context.sbom_dir,
+                args.daemon_debian_name.as_deref(),
+                context.debian_options,
+                &mut output.sources,
+            );
+            context
+                .reporter
+                .track_sbom(artifact.as_str(), &target, publish)
+                .await?;
+        }
+        PublishUnit::Docker {
+            artifact,
+            codename,
+            network,
+        } => {
+            let target = format!("docker {} {}", codename, network);
+            let repo = target_repo(args.target_registry.as_deref(), args.publish_to_docker_io);
+            let promote = promote_and_verify_docker(
+                artifact.as_str(),
+                &args.source_version,
+                version_for(context.artifact_versions, artifact, &args.target_version),
+                codename,
+                network,
+                &repo,
+                args.use_skopeo,
+                context.platforms,
+                args.pin_digest,
+                args.verify,
+                args.dry_run,
+                context.sbom_dir,
+                &context.debian_options.known_networks,
+                &mut output.digests,
+            );
+            context
+                .reporter
+                .track_sbom(artifact.as_str(), &target, promote)
+                .await?;
+        }
+    }
+    Ok(output)
+}
+
+/// `--no-lock` leaves deb-s3 uploads unserialized, so with `--parallel` above 1
+/// concurrent uploads would overwrite each other's manifest changes
+fn check_unlocked_parallelism(parallel: usize, no_lock: bool) -> ManagerResult<()> {
+    if no_lock && parallel > 1 {
+        return Err(ManagerError::ValidationError(format!(
+            "--no-lock can't be combined with --parallel {}: unlocked deb-s3 uploads running at once corrupt the repository manifest",
+            parallel
+        )));
+    }
     Ok(())
 }
 
+/// Run `run` over `units`, at most `limit` at a time, returning the outputs
+/// in unit order. Once a unit fails no further unit is started; the ones
+/// already running finish and the first error in unit order is returned.
+async fn run_units<U, O, F, Fut>(units: Vec<U>, limit: usize, run: F) -> ManagerResult<Vec<O>>
+where
+    F: Fn(U) -> Fut,
+    Fut: Future<Output = ManagerResult<O>>,
+{
+    let failed = Cell::new(false);
+    let mut results: Vec<(usize, Option<ManagerResult<O>>)> =
+        stream::iter(units.into_iter().enumerate())
+            .map(|(index, unit)| {
+                let failed = &failed;
+                let future = run(unit);
+                async move {
+                    if failed.get() {
+                        return (index, None);
+                    }
+                    let result = future.await;
+                    if result.is_err() {
+                        failed.set(true);
+                    }
+                    (index, Some(result))
+                }
+            })
+            .buffer_unordered(limit.max(1))
+            .collect()
+            .await;
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut outputs = Vec::new();
+    for (_, result) in results {
+        match result {
+            Some(Ok(output)) => outputs.push(output),
+            Some(Err(e)) => return Err(e),
+            None => {}
+        }
+    }
+    Ok(outputs)
+}
+
+/// Coordinates of everything the run targets, for `--emit-manifest`
+fn publish_manifest(
+    args: &PublishArgs,
+    artifacts: &[Artifact],
+    artifact_versions: &[(Artifact, String)],
+    networks: &[String],
+    codenames: &[String],
+) -> RunManifest {
+    let mut manifest = RunManifest::new("publish", args.dry_run);
+    for artifact in artifacts {
+        let options = NamesOptions {
+            version: version_for(artifact_versions, *artifact, &args.target_version),
+            channel: &args.channel,
+            profile: None,
+            arch: None,
+            docker_io: args.publish_to_docker_io,
+            target_registry: args.target_registry.as_deref(),
+            daemon_debian_name: args.daemon_debian_name.as_deref(),
+        };
+        let names = resolve_names(
+            std::slice::from_ref(artifact),
+            networks,
+            codenames,
+            &options,
+        );
+        manifest.add(&names, &options, !args.only_dockers, !args.only_debians);
+    }
+    if args.strip_network_from_archive {
+        manifest.strip_network_from_archive();
+    }
+    manifest
+}
+
+/// Which of the `--buildkite-build-id` builds supplied a published debian
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DebianBuildSource {
+    package: String,
+    codename: String,
+    build_id: String,
+}
+
+/// Debian publishing knobs that apply to every package in a run
+struct DebianOptions {
+    lock: LockMode,
+    staged: bool,
+    max_download_size: Option<u64>,
+    keep_versions: Option<usize>,
+    /// Publish to a local repository directory instead of S3
+    local_mirror: Option<PathBuf>,
+    /// Publish to an Artifactory repository instead of S3
+    artifactory: Option<ArtifactoryConfig>,
+    /// Read debians in place from storage when possible (see [`debian_source`])
+    stream: bool,
+    /// Release file suite, when not the channel
+    suite: Option<String>,
+    /// Every network a name could encode, for the cross-network bleed check
+    known_networks: Vec<String>,
+    /// Check the installed changelog when verifying
+    verify_changelog: bool,
+    /// What the binaries' `--version` output must contain when verifying
+    expected_version: Option<String>,
+    /// Repair an inconsistent manifest once when deb-s3 verify fails on it
+    auto_fix_manifests: bool,
+    /// Longest wait for another run's lock on a codename's cache
+    cache_lock_timeout: Duration,
+    /// AWS region of the debian repository bucket
+    s3_region: String,
+    /// Age past which a leftover deb-s3 lockfile is removed as stale
+    lockfile_max_age_secs: u64,
+}
+
+/// Artifactory settings when `--debian-publisher artifactory`, `None` for deb-s3
+fn artifactory_config(args: &PublishArgs) -> ManagerResult<Option<ArtifactoryConfig>> {
+    match args.debian_publisher {
+        DebianPublisherKind::DebS3 => Ok(None),
+        DebianPublisherKind::Artifactory => {
+            let unsupported = [
+                ("--local-mirror", args.local_mirror.is_some()),
+                ("--staged", args.staged),
+                ("--keep-versions", args.keep_versions.is_some()),
+            ];
+            if let Some((flag, _)) = unsupported.iter().find(|(_, set)| *set) {
+                return Err(ManagerError::ValidationError(format!(
+                    "{} is not supported with --debian-publisher artifactory",
+                    flag
+                )));
+            }
+            ArtifactoryConfig::from_args(
+                args.artifactory_url.as_deref(),
+                args.artifactory_repo.as_deref(),
+            )
+            .map(Some)
+        }
+    }
+}
+
+/// `--local-mirror`'s directory, refusing flags that only apply to S3
+/// repositories instead of ignoring them
+fn local_mirror_dir(args: &PublishArgs) -> ManagerResult<Option<PathBuf>> {
+    let Some(mirror) = &args.local_mirror else {
+        return Ok(None);
+    };
+    let unsupported = [
+        ("--staged", args.staged),
+        ("--debian-sign-key", args.debian_sign_key.is_some()),
+    ];
+    if let Some((flag, _)) = unsupported.iter().find(|(_, set)| *set) {
+        return Err(ManagerError::ValidationError(format!(
+            "{} is not supported with --local-mirror",
+            flag
+        )));
+    }
+    Ok(Some(PathBuf::from(mirror)))
+}
+
+/// Networks being published plus the defaults, so a name encoding a network
+/// outside `--networks` is still caught.
+fn known_networks(networks: &[String]) -> Vec<String> {
+    let mut known = networks.to_vec();
+    for network in parse_string_list(DEFAULT_NETWORKS) {
+        if !known.contains(&network) {
+            known.push(network);
+        }
+    }
+    known
+}
+
+/// Where `publish_debian` takes the `.deb` from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebianSource {
+    /// Download into the local debian cache first (the default)
+    Cache,
+    /// Hand the stored file to the publisher directly
+    Storage,
+}
+
+/// The storage fast path only applies when the stored bytes are published
+/// unchanged (same version and package name, so no reversion) and the
+/// backend exposes them as local files.
+fn debian_source(
+    stream: bool,
+    backend: &StorageBackend,
+    source_version: &str,
+    target_version: &str,
+    renamed: bool,
+) -> DebianSource {
+    if stream && backend.is_locally_addressable() && source_version == target_version && !renamed {
+        DebianSource::Storage
+    } else {
+        DebianSource::Cache
+    }
+}
+
+/// Directory a cached debian is reversioned into. Renamed packages get a
+/// subdirectory named after their source package: with
+/// `--strip-network-from-archive` every network's archive becomes
+/// `mina-archive_<version>.deb` and would otherwise overwrite each other.
+fn reversion_output_dir(
+    codename_cache: &Path,
+    artifact_full_name: &str,
+    new_name: &str,
+) -> PathBuf {
+    if new_name == artifact_full_name {
+        codename_cache.to_path_buf()
+    } else {
+        codename_cache.join(artifact_full_name)
+    }
+}
+
+/// Find the cached `.deb` of `artifact_full_name` and reversion/rename it
+/// when the target differs, returning the file to publish.
+async fn prepare_cached_debian(
+    codename_cache: &Path,
+    artifact_full_name: &str,
+    new_name: &str,
+    source_version: &str,
+    target_version: &str,
+    channel: &str,
+) -> ManagerResult<PathBuf> {
+    let output_dir = reversion_output_dir(codename_cache, artifact_full_name, new_name);
+    if source_version == target_version && new_name == artifact_full_name {
+        return Ok(output_dir.join(format!("{}_{}.deb", new_name, target_version)));
+    }
+
+    textln!(
+        " 🗃️  Rebuilding {} debian from {} to {}",
+        artifact_full_name,
+        source_version,
+        target_version
+    );
+
+    // Find the actual .deb file that matches the pattern
+    let mut entries = tokio::fs::read_dir(codename_cache).await?;
+    let mut found_deb_path = None;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
+            if filename.starts_with(&format!("{}_", artifact_full_name))
+                && filename.ends_with(".deb")
+            {
+                found_deb_path = Some(path);
+                break;
+            }
+        }
+    }
+
+    let actual_deb_path = found_deb_path.ok_or_else(|| {
+        crate::errors::ManagerError::IoError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!(
+                "Could not find .deb file matching pattern: {}_*.deb in {}",
+                artifact_full_name,
+                codename_cache.display()
+            ),
+        ))
+    })?;
+
+    // Use Rust reversion implementation
+    let new_deb_path = reversion_debian_package(
+        &actual_deb_path,
+        artifact_full_name,
+        source_version,
+        target_version,
+        "unstable",
+        channel,
+        Some(new_name),
+        Some(&output_dir),
+        None,
+        // A regenerated cache file; networks already get their own directory
+        true,
+        false,
+    )
+    .await?;
+
+    textln!(" ✅ Debian package reversioned: {}", new_deb_path.display());
+    Ok(new_deb_path)
+}
+
+/// Publish one debian package, returning where its SBOM was uploaded, if any
+#[allow(clippy::too_many_arguments)]
 async fn publish_debian(
     storage: &StorageClient,
     artifact: &str,
@@ -282,183 +952,762 @@ async fn publish_debian(
     debian_repo: &str,
     debian_sign_key: Option<&str>,
     new_artifact_name: Option<&str>,
-    buildkite_build_id: &str,
+    build_ids: &[String],
     debug: bool,
-) -> ManagerResult<()> {
-    // Download the debian package to cache
-    let cache_folder = get_debian_cache_folder();
-    get_cached_debian_or_download(
-        storage,
-        artifact,
-        codename,
-        network,
-        buildkite_build_id,
-        &cache_folder,
-    )
-    .await?;
-
-    let artifact_full_name = get_artifact_with_suffix(artifact, network, None);
-
+    sbom_dir: Option<&Path>,
+    daemon_debian_name: Option<&str>,
+    options: &DebianOptions,
+    sources: &mut Vec<DebianBuildSource>,
+) -> ManagerResult<Option<String>> {
+    let artifact_full_name = get_debian_package_name(artifact, network, None, daemon_debian_name);
     let new_name = new_artifact_name.unwrap_or(&artifact_full_name);
+    check_network_in_name(
+        "Debian package",
+        new_name,
+        network,
+        &options.known_networks,
+        new_artifact_name.is_none()
+            && daemon_debian_name.is_none()
+            && !get_suffix(artifact, network, None).is_empty(),
+    )?;
+    let cache_folder = get_debian_cache_folder();
 
-    // Build reversion command if needed
-    if source_version != target_version {
-        println!(
-            " 🗃️  Rebuilding {} debian from {} to {}",
-            artifact, source_version, target_version
-        );
-
-        // Find the actual .deb file that matches the pattern
-        let deb_files = tokio::fs::read_dir(cache_folder.join(codename)).await?;
-        let mut found_deb_path = None;
-
-        let mut entries = deb_files;
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                if filename.starts_with(&format!("{}_", artifact_full_name))
-                    && filename.ends_with(".deb")
-                {
-                    found_deb_path = Some(path);
-                    break;
-                }
-            }
+    let source = debian_source(
+        options.stream,
+        &storage.backend,
+        source_version,
+        target_version,
+        new_name != artifact_full_name,
+    );
+    // Held until the package is published, since that reads the reversioned
+    // file other runs would otherwise overwrite. Keyed by package, so other
+    // packages of the codename publish alongside it under --parallel.
+    let mut _cache_lock = None;
+    let (stored_deb, build_id) = match source {
+        DebianSource::Storage => {
+            let (path, build_id) = find_stored_debian(
+                storage,
+                &artifact_full_name,
+                target_version,
+                codename,
+                build_ids,
+            )
+            .await?;
+            textln!(
+                " ⏩ Publishing {} in place, skipping the cache",
+                path.display()
+            );
+            (Some(path), build_id)
         }
+        DebianSource::Cache => {
+            _cache_lock = Some(
+                CacheLock::acquire(
+                    &cache_folder,
+                    codename,
+                    &artifact_full_name,
+                    options.cache_lock_timeout,
+                )
+                .await?,
+            );
+            // Download the debian package to cache
+            let build_id = get_cached_debian_or_download(
+                storage,
+                &artifact_full_name,
+                codename,
+                build_ids,
+                &cache_folder,
+                options.max_download_size,
+            )
+            .await?;
+            (None, build_id)
+        }
+    };
+    sources.push(DebianBuildSource {
+        package: new_name.to_string(),
+        codename: codename.to_string(),
+        build_id: build_id.clone(),
+    });
 
-        let actual_deb_path = found_deb_path.ok_or_else(|| {
-            crate::errors::ManagerError::IoError(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!(
-                    "Could not find .deb file matching pattern: {}_*.deb in {}",
-                    artifact_full_name,
-                    cache_folder.join(codename).display()
-                ),
-            ))
-        })?;
-
-        // Use Rust reversion implementation
-        let new_deb_path = reversion_debian_package(
-            &actual_deb_path,
-            &artifact_full_name,
-            source_version,
-            target_version,
-            "unstable",
-            channel,
-            Some(new_name),
-        )
-        .await?;
-
-        println!(" ✅ Debian package reversioned: {}", new_deb_path.display());
-    }
+    let package_path = match stored_deb {
+        Some(path) => path,
+        None => {
+            prepare_cached_debian(
+                &cache_folder.join(codename),
+                &artifact_full_name,
+                new_name,
+                source_version,
+                target_version,
+                channel,
+            )
+            .await?
+        }
+    };
 
-    println!(
+    textln!(
         " 🍥  Publishing {} debian to {} channel with {} version",
-        artifact, channel, target_version
+        artifact,
+        channel,
+        target_version
     );
-    println!(
+    textln!(
         "     📦  Target debian version: {}",
         calculate_debian_version(artifact, target_version, codename, network, None)
     );
 
     if !dry_run {
         // Use Rust implementation for Debian package publishing
-        let package_path = cache_folder
-            .join(codename)
-            .join(format!("{}_{}.deb", new_name, target_version));
+        if let Some(mirror) = &options.local_mirror {
+            publish_to_local_mirror(&package_path, mirror, codename, channel).await?;
+            metrics::record(Counter::Published);
+            if verify {
+                verify_local_mirror_package(mirror, codename, channel, new_name, target_version)?;
+                metrics::record(Counter::Verified);
+            }
+        } else if let Some(config) = &options.artifactory {
+            let publisher = ArtifactoryPublisher::new(config.clone())?;
+            let deployment = publisher.publish(&package_path, codename, channel).await?;
+            metrics::record(Counter::Published);
+            if verify {
+                publisher.verify(&deployment, codename, channel).await?;
+                metrics::record(Counter::Verified);
+            }
+        } else {
+            publish_and_verify_deb_s3(
+                &package_path.to_string_lossy(),
+                new_name,
+                target_version,
+                debian_repo,
+                codename,
+                channel,
+                debian_sign_key,
+                verify,
+                debug,
+                options,
+            )
+            .await?;
+        }
+
+        if let Some(keep) = options.keep_versions {
+            match &options.local_mirror {
+                Some(mirror) => {
+                    prune_local_mirror_package(mirror, codename, channel, new_name, keep)?;
+                }
+                None => {
+                    prune_debian_package(
+                        &package_path.to_string_lossy(),
+                        target_version,
+                        debian_repo,
+                        &options.s3_region,
+                        codename,
+                        channel,
+                        keep,
+                        debian_sign_key,
+                        options.lock,
+                        options.lockfile_max_age_secs,
+                        options.suite.as_deref(),
+                    )
+                    .await?;
+                }
+            }
+        }
 
+        if let Some(sbom) = sbom_dir.and_then(|dir| find_sbom(dir, new_name, target_version)) {
+            let location = publish_debian_sbom(
+                storage,
+                &sbom,
+                &build_id,
+                codename,
+                new_name,
+                target_version,
+            )
+            .await?;
+            textln!("     📜 SBOM: {}", location);
+            return Ok(Some(location));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Publish `package_path` with deb-s3 (through the staging component when
+/// `--staged`), then install it from the channel when `verify` is set
+#[allow(clippy::too_many_arguments)]
+async fn publish_and_verify_deb_s3(
+    package_path: &str,
+    new_name: &str,
+    target_version: &str,
+    debian_repo: &str,
+    codename: &str,
+    channel: &str,
+    debian_sign_key: Option<&str>,
+    verify: bool,
+    debug: bool,
+    options: &DebianOptions,
+) -> ManagerResult<()> {
+    let verify_config = |channel: &str| DebianVerifyConfig {
+        signed: debian_sign_key.is_some(),
+        suite: options.suite.clone(),
+        verify_changelog: options.verify_changelog,
+        manifest: VerifyManifest {
+            expected_version: options.expected_version.clone(),
+            ..VerifyManifest::default()
+        },
+        ..DebianVerifyConfig::new(new_name, target_version, debian_repo, codename, channel)
+    };
+    if options.staged {
+        let staging = staging_component(channel);
+        run_staged(
+            publish_debian_package(
+                package_path,
+                target_version,
+                debian_repo,
+                &options.s3_region,
+                codename,
+                &staging,
+                debian_sign_key,
+                debug,
+                options.lock,
+                options.lockfile_max_age_secs,
+                options.suite.as_deref(),
+                options.auto_fix_manifests,
+            ),
+            verify_debian_package(verify_config(&staging)),
+            swap_staged_debian_package(
+                package_path,
+                target_version,
+                debian_repo,
+                &options.s3_region,
+                codename,
+                channel,
+                debian_sign_key,
+                debug,
+                options.lock,
+                options.lockfile_max_age_secs,
+                options.suite.as_deref(),
+            ),
+        )
+        .await?;
+    } else {
         publish_debian_package(
-            &package_path.to_string_lossy(),
+            package_path,
             target_version,
             debian_repo,
+            &options.s3_region,
             codename,
             channel,
             debian_sign_key,
             debug,
+            options.lock,
+            options.lockfile_max_age_secs,
+            options.suite.as_deref(),
+            options.auto_fix_manifests,
         )
         .await?;
+    }
+    metrics::record(Counter::Published);
 
-        if verify {
-            println!(
-                "     📋 Verifying: {} debian to {} channel with {} version",
-                new_name, channel, target_version
-            );
+    if verify {
+        textln!(
+            "     📋 Verifying: {} debian to {} channel with {} version",
+            new_name,
+            channel,
+            target_version
+        );
 
-            verify_debian_package(
-                new_name,
-                target_version,
-                debian_repo,
-                codename,
-                channel,
-                debian_sign_key.is_some(),
-            )
-            .await?;
-        }
+        verify_debian_package(verify_config(channel)).await?;
     }
 
     Ok(())
 }
 
+/// Copy one docker image to `target_repo`, returning where its SBOM was
+/// attached, if any
+#[allow(clippy::too_many_arguments)]
 async fn promote_and_verify_docker(
     artifact: &str,
     source_version: &str,
     target_version: &str,
     codename: &str,
     network: &str,
-    publish_to_docker_io: bool,
+    target_repo: &str,
+    use_skopeo: bool,
+    platforms: &[String],
+    pin_digest: bool,
     verify: bool,
     dry_run: bool,
-) -> ManagerResult<()> {
-    use crate::artifacts::get_suffix;
-
+    sbom_dir: Option<&Path>,
+    known_networks: &[String],
+    digests: &mut Vec<(String, String)>,
+) -> ManagerResult<Option<String>> {
     let network_suffix = get_suffix(artifact, Some(network), None);
-    let artifact_full_source_version = format!("{}-{}{}", source_version, codename, network_suffix);
-    let artifact_full_target_version = format!("{}-{}{}", target_version, codename, network_suffix);
+    let artifact_full_source_version = format!(
+        "{}-{}{}",
+        source_version,
+        docker_codename(codename),
+        network_suffix
+    );
+    let artifact_full_target_version = format!(
+        "{}-{}{}",
+        target_version,
+        docker_codename(codename),
+        network_suffix
+    );
 
-    println!(
+    textln!(
         " 🐋 Publishing {} docker for '{}' network and '{}' codename with '{}' version",
-        artifact, network, codename, target_version
+        artifact,
+        network,
+        codename,
+        target_version
     );
-    println!(
-        "    📦 Target version: {}",
-        calculate_docker_tag(
-            publish_to_docker_io,
-            artifact,
-            target_version,
-            codename,
-            Some(network),
-            None,
-            None,
-        )
+    let target_tag = calculate_docker_tag_in(
+        target_repo,
+        artifact,
+        target_version,
+        codename,
+        Some(network),
+        None,
+        None,
     );
-    println!();
+    textln!("    📦 Target version: {}", target_tag);
+    textln!();
+
+    // Only the part after the version, which could itself contain a network word
+    let tag_suffix = artifact_full_target_version
+        .strip_prefix(target_version)
+        .unwrap_or(&artifact_full_target_version);
+    check_network_in_name(
+        "Docker tag",
+        tag_suffix,
+        Some(network),
+        known_networks,
+        !network_suffix.is_empty(),
+    )?;
 
     if !dry_run {
         // Use Rust implementation for Docker image promotion
-        promote_docker_image(
+        let digest = promote_docker_image(
             artifact,
             &artifact_full_source_version,
             &artifact_full_target_version,
-            publish_to_docker_io,
+            target_repo,
             false, // not quiet
+            &[],
+            use_skopeo,
+            platforms,
+            pin_digest,
         )
         .await?;
+        metrics::record(Counter::Published);
+        if let Some(digest) = digest {
+            digests.push((target_tag.clone(), digest));
+        }
 
         if verify {
-            println!(
+            textln!(
                 "    📋 Verifying: {} docker for '{}' network and '{}' codename with '{}' version",
-                artifact, network, codename, target_version
+                artifact,
+                network,
+                codename,
+                target_version
             );
 
-            let repo = if publish_to_docker_io {
-                "docker.io/minaprotocol"
-            } else {
-                "gcr.io/o1labs-192920"
-            };
-            let full_version = format!("{}-{}{}", target_version, codename, network_suffix);
+            let full_version = format!(
+                "{}-{}{}",
+                target_version,
+                docker_codename(codename),
+                network_suffix
+            );
 
-            verify_docker_image(artifact, &full_version, repo, codename, &network_suffix).await?;
+            verify_docker_image(
+                artifact,
+                &full_version,
+                target_repo,
+                codename,
+                &network_suffix,
+                None,
+            )
+            .await?;
+        }
+
+        let sbom = sbom_dir.and_then(|dir| {
+            find_sbom(
+                dir,
+                get_docker_image_name(artifact),
+                &artifact_full_target_version,
+            )
+        });
+        if let Some(sbom) = sbom {
+            let location = attach_docker_sbom(&target_tag, &sbom).await?;
+            textln!("    📜 SBOM attached to: {}", location);
+            return Ok(Some(location));
         }
     }
 
-    Ok(())
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::get_repo;
+
+    const CHANNEL_INDEX: &str = "\
+Package: mina-archive
+Version: 3.0.1
+Architecture: amd64
+Filename: pool/stable/m/mi/mina-archive_3.0.1_amd64.deb
+SHA256: abc123
+
+Package: mina-logproc
+Version: 3.0.0
+Architecture: amd64
+Filename: pool/stable/m/mi/mina-logproc_3.0.0_amd64.deb
+SHA256: def456
+";
+
+    #[test]
+    fn only_missing_skips_published_units() {
+        let codenames = vec!["bullseye".to_string(), "focal".to_string()];
+        let networks = vec!["devnet".to_string()];
+        let units = publish_units(
+            &[Artifact::MinaLogproc, Artifact::MinaArchive],
+            &codenames,
+            &networks,
+            false,
+            false,
+            true,
+        );
+        let targets: Vec<UnitTarget> = units
+            .iter()
+            .map(|unit| unit_target(unit, &[], "3.0.1", get_repo(false), None))
+            .collect();
+
+        let mut inventory = Inventory::default();
+        inventory.add_index("bullseye", CHANNEL_INDEX);
+        inventory
+            .docker_tags
+            .insert("gcr.io/o1labs-192920/mina-archive:3.0.1-focal-devnet".to_string());
+
+        let missing: Vec<String> = targets
+            .iter()
+            .filter(|target| !inventory.has(target))
+            .map(|target| target.to_string())
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                // Only 3.0.0 is in the channel
+                "mina-logproc=3.0.1 debian for bullseye",
+                "mina-logproc=3.0.1 debian for focal",
+                "gcr.io/o1labs-192920/mina-archive:3.0.1-bullseye-devnet docker",
+                // bullseye's index doesn't count for focal
+                "mina-archive=3.0.1 debian for focal",
+            ]
+        );
+    }
+
+    #[test]
+    fn unit_targets_follow_overrides_and_names() {
+        let overrides = vec![(Artifact::MinaArchive, "3.0.1-1".to_string())];
+        let debian = PublishUnit::Debian {
+            artifact: Artifact::MinaArchive,
+            codename: "bullseye",
+            network: Some("devnet"),
+            new_name: None,
+        };
+        assert_eq!(
+            unit_target(&debian, &overrides, "3.0.1", get_repo(false), None),
+            UnitTarget::Debian {
+                codename: "bullseye".to_string(),
+                package: "mina-archive-devnet".to_string(),
+                version: "3.0.1-1".to_string(),
+            }
+        );
+
+        let daemon = PublishUnit::Debian {
+            artifact: Artifact::MinaDaemon,
+            codename: "bullseye",
+            network: Some("mainnet"),
+            new_name: None,
+        };
+        assert!(matches!(
+            unit_target(&daemon, &overrides, "3.0.1", get_repo(false), Some("mina-{network}-node")),
+            UnitTarget::Debian { package, version, .. }
+                if package == "mina-mainnet-node" && version == "3.0.1"
+        ));
+
+        let docker = PublishUnit::Docker {
+            artifact: Artifact::MinaArchive,
+            codename: "focal",
+            network: "mainnet",
+        };
+        assert_eq!(
+            unit_target(&docker, &overrides, "3.0.1", get_repo(true), None),
+            UnitTarget::Docker {
+                tag: "docker.io/minaprotocol/mina-archive:3.0.1-1-focal-mainnet".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn publish_units_follow_the_sequential_order() {
+        let codenames = vec!["bullseye".to_string(), "focal".to_string()];
+        let networks = vec!["devnet".to_string(), "mainnet".to_string()];
+        let units = publish_units(
+            &[Artifact::MinaLogproc, Artifact::MinaArchive],
+            &codenames,
+            &networks,
+            false,
+            false,
+            true,
+        );
+
+        let archive = |codename, network| PublishUnit::Debian {
+            artifact: Artifact::MinaArchive,
+            codename,
+            network: Some(network),
+            new_name: Some("mina-archive"),
+        };
+        let archive_docker = |codename, network| PublishUnit::Docker {
+            artifact: Artifact::MinaArchive,
+            codename,
+            network,
+        };
+        let logproc = |codename| PublishUnit::Debian {
+            artifact: Artifact::MinaLogproc,
+            codename,
+            network: None,
+            new_name: None,
+        };
+        assert_eq!(
+            units,
+            vec![
+                logproc("bullseye"),
+                logproc("focal"),
+                archive("bullseye", "devnet"),
+                archive_docker("bullseye", "devnet"),
+                archive("bullseye", "mainnet"),
+                archive_docker("bullseye", "mainnet"),
+                archive("focal", "devnet"),
+                archive_docker("focal", "devnet"),
+                archive("focal", "mainnet"),
+                archive_docker("focal", "mainnet"),
+            ]
+        );
+
+        let dockers_only = publish_units(
+            &[Artifact::MinaLogproc, Artifact::MinaDaemon],
+            &codenames,
+            &networks,
+            false,
+            true,
+            false,
+        );
+        assert_eq!(dockers_only.len(), 4);
+        assert!(dockers_only
+            .iter()
+            .all(|unit| matches!(unit, PublishUnit::Docker { .. })));
+    }
+
+    #[test]
+    fn test_local_mirror_rejects_s3_only_flags() {
+        use clap::Parser;
+
+        let publish_args = |extra: &[&str]| {
+            let mut argv = vec![
+                "release-manager",
+                "publish",
+                "--source-version",
+                "3.0.1",
+                "--target-version",
+                "3.0.1",
+                "--channel",
+                "unstable",
+                "--buildkite-build-id",
+                "123",
+            ];
+            argv.extend_from_slice(extra);
+            match crate::Cli::try_parse_from(argv).unwrap().command {
+                crate::Commands::Publish(args) => args,
+                _ => unreachable!(),
+            }
+        };
+
+        assert_eq!(local_mirror_dir(&publish_args(&[])).unwrap(), None);
+        assert_eq!(
+            local_mirror_dir(&publish_args(&["--local-mirror", "/tmp/mirror"])).unwrap(),
+            Some(PathBuf::from("/tmp/mirror"))
+        );
+        for flag in [&["--staged"][..], &["--debian-sign-key", "ABCD1234"]] {
+            let mut extra = vec!["--local-mirror", "/tmp/mirror"];
+            extra.extend_from_slice(flag);
+            let err = local_mirror_dir(&publish_args(&extra)).unwrap_err();
+            assert!(matches!(err, ManagerError::ValidationError(_)), "{}", err);
+            assert!(
+                err.to_string()
+                    .contains(&format!("{} is not supported with --local-mirror", flag[0])),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_lock_needs_sequential_uploads() {
+        assert!(check_unlocked_parallelism(1, true).is_ok());
+        assert!(check_unlocked_parallelism(4, false).is_ok());
+        let err = check_unlocked_parallelism(4, true).unwrap_err().to_string();
+        assert!(err.contains("--no-lock"));
+        assert!(err.contains("--parallel 4"));
+    }
+
+    #[tokio::test]
+    async fn run_units_bounded_and_ordered() {
+        let running = Cell::new(0);
+        let peak = Cell::new(0);
+        let outputs = run_units((0..6u64).collect(), 2, |unit| {
+            let (running, peak) = (&running, &peak);
+            async move {
+                running.set(running.get() + 1);
+                peak.set(peak.get().max(running.get()));
+                // Later units finish first
+                tokio::time::sleep(Duration::from_millis(30 - unit * 5)).await;
+                running.set(running.get() - 1);
+                Ok(unit * 10)
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outputs, vec![0, 10, 20, 30, 40, 50]);
+        assert_eq!(peak.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn run_units_stops_after_a_failure() {
+        let started = Cell::new(0);
+        let result = run_units((0..4).collect(), 1, |unit| {
+            let started = &started;
+            async move {
+                started.set(started.get() + 1);
+                if unit == 1 {
+                    Err(ManagerError::CommandFailed(format!("unit {}", unit)))
+                } else {
+                    Ok(unit)
+                }
+            }
+        })
+        .await;
+
+        assert!(matches!(result, Err(ManagerError::CommandFailed(msg)) if msg == "unit 1"));
+        assert_eq!(started.get(), 2);
+    }
+
+    #[test]
+    fn test_reversion_output_dir_unique_per_stripped_network() {
+        let cache = Path::new("/cache/bullseye");
+        assert_eq!(
+            reversion_output_dir(cache, "mina-devnet", "mina-devnet"),
+            PathBuf::from("/cache/bullseye")
+        );
+        let devnet = reversion_output_dir(cache, "mina-archive-devnet", "mina-archive");
+        let mainnet = reversion_output_dir(cache, "mina-archive-mainnet", "mina-archive");
+        assert_eq!(devnet, PathBuf::from("/cache/bullseye/mina-archive-devnet"));
+        assert_ne!(devnet, mainnet);
+    }
+
+    #[cfg(feature = "integration-test")]
+    fn build_archive_deb(dir: &Path, package: &str, version: &str) {
+        let root = dir.join(format!("{}-root", package));
+        std::fs::create_dir_all(root.join("DEBIAN")).unwrap();
+        std::fs::write(
+            root.join("DEBIAN/control"),
+            format!(
+                "Package: {}\nVersion: {}\nArchitecture: amd64\nMaintainer: O(1) Labs\nDescription: {}\n",
+                package, version, package
+            ),
+        )
+        .unwrap();
+        let out = std::process::Command::new("dpkg-deb")
+            .args(["-Zgzip", "--build"])
+            .arg(&root)
+            .arg(dir.join(format!("{}_{}.deb", package, version)))
+            .output()
+            .expect("dpkg-deb");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(feature = "integration-test")]
+    #[tokio::test]
+    async fn strip_network_from_archive_keeps_both_networks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = tmp.path().join("bullseye");
+        std::fs::create_dir_all(&cache).unwrap();
+        build_archive_deb(&cache, "mina-archive-devnet", "1.0.0");
+        build_archive_deb(&cache, "mina-archive-mainnet", "1.0.0");
+
+        let mut published = Vec::new();
+        for network in ["devnet", "mainnet"] {
+            let source = format!("mina-archive-{}", network);
+            let path =
+                prepare_cached_debian(&cache, &source, "mina-archive", "1.0.0", "1.0.1", "stable")
+                    .await
+                    .unwrap();
+            published.push((source, path));
+        }
+
+        assert_ne!(published[0].1, published[1].1);
+        for (source, path) in &published {
+            assert_eq!(path.file_name().unwrap(), "mina-archive_1.0.1.deb");
+            let fields = std::process::Command::new("dpkg-deb")
+                .args(["-f"])
+                .arg(path)
+                .args(["Package", "Description"])
+                .output()
+                .unwrap();
+            let fields = String::from_utf8_lossy(&fields.stdout);
+            assert!(fields.contains("Package: mina-archive\n"), "{}", fields);
+            assert!(
+                fields.contains(&format!("Description: {}", source)),
+                "{}",
+                fields
+            );
+        }
+    }
+
+    #[test]
+    fn test_debian_source_fast_path_only_when_unchanged_and_local() {
+        let local = StorageBackend::Local;
+        assert_eq!(
+            debian_source(true, &local, "1.0.0", "1.0.0", false),
+            DebianSource::Storage
+        );
+
+        // opt-in only
+        assert_eq!(
+            debian_source(false, &local, "1.0.0", "1.0.0", false),
+            DebianSource::Cache
+        );
+        // reversion needed
+        assert_eq!(
+            debian_source(true, &local, "1.0.0", "1.0.1", false),
+            DebianSource::Cache
+        );
+        // rename needed
+        assert_eq!(
+            debian_source(true, &local, "1.0.0", "1.0.0", true),
+            DebianSource::Cache
+        );
+        // remote backend
+        assert_eq!(
+            debian_source(true, &StorageBackend::Gs, "1.0.0", "1.0.0", false),
+            DebianSource::Cache
+        );
+    }
 }
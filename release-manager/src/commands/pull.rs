@@ -1,6 +1,7 @@
-use crate::artifacts::{get_artifact_with_suffix, parse_string_list};
+use crate::artifacts::{get_debian_package_name, parse_string_list};
 use crate::cli::PullArgs;
 use crate::errors::ManagerResult;
+use crate::output::textln;
 use crate::storage::{StorageBackend, StorageClient, StorageOperations};
 use crate::utils::{print_operation_info, validate_required_args};
 use colored::*;
@@ -22,6 +23,10 @@ pub async fn execute(args: PullArgs) -> ManagerResult<()> {
         ("Target", args.target.as_str()),
         ("Codenames", args.codenames.as_str()),
         ("Networks", args.networks.as_str()),
+        (
+            "Daemon debian name",
+            args.daemon_debian_name.as_deref().unwrap_or(""),
+        ),
     ];
 
     print_operation_info("Pulling mina artifacts", &params);
@@ -34,19 +39,21 @@ pub async fn execute(args: PullArgs) -> ManagerResult<()> {
     for artifact in &artifacts {
         for codename in &codenames {
             for network in &networks {
-                println!(
+                textln!(
                     "  📥  Pulling {} for {} codename and {} network",
-                    artifact, codename, network
+                    artifact,
+                    codename,
+                    network
                 );
 
-                let artifact_full_name = get_artifact_with_suffix(artifact, Some(network), None);
-                let remote_path = format!(
-                    "{}/{}/debians/{}/{}_*",
-                    storage.backend.root_path(),
-                    args.buildkite_build_id,
-                    codename,
-                    artifact_full_name
+                let artifact_full_name = get_debian_package_name(
+                    artifact,
+                    Some(network),
+                    None,
+                    args.daemon_debian_name.as_deref(),
                 );
+                let remote_path =
+                    storage.debian_glob(&args.buildkite_build_id, codename, &artifact_full_name)?;
 
                 // Download to target directory
                 storage.download(&remote_path, &args.target).await?;
@@ -54,6 +61,6 @@ pub async fn execute(args: PullArgs) -> ManagerResult<()> {
         }
     }
 
-    println!("{}", " ✅  Done.".green());
+    textln!("{}", " ✅  Done.".green());
     Ok(())
 }
@@ -3,7 +3,8 @@ use std::path::Path;
 
 use crate::cli::ReversionArgs;
 use crate::errors::{ManagerError, ManagerResult};
-use crate::reversion::reversion_debian_package;
+use crate::output::textln;
+use crate::reversion::{place_rebuilt_package, reversion_debian_package};
 
 /// Walk `{source_folder}/{codename}/*.deb` and reversion every package into
 /// `{output_folder}/{codename}/`. Mirrors `manager.sh reversion`.
@@ -16,19 +17,27 @@ pub async fn execute(args: ReversionArgs) -> ManagerResult<()> {
         )));
     }
 
-    println!();
-    println!(" ℹ️  Reversioning .deb packages with following parameters:");
-    println!(" - Source folder: {}", args.source_folder);
-    println!(" - Output folder: {}", args.output_folder);
-    println!(" - New version: {}", args.new_version);
+    textln!();
+    textln!(" ℹ️  Reversioning .deb packages with following parameters:");
+    textln!(" - Source folder: {}", args.source_folder);
+    textln!(" - Output folder: {}", args.output_folder);
+    textln!(" - New version: {}", args.new_version);
     if let Some(s) = &args.suite {
-        println!(" - Suite: {}", s);
+        textln!(" - Suite: {}", s);
     }
     if let Some(n) = &args.name {
-        println!(" - Rename to: {}", n);
+        textln!(" - Rename to: {}", n);
+    }
+    if let Some(f) = &args.deb_format_version {
+        textln!(" - Deb format version: {}", f);
+    }
+    if args.dry_run {
+        textln!(" - Dry run: nothing will be built or written");
     }
 
-    tokio::fs::create_dir_all(&args.output_folder).await?;
+    if !args.dry_run {
+        tokio::fs::create_dir_all(&args.output_folder).await?;
+    }
 
     let mut total = 0usize;
     let mut success = 0usize;
@@ -46,7 +55,9 @@ pub async fn execute(args: ReversionArgs) -> ManagerResult<()> {
             .unwrap_or("")
             .to_string();
         let output_codename_dir = Path::new(&args.output_folder).join(&codename);
-        tokio::fs::create_dir_all(&output_codename_dir).await?;
+        if !args.dry_run {
+            tokio::fs::create_dir_all(&output_codename_dir).await?;
+        }
 
         let mut debs = tokio::fs::read_dir(&codename_path).await?;
         while let Some(deb_entry) = debs.next_entry().await? {
@@ -57,16 +68,18 @@ pub async fn execute(args: ReversionArgs) -> ManagerResult<()> {
             };
 
             total += 1;
-            println!(
+            textln!(
                 "  🔄  Reversioning {}/{} -> version {}",
-                codename, basename, args.new_version
+                codename,
+                basename,
+                args.new_version
             );
 
             // Filename pattern: {name}_{version}_{arch}.deb
             let stem = basename.trim_end_matches(".deb");
             let parts: Vec<&str> = stem.rsplitn(3, '_').collect(); // [arch, version, name]
             if parts.len() != 3 {
-                println!(
+                textln!(
                     "  ⚠️  Warning: cannot parse name/version/arch from {} — skipping",
                     basename
                 );
@@ -93,15 +106,26 @@ pub async fn execute(args: ReversionArgs) -> ManagerResult<()> {
                 suite,
                 suite,
                 Some(final_name),
+                None,
+                args.deb_format_version.as_deref(),
+                // Only an intermediate file; the real output is guarded below
+                true,
+                args.dry_run,
             )
             .await;
 
             match result {
+                Ok(_) if args.dry_run => {
+                    textln!("  🔍  Would write {}", output_file.display());
+                    success += 1;
+                }
                 Ok(produced) => {
                     if produced != output_file {
-                        if let Err(e) = tokio::fs::rename(&produced, &output_file).await {
-                            println!(
-                                "  ⚠️  Reversion succeeded but rename to {} failed: {}",
+                        if let Err(e) =
+                            place_rebuilt_package(&produced, &output_file, args.overwrite)
+                        {
+                            textln!(
+                                "  ⚠️  Reversion succeeded but moving to {} failed: {}",
                                 output_file.display(),
                                 e
                             );
@@ -112,9 +136,11 @@ pub async fn execute(args: ReversionArgs) -> ManagerResult<()> {
                     success += 1;
                 }
                 Err(e) => {
-                    println!(
+                    textln!(
                         "  ⚠️  Warning: failed to reversion {}/{}: {} — skipping",
-                        codename, basename, e
+                        codename,
+                        basename,
+                        e
                     );
                     fail += 1;
                 }
@@ -122,25 +148,26 @@ pub async fn execute(args: ReversionArgs) -> ManagerResult<()> {
         }
     }
 
-    println!();
+    textln!();
     if total == 0 {
-        println!(
+        textln!(
             " ⚠️  No .deb files found in {}/{{codename}}/ subdirectories.",
             args.source_folder
         );
     } else {
-        println!(
+        textln!(
             " ℹ️  Summary: {}/{} packages reversioned successfully.",
-            success, total
+            success,
+            total
         );
         if fail > 0 {
-            println!(
+            textln!(
                 "{}",
                 format!(" ⚠️  {} package(s) failed to reversion.", fail).yellow()
             );
         }
     }
-    println!(" ✅  Done.");
-    println!();
+    textln!(" ✅  Done.");
+    textln!();
     Ok(())
 }
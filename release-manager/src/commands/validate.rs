@@ -4,11 +4,11 @@ use sha2::{Digest, Sha256};
 use crate::artifacts::parse_string_list;
 use crate::cli::ValidateArgs;
 use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
 use crate::process::{CommandExecutor, RealExecutor, S3Config};
+use crate::release_signature::{check_release_signature, KeyExpiry};
 use crate::utils::print_operation_info;
 
-const S3_REGION: &str = "us-west-2";
-
 pub async fn execute(args: ValidateArgs) -> ManagerResult<()> {
     let exec = RealExecutor;
     let client = reqwest::Client::new();
@@ -33,23 +33,28 @@ pub async fn execute_with(
         "Validating debian repository",
         &[
             ("Repository", args.debian_repo.as_str()),
+            ("S3 region", args.s3_region.as_str()),
             ("Channel", args.channel.as_str()),
             ("Codenames", args.codenames.as_str()),
             ("Architectures", args.archs.as_str()),
             ("Fix mode", if args.fix { "yes" } else { "no" }),
+            (
+                "Signing key check",
+                if args.check_signing_key { "yes" } else { "no" },
+            ),
         ],
     );
 
     let mut any_failed = false;
 
     for codename in &codenames {
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("  {} / {} / {}", args.debian_repo, codename, args.channel);
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!();
+        textln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        textln!("  {} / {} / {}", args.debian_repo, codename, args.channel);
+        textln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        textln!();
 
         for arch in &archs {
-            println!(" 📋 Packages [{}]:", arch);
+            textln!(" 📋 Packages [{}]:", arch);
             // The bucket flag for `deb-s3 list` is the S3 bucket name —
             // production passes the host name (e.g. `packages.o1test.net`),
             // which doubles as the bucket. In tests with MinIO the
@@ -61,7 +66,7 @@ pub async fn execute_with(
             let mut argv: Vec<String> = vec![
                 "list".to_string(),
                 format!("--bucket={}", bucket),
-                format!("--s3-region={}", S3_REGION),
+                format!("--s3-region={}", args.s3_region),
                 "--codename".to_string(),
                 codename.to_string(),
                 "--component".to_string(),
@@ -75,12 +80,12 @@ pub async fn execute_with(
                 .run("deb-s3", &argv_refs)
                 .map_err(|e| ManagerError::ValidationError(format!("deb-s3 list: {}", e)))?;
             for line in out.stdout.lines() {
-                println!("    {}", line);
+                textln!("    {}", line);
             }
             for line in out.stderr.lines() {
                 eprintln!("    {}", line);
             }
-            println!();
+            textln!();
         }
 
         if !args.list_only {
@@ -92,12 +97,12 @@ pub async fn execute_with(
                 }
             }
 
-            println!(" 🔍 Verifying manifest structure...");
+            textln!(" 🔍 Verifying manifest structure...");
             let bucket = bucket_name(&args.debian_repo);
             let mut verify_args: Vec<String> = vec![
                 "verify".to_string(),
                 format!("--bucket={}", bucket),
-                format!("--s3-region={}", S3_REGION),
+                format!("--s3-region={}", args.s3_region),
                 format!("--codename={}", codename),
                 format!("--component={}", args.channel),
             ];
@@ -106,9 +111,9 @@ pub async fn execute_with(
                 if let Some(key) = args.debian_sign_key.as_deref() {
                     verify_args.push("--sign".to_string());
                     verify_args.push(key.to_string());
-                    println!("    🔧 Fix mode: will repair manifests + re-sign InRelease");
+                    textln!("    🔧 Fix mode: will repair manifests + re-sign InRelease");
                 } else {
-                    println!(
+                    textln!(
                         "    🔧 Fix mode: will repair manifests (unsigned — pass \
                          --debian-sign-key to re-sign InRelease)"
                     );
@@ -120,7 +125,7 @@ pub async fn execute_with(
                 .run("deb-s3", &argv_refs)
                 .map_err(|e| ManagerError::ValidationError(format!("deb-s3 verify: {}", e)))?;
             for line in out.stdout.lines() {
-                println!("    {}", line);
+                textln!("    {}", line);
             }
             for line in out.stderr.lines() {
                 eprintln!("    {}", line);
@@ -130,25 +135,39 @@ pub async fn execute_with(
             }
 
             if args.fix {
-                println!();
-                println!(" 🗑️  Invalidating CloudFront cache for {}...", codename);
+                textln!();
+                textln!(" 🗑️  Invalidating CloudFront cache for {}...", codename);
                 invalidate_cloudfront(exec, &args.debian_repo, codename)?;
             }
         }
 
-        println!();
+        if args.check_signing_key
+            && !verify_signing_key(
+                exec,
+                http,
+                &args.debian_repo,
+                codename,
+                args.expiry_warn_days,
+                args.fail_on_expiring,
+            )
+            .await
+        {
+            any_failed = true;
+        }
+
+        textln!();
     }
 
     if any_failed {
-        println!("{}", " ❌  Some validations failed.".red());
+        textln!("{}", " ❌  Some validations failed.".red());
         if !args.fix {
-            println!("    Run with --fix to attempt repair.");
+            textln!("    Run with --fix to attempt repair.");
         }
         Err(ManagerError::ValidationError(
             "validate found mismatches".into(),
         ))
     } else {
-        println!("{}", " ✅  All validations passed.".green());
+        textln!("{}", " ✅  All validations passed.".green());
         Ok(())
     }
 }
@@ -160,7 +179,7 @@ async fn verify_sha256_for_arch(
     channel: &str,
     arch: &str,
 ) -> ManagerResult<bool> {
-    println!(" 🔒 Verifying SHA256 hashes [{}]...", arch);
+    textln!(" 🔒 Verifying SHA256 hashes [{}]...", arch);
 
     let packages_url = format!(
         "{}/dists/{}/{}/binary-{}/Packages",
@@ -175,7 +194,7 @@ async fn verify_sha256_for_arch(
             ManagerError::ValidationError(format!("Failed to read Packages body: {}", e))
         })?,
         _ => {
-            println!("    ⚠️  Could not fetch {}", packages_url);
+            textln!("    ⚠️  Could not fetch {}", packages_url);
             return Ok(false);
         }
     };
@@ -198,7 +217,7 @@ async fn verify_sha256_for_arch(
             Ok(resp) if resp.status().is_success() => match resp.bytes().await {
                 Ok(b) => b,
                 Err(e) => {
-                    println!(
+                    textln!(
                         "    ✗ {} {}: failed to read body ({})",
                         entry.package.as_deref().unwrap_or("?"),
                         entry.version.as_deref().unwrap_or("?"),
@@ -209,7 +228,7 @@ async fn verify_sha256_for_arch(
                 }
             },
             Ok(resp) => {
-                println!(
+                textln!(
                     "    ✗ {} {}: HTTP {}",
                     entry.package.as_deref().unwrap_or("?"),
                     entry.version.as_deref().unwrap_or("?"),
@@ -219,7 +238,7 @@ async fn verify_sha256_for_arch(
                 continue;
             }
             Err(e) => {
-                println!(
+                textln!(
                     "    ✗ {} {}: fetch failed ({})",
                     entry.package.as_deref().unwrap_or("?"),
                     entry.version.as_deref().unwrap_or("?"),
@@ -234,39 +253,86 @@ async fn verify_sha256_for_arch(
         let pkg = entry.package.as_deref().unwrap_or("?");
         let ver = entry.version.as_deref().unwrap_or("?");
         if actual != expected {
-            println!("    ✗ {} {}: SHA256 mismatch", pkg, ver);
-            println!("      manifest: {}", expected);
-            println!("      actual:   {}", actual);
+            textln!("    ✗ {} {}: SHA256 mismatch", pkg, ver);
+            textln!("      manifest: {}", expected);
+            textln!("      actual:   {}", actual);
             mismatches += 1;
         } else {
-            println!("    ✓ {} {} OK", pkg, ver);
+            textln!("    ✓ {} {} OK", pkg, ver);
         }
     }
 
     if mismatches > 0 {
-        println!("    ❌ {} hash mismatch(es) found", mismatches);
+        textln!("    ❌ {} hash mismatch(es) found", mismatches);
     } else if total == 0 {
-        println!("    ℹ️  No packages with SHA256 in manifest");
+        textln!("    ℹ️  No packages with SHA256 in manifest");
     } else {
-        println!("    ✅ All {} hashes valid", total);
+        textln!("    ✅ All {} hashes valid", total);
     }
-    println!();
+    textln!();
 
     Ok(mismatches == 0)
 }
 
+/// Check the codename's Release signature and signing key expiry. A bad
+/// signature or expired key fails; a key expiring within `warn_days` only
+/// fails with `fail_on_expiring`.
+async fn verify_signing_key(
+    exec: &dyn CommandExecutor,
+    http: &reqwest::Client,
+    debian_repo: &str,
+    codename: &str,
+    warn_days: i64,
+    fail_on_expiring: bool,
+) -> bool {
+    textln!(" 🔑 Verifying Release signature...");
+    match check_release_signature(exec, http, &repo_base(debian_repo), codename, warn_days).await {
+        Ok(KeyExpiry::Never) => {
+            textln!("    ✅ Signature valid, signing key never expires");
+            true
+        }
+        Ok(KeyExpiry::Valid { days_left }) => {
+            textln!(
+                "    ✅ Signature valid, signing key expires in {} days",
+                days_left
+            );
+            true
+        }
+        Ok(KeyExpiry::Expiring { days_left }) => {
+            textln!(
+                "    ⚠️  Signing key expires in {} days (warning window: {} days)",
+                days_left,
+                warn_days
+            );
+            !fail_on_expiring
+        }
+        Ok(KeyExpiry::Expired) => {
+            textln!("    ❌ Release is signed with an expired key");
+            false
+        }
+        Err(e) => {
+            textln!("    ❌ {}", e);
+            false
+        }
+    }
+}
+
 #[derive(Debug, Default)]
-struct PackagesEntry {
-    package: Option<String>,
-    version: Option<String>,
-    filename: Option<String>,
-    sha256: Option<String>,
+pub(crate) struct PackagesEntry {
+    pub(crate) package: Option<String>,
+    pub(crate) version: Option<String>,
+    pub(crate) filename: Option<String>,
+    pub(crate) sha256: Option<String>,
 }
 
+/// Architecture whose Packages index is consulted for presence checks;
+/// deb-s3 lists `all` packages there too
+pub(crate) const PRESENCE_INDEX_ARCH: &str = "amd64";
+
 /// Treat `debian_repo` as a URL base. If it already has a scheme (test setups
 /// pointing at a wiremock or MinIO at `http://127.0.0.1:PORT/bucket`), use
 /// it as-is; otherwise prepend `https://` to match production.
-fn repo_base(debian_repo: &str) -> String {
+pub(crate) fn repo_base(debian_repo: &str) -> String {
     if debian_repo.contains("://") {
         debian_repo.trim_end_matches('/').to_string()
     } else {
@@ -289,7 +355,7 @@ fn bucket_name(debian_repo: &str) -> String {
     debian_repo.to_string()
 }
 
-fn parse_packages_file(body: &str) -> Vec<PackagesEntry> {
+pub(crate) fn parse_packages_file(body: &str) -> Vec<PackagesEntry> {
     let mut entries = Vec::new();
     let mut current = PackagesEntry::default();
     for line in body.lines() {
@@ -318,6 +384,17 @@ fn parse_packages_file(body: &str) -> Vec<PackagesEntry> {
     entries
 }
 
+/// A package's entry in a Packages index, at exactly `version`
+pub(crate) fn find_package<'a>(
+    entries: &'a [PackagesEntry],
+    package: &str,
+    version: &str,
+) -> Option<&'a PackagesEntry> {
+    entries.iter().find(|entry| {
+        entry.package.as_deref() == Some(package) && entry.version.as_deref() == Some(version)
+    })
+}
+
 fn invalidate_cloudfront(
     exec: &dyn CommandExecutor,
     debian_repo: &str,
@@ -329,7 +406,7 @@ fn invalidate_cloudfront(
         Err(_) => String::new(),
     };
     if cf_domain.is_empty() {
-        println!(
+        textln!(
             "    ⚠️  No CNAME found for {} — skipping CDN invalidation",
             debian_repo
         );
@@ -353,7 +430,7 @@ fn invalidate_cloudfront(
         Err(_) => String::new(),
     };
     if dist_id.is_empty() || dist_id == "None" {
-        println!("    ⚠️  Could not find CloudFront distribution");
+        textln!("    ⚠️  Could not find CloudFront distribution");
         return Ok(());
     }
 
@@ -375,15 +452,15 @@ fn invalidate_cloudfront(
         })?;
 
     for line in out.stdout.lines() {
-        println!("    {}", line);
+        textln!("    {}", line);
     }
     for line in out.stderr.lines() {
         eprintln!("    {}", line);
     }
     if out.is_success() {
-        println!("    ✅ Cache invalidation submitted");
+        textln!("    ✅ Cache invalidation submitted");
     } else {
-        println!("    ⚠️  Cache invalidation command failed");
+        textln!("    ⚠️  Cache invalidation command failed");
     }
     Ok(())
 }
@@ -391,6 +468,7 @@ fn invalidate_cloudfront(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::DEFAULT_S3_REGION;
 
     #[test]
     fn parse_packages_basic() {
@@ -499,10 +577,14 @@ SHA256: deadbeef
             codenames: "bullseye".to_string(),
             channel: "develop".to_string(),
             archs: "amd64".to_string(),
+            s3_region: DEFAULT_S3_REGION.to_string(),
             debian_repo: server.uri(),
             debian_sign_key: None,
             fix: false,
             list_only: false,
+            check_signing_key: false,
+            expiry_warn_days: 30,
+            fail_on_expiring: false,
         };
 
         let http = reqwest::Client::new();
@@ -556,10 +638,14 @@ SHA256: deadbeef
             codenames: "bullseye".to_string(),
             channel: "develop".to_string(),
             archs: "amd64".to_string(),
+            s3_region: DEFAULT_S3_REGION.to_string(),
             debian_repo: server.uri(),
             debian_sign_key: None,
             fix: false,
             list_only: false,
+            check_signing_key: false,
+            expiry_warn_days: 30,
+            fail_on_expiring: false,
         };
 
         let http = reqwest::Client::new();
@@ -758,10 +844,14 @@ SHA256: deadbeef
             codenames: "bullseye".to_string(),
             channel: "develop".to_string(),
             archs: "amd64".to_string(),
+            s3_region: DEFAULT_S3_REGION.to_string(),
             debian_repo: format!("{}/{}", endpoint, bucket),
             debian_sign_key: None,
             fix: false,
             list_only: false,
+            check_signing_key: false,
+            expiry_warn_days: 30,
+            fail_on_expiring: false,
         };
 
         // Real deb-s3 calls go through MixedExecutor → RealExecutor; the
@@ -784,6 +874,7 @@ SHA256: deadbeef
         );
     }
 
+    #[cfg(feature = "integration-test")]
     fn command_available(cmd: &str) -> bool {
         std::process::Command::new("sh")
             .arg("-c")
@@ -793,6 +884,7 @@ SHA256: deadbeef
             .unwrap_or(false)
     }
 
+    #[cfg(feature = "integration-test")]
     fn run_with_env(program: &str, args: &[&str], env: &[(&str, &str)]) {
         let mut cmd = std::process::Command::new(program);
         cmd.args(args);
@@ -856,10 +948,14 @@ SHA256: deadbeef
             codenames: "bullseye".to_string(),
             channel: "develop".to_string(),
             archs: "amd64".to_string(),
+            s3_region: DEFAULT_S3_REGION.to_string(),
             debian_repo: server.uri(),
             debian_sign_key: Some("KEYID".to_string()),
             fix: true,
             list_only: false,
+            check_signing_key: false,
+            expiry_warn_days: 30,
+            fail_on_expiring: false,
         };
 
         let http = reqwest::Client::new();
@@ -887,4 +983,81 @@ SHA256: deadbeef
             fix_call.args
         );
     }
+
+    /// `--check-signing-key` with a signing key 5 days from expiry: a
+    /// warning by default, a failure with `--fail-on-expiring`
+    #[tokio::test]
+    async fn validate_signing_key_near_expiry() {
+        use crate::process::{CommandOutput, MockCommandExecutor};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/keys/minaprotocol.asc"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string("-----BEGIN PGP PUBLIC KEY BLOCK-----"),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/dists/bullseye/InRelease"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string("-----BEGIN PGP SIGNED MESSAGE-----"),
+            )
+            .mount(&server)
+            .await;
+
+        let fpr = "59495A8E3DF6D13C712BA35275D7707E7AEB8BDC";
+        let expires = chrono::Utc::now().timestamp() + 5 * 86_400 + 3_600;
+        let exec = MockCommandExecutor::new();
+        exec.expect_args_starting_with("deb-s3", &["list"], CommandOutput::success(""));
+        exec.expect(
+            "gpg",
+            |args| args.contains(&"--import"),
+            CommandOutput::success(""),
+        );
+        exec.expect(
+            "gpg",
+            |args| args.contains(&"--verify"),
+            CommandOutput::success(format!(
+                "[GNUPG:] GOODSIG 75D7707E7AEB8BDC Mina\n\
+                 [GNUPG:] VALIDSIG {fpr} 2026-10-17 1792207216 0 4 0 1 10 01 {fpr}\n"
+            )),
+        );
+        exec.expect(
+            "gpg",
+            |args| args.contains(&"--list-keys"),
+            CommandOutput::success(format!(
+                "pub:u:2048:1:75D7707E7AEB8BDC:1792207216:{expires}::u:::scSC::::::23::0:\n\
+                 fpr:::::::::{fpr}:\n"
+            )),
+        );
+
+        let args = |fail_on_expiring| ValidateArgs {
+            codenames: "bullseye".to_string(),
+            channel: "develop".to_string(),
+            archs: "amd64".to_string(),
+            s3_region: DEFAULT_S3_REGION.to_string(),
+            debian_repo: server.uri(),
+            debian_sign_key: None,
+            fix: false,
+            list_only: true,
+            check_signing_key: true,
+            expiry_warn_days: 30,
+            fail_on_expiring,
+        };
+
+        let http = reqwest::Client::new();
+        let result = execute_with(args(false), &exec, &http, &S3Config::default()).await;
+        assert!(
+            result.is_ok(),
+            "expiring key should only warn: {:?}",
+            result.err()
+        );
+        assert_eq!(exec.call_count("gpg"), 3);
+
+        let result = execute_with(args(true), &exec, &http, &S3Config::default()).await;
+        assert!(result.is_err(), "--fail-on-expiring should fail");
+    }
 }
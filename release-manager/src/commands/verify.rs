@@ -1,14 +1,32 @@
 use crate::artifacts::{
-    calculate_docker_tag, combine_docker_suffixes, get_artifact_with_suffix, get_repo,
-    parse_artifact_list, parse_string_list, Artifact,
+    calculate_docker_tag, combine_docker_suffixes, docker_image_tag, get_debian_package_name,
+    get_docker_image_name, parse_artifact_list, parse_docker_registries, parse_string_list,
+    print_no_docker_image, Artifact, DockerRegistry,
 };
+use crate::baseline::{Baseline, VerifiedPackage};
 use crate::cli::VerifyArgs;
-use crate::errors::ManagerResult;
+use crate::docker_promote::resolve_digest;
+use crate::errors::{ManagerError, ManagerResult};
+use crate::output::{text, textln, Reporter};
+use crate::run_manifest::RunManifest;
 use crate::utils::print_operation_info;
-use crate::verification::{verify_debian_package, verify_docker_image};
+use crate::verification::{
+    self, check_digest_drift, docker_installed_version, pinned_reference, verify_debian_package,
+    verify_docker_image, verify_docker_reference, verify_layer_consistency, with_verify_timeout,
+    AptRetry, DebianVerifyConfig, RepoSnapshot, VerifyManifest, DEFAULT_VERIFY_CONCURRENCY,
+};
 use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
+/// Verify the requested artifacts, carrying on past failures unless
+/// `--fail-fast`, and return every check's result. Fails if any check did.
+pub async fn execute(
+    args: VerifyArgs,
+    reporter: &Reporter,
+) -> ManagerResult<Vec<VerificationResult>> {
     // Parse lists
     let artifacts = parse_artifact_list(&args.artifacts)?;
     let networks = parse_string_list(&args.networks);
@@ -16,10 +34,40 @@ pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
 
     // Print operation info
     let docker_io_str = args.docker_io.to_string();
+    let docker_registries_str = args.docker_registries.as_deref().unwrap_or("");
     let signed_debian_repo_str = args.signed_debian_repo.to_string();
     let only_debians_str = args.only_debians.to_string();
     let only_dockers_str = args.only_dockers.to_string();
     let docker_suffix_str = args.docker_suffix.as_deref().unwrap_or("");
+    let check_layer_consistency_str = args.check_layer_consistency.to_string();
+    let verify_cross_consistency_str = args.verify_cross_consistency.to_string();
+    let platform_str = args.platform.as_deref().unwrap_or("");
+    let strict_deps_str = args.strict_deps.to_string();
+    let verify_changelog_str = args.verify_changelog.to_string();
+    let quick_verify_str = args.quick_verify.to_string();
+    let fail_fast_str = args.fail_fast.to_string();
+    let rosetta_smoke_str = args.rosetta_smoke.to_string();
+    let verify_concurrency_str = args.verify_concurrency.to_string();
+    let from_manifest_str = args.from_manifest.as_deref().unwrap_or("");
+    let verify_timeout_str = args
+        .verify_timeout
+        .map(|secs| format!("{}s", secs))
+        .unwrap_or_default();
+    let save_baseline_str = args.save_baseline.as_deref().unwrap_or("");
+    let compare_baseline_str = args
+        .compare_baseline
+        .as_deref()
+        .map(|path| {
+            format!(
+                "{} (size threshold {}%)",
+                path, args.baseline_size_threshold
+            )
+        })
+        .unwrap_or_default();
+    let apt_retries_str = format!(
+        "{} (first after {}s)",
+        args.apt_retries, args.apt_retry_delay
+    );
 
     let params = vec![
         ("Verifying artifacts", args.artifacts.as_str()),
@@ -27,171 +75,319 @@ pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
         ("Version", args.version.as_str()),
         ("Promoting codenames", args.codenames.as_str()),
         ("Published to docker.io", docker_io_str.as_str()),
+        ("Docker registries", docker_registries_str),
         ("Debian repo", args.debian_repo.as_str()),
+        ("Debian suite", args.debian_suite.as_deref().unwrap_or("")),
         ("Debian repos is signed", signed_debian_repo_str.as_str()),
         ("Channel", args.channel.as_str()),
         ("Only debians", only_debians_str.as_str()),
         ("Only dockers", only_dockers_str.as_str()),
         ("Docker suffix", docker_suffix_str),
+        (
+            "Check layer consistency",
+            check_layer_consistency_str.as_str(),
+        ),
+        (
+            "Verify cross consistency",
+            verify_cross_consistency_str.as_str(),
+        ),
+        ("Platform", platform_str),
+        ("Strict deps", strict_deps_str.as_str()),
+        ("Verify changelog", verify_changelog_str.as_str()),
+        ("Apt retries", apt_retries_str.as_str()),
+        ("Manifest", args.manifest.as_deref().unwrap_or("")),
+        ("Quick verify", quick_verify_str.as_str()),
+        ("Rosetta smoke", rosetta_smoke_str.as_str()),
+        ("Fail fast", fail_fast_str.as_str()),
+        (
+            "Verify removal",
+            args.verify_removal.map_or("", |mode| mode.as_str()),
+        ),
+        ("Verify concurrency", verify_concurrency_str.as_str()),
+        (
+            "Expected version",
+            args.expected_version.as_deref().unwrap_or(""),
+        ),
+        (
+            "Daemon binary name",
+            args.daemon_binary_name.as_deref().unwrap_or(""),
+        ),
+        (
+            "Daemon debian name",
+            args.daemon_debian_name.as_deref().unwrap_or(""),
+        ),
+        ("Install method", args.install_method.as_str()),
+        ("Sources format", args.sources_format.as_str()),
+        ("Repo snapshot", args.repo_snapshot.as_deref().unwrap_or("")),
+        ("From manifest", from_manifest_str),
+        ("Verify timeout", verify_timeout_str.as_str()),
+        ("Save baseline", save_baseline_str),
+        ("Compare baseline", compare_baseline_str.as_str()),
     ];
 
-    print_operation_info("Verifying mina artifacts", &params);
+    // `--json-stream` records go to stdout, everything else to stderr
+    let stream_out = args
+        .json_stream
+        .then(|| Box::new(std::io::stdout()) as Box<dyn Write + Send>);
+    let stream = CheckStream::new(stream_out, args.fail_fast, reporter);
+    if !stream.enabled {
+        print_operation_info("Verifying mina artifacts", &params);
+    }
+
+    let mut manifest = args
+        .manifest
+        .as_deref()
+        .map(VerifyManifest::load)
+        .transpose()?;
+    if args.quick_verify {
+        manifest.get_or_insert_with(VerifyManifest::default).quick = true;
+    }
+    if args.verify_concurrency != DEFAULT_VERIFY_CONCURRENCY {
+        manifest
+            .get_or_insert_with(VerifyManifest::default)
+            .concurrency = Some(args.verify_concurrency);
+    }
+    if let Some(mode) = args.verify_removal {
+        manifest.get_or_insert_with(VerifyManifest::default).removal = Some(mode);
+    }
+    if args.rosetta_smoke {
+        manifest
+            .get_or_insert_with(VerifyManifest::default)
+            .rosetta_smoke = true;
+    }
+    if let Some(expected) = &args.expected_version {
+        manifest
+            .get_or_insert_with(VerifyManifest::default)
+            .expected_version = Some(expected.clone());
+    }
+    if let Some(name) = &args.daemon_binary_name {
+        manifest
+            .get_or_insert_with(VerifyManifest::default)
+            .daemon_binary = Some(name.clone());
+    }
+
+    if let Some(path) = &args.from_manifest {
+        verify_from_manifest(path, manifest.as_ref(), &stream).await?;
+        let results = stream.finish();
+        if !stream.enabled {
+            print_summary(&results);
+        }
+        check_results(&results)?;
+        if !stream.enabled {
+            textln!("{}", " ✅  Verification done.".green());
+        }
+        return Ok(results);
+    }
+
+    let registries = match args.docker_registries.as_deref() {
+        Some(list) => parse_docker_registries(list)?,
+        None => vec![DockerRegistry::from_docker_io(args.docker_io)],
+    };
+    let mut registry_results = RegistryResults::default();
+    let verify_timeout = VerifyTimeout::new(args.verify_timeout.map(Duration::from_secs));
+    let retry = AptRetry {
+        retries: args.apt_retries,
+        delay_secs: args.apt_retry_delay,
+    };
+    let repo_snapshot = args
+        .repo_snapshot
+        .as_deref()
+        .map(RepoSnapshot::parse)
+        .transpose()?;
+    let debian_config =
+        |package: &str, codename: &str, manifest: Option<&VerifyManifest>| DebianVerifyConfig {
+            signed: args.signed_debian_repo,
+            platform: args.platform.clone(),
+            strict_deps: args.strict_deps,
+            retry,
+            suite: args.debian_suite.clone(),
+            manifest: manifest.cloned().unwrap_or_default(),
+            verify_changelog: args.verify_changelog,
+            install_method: args.install_method,
+            sources_format: args.sources_format,
+            repo_snapshot: repo_snapshot.clone(),
+            ..DebianVerifyConfig::new(
+                package,
+                &args.version,
+                &args.debian_repo,
+                codename,
+                &args.channel,
+            )
+        };
 
-    let repo = get_repo(args.docker_io);
+    // Debians installed, for the baseline and cross-consistency checks
+    let mut verified = Vec::new();
 
     // Process each artifact
     for artifact in &artifacts {
+        let has_docker = artifact.has_docker_image();
+        if !args.only_debians && !has_docker {
+            print_no_docker_image(artifact.as_str());
+        }
         for codename in &codenames {
             match artifact {
                 Artifact::MinaLogproc => {
                     if !args.only_dockers {
-                        println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
+                        textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
                                  artifact.as_str(), args.channel, args.version, codename);
 
-                        verify_debian(
-                            artifact.as_str(),
-                            &args.version,
-                            codename,
-                            &args.debian_repo,
-                            &args.channel,
-                            args.signed_debian_repo,
-                            args.debug,
-                        )
-                        .await?;
-                    }
-
-                    if !args.only_debians {
-                        println!("    ℹ️  There is no mina-logproc docker image. skipping");
+                        verified.extend(
+                            verify_debian(
+                                debian_config(artifact.as_str(), codename, manifest.as_ref()),
+                                None,
+                                &verify_timeout,
+                                &stream,
+                                args.debug,
+                            )
+                            .await?,
+                        );
                     }
                 }
 
                 Artifact::MinaArchive => {
                     for network in &networks {
-                        let artifact_full_name =
-                            get_artifact_with_suffix(artifact.as_str(), Some(network), None);
+                        let network_manifest =
+                            manifest.as_ref().map(|m| m.for_network(Some(network)));
+                        let artifact_full_name = get_debian_package_name(
+                            artifact.as_str(),
+                            Some(network),
+                            None,
+                            args.daemon_debian_name.as_deref(),
+                        );
                         let docker_suffix_combined =
                             combine_docker_suffixes(network, args.docker_suffix.as_deref());
 
                         if !args.only_dockers {
-                            println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
+                            textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
                                      artifact.as_str(), args.channel, args.version, codename);
 
-                            verify_debian(
-                                &artifact_full_name,
-                                &args.version,
-                                codename,
-                                &args.debian_repo,
-                                &args.channel,
-                                args.signed_debian_repo,
-                                args.debug,
-                            )
-                            .await?;
-                            println!();
-                        }
-
-                        if !args.only_debians {
-                            println!(
-                                "      📋  Verifying: {} docker on {}",
-                                artifact.as_str(),
-                                calculate_docker_tag(
-                                    args.docker_io,
-                                    artifact.as_str(),
-                                    &args.version,
-                                    codename,
+                            verified.extend(
+                                verify_debian(
+                                    debian_config(
+                                        &artifact_full_name,
+                                        codename,
+                                        network_manifest.as_ref(),
+                                    ),
                                     Some(network),
-                                    None,
-                                    None,
+                                    &verify_timeout,
+                                    &stream,
+                                    args.debug,
                                 )
+                                .await?,
                             );
+                            textln!();
+                        }
 
-                            verify_docker(
+                        if !args.only_debians && has_docker {
+                            verify_docker_on_registries(
+                                &mut registry_results,
+                                &registries,
+                                |docker_io| {
+                                    calculate_docker_tag(
+                                        docker_io,
+                                        artifact.as_str(),
+                                        &args.version,
+                                        codename,
+                                        Some(network),
+                                        None,
+                                        None,
+                                    )
+                                },
                                 artifact.as_str(),
                                 &args.version,
                                 codename,
                                 &docker_suffix_combined,
-                                repo,
+                                Some(network),
+                                network_manifest.as_ref(),
+                                &verify_timeout,
+                                &stream,
                                 args.debug,
                             )
                             .await?;
-                            println!();
+                            textln!();
                         }
                     }
                 }
 
                 Artifact::MinaRosetta => {
                     for network in &networks {
-                        let artifact_full_name =
-                            get_artifact_with_suffix(artifact.as_str(), Some(network), None);
+                        let network_manifest =
+                            manifest.as_ref().map(|m| m.for_network(Some(network)));
+                        let artifact_full_name = get_debian_package_name(
+                            artifact.as_str(),
+                            Some(network),
+                            None,
+                            args.daemon_debian_name.as_deref(),
+                        );
                         let docker_suffix_combined =
                             combine_docker_suffixes(network, args.docker_suffix.as_deref());
 
                         if !args.only_dockers {
-                            println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
+                            textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
                                      artifact_full_name, args.channel, args.version, codename);
-                            println!();
-
-                            verify_debian(
-                                &artifact_full_name,
-                                &args.version,
-                                codename,
-                                &args.debian_repo,
-                                &args.channel,
-                                args.signed_debian_repo,
-                                args.debug,
-                            )
-                            .await?;
-                            println!();
-                        }
+                            textln!();
 
-                        if !args.only_debians {
-                            println!(
-                                "      📋  Verifying: {} docker on {}",
-                                artifact.as_str(),
-                                calculate_docker_tag(
-                                    args.docker_io,
-                                    &artifact_full_name,
-                                    &args.version,
-                                    codename,
-                                    None,
-                                    None,
-                                    None,
+                            verified.extend(
+                                verify_debian(
+                                    debian_config(
+                                        &artifact_full_name,
+                                        codename,
+                                        network_manifest.as_ref(),
+                                    ),
+                                    Some(network),
+                                    &verify_timeout,
+                                    &stream,
+                                    args.debug,
                                 )
+                                .await?,
                             );
-                            println!();
+                            textln!();
+                        }
 
-                            verify_docker(
+                        if !args.only_debians && has_docker {
+                            verify_docker_on_registries(
+                                &mut registry_results,
+                                &registries,
+                                |docker_io| {
+                                    calculate_docker_tag(
+                                        docker_io,
+                                        &artifact_full_name,
+                                        &args.version,
+                                        codename,
+                                        None,
+                                        None,
+                                        None,
+                                    )
+                                },
                                 artifact.as_str(),
                                 &args.version,
                                 codename,
                                 &docker_suffix_combined,
-                                repo,
+                                Some(network),
+                                network_manifest.as_ref(),
+                                &verify_timeout,
+                                &stream,
                                 args.debug,
                             )
                             .await?;
-                            println!();
+                            textln!();
                         }
                     }
                 }
 
                 Artifact::Minimina => {
                     if !args.only_dockers {
-                        println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename",
+                        textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename",
                                  artifact.as_str(), args.channel, args.version, codename);
-                        verify_debian(
-                            artifact.as_str(),
-                            &args.version,
-                            codename,
-                            &args.debian_repo,
-                            &args.channel,
-                            args.signed_debian_repo,
-                            args.debug,
-                        )
-                        .await?;
-                    }
-                    if !args.only_debians {
-                        println!(
-                            "    ℹ️  There is no {} docker image. skipping",
-                            artifact.as_str()
+                        verified.extend(
+                            verify_debian(
+                                debian_config(artifact.as_str(), codename, manifest.as_ref()),
+                                None,
+                                &verify_timeout,
+                                &stream,
+                                args.debug,
+                            )
+                            .await?,
                         );
                     }
                 }
@@ -203,26 +399,30 @@ pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
                 | Artifact::MinaPostforkMesa
                 | Artifact::MinaPreforkMesa => {
                     for network in &networks {
-                        let artifact_full_name =
-                            get_artifact_with_suffix(artifact.as_str(), Some(network), None);
+                        let network_manifest =
+                            manifest.as_ref().map(|m| m.for_network(Some(network)));
+                        let artifact_full_name = get_debian_package_name(
+                            artifact.as_str(),
+                            Some(network),
+                            None,
+                            args.daemon_debian_name.as_deref(),
+                        );
                         if !args.only_dockers {
-                            println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename",
+                            textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename",
                                      artifact_full_name, args.channel, args.version, codename);
-                            verify_debian(
-                                &artifact_full_name,
-                                &args.version,
-                                codename,
-                                &args.debian_repo,
-                                &args.channel,
-                                args.signed_debian_repo,
-                                args.debug,
-                            )
-                            .await?;
-                        }
-                        if !args.only_debians {
-                            println!(
-                                "    ℹ️  There is no {} docker image. skipping",
-                                artifact.as_str()
+                            verified.extend(
+                                verify_debian(
+                                    debian_config(
+                                        &artifact_full_name,
+                                        codename,
+                                        network_manifest.as_ref(),
+                                    ),
+                                    Some(network),
+                                    &verify_timeout,
+                                    &stream,
+                                    args.debug,
+                                )
+                                .await?,
                             );
                         }
                     }
@@ -230,48 +430,61 @@ pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
 
                 Artifact::MinaGeneric | Artifact::RosettaGeneric => {
                     for network in &networks {
-                        let artifact_full_name =
-                            get_artifact_with_suffix(artifact.as_str(), Some(network), None);
+                        let network_manifest =
+                            manifest.as_ref().map(|m| m.for_network(Some(network)));
+                        let artifact_full_name = get_debian_package_name(
+                            artifact.as_str(),
+                            Some(network),
+                            None,
+                            args.daemon_debian_name.as_deref(),
+                        );
                         let docker_suffix_combined =
                             combine_docker_suffixes(network, args.docker_suffix.as_deref());
 
                         if !args.only_dockers {
-                            println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename",
+                            textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename",
                                      artifact_full_name, args.channel, args.version, codename);
-                            verify_debian(
-                                &artifact_full_name,
-                                &args.version,
-                                codename,
-                                &args.debian_repo,
-                                &args.channel,
-                                args.signed_debian_repo,
-                                args.debug,
-                            )
-                            .await?;
+                            verified.extend(
+                                verify_debian(
+                                    debian_config(
+                                        &artifact_full_name,
+                                        codename,
+                                        network_manifest.as_ref(),
+                                    ),
+                                    Some(network),
+                                    &verify_timeout,
+                                    &stream,
+                                    args.debug,
+                                )
+                                .await?,
+                            );
                         }
 
-                        if !args.only_debians {
+                        if !args.only_debians && has_docker {
                             // calculate_docker_tag applies the get_docker_image_name
                             // mapping (mina-generic -> mina-daemon, rosetta-generic -> mina-rosetta).
-                            println!(
-                                "      📋  Verifying: {} docker on {}",
-                                artifact.as_str(),
-                                calculate_docker_tag(
-                                    args.docker_io,
-                                    artifact.as_str(),
-                                    &args.version,
-                                    codename,
-                                    Some(network),
-                                    None,
-                                    None,
-                                )
-                            );
-                            verify_docker(
-                                crate::artifacts::get_docker_image_name(artifact.as_str()),
+                            verify_docker_on_registries(
+                                &mut registry_results,
+                                &registries,
+                                |docker_io| {
+                                    calculate_docker_tag(
+                                        docker_io,
+                                        artifact.as_str(),
+                                        &args.version,
+                                        codename,
+                                        Some(network),
+                                        None,
+                                        None,
+                                    )
+                                },
+                                get_docker_image_name(artifact.as_str()),
                                 &args.version,
                                 codename,
                                 &docker_suffix_combined,
-                                repo,
+                                Some(network),
+                                network_manifest.as_ref(),
+                                &verify_timeout,
+                                &stream,
                                 args.debug,
                             )
                             .await?;
@@ -281,55 +494,66 @@ pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
 
                 Artifact::MinaDaemon => {
                     for network in &networks {
-                        let artifact_full_name =
-                            get_artifact_with_suffix(artifact.as_str(), Some(network), None);
+                        let network_manifest =
+                            manifest.as_ref().map(|m| m.for_network(Some(network)));
+                        let artifact_full_name = get_debian_package_name(
+                            artifact.as_str(),
+                            Some(network),
+                            None,
+                            args.daemon_debian_name.as_deref(),
+                        );
                         let docker_suffix_combined =
                             combine_docker_suffixes(network, args.docker_suffix.as_deref());
 
                         if !args.only_dockers {
-                            println!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
+                            textln!("     📋  Verifying: {} debian on {} channel with {} version for {} codename", 
                                      artifact_full_name, args.channel, args.version, codename);
-                            println!();
+                            textln!();
 
-                            verify_debian(
-                                &artifact_full_name,
-                                &args.version,
-                                codename,
-                                &args.debian_repo,
-                                &args.channel,
-                                args.signed_debian_repo,
-                                args.debug,
-                            )
-                            .await?;
-                            println!();
-                        }
-
-                        if !args.only_debians {
-                            println!(
-                                "      📋  Verifying: {} docker on {}",
-                                artifact.as_str(),
-                                calculate_docker_tag(
-                                    args.docker_io,
-                                    &artifact_full_name,
-                                    &args.version,
-                                    codename,
-                                    None,
-                                    None,
-                                    None,
+                            verified.extend(
+                                verify_debian(
+                                    debian_config(
+                                        &artifact_full_name,
+                                        codename,
+                                        network_manifest.as_ref(),
+                                    ),
+                                    Some(network),
+                                    &verify_timeout,
+                                    &stream,
+                                    args.debug,
                                 )
+                                .await?,
                             );
-                            println!();
+                            textln!();
+                        }
 
-                            verify_docker(
+                        if !args.only_debians && has_docker {
+                            verify_docker_on_registries(
+                                &mut registry_results,
+                                &registries,
+                                |docker_io| {
+                                    calculate_docker_tag(
+                                        docker_io,
+                                        &artifact_full_name,
+                                        &args.version,
+                                        codename,
+                                        None,
+                                        None,
+                                        None,
+                                    )
+                                },
                                 artifact.as_str(),
                                 &args.version,
                                 codename,
                                 &docker_suffix_combined,
-                                repo,
+                                Some(network),
+                                network_manifest.as_ref(),
+                                &verify_timeout,
+                                &stream,
                                 args.debug,
                             )
                             .await?;
-                            println!();
+                            textln!();
                         }
                     }
                 }
@@ -337,20 +561,327 @@ pub async fn execute(args: VerifyArgs) -> ManagerResult<()> {
         }
     }
 
-    println!("{}", " ✅  Verification done.".green());
+    let results = stream.finish();
+    let timeouts = verify_timeout.report();
+    let registries_outcome = if stream.enabled {
+        // Each check's result is already on the stream
+        registry_results.failure(&registries)
+    } else {
+        print_summary(&results);
+        registry_results.report(&registries)
+    };
+    check_results(&results)?;
+    registries_outcome?;
+    timeouts?;
+
+    if args.check_layer_consistency && !args.only_debians {
+        for registry in &registries {
+            check_layer_consistency(&args, &artifacts, &networks, &codenames, registry.repo())
+                .await?;
+        }
+    }
+
+    if args.verify_cross_consistency {
+        for registry in &registries {
+            check_cross_consistency(
+                &args,
+                &artifacts,
+                &networks,
+                &codenames,
+                registry.repo(),
+                &verified,
+            )
+            .await?;
+        }
+    }
+
+    apply_baseline(&args, &verified)?;
+
+    if !stream.enabled {
+        textln!("{}", " ✅  Verification done.".green());
+    }
+    Ok(results)
+}
+
+/// Print one row per check, failures followed by their errors
+fn print_summary(results: &[VerificationResult]) {
+    if results.is_empty() {
+        return;
+    }
+    textln!(" 📊  Verification summary:");
+    text!("{}", summary_table(results));
+    textln!();
+}
+
+/// Fixed-width table of `results`: kind, artifact, codename, network and
+/// result, with each failure's error indented under its row
+fn summary_table(results: &[VerificationResult]) -> String {
+    let artifact_width = results
+        .iter()
+        .map(|r| r.artifact.len())
+        .chain(["ARTIFACT".len()])
+        .max()
+        .unwrap_or_default();
+    let mut table = format!(
+        "    {:<6}  {:<artifact_width$}  {:<8}  {:<8}  RESULT\n",
+        "KIND", "ARTIFACT", "CODENAME", "NETWORK"
+    );
+    for result in results {
+        table.push_str(&format!(
+            "    {:<6}  {:<artifact_width$}  {:<8}  {:<8}  {}\n",
+            result.kind,
+            result.artifact,
+            result.codename,
+            result.network.as_deref().unwrap_or("-"),
+            result.result
+        ));
+        if let Some(error) = &result.error {
+            table.push_str(&format!("        {}: {}\n", result.target, error));
+        }
+    }
+    table
+}
+
+/// Fail if any check failed. Timeouts are left to [`VerifyTimeout`] and
+/// [`RegistryResults`], which report them as such.
+fn check_results(results: &[VerificationResult]) -> ManagerResult<()> {
+    let failed: Vec<&str> = results
+        .iter()
+        .filter(|r| r.result == "failed")
+        .map(|r| r.target.as_str())
+        .collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(ManagerError::ValidationError(format!(
+        "{} of {} verifications failed: {}",
+        failed.len(),
+        results.len(),
+        failed.join(", ")
+    )))
+}
+
+/// Save the debians verified in this run as a baseline and/or fail when
+/// they differ from an earlier one. A requested baseline is written before
+/// the comparison fails, so it always reflects this run.
+fn apply_baseline(args: &VerifyArgs, verified: &[VerifiedPackage]) -> ManagerResult<()> {
+    let current = Baseline {
+        packages: verified.to_vec(),
+    };
+    if let Some(path) = &args.save_baseline {
+        current.save(path)?;
+    }
+
+    let Some(path) = &args.compare_baseline else {
+        return Ok(());
+    };
+    let changes = Baseline::load(path)?.compare(&current, args.baseline_size_threshold);
+    if changes.is_empty() {
+        textln!(" ✅ No changes from baseline {}", path);
+        return Ok(());
+    }
+
+    textln!(" ⚠️  Changes from baseline {}:", path);
+    for change in &changes {
+        textln!("    {}", change);
+    }
+    Err(ManagerError::ValidationError(format!(
+        "{} change(s) from baseline {}",
+        changes.len(),
+        path
+    )))
+}
+
+/// Compare the layer stacks of each network's image for every docker
+/// artifact/codename pair, failing if they don't share a base.
+async fn check_layer_consistency(
+    args: &VerifyArgs,
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    repo: &str,
+) -> ManagerResult<()> {
+    if networks.len() < 2 {
+        textln!("    ℹ️  Layer consistency check needs at least two networks. skipping");
+        return Ok(());
+    }
+
+    for artifact in artifacts {
+        if !artifact.has_docker_image() {
+            continue;
+        }
+
+        for codename in codenames {
+            textln!(
+                "     📋  Checking layer consistency: {} docker for {} codename across {}",
+                artifact.as_str(),
+                codename,
+                networks.join(", ")
+            );
+            let images: Vec<(String, String)> = networks
+                .iter()
+                .map(|network| {
+                    let image = docker_image_tag(
+                        repo,
+                        artifact.as_str(),
+                        &args.version,
+                        codename,
+                        &combine_docker_suffixes(network, args.docker_suffix.as_deref()),
+                    );
+                    (network.clone(), image)
+                })
+                .collect();
+
+            verify_layer_consistency(&images).await?;
+            textln!();
+        }
+    }
+
+    Ok(())
+}
+
+/// Verify the docker images of a publish/promote `--emit-manifest` file,
+/// pinned to the digests recorded at publish time. A tag that no longer
+/// points at its recorded digest fails.
+async fn verify_from_manifest(
+    path: &str,
+    verify_manifest: Option<&VerifyManifest>,
+    stream: &CheckStream<'_>,
+) -> ManagerResult<()> {
+    let published = RunManifest::load(path)?;
+    if published.dockers.is_empty() {
+        textln!("    ℹ️  {} lists no docker images. skipping", path);
+    }
+
+    for docker in &published.dockers {
+        textln!(
+            "     📋  Verifying: {} docker from {}",
+            docker.reference,
+            path
+        );
+        let image = match &docker.digest {
+            Some(digest) => {
+                let current = resolve_digest(&docker.reference).await;
+                check_digest_drift(&docker.reference, digest, current.as_deref())?;
+                pinned_reference(&docker.reference, digest)
+            }
+            None => {
+                textln!(
+                    "    ⚠️  No digest recorded for {}, verifying the tag",
+                    docker.reference
+                );
+                docker.reference.clone()
+            }
+        };
+        let verify_manifest = verify_manifest.map(|m| m.for_network(docker.network.as_deref()));
+        let record = VerificationResult::new(
+            &docker.artifact,
+            "docker",
+            &docker.codename,
+            docker.network.as_deref(),
+            &image,
+        );
+        let outcome = stream
+            .observe(
+                record,
+                verify_docker_reference(&docker.artifact, &image, verify_manifest.as_ref()),
+            )
+            .await;
+        stream.settle(outcome.map(Some))?;
+        textln!();
+    }
+
+    Ok(())
+}
+
+/// Compare the version of each docker image's debian with the one installed
+/// from the repository during this run (`verified`).
+async fn check_cross_consistency(
+    args: &VerifyArgs,
+    artifacts: &[Artifact],
+    networks: &[String],
+    codenames: &[String],
+    repo: &str,
+    verified: &[VerifiedPackage],
+) -> ManagerResult<()> {
+    for artifact in artifacts {
+        if !artifact.has_docker_image() {
+            continue;
+        }
+
+        for network in networks {
+            let package = get_debian_package_name(
+                artifact.as_str(),
+                Some(network),
+                None,
+                args.daemon_debian_name.as_deref(),
+            );
+            for codename in codenames {
+                let Some(debian_version) = verified
+                    .iter()
+                    .rev()
+                    .find(|v| v.package == package && v.codename == *codename)
+                    .map(|v| &v.version)
+                else {
+                    textln!(
+                        "    ℹ️  No installed version recorded for {} on {}. skipping",
+                        package,
+                        codename
+                    );
+                    continue;
+                };
+                let image = docker_image_tag(
+                    repo,
+                    artifact.as_str(),
+                    &args.version,
+                    codename,
+                    &combine_docker_suffixes(network, args.docker_suffix.as_deref()),
+                );
+                textln!(
+                    "     📋  Cross-checking versions: {} debian and {}",
+                    package,
+                    image
+                );
+                let docker_version = docker_installed_version(&image, &package).await?;
+                verification::check_cross_consistency(
+                    artifact.as_str(),
+                    &args.version,
+                    debian_version,
+                    &docker_version,
+                )?;
+                textln!("    ✅ Both report {}", debian_version);
+            }
+        }
+    }
+
     Ok(())
 }
 
 async fn verify_debian(
-    artifact: &str,
-    version: &str,
-    codename: &str,
-    debian_repo: &str,
-    channel: &str,
-    signed: bool,
+    config: DebianVerifyConfig,
+    network: Option<&str>,
+    timeout: &VerifyTimeout,
+    stream: &CheckStream<'_>,
     _debug: bool,
-) -> ManagerResult<()> {
-    verify_debian_package(artifact, version, debian_repo, codename, channel, signed).await
+) -> ManagerResult<Option<VerifiedPackage>> {
+    let what = format!(
+        "{}={} debian on {}",
+        config.package, config.version, config.codename
+    );
+    let record = VerificationResult::new(
+        &config.package,
+        "debian",
+        &config.codename,
+        network,
+        &format!("{}={}", config.package, config.version),
+    );
+    let outcome = stream
+        .observe(
+            record,
+            with_verify_timeout(timeout.limit, &what, verify_debian_package(config)),
+        )
+        .await;
+    stream.settle(timeout.absorb(&what, outcome))
 }
 
 async fn verify_docker(
@@ -359,7 +890,474 @@ async fn verify_docker(
     codename: &str,
     suffix: &str,
     repo: &str,
+    manifest: Option<&VerifyManifest>,
     _debug: bool,
 ) -> ManagerResult<()> {
-    verify_docker_image(artifact, version, repo, codename, suffix).await
+    verify_docker_image(artifact, version, repo, codename, suffix, manifest).await
+}
+
+/// Verify one docker image in every registry, printing the tag being checked
+/// and recording the outcome. With a single registry and `--fail-fast` a
+/// failure is returned straight away, as before `--docker-registries` existed.
+#[allow(clippy::too_many_arguments)]
+async fn verify_docker_on_registries(
+    results: &mut RegistryResults,
+    registries: &[DockerRegistry],
+    tag: impl Fn(bool) -> String,
+    image: &str,
+    version: &str,
+    codename: &str,
+    suffix: &str,
+    network: Option<&str>,
+    manifest: Option<&VerifyManifest>,
+    timeout: &VerifyTimeout,
+    stream: &CheckStream<'_>,
+    debug: bool,
+) -> ManagerResult<()> {
+    for registry in registries {
+        let tag = tag(registry.is_docker_io());
+        textln!("      📋  Verifying: {} docker on {}", image, tag);
+
+        let record = VerificationResult::new(image, "docker", codename, network, &tag);
+        let outcome = stream
+            .observe(
+                record,
+                with_verify_timeout(
+                    timeout.limit,
+                    &tag,
+                    verify_docker(
+                        image,
+                        version,
+                        codename,
+                        suffix,
+                        registry.repo(),
+                        manifest,
+                        debug,
+                    ),
+                ),
+            )
+            .await;
+        match outcome {
+            // A timeout never stops the matrix; it's reported with the rest
+            Err(e @ ManagerError::Timeout { .. }) => {
+                textln!("    ⏱️  {}", e.to_string().red());
+                results.record(*registry, tag, Err(e))
+            }
+            Err(e) if registries.len() == 1 && stream.fail_fast => return Err(e),
+            outcome => results.record(*registry, tag, outcome),
+        }
+    }
+    Ok(())
+}
+
+/// `--verify-timeout` for debian verifications, collecting the artifacts
+/// that ran out of time so the rest of the matrix still gets verified.
+/// Docker timeouts are collected per registry in [`RegistryResults`].
+struct VerifyTimeout {
+    limit: Option<Duration>,
+    timed_out: Mutex<Vec<String>>,
+}
+
+impl VerifyTimeout {
+    fn new(limit: Option<Duration>) -> Self {
+        Self {
+            limit,
+            timed_out: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Take the outcome of a verification run under the limit; a timeout is
+    /// recorded and swallowed as no value, any other failure returned as is
+    fn absorb<T>(&self, what: &str, outcome: ManagerResult<T>) -> ManagerResult<Option<T>> {
+        match outcome {
+            Err(e @ ManagerError::Timeout { .. }) => {
+                textln!("    ⏱️  {}", e.to_string().red());
+                self.timed_out.lock().unwrap().push(what.to_string());
+                Ok(None)
+            }
+            other => other.map(Some),
+        }
+    }
+
+    fn report(&self) -> ManagerResult<()> {
+        let timed_out = self.timed_out.lock().unwrap();
+        if timed_out.is_empty() {
+            return Ok(());
+        }
+        Err(ManagerError::Timeout {
+            operation: format!("Verification of {}", timed_out.join(", ")),
+            seconds: self.limit.map(|l| l.as_secs()).unwrap_or_default(),
+        })
+    }
+}
+
+/// One finished verification, returned from [`execute`] and printed as a
+/// JSON line by `--json-stream`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerificationResult {
+    /// Debian package or docker image verified
+    pub artifact: String,
+    /// `debian` or `docker`
+    pub kind: &'static str,
+    pub codename: String,
+    pub network: Option<String>,
+    /// `<package>=<version>` or the docker reference
+    pub target: String,
+    /// `passed`, `failed` or `timeout`
+    pub result: &'static str,
+    /// Seconds the check took
+    pub duration: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl VerificationResult {
+    fn new(
+        artifact: &str,
+        kind: &'static str,
+        codename: &str,
+        network: Option<&str>,
+        target: &str,
+    ) -> Self {
+        Self {
+            artifact: artifact.to_string(),
+            kind,
+            codename: codename.to_string(),
+            network: network.map(str::to_string),
+            target: target.to_string(),
+            result: "passed",
+            duration: 0.0,
+            error: None,
+        }
+    }
+
+    /// This check with `outcome`, taking `elapsed`
+    fn finish<T>(self, outcome: &ManagerResult<T>, elapsed: Duration) -> Self {
+        let result = match outcome {
+            Ok(_) => "passed",
+            Err(ManagerError::Timeout { .. }) => "timeout",
+            Err(_) => "failed",
+        };
+        Self {
+            result,
+            duration: (elapsed.as_secs_f64() * 1000.0).round() / 1000.0,
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+            ..self
+        }
+    }
+}
+
+/// `--json-stream`: a [`VerificationResult`] line on the original stdout per
+/// finished check. Every check is also recorded for `--output json` and
+/// collected for the summary.
+struct CheckStream<'a> {
+    enabled: bool,
+    /// Where the records go, stdout outside of tests
+    out: Option<Mutex<Box<dyn Write + Send>>>,
+    /// `--fail-fast`: stop at the first failed check
+    fail_fast: bool,
+    reporter: &'a Reporter,
+    results: Mutex<Vec<VerificationResult>>,
+}
+
+impl<'a> CheckStream<'a> {
+    fn new(out: Option<Box<dyn Write + Send>>, fail_fast: bool, reporter: &'a Reporter) -> Self {
+        Self {
+            enabled: out.is_some(),
+            out: out.map(Mutex::new),
+            fail_fast,
+            reporter,
+            results: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Run `verification`, then stream and record its outcome
+    async fn observe<F, T>(&self, record: VerificationResult, verification: F) -> ManagerResult<T>
+    where
+        F: std::future::Future<Output = ManagerResult<T>>,
+    {
+        let started = Instant::now();
+        let outcome = verification.await;
+        self.reporter.record(
+            &record.artifact,
+            &format!("{} {} {}", record.kind, record.codename, record.target),
+            &outcome,
+        );
+        let record = record.finish(&outcome, started.elapsed());
+        if let Some(out) = &self.out {
+            let mut out = out.lock().unwrap();
+            writeln!(out, "{}", serde_json::to_string(&record)?)?;
+            out.flush()?;
+        }
+        self.results.lock().unwrap().push(record);
+        outcome
+    }
+
+    /// A check's outcome for the caller: with `--fail-fast` a failure stops
+    /// the run, otherwise it's already recorded and the matrix carries on
+    /// without a value
+    fn settle<T>(&self, outcome: ManagerResult<Option<T>>) -> ManagerResult<Option<T>> {
+        match outcome {
+            Err(e) if self.fail_fast => Err(e),
+            Err(e) => {
+                if !self.enabled {
+                    textln!("    ❌ {}", e.to_string().red());
+                }
+                Ok(None)
+            }
+            Ok(value) => Ok(value),
+        }
+    }
+
+    /// Every check recorded so far, in order
+    fn finish(&self) -> Vec<VerificationResult> {
+        std::mem::take(&mut *self.results.lock().unwrap())
+    }
+}
+
+/// Docker verification outcomes per registry, so one registry's failure
+/// doesn't hide the state of the others.
+#[derive(Debug, Default)]
+struct RegistryResults {
+    /// (registry, tag, error if verification failed)
+    entries: Vec<(DockerRegistry, String, Option<String>)>,
+}
+
+impl RegistryResults {
+    fn record(&mut self, registry: DockerRegistry, tag: String, outcome: ManagerResult<()>) {
+        self.entries
+            .push((registry, tag, outcome.err().map(|e| e.to_string())));
+    }
+
+    /// Verified count, total count and failed tags for `registry`
+    fn summary(&self, registry: DockerRegistry) -> (usize, usize, Vec<&str>) {
+        let entries: Vec<_> = self.entries.iter().filter(|e| e.0 == registry).collect();
+        let failed: Vec<&str> = entries
+            .iter()
+            .filter(|e| e.2.is_some())
+            .map(|e| e.1.as_str())
+            .collect();
+        (entries.len() - failed.len(), entries.len(), failed)
+    }
+
+    /// Print one line per registry and fail if any image failed anywhere
+    fn report(&self, registries: &[DockerRegistry]) -> ManagerResult<()> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        for registry in registries {
+            let (verified, total, failed) = self.summary(*registry);
+            if failed.is_empty() {
+                textln!(
+                    "{}",
+                    format!(
+                        " ✅  {}: {}/{} docker images verified",
+                        registry.as_str(),
+                        verified,
+                        total
+                    )
+                    .green()
+                );
+            } else {
+                textln!(
+                    "{}",
+                    format!(
+                        " ❌  {}: {}/{} docker images verified, failed: {}",
+                        registry.as_str(),
+                        verified,
+                        total,
+                        failed.join(", ")
+                    )
+                    .red()
+                );
+            }
+        }
+
+        for (_, tag, error) in &self.entries {
+            if let Some(error) = error {
+                textln!("    {}: {}", tag, error);
+            }
+        }
+
+        self.failure(registries)
+    }
+
+    /// Fail if any image failed in any registry, without printing
+    fn failure(&self, registries: &[DockerRegistry]) -> ManagerResult<()> {
+        let failures: Vec<String> = registries
+            .iter()
+            .filter_map(|registry| {
+                let (_, _, failed) = self.summary(*registry);
+                (!failed.is_empty())
+                    .then(|| format!("{} ({})", registry.as_str(), failed.join(", ")))
+            })
+            .collect();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ManagerError::ValidationError(format!(
+                "Docker verification failed in {}",
+                failures.join("; ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_results_aggregate_per_registry() {
+        let registries = vec![DockerRegistry::Gcr, DockerRegistry::DockerIo];
+        let mut results = RegistryResults::default();
+        results.record(DockerRegistry::Gcr, "gcr/a".to_string(), Ok(()));
+        results.record(DockerRegistry::DockerIo, "dockerio/a".to_string(), Ok(()));
+        results.record(DockerRegistry::Gcr, "gcr/b".to_string(), Ok(()));
+        results.record(
+            DockerRegistry::DockerIo,
+            "dockerio/b".to_string(),
+            Err(ManagerError::ArtifactNotFound(
+                "manifest unknown".to_string(),
+            )),
+        );
+
+        assert_eq!(results.summary(DockerRegistry::Gcr), (2, 2, vec![]));
+        assert_eq!(
+            results.summary(DockerRegistry::DockerIo),
+            (1, 2, vec!["dockerio/b"])
+        );
+
+        let err = results.report(&registries).unwrap_err().to_string();
+        assert!(err.contains("dockerio (dockerio/b)"));
+        assert!(!err.contains("gcr"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_timeout_records_and_continues() {
+        let timeout = VerifyTimeout::new(Some(Duration::from_millis(50)));
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        };
+        let limit = timeout.limit;
+        let what = "mina-devnet debian";
+        assert!(timeout
+            .absorb(what, with_verify_timeout(limit, what, slow).await)
+            .is_ok());
+        let what = "mina-mainnet debian";
+        assert!(timeout
+            .absorb(
+                what,
+                with_verify_timeout(limit, what, async { Ok(()) }).await
+            )
+            .is_ok());
+        let what = "mina-logproc debian";
+        let failing = async { Err::<(), _>(ManagerError::CommandFailed("apt".to_string())) };
+        assert!(matches!(
+            timeout.absorb(what, with_verify_timeout(limit, what, failing).await),
+            Err(ManagerError::CommandFailed(_))
+        ));
+
+        let err = timeout.report().unwrap_err();
+        assert!(matches!(
+            &err,
+            ManagerError::Timeout { operation, .. }
+                if operation == "Verification of mina-devnet debian"
+        ));
+    }
+
+    #[test]
+    fn test_check_record_json() {
+        let record = VerificationResult::new(
+            "mina-devnet",
+            "debian",
+            "bullseye",
+            Some("devnet"),
+            "mina-devnet=3.0.1",
+        );
+        assert_eq!(
+            serde_json::to_string(&record.clone().finish(&Ok(()), Duration::from_millis(1500)))
+                .unwrap(),
+            r#"{"artifact":"mina-devnet","kind":"debian","codename":"bullseye","network":"devnet","target":"mina-devnet=3.0.1","result":"passed","duration":1.5}"#
+        );
+
+        let failed = record.clone().finish(
+            &Err::<(), _>(ManagerError::CommandFailed("apt".to_string())),
+            Duration::from_secs(2),
+        );
+        assert_eq!(failed.result, "failed");
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("Command execution failed: apt")
+        );
+        let timed_out = record.finish(
+            &Err::<(), _>(ManagerError::Timeout {
+                operation: "Verification of mina-devnet".to_string(),
+                seconds: 60,
+            }),
+            Duration::from_secs(60),
+        );
+        assert_eq!(timed_out.result, "timeout");
+    }
+
+    #[tokio::test]
+    async fn test_check_stream_collects_and_continues() {
+        let reporter = Reporter::new(crate::output::OutputFormat::Text);
+        let record = |artifact: &str| {
+            VerificationResult::new(
+                artifact,
+                "debian",
+                "bullseye",
+                Some("devnet"),
+                &format!("{}=3.0.1", artifact),
+            )
+        };
+        let failing = || async { Err::<(), _>(ManagerError::CommandFailed("apt".to_string())) };
+
+        let streamed = tempfile::NamedTempFile::new().unwrap();
+        let stream = CheckStream::new(Some(Box::new(streamed.reopen().unwrap())), false, &reporter);
+        let outcome = stream.observe(record("mina-devnet"), failing()).await;
+        assert_eq!(stream.settle(outcome.map(Some)).unwrap(), None);
+        let outcome = stream
+            .observe(record("mina-logproc"), async { Ok(()) })
+            .await;
+        assert_eq!(stream.settle(outcome.map(Some)).unwrap(), Some(()));
+
+        let results = stream.finish();
+        assert_eq!(
+            results.iter().map(|r| r.result).collect::<Vec<_>>(),
+            ["failed", "passed"]
+        );
+        let lines = std::fs::read_to_string(streamed.path()).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        assert!(lines.starts_with("{\"artifact\":\"mina-devnet\""));
+        let err = check_results(&results).unwrap_err().to_string();
+        assert!(err.contains("1 of 2 verifications failed: mina-devnet=3.0.1"));
+
+        let table = summary_table(&results);
+        assert!(table.starts_with("    KIND    ARTIFACT      CODENAME  NETWORK   RESULT\n"));
+        assert!(table.contains("    debian  mina-devnet   bullseye  devnet    failed\n"));
+        assert!(table.contains("        mina-devnet=3.0.1: Command execution failed: apt\n"));
+        assert!(table.contains("    debian  mina-logproc  bullseye  devnet    passed\n"));
+
+        let stream = CheckStream::new(None, true, &reporter);
+        let outcome = stream.observe(record("mina-devnet"), failing()).await;
+        assert!(stream.settle(outcome.map(Some)).is_err());
+        assert!(check_results(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_registry_results_all_passing() {
+        let mut results = RegistryResults::default();
+        assert!(results.report(&[DockerRegistry::Gcr]).is_ok());
+
+        results.record(DockerRegistry::Gcr, "gcr/a".to_string(), Ok(()));
+        results.record(DockerRegistry::DockerIo, "dockerio/a".to_string(), Ok(()));
+        assert!(results
+            .report(&[DockerRegistry::Gcr, DockerRegistry::DockerIo])
+            .is_ok());
+    }
 }
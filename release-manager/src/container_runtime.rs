@@ -0,0 +1,79 @@
+//! `--container-runtime`: which CLI runs containers, `docker` or its
+//! drop-in `podman` on hardened CI images that don't ship docker.
+
+use clap::ValueEnum;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+        }
+    }
+}
+
+static RUNTIME: OnceLock<ContainerRuntime> = OnceLock::new();
+
+/// Set the process-wide runtime, detecting it from PATH when `None`; only
+/// the first call takes effect
+pub fn configure(runtime: Option<ContainerRuntime>) {
+    let _ = RUNTIME.set(runtime.unwrap_or_else(|| detect(on_path)));
+}
+
+/// The configured runtime, `docker` until [`configure`] is called
+pub fn runtime() -> ContainerRuntime {
+    RUNTIME.get().copied().unwrap_or(ContainerRuntime::Docker)
+}
+
+/// Program to run container commands with
+pub fn container_runtime() -> &'static str {
+    runtime().as_str()
+}
+
+/// Whether the runtime has `buildx imagetools` for registry-side manifest
+/// work; podman doesn't, so that goes through skopeo instead
+pub fn has_imagetools() -> bool {
+    runtime() == ContainerRuntime::Docker
+}
+
+/// `docker` when it's installed, else `podman` when that is, else `docker`
+/// so the prerequisite check reports what's missing
+fn detect(installed: impl Fn(&str) -> bool) -> ContainerRuntime {
+    if !installed("docker") && installed("podman") {
+        ContainerRuntime::Podman
+    } else {
+        ContainerRuntime::Docker
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| is_file(&dir.join(program))))
+        .unwrap_or(false)
+}
+
+fn is_file(path: &Path) -> bool {
+    path.metadata().map(|m| m.is_file()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_container_runtime() {
+        assert_eq!(detect(|_| true), ContainerRuntime::Docker);
+        assert_eq!(detect(|p| p == "podman"), ContainerRuntime::Podman);
+        assert_eq!(detect(|p| p == "docker"), ContainerRuntime::Docker);
+        assert_eq!(detect(|_| false), ContainerRuntime::Docker);
+        assert_eq!(ContainerRuntime::Podman.as_str(), "podman");
+    }
+}
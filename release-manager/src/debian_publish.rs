@@ -1,9 +1,315 @@
+use crate::artifacts::debian_version_cmp;
+use crate::commands::validate::{find_package, parse_packages_file, PRESENCE_INDEX_ARCH};
 use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::trace::TracedCommand;
+use crate::utils::{stderr_excerpt, within};
 use chrono::NaiveDateTime;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use std::future::Future;
 use std::path::Path;
+use std::time::Duration;
 use tokio::process::Command as AsyncCommand;
 
+/// How old a deb-s3 lockfile must be before a failed upload removes it,
+/// unless `--lockfile-max-age` is set
+pub const DEFAULT_LOCKFILE_MAX_AGE_SECS: u64 = 300;
+
+/// `--debian-publisher`: where publish sends debians
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DebianPublisherKind {
+    /// An S3 bucket, through deb-s3
+    #[default]
+    #[value(name = "deb-s3")]
+    DebS3,
+    /// A JFrog Artifactory Debian repository
+    Artifactory,
+}
+
+impl DebianPublisherKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DebianPublisherKind::DebS3 => "deb-s3",
+            DebianPublisherKind::Artifactory => "artifactory",
+        }
+    }
+}
+
+/// How `deb-s3 upload` guards the repository against concurrent writers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Pass `--lock`. deb-s3 has no timeout flag of its own, so an optional
+    /// timeout (seconds) is enforced around the whole upload instead.
+    Lock { timeout_secs: Option<u64> },
+    /// Skip `--lock` entirely. Only safe when uploads to the same
+    /// bucket/codename/component are serialized externally: two concurrent
+    /// unlocked uploads can overwrite each other's Packages manifest and
+    /// silently drop packages from the index.
+    NoLock,
+}
+
+impl Default for LockMode {
+    fn default() -> Self {
+        LockMode::Lock { timeout_secs: None }
+    }
+}
+
+impl LockMode {
+    /// Build a mode from the `--no-lock` / `--lock-timeout` flags
+    pub fn from_args(no_lock: bool, lock_timeout: Option<u64>) -> Self {
+        if no_lock {
+            LockMode::NoLock
+        } else {
+            LockMode::Lock {
+                timeout_secs: lock_timeout,
+            }
+        }
+    }
+}
+
+/// Markers of a Ruby/RubyGems failure while loading deb-s3 itself, as
+/// opposed to deb-s3 running and reporting an upload problem.
+const RUBY_LOAD_FAILURE_MARKERS: &[&str] = &[
+    "(LoadError)",
+    "Gem::MissingSpecError",
+    "Gem::MissingSpecVersionError",
+    "Gem::ConflictError",
+    "Gem::LoadError",
+    "cannot load such file",
+];
+
+/// If `stderr` from a deb-s3 invocation looks like the gem failed to load,
+/// return the line that explains why.
+pub fn detect_broken_deb_s3(stderr: &str) -> Option<String> {
+    stderr
+        .lines()
+        .find(|line| RUBY_LOAD_FAILURE_MARKERS.iter().any(|m| line.contains(m)))
+        .map(|line| line.trim().to_string())
+}
+
+/// Markers of `deb-s3 verify` finding the manifest out of sync with the pool,
+/// which `--fix-manifests` repairs by dropping the dangling entries
+const MANIFEST_INCONSISTENCY_MARKERS: &[&str] = &[
+    "The following packages are missing",
+    "package(s) from the manifest",
+];
+
+/// Why `deb-s3 verify` failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyFailure {
+    /// The deb-s3 gem itself couldn't load
+    Broken(String),
+    /// The manifest lists packages the pool doesn't have
+    ManifestInconsistent {
+        /// The offending manifest entries, as deb-s3 printed them
+        discrepancies: Vec<String>,
+        output: String,
+    },
+    Other(String),
+}
+
+impl VerifyFailure {
+    pub fn into_error(self) -> ManagerError {
+        match self {
+            VerifyFailure::Broken(cause) => ManagerError::DebS3Broken(cause),
+            VerifyFailure::ManifestInconsistent { output, .. } | VerifyFailure::Other(output) => {
+                ManagerError::CommandFailed(format!("deb-s3 verify failed: {}", output))
+            }
+        }
+    }
+}
+
+/// Classify the output of a failed `deb-s3 verify`. The entries listed
+/// after a manifest inconsistency marker are collected as discrepancies.
+pub fn classify_verify_failure(stdout: &str, stderr: &str) -> VerifyFailure {
+    if let Some(cause) = detect_broken_deb_s3(stderr) {
+        return VerifyFailure::Broken(cause);
+    }
+
+    let output = format!("{}{}", stdout, stderr);
+    let mut lines = output.lines();
+    if !lines.by_ref().any(|line| {
+        MANIFEST_INCONSISTENCY_MARKERS
+            .iter()
+            .any(|m| line.contains(m))
+    }) {
+        return VerifyFailure::Other(stderr.to_string());
+    }
+
+    let discrepancies = lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take_while(|line| !line.starts_with('>'))
+        .map(str::to_string)
+        .collect();
+    VerifyFailure::ManifestInconsistent {
+        discrepancies,
+        output,
+    }
+}
+
+/// Whether a verify failure should be followed by a `--fix-manifests` pass:
+/// only manifest inconsistencies, only when enabled, and only once
+pub fn should_auto_fix(failure: &VerifyFailure, enabled: bool, already_fixed: bool) -> bool {
+    enabled && !already_fixed && matches!(failure, VerifyFailure::ManifestInconsistent { .. })
+}
+
+/// Error for a failed deb-s3 run: [`ManagerError::DebS3Broken`] when the
+/// gem itself couldn't load, otherwise a regular `CommandFailed`.
+pub fn deb_s3_error(context: &str, stdout: &str, stderr: &str) -> ManagerError {
+    match detect_broken_deb_s3(stderr) {
+        Some(cause) => ManagerError::DebS3Broken(cause),
+        None => ManagerError::CommandFailed(format!(
+            "{}. Stdout: {}, Stderr: {}",
+            context,
+            stderr_excerpt(stdout),
+            stderr_excerpt(stderr)
+        )),
+    }
+}
+
+/// Suffix of the hidden component that `--staged` publishes land in first
+pub const STAGING_SUFFIX: &str = "-staging";
+
+/// Staging component for a live channel, e.g. `unstable` -> `unstable-staging`
+pub fn staging_component(channel: &str) -> String {
+    format!("{}{}", channel, STAGING_SUFFIX)
+}
+
+/// Run a staged publish: upload to staging, verify staging, then swap into
+/// the live channel. Each step only starts once the previous one succeeded,
+/// so a failed upload or verification leaves the live channel untouched.
+pub async fn run_staged<U, V, S, T>(upload: U, verify: V, swap: S) -> ManagerResult<()>
+where
+    U: Future<Output = ManagerResult<()>>,
+    V: Future<Output = ManagerResult<T>>,
+    S: Future<Output = ManagerResult<()>>,
+{
+    textln!("    🎭 Staged publish 1/3: uploading to staging component");
+    upload.await?;
+
+    textln!("    🎭 Staged publish 2/3: verifying staging component");
+    if let Err(e) = verify.await {
+        textln!("    ❌ Staging verification failed; live channel left untouched");
+        return Err(e);
+    }
+
+    textln!("    🎭 Staged publish 3/3: swapping into live channel");
+    swap.await
+}
+
+/// Versions to delete so that only the `keep` most recent (by Debian version
+/// ordering) remain. Returned newest first; duplicates (one entry per arch in
+/// `deb-s3 list`) are collapsed.
+pub fn versions_to_prune(versions: &[String], keep: usize) -> Vec<String> {
+    let mut sorted: Vec<String> = versions.to_vec();
+    sorted.sort_by(|a, b| debian_version_cmp(b, a));
+    sorted.dedup();
+    sorted.into_iter().skip(keep).collect()
+}
+
+/// One package line of a `deb-s3 list` listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageEntry {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+}
+
+/// Parse `deb-s3 list` output (`<name> <version> <arch>`, column-padded)
+/// into entries. Anything that isn't a package line (headers, `>>` progress
+/// messages, blank lines, warnings) is skipped rather than rejected.
+pub fn parse_deb_s3_list(output: &str) -> Vec<PackageEntry> {
+    output.lines().filter_map(parse_deb_s3_list_line).collect()
+}
+
+fn parse_deb_s3_list_line(line: &str) -> Option<PackageEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [name, version, arch] = fields.as_slice() else {
+        return None;
+    };
+
+    // Debian policy: lowercase alphanumerics plus `+-.`, starting alphanumeric
+    let valid_name = name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '-' | '.'));
+    // Versions (after an optional epoch) start with a digit
+    let upstream = version.split_once(':').map_or(*version, |(_, rest)| rest);
+    let valid_version = upstream.starts_with(|c: char| c.is_ascii_digit());
+    let valid_arch = !arch.is_empty()
+        && arch
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    (valid_name && valid_version && valid_arch).then(|| PackageEntry {
+        name: name.to_string(),
+        version: version.to_string(),
+        arch: arch.to_string(),
+    })
+}
+
+/// Versions of `package` in a `deb-s3 list` listing
+fn parse_listed_versions(listing: &str, package: &str) -> Vec<String> {
+    parse_deb_s3_list(listing)
+        .into_iter()
+        .filter(|entry| entry.name == package)
+        .map(|entry| entry.version)
+        .collect()
+}
+
+/// Package name from a `<name>_<version>.deb` path
+fn package_name_from_path(package_path: &str) -> Option<&str> {
+    Path::new(package_path)
+        .file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|f| f.split('_').next())
+        .filter(|name| !name.is_empty())
+}
+
+/// Architecture of the Packages index listing a `<name>_<version>_<arch>.deb`.
+/// `all` packages, and file names without an architecture, are looked up in
+/// the [`PRESENCE_INDEX_ARCH`] index, where deb-s3 lists them too.
+fn index_arch_from_path(package_path: &str) -> &str {
+    Path::new(package_path)
+        .file_stem()
+        .and_then(|f| f.to_str())
+        .and_then(|f| f.splitn(3, '_').nth(2))
+        .filter(|arch| !arch.is_empty() && *arch != "all")
+        .unwrap_or(PRESENCE_INDEX_ARCH)
+}
+
+/// Check the Packages `index` read from `location` lists `package` at
+/// `version`
+pub fn check_indexed(
+    index: &str,
+    package: &str,
+    version: &str,
+    location: &str,
+) -> ManagerResult<()> {
+    let entries = parse_packages_file(index);
+    if find_package(&entries, package, version).is_some() {
+        return Ok(());
+    }
+    let listed: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.package.as_deref() == Some(package))
+        .filter_map(|entry| entry.version.as_deref())
+        .collect();
+    Err(ManagerError::CommandFailed(format!(
+        "{}={} is not in {} after upload (listed versions: {})",
+        package,
+        version,
+        location,
+        if listed.is_empty() {
+            "none".to_string()
+        } else {
+            listed.join(", ")
+        }
+    )))
+}
+
 /// Configuration for Debian package publishing
 #[derive(Debug, Clone)]
 pub struct DebianPublishConfig {
@@ -13,6 +319,8 @@ pub struct DebianPublishConfig {
     pub version: String,
     /// S3 bucket for repository
     pub bucket: String,
+    /// AWS region of the bucket
+    pub s3_region: String,
     /// Codename (bullseye, focal, etc.)
     pub codename: String,
     /// Release channel (stable, unstable, etc.)
@@ -21,6 +329,23 @@ pub struct DebianPublishConfig {
     pub sign_key: Option<String>,
     /// Debug flag to enable verbose output
     pub debug: bool,
+    /// Repository locking behaviour
+    pub lock: LockMode,
+    /// Age in seconds past which a leftover lockfile is considered stale
+    pub lockfile_max_age_secs: u64,
+    /// `Suite:` written to the Release file. Defaults to the release channel;
+    /// rolling releases (e.g. `edge`) set it independently of the codename,
+    /// which still names the `dists/` directory.
+    pub suite: Option<String>,
+    /// On a verify failure caused by an inconsistent manifest, run
+    /// `deb-s3 verify --fix-manifests` and verify once more
+    pub auto_fix_manifests: bool,
+}
+
+/// Whether a lockfile last written at `lockfile_time` is more than
+/// `max_age_secs` old at `now`
+fn lockfile_is_stale(lockfile_time: DateTime<Utc>, now: DateTime<Utc>, max_age_secs: u64) -> bool {
+    now.signed_duration_since(lockfile_time).num_seconds() > max_age_secs as i64
 }
 
 /// Debian package publisher using deb-s3
@@ -34,9 +359,14 @@ impl DebianPublisher {
         Self { config }
     }
 
+    /// Suite written to the Release file
+    fn suite(&self) -> &str {
+        self.config.suite.as_deref().unwrap_or(&self.config.release)
+    }
+
     /// Remove stale lockfile from S3 repository
     pub async fn remove_lockfile(&self) -> ManagerResult<()> {
-        println!("    🔍 Checking lockfile status...");
+        textln!("    🔍 Checking lockfile status...");
 
         let lockfile_path = format!(
             "s3://{}/dists/{}/{}/binary-/lockfile",
@@ -45,27 +375,25 @@ impl DebianPublisher {
 
         // Check if lockfile exists and get its timestamp
         let mut ls_cmd = AsyncCommand::new("aws");
-        ls_cmd.args(&["s3", "ls", &lockfile_path]);
+        ls_cmd.args(self.aws_s3_args("ls", &lockfile_path));
 
-        let ls_output = ls_cmd.output().await.map_err(|e| {
-            ManagerError::CommandFailed(format!("Failed to execute aws s3 ls: {}", e))
-        })?;
+        let ls_output = ls_cmd.timed_output("Failed to execute aws s3 ls").await?;
 
         if !ls_output.status.success() {
-            println!("    ℹ️  No lockfile found");
+            textln!("    ℹ️  No lockfile found");
             return Ok(());
         }
 
         let ls_stdout = String::from_utf8_lossy(&ls_output.stdout);
         if ls_stdout.trim().is_empty() {
-            println!("    ℹ️  No lockfile found");
+            textln!("    ℹ️  No lockfile found");
             return Ok(());
         }
 
         // Parse the timestamp from ls output (format: "2023-12-01 14:30:45")
         let parts: Vec<&str> = ls_stdout.split_whitespace().collect();
         if parts.len() < 2 {
-            println!("    ⚠️  Could not parse lockfile timestamp. Deleting anyway...");
+            textln!("    ⚠️  Could not parse lockfile timestamp. Deleting anyway...");
             self.delete_lockfile(&lockfile_path).await?;
             return Err(ManagerError::CommandFailed(
                 "Could not get lockfile timestamp from S3 bucket. Check AWS credentials."
@@ -84,17 +412,20 @@ impl DebianPublisher {
 
         let now = Utc::now();
         let time_diff = now.signed_duration_since(lockfile_time).num_seconds();
+        let max_age = self.config.lockfile_max_age_secs;
 
-        if time_diff > 300 {
-            println!(
-                "    🕒 Lockfile is older than 5 minutes ({} seconds). Deleting...",
+        if lockfile_is_stale(lockfile_time, now, max_age) {
+            textln!(
+                "    🕒 Lockfile is older than {}s ({} seconds). Deleting...",
+                max_age,
                 time_diff
             );
             self.delete_lockfile(&lockfile_path).await?;
-            println!("    ✅ Lockfile deleted");
+            textln!("    ✅ Lockfile deleted");
         } else {
-            println!(
-                "    ⏰ Lockfile is younger than 5 minutes ({} seconds). Refusing to delete.",
+            textln!(
+                "    ⏰ Lockfile is younger than {}s ({} seconds). Refusing to delete.",
+                max_age,
                 time_diff
             );
             return Err(ManagerError::ValidationError(
@@ -106,22 +437,26 @@ impl DebianPublisher {
         Ok(())
     }
 
+    /// `aws s3 <action> <path>` against the repository bucket's region, so
+    /// lock cleanup reaches the bucket deb-s3 locked
+    fn aws_s3_args(&self, action: &str, path: &str) -> Vec<String> {
+        vec![
+            "s3".to_string(),
+            action.to_string(),
+            path.to_string(),
+            "--region".to_string(),
+            self.config.s3_region.clone(),
+        ]
+    }
+
     /// Delete lockfile from S3
     async fn delete_lockfile(&self, lockfile_path: &str) -> ManagerResult<()> {
         let mut rm_cmd = AsyncCommand::new("aws");
-        rm_cmd.args(&["s3", "rm", lockfile_path]);
-
-        let rm_output = rm_cmd.output().await.map_err(|e| {
-            ManagerError::CommandFailed(format!("Failed to execute aws s3 rm: {}", e))
-        })?;
+        rm_cmd.args(self.aws_s3_args("rm", lockfile_path));
 
-        if !rm_output.status.success() {
-            let stderr = String::from_utf8_lossy(&rm_output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "Failed to delete lockfile: {}",
-                stderr
-            )));
-        }
+        rm_cmd
+            .checked_output("Failed to execute aws s3 rm", "Failed to delete lockfile")
+            .await?;
 
         Ok(())
     }
@@ -130,12 +465,12 @@ impl DebianPublisher {
     pub async fn publish(&self) -> ManagerResult<()> {
         self.validate_config()?;
 
-        println!(" 📦 Publishing Debian package to S3 repository:");
-        println!("    📁 Package: {}", self.config.package_path);
-        println!("    🏷️  Version: {}", self.config.version);
-        println!("    🪣 Bucket: {}", self.config.bucket);
-        println!("    📋 Codename: {}", self.config.codename);
-        println!("    🚀 Release: {}", self.config.release);
+        textln!(" 📦 Publishing Debian package to S3 repository:");
+        textln!("    📁 Package: {}", self.config.package_path);
+        textln!("    🏷️  Version: {}", self.config.version);
+        textln!("    🪣 Bucket: {}", self.config.bucket);
+        textln!("    📋 Codename: {}", self.config.codename);
+        textln!("    🚀 Release: {}", self.config.release);
 
         // Check if package file exists
         if !Path::new(&self.config.package_path).exists() {
@@ -147,64 +482,56 @@ impl DebianPublisher {
 
         // Build deb-s3 upload command
         let mut cmd = AsyncCommand::new("deb-s3");
-        cmd.arg("upload")
-            .arg("--s3-region=us-west-2")
-            .arg("--bucket")
-            .arg(&self.config.bucket)
-            .arg("--codename")
-            .arg(&self.config.codename)
-            .arg("--component")
-            .arg(&self.config.release)
-            .arg("--suite")
-            .arg(&self.config.release)
-            .arg("--preserve-versions")
-            .arg("--lock")
-            .arg("--fail-if-exists")
-            .arg("--cache-control=max-age=120")
-            .arg(&self.config.package_path);
+        cmd.args(self.upload_args()).kill_on_drop(true);
 
-        // Add signing if specified
-        if let Some(sign_key) = &self.config.sign_key {
-            cmd.arg("--sign").arg(sign_key);
+        if self.config.lock == LockMode::NoLock {
+            textln!("    ⚠️  Uploading without --lock; concurrent uploads must be serialized externally");
         }
 
-        println!("    🔄 Executing: deb-s3 upload...");
+        textln!("    🔄 Executing: deb-s3 upload...");
         if self.config.debug {
-            println!("    📜 Command: {:?}", cmd);
+            textln!("    📜 Command: {:?}", cmd);
         }
 
-        let output = cmd
-            .output()
-            .await
-            .map_err(|e| ManagerError::CommandFailed(format!("Failed to execute deb-s3: {}", e)))?;
+        let output = match self.config.lock {
+            LockMode::Lock {
+                timeout_secs: Some(secs),
+            } => {
+                within(
+                    "deb-s3 upload under --lock-timeout",
+                    Duration::from_secs(secs),
+                    cmd.timed_output("Failed to execute deb-s3"),
+                )
+                .await??
+            }
+            _ => cmd.timed_output("Failed to execute deb-s3").await?,
+        };
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             let stdout = String::from_utf8_lossy(&output.stdout);
 
-            println!("    ❌ Upload failed");
+            textln!("    ❌ Upload failed");
             // Check if error is due to lockfile conflict
-            if stderr.contains("lockfile") || stderr.contains("locked") {
-                println!(
+            let locked = self.config.lock != LockMode::NoLock;
+            if locked && (stderr.contains("lockfile") || stderr.contains("locked")) {
+                textln!(
                     "    🔒 Lockfile conflict detected. Attempting to remove stale lockfile..."
                 );
                 if let Err(lockfile_err) = self.remove_lockfile().await {
-                    println!("    ⚠️  Failed to remove lockfile: {}", lockfile_err);
+                    textln!("    ⚠️  Failed to remove lockfile: {}", lockfile_err);
                 } else {
-                    println!("    ✅ Lockfile removed. Please retry the upload.");
+                    textln!("    ✅ Lockfile removed. Please retry the upload.");
                 }
             }
 
-            return Err(ManagerError::CommandFailed(format!(
-                "deb-s3 upload failed. Stdout: {}, Stderr: {}",
-                stdout, stderr
-            )));
+            return Err(deb_s3_error("deb-s3 upload failed", &stdout, &stderr));
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
-        println!("    ✅ Upload completed successfully");
+        textln!("    ✅ Upload completed successfully");
         if !stdout.is_empty() {
-            println!("    📄 Output: {}", stdout.trim());
+            textln!("    📄 Output: {}", stdout.trim());
         }
 
         // Verify the upload
@@ -213,39 +540,318 @@ impl DebianPublisher {
         Ok(())
     }
 
-    /// Verify that the package was uploaded successfully
-    async fn verify_upload(&self) -> ManagerResult<()> {
-        println!("    🔍 Verifying package upload...");
+    fn s3_region_arg(&self) -> String {
+        format!("--s3-region={}", self.config.s3_region)
+    }
+
+    /// Arguments for `deb-s3 upload`
+    fn upload_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "upload".to_string(),
+            self.s3_region_arg(),
+            "--bucket".to_string(),
+            self.config.bucket.clone(),
+            "--codename".to_string(),
+            self.config.codename.clone(),
+            "--component".to_string(),
+            self.config.release.clone(),
+            "--suite".to_string(),
+            self.suite().to_string(),
+            "--preserve-versions".to_string(),
+        ];
+        if let LockMode::Lock { .. } = self.config.lock {
+            args.push("--lock".to_string());
+        }
+        args.extend([
+            "--fail-if-exists".to_string(),
+            "--cache-control=max-age=120".to_string(),
+            self.config.package_path.clone(),
+        ]);
+
+        // Add signing if specified
+        if let Some(sign_key) = &self.config.sign_key {
+            args.push("--sign".to_string());
+            args.push(sign_key.clone());
+        }
+        args
+    }
+
+    /// Arguments for `deb-s3 copy`, which copies this package version from the
+    /// configured component into `to_component` with a single manifest update
+    fn copy_args(&self, to_component: &str) -> ManagerResult<Vec<String>> {
+        let package = package_name_from_path(&self.config.package_path).ok_or_else(|| {
+            ManagerError::ValidationError(format!(
+                "Could not derive package name from {}",
+                self.config.package_path
+            ))
+        })?;
+
+        let mut args = vec![
+            "copy".to_string(),
+            package.to_string(),
+            self.config.codename.clone(),
+            to_component.to_string(),
+            self.s3_region_arg(),
+            "--bucket".to_string(),
+            self.config.bucket.clone(),
+            "--codename".to_string(),
+            self.config.codename.clone(),
+            "--component".to_string(),
+            self.config.release.clone(),
+            "--versions".to_string(),
+            self.config.version.clone(),
+            "--preserve-versions".to_string(),
+            "--cache-control=max-age=120".to_string(),
+        ];
+        if let LockMode::Lock { .. } = self.config.lock {
+            args.push("--lock".to_string());
+        }
+        if let Some(sign_key) = &self.config.sign_key {
+            args.push("--sign".to_string());
+            args.push(sign_key.clone());
+        }
+        Ok(args)
+    }
+
+    /// Copy the package from the configured (staging) component into
+    /// `to_component`
+    pub async fn copy_to(&self, to_component: &str) -> ManagerResult<()> {
+        self.validate_config()?;
+
+        textln!(
+            "    🔀 Copying {} {} from {} to {}",
+            self.config.package_path,
+            self.config.version,
+            self.config.release,
+            to_component
+        );
 
-        // Build deb-s3 verify command
         let mut cmd = AsyncCommand::new("deb-s3");
-        cmd.arg("verify")
-            .arg("--bucket")
-            .arg(&self.config.bucket)
-            .arg("--s3-region=us-west-2")
-            .arg("--codename")
-            .arg(&self.config.codename)
-            .arg("--component")
-            .arg(&self.config.release)
-            .arg("--suite")
-            .arg(&self.config.release);
-
-        let output = cmd.output().await.map_err(|e| {
-            ManagerError::CommandFailed(format!("Failed to execute deb-s3 verify: {}", e))
+        cmd.args(self.copy_args(to_component)?);
+        if self.config.debug {
+            textln!("    📜 Command: {:?}", cmd);
+        }
+
+        let output = cmd.timed_output("Failed to execute deb-s3 copy").await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            return Err(deb_s3_error("deb-s3 copy failed", &stdout, &stderr));
+        }
+
+        textln!("    ✅ Copied into {}", to_component);
+        Ok(())
+    }
+
+    /// Arguments selecting this repository's codename and component, shared by
+    /// `deb-s3 list`/`delete`
+    fn repository_args(&self) -> Vec<String> {
+        vec![
+            self.s3_region_arg(),
+            "--bucket".to_string(),
+            self.config.bucket.clone(),
+            "--codename".to_string(),
+            self.config.codename.clone(),
+            "--component".to_string(),
+            self.config.release.clone(),
+        ]
+    }
+
+    /// Arguments for `deb-s3 delete` of one version of `package`, limited to
+    /// `arch` when given
+    fn delete_args(&self, package: &str, version: &str, arch: Option<&str>) -> Vec<String> {
+        let mut args = vec![
+            "delete".to_string(),
+            package.to_string(),
+            "--versions".to_string(),
+            version.to_string(),
+        ];
+        if let Some(arch) = arch {
+            args.push("--arch".to_string());
+            args.push(arch.to_string());
+        }
+        args.extend(self.repository_args());
+        if let LockMode::Lock { .. } = self.config.lock {
+            args.push("--lock".to_string());
+        }
+        if let Some(sign_key) = &self.config.sign_key {
+            args.push("--sign".to_string());
+            args.push(sign_key.clone());
+        }
+        args
+    }
+
+    /// Delete all but the `keep` most recent versions of the published package
+    /// from the channel
+    pub async fn prune(&self, keep: usize) -> ManagerResult<Vec<String>> {
+        let package = package_name_from_path(&self.config.package_path).ok_or_else(|| {
+            ManagerError::ValidationError(format!(
+                "Could not derive package name from {}",
+                self.config.package_path
+            ))
         })?;
 
+        let mut list_cmd = AsyncCommand::new("deb-s3");
+        list_cmd.arg("list").args(self.repository_args());
+        let output = list_cmd
+            .timed_output("Failed to execute deb-s3 list")
+            .await?;
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "deb-s3 verify failed: {}",
-                stderr
-            )));
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            return Err(deb_s3_error("deb-s3 list failed", &stdout, &stderr));
+        }
+
+        let listing = String::from_utf8_lossy(&output.stdout);
+        let pruned = versions_to_prune(&parse_listed_versions(&listing, package), keep);
+        if pruned.is_empty() {
+            textln!("    🧹 Nothing to prune for {} (keeping {})", package, keep);
+            return Ok(pruned);
+        }
+
+        for version in &pruned {
+            textln!("    🧹 Pruning {} {}", package, version);
+            self.delete(package, version, None).await?;
+        }
+
+        Ok(pruned)
+    }
+
+    /// Remove one version of `package` from the channel with `deb-s3 delete`
+    pub async fn delete(
+        &self,
+        package: &str,
+        version: &str,
+        arch: Option<&str>,
+    ) -> ManagerResult<()> {
+        let mut cmd = AsyncCommand::new("deb-s3");
+        cmd.args(self.delete_args(package, version, arch));
+        if self.config.debug {
+            textln!("    📜 Command: {:?}", cmd);
+        }
+        let output = cmd.timed_output("Failed to execute deb-s3 delete").await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            return Err(deb_s3_error("deb-s3 delete failed", &stdout, &stderr));
         }
+        Ok(())
+    }
+
+    /// Verify that the package was uploaded successfully, repairing the
+    /// manifest once first if `auto_fix_manifests` allows it
+    async fn verify_upload(&self) -> ManagerResult<()> {
+        textln!("    🔍 Verifying package upload...");
 
-        println!("    ✅ Package verification successful");
+        let mut fixed = false;
+        while let Some(failure) = self.run_verify(false).await? {
+            if !should_auto_fix(&failure, self.config.auto_fix_manifests, fixed) {
+                return Err(failure.into_error());
+            }
+            if let VerifyFailure::ManifestInconsistent { discrepancies, .. } = &failure {
+                textln!(
+                    "    🔧 Manifest inconsistent ({} entries), running --fix-manifests",
+                    discrepancies.len()
+                );
+            }
+            if let Some(failure) = self.run_verify(true).await? {
+                return Err(failure.into_error());
+            }
+            fixed = true;
+        }
+        self.confirm_indexed().await?;
+
+        textln!("    ✅ Package verification successful");
         Ok(())
     }
 
+    /// `s3://` URL of the Packages index the upload lands in
+    fn packages_index_url(&self) -> String {
+        format!(
+            "s3://{}/dists/{}/{}/binary-{}/Packages",
+            self.config.bucket,
+            self.config.codename,
+            self.config.release,
+            index_arch_from_path(&self.config.package_path)
+        )
+    }
+
+    /// Check the component's Packages index lists the uploaded version.
+    /// `deb-s3 verify` only checks the manifest is consistent, which it also
+    /// is when deb-s3 skipped our file, e.g. under `--fail-if-exists`
+    async fn confirm_indexed(&self) -> ManagerResult<()> {
+        let package = package_name_from_path(&self.config.package_path).ok_or_else(|| {
+            ManagerError::ValidationError(format!(
+                "Could not derive package name from {}",
+                self.config.package_path
+            ))
+        })?;
+        let index_url = self.packages_index_url();
+
+        let mut cmd = AsyncCommand::new("aws");
+        cmd.args([
+            "s3",
+            "cp",
+            &index_url,
+            "-",
+            "--region",
+            &self.config.s3_region,
+        ]);
+        let output = cmd
+            .checked_output(
+                "Failed to execute aws s3 cp",
+                &format!("Could not download {}", index_url),
+            )
+            .await?;
+
+        check_indexed(
+            &String::from_utf8_lossy(&output.stdout),
+            package,
+            &self.config.version,
+            &index_url,
+        )
+    }
+
+    /// Arguments for `deb-s3 verify`, repairing the manifest with `fix`
+    fn verify_args(&self, fix: bool) -> Vec<String> {
+        let mut args = vec![
+            "verify".to_string(),
+            "--bucket".to_string(),
+            self.config.bucket.clone(),
+            self.s3_region_arg(),
+            "--codename".to_string(),
+            self.config.codename.clone(),
+            "--component".to_string(),
+            self.config.release.clone(),
+            "--suite".to_string(),
+            self.suite().to_string(),
+        ];
+        if fix {
+            args.push("--fix-manifests".to_string());
+            if let Some(sign_key) = &self.config.sign_key {
+                args.push("--sign".to_string());
+                args.push(sign_key.clone());
+            }
+        }
+        args
+    }
+
+    /// Run `deb-s3 verify`, returning why it failed if it did
+    async fn run_verify(&self, fix: bool) -> ManagerResult<Option<VerifyFailure>> {
+        let mut cmd = AsyncCommand::new("deb-s3");
+        cmd.args(self.verify_args(fix));
+
+        let output = cmd.timed_output("Failed to execute deb-s3 verify").await?;
+
+        if output.status.success() {
+            return Ok(None);
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(Some(classify_verify_failure(&stdout, &stderr)))
+    }
+
     /// Validate configuration parameters
     fn validate_config(&self) -> ManagerResult<()> {
         if self.config.package_path.is_empty() {
@@ -278,29 +884,144 @@ impl DebianPublisher {
 }
 
 /// High-level function to publish a Debian package
+#[allow(clippy::too_many_arguments)]
 pub async fn publish_debian_package(
     package_path: &str,
     version: &str,
     bucket: &str,
+    s3_region: &str,
     codename: &str,
     release: &str,
     sign_key: Option<&str>,
     debug: bool,
+    lock: LockMode,
+    lockfile_max_age_secs: u64,
+    suite: Option<&str>,
+    auto_fix_manifests: bool,
 ) -> ManagerResult<()> {
     let config = DebianPublishConfig {
         package_path: package_path.to_string(),
         version: version.to_string(),
         bucket: bucket.to_string(),
+        s3_region: s3_region.to_string(),
         codename: codename.to_string(),
         release: release.to_string(),
         sign_key: sign_key.map(|s| s.to_string()),
         debug,
+        lock,
+        lockfile_max_age_secs,
+        suite: suite.map(|s| s.to_string()),
+        auto_fix_manifests,
     };
 
     let publisher = DebianPublisher::new(config);
     publisher.publish().await
 }
 
+/// High-level function to swap a package published to `<channel>-staging`
+/// into the live `channel`
+#[allow(clippy::too_many_arguments)]
+pub async fn swap_staged_debian_package(
+    package_path: &str,
+    version: &str,
+    bucket: &str,
+    s3_region: &str,
+    codename: &str,
+    channel: &str,
+    sign_key: Option<&str>,
+    debug: bool,
+    lock: LockMode,
+    lockfile_max_age_secs: u64,
+    suite: Option<&str>,
+) -> ManagerResult<()> {
+    let config = DebianPublishConfig {
+        package_path: package_path.to_string(),
+        version: version.to_string(),
+        bucket: bucket.to_string(),
+        s3_region: s3_region.to_string(),
+        codename: codename.to_string(),
+        release: staging_component(channel),
+        sign_key: sign_key.map(|s| s.to_string()),
+        debug,
+        lock,
+        lockfile_max_age_secs,
+        suite: suite.map(|s| s.to_string()),
+        auto_fix_manifests: false,
+    };
+
+    let publisher = DebianPublisher::new(config);
+    publisher.copy_to(channel).await
+}
+
+/// High-level function to prune a published package down to the `keep`
+/// most recent versions in `channel`
+#[allow(clippy::too_many_arguments)]
+pub async fn prune_debian_package(
+    package_path: &str,
+    version: &str,
+    bucket: &str,
+    s3_region: &str,
+    codename: &str,
+    channel: &str,
+    keep: usize,
+    sign_key: Option<&str>,
+    lock: LockMode,
+    lockfile_max_age_secs: u64,
+    suite: Option<&str>,
+) -> ManagerResult<Vec<String>> {
+    let config = DebianPublishConfig {
+        package_path: package_path.to_string(),
+        version: version.to_string(),
+        bucket: bucket.to_string(),
+        s3_region: s3_region.to_string(),
+        codename: codename.to_string(),
+        release: channel.to_string(),
+        sign_key: sign_key.map(|s| s.to_string()),
+        debug: false,
+        lock,
+        lockfile_max_age_secs,
+        suite: suite.map(|s| s.to_string()),
+        auto_fix_manifests: false,
+    };
+
+    let publisher = DebianPublisher::new(config);
+    publisher.prune(keep).await
+}
+
+/// High-level function to remove one version of `package` for `arch` from
+/// `channel`
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_debian_package(
+    package: &str,
+    version: &str,
+    arch: &str,
+    bucket: &str,
+    s3_region: &str,
+    codename: &str,
+    channel: &str,
+    sign_key: Option<&str>,
+    debug: bool,
+    lockfile_max_age_secs: u64,
+) -> ManagerResult<()> {
+    let config = DebianPublishConfig {
+        package_path: String::new(),
+        version: version.to_string(),
+        bucket: bucket.to_string(),
+        s3_region: s3_region.to_string(),
+        codename: codename.to_string(),
+        release: channel.to_string(),
+        sign_key: sign_key.map(|s| s.to_string()),
+        debug,
+        lock: LockMode::default(),
+        lockfile_max_age_secs,
+        suite: None,
+        auto_fix_manifests: false,
+    };
+
+    let publisher = DebianPublisher::new(config);
+    publisher.delete(package, version, Some(arch)).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -313,10 +1034,15 @@ mod tests {
             package_path: temp_file.path().to_string_lossy().to_string(),
             version: "1.0.0".to_string(),
             bucket: "test-bucket".to_string(),
+            s3_region: "us-west-2".to_string(),
             codename: "bullseye".to_string(),
             release: "stable".to_string(),
             sign_key: None,
             debug: false,
+            lock: LockMode::default(),
+            lockfile_max_age_secs: DEFAULT_LOCKFILE_MAX_AGE_SECS,
+            suite: None,
+            auto_fix_manifests: false,
         };
 
         let publisher = DebianPublisher::new(config);
@@ -329,10 +1055,15 @@ mod tests {
             package_path: "/tmp/test.deb".to_string(),
             version: "".to_string(),
             bucket: "test-bucket".to_string(),
+            s3_region: "us-west-2".to_string(),
             codename: "bullseye".to_string(),
             release: "stable".to_string(),
             sign_key: None,
             debug: false,
+            lock: LockMode::default(),
+            lockfile_max_age_secs: DEFAULT_LOCKFILE_MAX_AGE_SECS,
+            suite: None,
+            auto_fix_manifests: false,
         };
 
         let publisher = DebianPublisher::new(config);
@@ -345,13 +1076,463 @@ mod tests {
             package_path: "/tmp/test.deb".to_string(),
             version: "1.0.0".to_string(),
             bucket: "".to_string(),
+            s3_region: "us-west-2".to_string(),
             codename: "bullseye".to_string(),
             release: "stable".to_string(),
             sign_key: None,
             debug: false,
+            lock: LockMode::default(),
+            lockfile_max_age_secs: DEFAULT_LOCKFILE_MAX_AGE_SECS,
+            suite: None,
+            auto_fix_manifests: false,
         };
 
         let publisher = DebianPublisher::new(config);
         assert!(publisher.validate_config().is_err());
     }
+
+    fn publisher_with_lock(lock: LockMode) -> DebianPublisher {
+        DebianPublisher::new(DebianPublishConfig {
+            package_path: "/tmp/mina-devnet_1.0.0.deb".to_string(),
+            version: "1.0.0".to_string(),
+            bucket: "test-bucket".to_string(),
+            s3_region: "us-west-2".to_string(),
+            codename: "bullseye".to_string(),
+            release: "unstable".to_string(),
+            sign_key: None,
+            debug: false,
+            lock,
+            lockfile_max_age_secs: DEFAULT_LOCKFILE_MAX_AGE_SECS,
+            suite: None,
+            auto_fix_manifests: false,
+        })
+    }
+
+    #[test]
+    fn test_check_indexed_version() {
+        let index = "Package: mina-devnet\nVersion: 0.9.0\nFilename: pool/unstable/m/mina-devnet/mina-devnet_0.9.0.deb\n\nPackage: mina-archive-devnet\nVersion: 1.0.0\nFilename: pool/unstable/m/mina-archive-devnet/mina-archive-devnet_1.0.0.deb\n";
+        let location = "s3://test-bucket/dists/bullseye/unstable/binary-amd64/Packages";
+        assert!(check_indexed(index, "mina-archive-devnet", "1.0.0", location).is_ok());
+        match check_indexed(index, "mina-devnet", "1.0.0", location) {
+            Err(ManagerError::CommandFailed(msg)) => {
+                assert!(
+                    msg.contains("mina-devnet=1.0.0 is not in s3://test-bucket"),
+                    "{}",
+                    msg
+                );
+                assert!(msg.contains("listed versions: 0.9.0"), "{}", msg);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert_eq!(
+            publisher_with_lock(LockMode::default()).packages_index_url(),
+            location
+        );
+        assert_eq!(
+            index_arch_from_path("/tmp/mina-devnet_1.0.0_arm64.deb"),
+            "arm64"
+        );
+        assert_eq!(
+            index_arch_from_path("/tmp/mina-devnet_1.0.0_all.deb"),
+            "amd64"
+        );
+    }
+
+    #[test]
+    fn test_upload_args_lock_modes() {
+        let locked = publisher_with_lock(LockMode::default()).upload_args();
+        assert!(locked.contains(&"--lock".to_string()));
+
+        let timed = publisher_with_lock(LockMode::Lock {
+            timeout_secs: Some(60),
+        })
+        .upload_args();
+        assert_eq!(timed, locked);
+
+        let unlocked = publisher_with_lock(LockMode::NoLock).upload_args();
+        assert!(!unlocked.contains(&"--lock".to_string()));
+        assert_eq!(unlocked.len(), locked.len() - 1);
+        assert_eq!(unlocked.last().unwrap(), "/tmp/mina-devnet_1.0.0.deb");
+    }
+
+    #[test]
+    fn test_s3_region_in_every_deb_s3_call() {
+        let mut config = publisher_with_lock(LockMode::default()).config;
+        config.s3_region = "eu-central-1".to_string();
+        let publisher = DebianPublisher::new(config);
+        for args in [
+            publisher.upload_args(),
+            publisher.copy_args("stable").unwrap(),
+            publisher.delete_args("mina-devnet", "1.0.0", None),
+            publisher.verify_args(true),
+        ] {
+            assert!(args.contains(&"--s3-region=eu-central-1".to_string()));
+            assert!(!args.iter().any(|a| a.contains("us-west-2")));
+        }
+        // Lock cleanup goes through the aws cli instead
+        let lockfile = "s3://test-bucket/dists/bullseye/unstable/binary-/lockfile";
+        for action in ["ls", "rm"] {
+            assert_eq!(
+                publisher.aws_s3_args(action, lockfile),
+                ["s3", action, lockfile, "--region", "eu-central-1"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_upload_args_suite_separate_from_codename() {
+        let default = publisher_with_lock(LockMode::default()).upload_args();
+        let suite_at = default.iter().position(|a| a == "--suite").unwrap();
+        assert_eq!(default[suite_at + 1], "unstable");
+
+        let mut config = publisher_with_lock(LockMode::default()).config;
+        config.suite = Some("edge".to_string());
+        let edge = DebianPublisher::new(config).upload_args();
+        let codename_at = edge.iter().position(|a| a == "--codename").unwrap();
+        let component_at = edge.iter().position(|a| a == "--component").unwrap();
+        assert_eq!(edge[codename_at + 1], "bullseye");
+        assert_eq!(edge[component_at + 1], "unstable");
+        assert_eq!(edge[suite_at + 1], "edge");
+    }
+
+    #[test]
+    fn test_lockfile_is_stale() {
+        let written = NaiveDateTime::parse_from_str("2024-01-01 12:00:00", "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc();
+        let after = |secs| written + chrono::Duration::seconds(secs);
+
+        assert!(!lockfile_is_stale(written, after(300), 300));
+        assert!(lockfile_is_stale(written, after(301), 300));
+        // A larger threshold keeps an 8-minute upload's lockfile
+        assert!(!lockfile_is_stale(written, after(480), 900));
+        assert!(lockfile_is_stale(written, after(901), 900));
+        // A lockfile stamped in the future (clock skew) is never stale
+        assert!(!lockfile_is_stale(after(60), written, 0));
+    }
+
+    #[test]
+    fn test_lock_mode_from_args() {
+        assert_eq!(LockMode::from_args(false, None), LockMode::default());
+        assert_eq!(
+            LockMode::from_args(false, Some(30)),
+            LockMode::Lock {
+                timeout_secs: Some(30)
+            }
+        );
+        assert_eq!(LockMode::from_args(true, None), LockMode::NoLock);
+    }
+
+    const RUBY_BACKTRACE: &str = "\
+/usr/lib/ruby/3.0.0/rubygems/core_ext/kernel_require.rb:85:in `require': cannot load such file -- aws-sdk-s3 (LoadError)
+\tfrom /usr/lib/ruby/3.0.0/rubygems/core_ext/kernel_require.rb:85:in `require'
+\tfrom /var/lib/gems/3.0.0/gems/deb-s3-0.11.8/lib/deb/s3/utils.rb:3:in `<top (required)>'
+\tfrom /var/lib/gems/3.0.0/gems/deb-s3-0.11.8/lib/deb/s3/cli.rb:5:in `<top (required)>'
+\tfrom /usr/local/bin/deb-s3:23:in `<main>'
+";
+
+    #[test]
+    fn test_ruby_load_error_is_reported_as_broken_deb_s3() {
+        let err = deb_s3_error("deb-s3 upload failed", "", RUBY_BACKTRACE);
+        match &err {
+            ManagerError::DebS3Broken(cause) => assert_eq!(
+                cause,
+                "/usr/lib/ruby/3.0.0/rubygems/core_ext/kernel_require.rb:85:in `require': cannot load such file -- aws-sdk-s3 (LoadError)"
+            ),
+            other => panic!("expected DebS3Broken, got {:?}", other),
+        }
+        assert!(err.to_string().contains("gem install deb-s3"));
+    }
+
+    #[test]
+    fn test_missing_gem_spec_is_reported_as_broken_deb_s3() {
+        let stderr = "/usr/lib/ruby/3.0.0/rubygems/dependency.rb:311:in `to_specs': Could not find 'aws-sdk-core' (~> 3) among 42 total gem(s) (Gem::MissingSpecError)";
+        assert!(matches!(
+            deb_s3_error("deb-s3 upload failed", "", stderr),
+            ManagerError::DebS3Broken(_)
+        ));
+    }
+
+    #[test]
+    fn test_regular_upload_failure_stays_command_failed() {
+        let stderr =
+            "Package mina-devnet 1.0.0 already exists in the repository (--fail-if-exists)";
+        assert!(detect_broken_deb_s3(stderr).is_none());
+        assert!(matches!(
+            deb_s3_error("deb-s3 upload failed", "", stderr),
+            ManagerError::CommandFailed(_)
+        ));
+    }
+
+    #[test]
+    fn test_staging_component() {
+        assert_eq!(staging_component("unstable"), "unstable-staging");
+        assert_eq!(staging_component("stable"), "stable-staging");
+    }
+
+    #[test]
+    fn test_copy_args_from_staging() {
+        let mut publisher = publisher_with_lock(LockMode::default());
+        publisher.config.release = staging_component("unstable");
+        assert_eq!(
+            publisher.copy_args("unstable").unwrap(),
+            vec![
+                "copy",
+                "mina-devnet",
+                "bullseye",
+                "unstable",
+                "--s3-region=us-west-2",
+                "--bucket",
+                "test-bucket",
+                "--codename",
+                "bullseye",
+                "--component",
+                "unstable-staging",
+                "--versions",
+                "1.0.0",
+                "--preserve-versions",
+                "--cache-control=max-age=120",
+                "--lock",
+            ]
+        );
+    }
+
+    async fn step(
+        log: &std::sync::Mutex<Vec<&'static str>>,
+        name: &'static str,
+        ok: bool,
+    ) -> ManagerResult<()> {
+        log.lock().unwrap().push(name);
+        if ok {
+            Ok(())
+        } else {
+            Err(ManagerError::ValidationError(format!("{} failed", name)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_staged_sequencing() {
+        let log = std::sync::Mutex::new(Vec::new());
+        run_staged(
+            step(&log, "upload", true),
+            step(&log, "verify", true),
+            step(&log, "swap", true),
+        )
+        .await
+        .unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["upload", "verify", "swap"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_staged_verification_failure_skips_swap() {
+        let log = std::sync::Mutex::new(Vec::new());
+        let result = run_staged(
+            step(&log, "upload", true),
+            step(&log, "verify", false),
+            step(&log, "swap", true),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*log.lock().unwrap(), vec!["upload", "verify"]);
+
+        let log = std::sync::Mutex::new(Vec::new());
+        let result = run_staged(
+            step(&log, "upload", false),
+            step(&log, "verify", true),
+            step(&log, "swap", true),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*log.lock().unwrap(), vec!["upload"]);
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_versions_to_prune() {
+        let versions = strings(&[
+            "3.0.0-bullseye",
+            "3.0.10-bullseye",
+            "3.0.2-bullseye",
+            "3.0.1~rc1-bullseye",
+            "3.0.1-bullseye",
+        ]);
+        assert_eq!(
+            versions_to_prune(&versions, 2),
+            strings(&["3.0.1-bullseye", "3.0.1~rc1-bullseye", "3.0.0-bullseye"])
+        );
+        assert!(versions_to_prune(&versions, 5).is_empty());
+        assert!(versions_to_prune(&versions, 10).is_empty());
+        assert_eq!(versions_to_prune(&versions, 0).len(), 5);
+    }
+
+    #[test]
+    fn test_versions_to_prune_collapses_arch_duplicates() {
+        let listing = "\
+mina-devnet 1.0.0 amd64
+mina-devnet 1.0.0 arm64
+mina-devnet 1.1.0 amd64
+mina-mainnet 0.9.0 amd64
+";
+        let versions = parse_listed_versions(listing, "mina-devnet");
+        assert_eq!(versions, strings(&["1.0.0", "1.0.0", "1.1.0"]));
+        assert_eq!(versions_to_prune(&versions, 1), strings(&["1.0.0"]));
+    }
+
+    #[test]
+    fn test_parse_deb_s3_list() {
+        // Layout of `deb-s3 list --codename bullseye --component unstable`,
+        // including the progress line deb-s3 prints first
+        let output = "\
+>> Retrieving existing manifests
+mina-archive-devnet    3.0.1-bullseye-devnet      amd64
+mina-devnet            3.0.1-bullseye-devnet      amd64
+mina-devnet            3.0.1-bullseye-devnet      arm64
+mina-logproc           1.0.0-ab12cd3              all
+mina-rosetta-mainnet   1:3.0.0~alpha1-bullseye    amd64
+";
+        assert_eq!(
+            parse_deb_s3_list(output),
+            vec![
+                PackageEntry {
+                    name: "mina-archive-devnet".to_string(),
+                    version: "3.0.1-bullseye-devnet".to_string(),
+                    arch: "amd64".to_string(),
+                },
+                PackageEntry {
+                    name: "mina-devnet".to_string(),
+                    version: "3.0.1-bullseye-devnet".to_string(),
+                    arch: "amd64".to_string(),
+                },
+                PackageEntry {
+                    name: "mina-devnet".to_string(),
+                    version: "3.0.1-bullseye-devnet".to_string(),
+                    arch: "arm64".to_string(),
+                },
+                PackageEntry {
+                    name: "mina-logproc".to_string(),
+                    version: "1.0.0-ab12cd3".to_string(),
+                    arch: "all".to_string(),
+                },
+                PackageEntry {
+                    name: "mina-rosetta-mainnet".to_string(),
+                    version: "1:3.0.0~alpha1-bullseye".to_string(),
+                    arch: "amd64".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_deb_s3_list_edge_cases() {
+        assert!(parse_deb_s3_list("").is_empty());
+
+        let output = "\
+Package                Version   Arch
+\t mina-devnet \t 1.0.0   amd64  \r
+
+mina-devnet 1.0.0
+mina-devnet 1.0.0 amd64 extra
+WARNING: something happened here
+Done. 2 packages
+mina-mainnet 2.0.0 arm64
+";
+        let entries = parse_deb_s3_list(output);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.name.as_str(), e.version.as_str(), e.arch.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("mina-devnet", "1.0.0", "amd64"),
+                ("mina-mainnet", "2.0.0", "arm64")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_args() {
+        let publisher = publisher_with_lock(LockMode::NoLock);
+        assert_eq!(
+            publisher.delete_args("mina-devnet", "0.9.0", None),
+            strings(&[
+                "delete",
+                "mina-devnet",
+                "--versions",
+                "0.9.0",
+                "--s3-region=us-west-2",
+                "--bucket",
+                "test-bucket",
+                "--codename",
+                "bullseye",
+                "--component",
+                "unstable",
+            ])
+        );
+
+        let signed = DebianPublisher::new(DebianPublishConfig {
+            sign_key: Some("KEY".to_string()),
+            ..publisher.config.clone()
+        });
+        let args = signed.delete_args("mina-devnet", "0.9.0", Some("amd64"));
+        assert_eq!(&args[4..6], &strings(&["--arch", "amd64"])[..]);
+        assert_eq!(&args[args.len() - 2..], &strings(&["--sign", "KEY"])[..]);
+    }
+
+    #[test]
+    fn test_auto_fix_decision() {
+        let missing = classify_verify_failure(
+            ">> Retrieving existing manifests\n -- The following packages are missing:\n\n\
+             mina-devnet 1.0.0 amd64\n\
+             mina-archive-devnet 1.0.0 amd64\n",
+            "",
+        );
+        match &missing {
+            VerifyFailure::ManifestInconsistent { discrepancies, .. } => assert_eq!(
+                discrepancies,
+                &vec![
+                    "mina-devnet 1.0.0 amd64".to_string(),
+                    "mina-archive-devnet 1.0.0 amd64".to_string()
+                ]
+            ),
+            other => panic!("expected ManifestInconsistent, got {:?}", other),
+        }
+        assert!(should_auto_fix(&missing, true, false));
+        // Off by default, and never twice
+        assert!(!should_auto_fix(&missing, false, false));
+        assert!(!should_auto_fix(&missing, true, true));
+
+        let denied = classify_verify_failure("", "Aws::S3::Errors::AccessDenied: Access Denied");
+        assert_eq!(
+            denied,
+            VerifyFailure::Other("Aws::S3::Errors::AccessDenied: Access Denied".to_string())
+        );
+        assert!(!should_auto_fix(&denied, true, false));
+
+        let broken = classify_verify_failure(
+            "",
+            "/usr/lib/ruby/3.0.0/rubygems.rb:283:in `find_spec_for_exe': can't find gem deb-s3 (Gem::MissingSpecError)",
+        );
+        assert!(matches!(broken, VerifyFailure::Broken(_)));
+        assert!(!should_auto_fix(&broken, true, false));
+        assert!(matches!(broken.into_error(), ManagerError::DebS3Broken(_)));
+    }
+
+    #[test]
+    fn test_verify_args_fix_manifests() {
+        let mut config = publisher_with_lock(LockMode::default()).config;
+        config.sign_key = Some("KEYID".to_string());
+        let publisher = DebianPublisher::new(config);
+
+        let plain = publisher.verify_args(false);
+        assert!(!plain.contains(&"--fix-manifests".to_string()));
+        assert!(!plain.contains(&"--sign".to_string()));
+
+        let fix = publisher.verify_args(true);
+        assert_eq!(&fix[..plain.len()], &plain[..]);
+        assert_eq!(&fix[plain.len()..], &["--fix-manifests", "--sign", "KEYID"]);
+    }
 }
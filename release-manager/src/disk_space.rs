@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use crate::errors::{ManagerError, ManagerResult};
+
+/// Free space a reversion needs, in multiples of the package size: the
+/// package itself, its extracted tree and the rebuilt package
+pub const REVERSION_SPACE_FACTOR: u64 = 3;
+
+/// Bytes needed to download and reversion packages totalling `package_size`
+pub fn reversion_space_estimate(package_size: u64) -> u64 {
+    package_size.saturating_mul(REVERSION_SPACE_FACTOR)
+}
+
+/// How many bytes `available` falls short of `required`, if it does
+fn shortfall(available: u64, required: u64) -> Option<u64> {
+    required.checked_sub(available).filter(|short| *short > 0)
+}
+
+/// Fail before starting work that needs `required` bytes on the filesystem
+/// holding `dir`, naming how much is missing, instead of partway through
+pub fn check_free_space(dir: &Path, required: u64, what: &str) -> ManagerResult<()> {
+    let available = fs2::available_space(dir)?;
+    match shortfall(available, required) {
+        Some(short) => Err(ManagerError::ValidationError(format!(
+            "Not enough free space in {} to {}: needs about {} bytes, {} available ({} short)",
+            dir.display(),
+            what,
+            required,
+            available,
+            short
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space_estimate_and_shortfall() {
+        assert_eq!(reversion_space_estimate(100), 300);
+        assert_eq!(reversion_space_estimate(u64::MAX), u64::MAX);
+
+        assert_eq!(shortfall(300, 300), None);
+        assert_eq!(shortfall(1_000, 300), None);
+        assert_eq!(shortfall(250, 300), Some(50));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_free_space(dir.path(), 1, "download").is_ok());
+        let err = check_free_space(dir.path(), u64::MAX, "reversion mina-archive")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("to reversion mina-archive: needs about 18446744073709551615 bytes"));
+        assert!(err.contains(" short)"));
+    }
+}
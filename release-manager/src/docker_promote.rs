@@ -1,4 +1,14 @@
+use crate::artifacts::{debian_version_cmp, docker_codename};
+use crate::container_runtime::{container_runtime, has_imagetools};
 use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::registry_limits;
+use crate::trace::TracedCommand;
+use crate::utils::stderr_excerpt;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
 use tokio::process::Command as AsyncCommand;
 
 /// Configuration for Docker image promotion
@@ -10,10 +20,22 @@ pub struct DockerPromoteConfig {
     pub source_version: String,
     /// Target version/tag
     pub target_version: String,
-    /// Whether to publish to docker.io (vs gcr.io)
-    pub publish_to_docker_io: bool,
+    /// Registry to promote into, e.g. `ghcr.io/minaprotocol`; the source
+    /// registry (gcr.io) when `None`
+    pub target_registry: Option<String>,
     /// Quiet mode (minimal output)
     pub quiet: bool,
+    /// OCI annotations to add/override on the target
+    pub annotations: Vec<(String, String)>,
+    /// Copy with `skopeo` registry-to-registry instead of through the local
+    /// Docker daemon
+    pub use_skopeo: bool,
+    /// Platforms (`os/arch[/variant]`) of a multi-arch source to copy; all
+    /// of them when empty
+    pub platforms: Vec<String>,
+    /// Copy from the digest the source tag resolves to up front, failing if
+    /// the tag moves before the promotion is done
+    pub pin_digest: bool,
 }
 
 /// Docker image promoter
@@ -21,8 +43,354 @@ pub struct DockerPromoter {
     config: DockerPromoteConfig,
 }
 
+/// Pull the pushed manifest digest out of `docker push` (`<tag>: digest:
+/// sha256:... size: N`) or `docker buildx imagetools inspect` (`Digest:
+/// sha256:...`) output. The last one wins, as push reports the final tag last.
+pub fn parse_digest(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let lower = line.to_ascii_lowercase();
+            let start = lower.find("digest:")? + "digest:".len();
+            let digest = line[start..].split_whitespace().next()?;
+            let hex = digest.strip_prefix("sha256:")?;
+            (hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                .then(|| digest.to_string())
+        })
+        .next_back()
+}
+
+/// Command printing the raw manifest or manifest list of `image`: `skopeo
+/// inspect --raw` when asked to or when the runtime has no `buildx
+/// imagetools` (podman), `<runtime> buildx imagetools inspect --raw`
+/// otherwise
+fn inspect_raw_command(image: &str, use_skopeo: bool) -> AsyncCommand {
+    if use_skopeo || !has_imagetools() {
+        let mut cmd = AsyncCommand::new("skopeo");
+        cmd.args(["inspect", "--raw", &format!("docker://{}", image)]);
+        cmd
+    } else {
+        let mut cmd = AsyncCommand::new(container_runtime());
+        cmd.args(["buildx", "imagetools", "inspect", "--raw", image]);
+        cmd
+    }
+}
+
+/// Digest `image` currently points at in its registry, if it resolves
+pub async fn resolve_digest(image: &str) -> Option<String> {
+    if !has_imagetools() {
+        // A manifest's digest is the hash of its exact bytes
+        let output = inspect_raw_command(image, true)
+            .timed_output("Failed to inspect image")
+            .await
+            .ok()?;
+        return output
+            .status
+            .success()
+            .then(|| format!("sha256:{}", hex::encode(Sha256::digest(&output.stdout))));
+    }
+    let output = AsyncCommand::new(container_runtime())
+        .args(["buildx", "imagetools", "inspect", image])
+        .timed_output("Failed to inspect image")
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_digest(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Fail with [`ManagerError::ArtifactNotFound`] naming `source_image` unless
+/// the registry reported it present
+fn check_source_exists(source_image: &str, exists: bool) -> ManagerResult<()> {
+    if exists {
+        Ok(())
+    } else {
+        Err(ManagerError::ArtifactNotFound(format!(
+            "Source image {} not found; nothing to promote",
+            source_image
+        )))
+    }
+}
+
+/// Media types of a multi-arch image: an OCI image index or a Docker
+/// manifest list
+const MANIFEST_LIST_MEDIA_TYPES: &[&str] = &[
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+];
+
+/// One platform image a manifest list refers to
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlatformManifest {
+    /// `os/arch[/variant]`
+    pub platform: String,
+    pub digest: String,
+}
+
+/// Parse a comma-separated `--platforms` list of `os/arch[/variant]`
+pub fn parse_platforms(input: &str) -> ManagerResult<Vec<String>> {
+    let platforms: Vec<String> = input
+        .split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    for platform in &platforms {
+        let parts: Vec<&str> = platform.split('/').collect();
+        if !(2..=3).contains(&parts.len()) || parts.iter().any(|part| part.is_empty()) {
+            return Err(ManagerError::ValidationError(format!(
+                "Invalid platform '{}': expected os/arch[/variant], e.g. linux/arm64",
+                platform
+            )));
+        }
+    }
+    Ok(platforms)
+}
+
+/// Platform images of raw manifest JSON, or `None` when it's a
+/// single-platform manifest. Entries without a real platform, such as
+/// buildx's `unknown/unknown` attestations, are left out.
+pub fn parse_manifest_list(raw: &[u8]) -> ManagerResult<Option<Vec<PlatformManifest>>> {
+    let manifest: serde_json::Value = serde_json::from_slice(raw)?;
+    let is_list = match manifest.get("mediaType").and_then(|t| t.as_str()) {
+        Some(media_type) => MANIFEST_LIST_MEDIA_TYPES.contains(&media_type),
+        // mediaType is optional in an OCI index
+        None => manifest.get("manifests").is_some(),
+    };
+    if !is_list {
+        return Ok(None);
+    }
+
+    let entries = manifest
+        .get("manifests")
+        .and_then(|m| m.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    Ok(Some(
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let platform = entry.get("platform")?;
+                let os = platform.get("os")?.as_str()?;
+                let arch = platform.get("architecture")?.as_str()?;
+                if os == "unknown" || arch == "unknown" {
+                    return None;
+                }
+                let platform = match platform.get("variant").and_then(|v| v.as_str()) {
+                    Some(variant) => format!("{}/{}/{}", os, arch, variant),
+                    None => format!("{}/{}", os, arch),
+                };
+                Some(PlatformManifest {
+                    platform,
+                    digest: entry.get("digest")?.as_str()?.to_string(),
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// Whether `requested` names `platform`; one without a variant matches any
+/// variant (`linux/arm64` matches `linux/arm64/v8`)
+fn platform_matches(requested: &str, platform: &str) -> bool {
+    platform == requested
+        || platform
+            .strip_prefix(requested)
+            .is_some_and(|rest| rest.starts_with('/') && requested.matches('/').count() == 1)
+}
+
+/// The entries of `image`'s manifest list to copy: those of `requested`
+/// platforms, or all of them when none are. A requested platform the list
+/// doesn't have is an error.
+pub fn select_platforms(
+    image: &str,
+    entries: &[PlatformManifest],
+    requested: &[String],
+) -> ManagerResult<Vec<PlatformManifest>> {
+    if requested.is_empty() {
+        return Ok(entries.to_vec());
+    }
+
+    let mut selected = Vec::new();
+    for platform in requested {
+        let matching: Vec<&PlatformManifest> = entries
+            .iter()
+            .filter(|entry| platform_matches(platform, &entry.platform))
+            .collect();
+        if matching.is_empty() {
+            let available: Vec<&str> = entries.iter().map(|e| e.platform.as_str()).collect();
+            return Err(ManagerError::ValidationError(format!(
+                "{} has no {} image (has {})",
+                image,
+                platform,
+                available.join(", ")
+            )));
+        }
+        for entry in matching {
+            if !selected.contains(entry) {
+                selected.push(entry.clone());
+            }
+        }
+    }
+    Ok(selected)
+}
+
+/// Digest of a raw manifest or manifest list, as the registry addresses it
+pub fn manifest_digest(raw: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(raw)))
+}
+
+/// Fail when `image` no longer points at the digest it was pinned to when
+/// the promotion started
+pub fn check_digest_unchanged(image: &str, pinned: &str, current: &str) -> ManagerResult<()> {
+    if pinned == current {
+        return Ok(());
+    }
+    Err(ManagerError::ValidationError(format!(
+        "{} moved from {} to {} during promotion; the target got {}, promote again if the new image is intended",
+        image, pinned, current, pinned
+    )))
+}
+
+/// Fail unless `target`'s raw manifest is a manifest list covering every
+/// `expected` platform
+pub fn check_manifest_list(
+    target: &str,
+    raw: &[u8],
+    expected: &[PlatformManifest],
+) -> ManagerResult<()> {
+    let Some(entries) = parse_manifest_list(raw)? else {
+        return Err(ManagerError::ValidationError(format!(
+            "{} is not a manifest list after promotion",
+            target
+        )));
+    };
+    let missing: Vec<&str> = expected
+        .iter()
+        .filter(|e| !entries.iter().any(|t| t.platform == e.platform))
+        .map(|e| e.platform.as_str())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(ManagerError::ValidationError(format!(
+            "{} is missing {} after promotion",
+            target,
+            missing.join(", ")
+        )))
+    }
+}
+
 const GCR_REGISTRY: &str = "gcr.io/o1labs-192920";
-const DOCKER_IO_REGISTRY: &str = "docker.io/minaprotocol";
+
+/// Parse a `key=value` annotation (e.g.
+/// `org.opencontainers.image.revision=abc123`). The key must be non-empty and
+/// free of whitespace; the value may be empty or contain `=`.
+pub fn parse_annotation(annotation: &str) -> ManagerResult<(String, String)> {
+    parse_key_value("annotation", annotation)
+}
+
+/// Parse a `key=value` `--require-label`, with the same rules as
+/// [`parse_annotation`]
+pub fn parse_required_label(label: &str) -> ManagerResult<(String, String)> {
+    parse_key_value("label", label)
+}
+
+fn parse_key_value(kind: &str, pair: &str) -> ManagerResult<(String, String)> {
+    let (key, value) = pair.split_once('=').ok_or_else(|| {
+        ManagerError::ValidationError(format!("Invalid {} '{}': expected key=value", kind, pair))
+    })?;
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Err(ManagerError::ValidationError(format!(
+            "Invalid {} key '{}' in '{}'",
+            kind, key, pair
+        )));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Labels out of `docker image inspect --format '{{json .Config.Labels}}'`,
+/// which prints `null` for an image without any
+pub fn parse_image_labels(output: &[u8]) -> ManagerResult<HashMap<String, String>> {
+    let labels: Option<HashMap<String, String>> = serde_json::from_slice(output)?;
+    Ok(labels.unwrap_or_default())
+}
+
+/// Fail unless `labels` carries every `required` label with its value,
+/// listing every missing or mismatched one
+pub fn check_required_labels(
+    image: &str,
+    labels: &HashMap<String, String>,
+    required: &[(String, String)],
+) -> ManagerResult<()> {
+    let problems: Vec<String> = required
+        .iter()
+        .filter_map(|(key, expected)| match labels.get(key) {
+            None => Some(format!("{} is missing", key)),
+            Some(actual) if actual != expected => {
+                Some(format!("{} is '{}', expected '{}'", key, actual, expected))
+            }
+            Some(_) => None,
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ManagerError::ValidationError(format!(
+            "Refusing to promote {}: {}",
+            image,
+            problems.join("; ")
+        )))
+    }
+}
+
+/// Pull `image` and return its labels
+pub async fn inspect_image_labels(image: &str) -> ManagerResult<HashMap<String, String>> {
+    let permit = registry_limits::acquire(image).await;
+    AsyncCommand::new(container_runtime())
+        .args(["pull", "-q", image])
+        .checked_output(
+            "Failed to pull image",
+            &format!("Docker pull failed for {}", image),
+        )
+        .await?;
+    drop(permit);
+
+    let output = AsyncCommand::new(container_runtime())
+        .args([
+            "image",
+            "inspect",
+            "--format",
+            "{{json .Config.Labels}}",
+            image,
+        ])
+        .checked_output(
+            "Failed to inspect image",
+            &format!("Docker inspect failed for {}", image),
+        )
+        .await?;
+
+    parse_image_labels(&output.stdout)
+}
+
+/// Check the gcr.io source image `name:source_tag` carries the `required`
+/// labels before it gets promoted. Nothing is pulled when none are required.
+pub async fn check_source_labels(
+    name: &str,
+    source_tag: &str,
+    required: &[(String, String)],
+) -> ManagerResult<()> {
+    if required.is_empty() {
+        return Ok(());
+    }
+
+    let source_image = format!("{}/{}:{}", GCR_REGISTRY, name, source_tag);
+    textln!("    🔖 Checking required labels on {}", source_image);
+    let labels = inspect_image_labels(&source_image).await?;
+    check_required_labels(&source_image, &labels, required)?;
+    textln!("    ✅ Required labels present");
+    Ok(())
+}
 
 impl DockerPromoter {
     /// Create a new DockerPromoter
@@ -30,47 +398,47 @@ impl DockerPromoter {
         Self { config }
     }
 
-    /// Promote Docker image from source to target version/registry
-    pub async fn promote(&self) -> ManagerResult<()> {
+    /// Promote Docker image from source to target version/registry,
+    /// returning the target's digest when the registry reported one
+    pub async fn promote(&self) -> ManagerResult<Option<String>> {
         self.validate_config()?;
 
         if !self.config.quiet {
-            println!(" 🐋 Promoting Docker image:");
-            println!("    📦 Name: {}", self.config.name);
-            println!("    🏷️  Source: {}", self.config.source_version);
-            println!("    🎯 Target: {}", self.config.target_version);
-            println!(
-                "    🌐 Publish to docker.io: {}",
-                self.config.publish_to_docker_io
-            );
+            textln!(" 🐋 Promoting Docker image:");
+            textln!("    📦 Name: {}", self.config.name);
+            textln!("    🏷️  Source: {}", self.config.source_version);
+            textln!("    🎯 Target: {}", self.config.target_version);
+            textln!("    🌐 Target registry: {}", self.target_registry());
         }
 
-        let config = if self.config.publish_to_docker_io {
-            DockerRegistryConfig {
-                source_registry: GCR_REGISTRY.to_string(),
-                target_registry: DOCKER_IO_REGISTRY.to_string(),
-                image_name: self.config.name.clone(),
-                source_tag: self.config.source_version.clone(),
-                target_tag: self.config.target_version.clone(),
-            }
-        } else {
-            DockerRegistryConfig {
-                source_registry: GCR_REGISTRY.to_string(),
-                target_registry: GCR_REGISTRY.to_string(),
-                image_name: self.config.name.clone(),
-                source_tag: self.config.source_version.clone(),
-                target_tag: self.config.target_version.clone(),
-            }
+        let config = DockerRegistryConfig {
+            source_registry: GCR_REGISTRY.to_string(),
+            target_registry: self.target_registry().to_string(),
+            image_name: self.config.name.clone(),
+            source_tag: self.config.source_version.clone(),
+            target_tag: self.config.target_version.clone(),
+            annotations: self.config.annotations.clone(),
+            use_skopeo: self.config.use_skopeo,
+            platforms: self.config.platforms.clone(),
+            pin_digest: self.config.pin_digest,
         };
 
         let manager = DockerRegistryManager::new(config);
-        manager.cross_registry_promote().await?;
+        let digest = manager.cross_registry_promote().await?;
 
         if !self.config.quiet {
-            println!("    ✅ Docker image promotion successful");
+            textln!("    ✅ Docker image promotion successful");
         }
 
-        Ok(())
+        Ok(digest)
+    }
+
+    /// Registry the image is promoted into
+    fn target_registry(&self) -> &str {
+        self.config
+            .target_registry
+            .as_deref()
+            .unwrap_or(GCR_REGISTRY)
     }
 
     /// Validate configuration parameters
@@ -80,6 +448,11 @@ impl DockerPromoter {
                 "Name cannot be empty".to_string(),
             ));
         }
+        if self.config.target_registry.as_deref() == Some("") {
+            return Err(ManagerError::ValidationError(
+                "Target registry cannot be empty".to_string(),
+            ));
+        }
         if self.config.source_version.is_empty() {
             return Err(ManagerError::ValidationError(
                 "Source version cannot be empty".to_string(),
@@ -94,20 +467,243 @@ impl DockerPromoter {
     }
 }
 
-/// High-level function to promote a Docker image
+/// Appended to the final tag while a verify-before-commit promotion is
+/// unverified. The pid keeps concurrent runs from sharing a staging tag.
+pub fn staging_suffix() -> String {
+    format!("-unverified-{}", std::process::id())
+}
+
+/// Tag a verify-before-commit promotion pushes to before `target_tag`
+pub fn staging_tag(target_tag: &str) -> String {
+    format!("{}{}", target_tag, staging_suffix())
+}
+
+/// Registry steps of a verify-before-commit promotion
+#[async_trait]
+pub trait StagedPromotion {
+    /// Copy the source image to `staging_tag` in the target registry
+    async fn push(&self, staging_tag: &str) -> ManagerResult<()>;
+    /// Verify the image under `staging_tag`
+    async fn verify(&self, staging_tag: &str) -> ManagerResult<()>;
+    /// Point the final tag at the verified image, returning its digest
+    async fn commit(&self, staging_tag: &str) -> ManagerResult<Option<String>>;
+    /// Remove `staging_tag` from the registry
+    async fn cleanup(&self, staging_tag: &str) -> ManagerResult<()>;
+}
+
+/// Push to a staging tag, verify it, and only then move `target_tag` to it,
+/// so the final tag never points at an unverified image. The staging tag
+/// is removed whether or not verification passed.
+pub async fn promote_verified<P: StagedPromotion + Sync + ?Sized>(
+    promotion: &P,
+    target_tag: &str,
+) -> ManagerResult<Option<String>> {
+    let staging = staging_tag(target_tag);
+    textln!("    🧪 Staging as {} until verified", staging);
+
+    promotion.push(&staging).await?;
+    let result = match promotion.verify(&staging).await {
+        Ok(()) => promotion.commit(&staging).await,
+        Err(e) => {
+            textln!("    ❌ {} failed verification, not committing", staging);
+            Err(e)
+        }
+    };
+
+    if let Err(e) = promotion.cleanup(&staging).await {
+        textln!(
+            "    ⚠️  Could not remove staging tag {}, delete it by hand: {}",
+            staging,
+            e
+        );
+    }
+
+    result
+}
+
+/// Point `registry/name:to_tag` at the image under `from_tag`, registry-side,
+/// returning the digest it now resolves to. Without annotations to add, the
+/// copy goes through skopeo when `use_skopeo` or the runtime has no `buildx
+/// imagetools`.
+pub async fn retag_docker_image(
+    registry: &str,
+    name: &str,
+    from_tag: &str,
+    to_tag: &str,
+    annotations: &[(String, String)],
+    use_skopeo: bool,
+) -> ManagerResult<Option<String>> {
+    let manager = DockerRegistryManager::new(DockerRegistryConfig {
+        source_registry: registry.to_string(),
+        target_registry: registry.to_string(),
+        image_name: name.to_string(),
+        source_tag: from_tag.to_string(),
+        target_tag: to_tag.to_string(),
+        annotations: annotations.to_vec(),
+        use_skopeo: use_skopeo || !has_imagetools(),
+        platforms: vec![],
+        pin_digest: false,
+    });
+    manager.validate_config()?;
+
+    let source = format!("{}/{}:{}", registry, name, from_tag);
+    let target = format!("{}/{}:{}", registry, name, to_tag);
+    textln!("    🏷️  Committing: {} -> {}", source, target);
+
+    if manager.config.use_skopeo && annotations.is_empty() {
+        return manager.skopeo_copy(&source, &target).await;
+    }
+    manager
+        .imagetools_create(std::slice::from_ref(&source), &target)
+        .await?;
+    Ok(manager.inspect_digest(&target).await)
+}
+
+/// Arguments for `regctl tag delete`, which removes a tag without touching
+/// other tags of the same manifest
+fn delete_tag_args(image: &str) -> Vec<String> {
+    vec!["tag".to_string(), "delete".to_string(), image.to_string()]
+}
+
+/// Delete `registry/name:tag`
+pub async fn delete_docker_tag(registry: &str, name: &str, tag: &str) -> ManagerResult<()> {
+    let image = format!("{}/{}:{}", registry, name, tag);
+    textln!("    🧹 Removing: {}", image);
+
+    AsyncCommand::new("regctl")
+        .args(delete_tag_args(&image))
+        .checked_output("Failed to run regctl", "regctl tag delete failed")
+        .await?;
+
+    Ok(())
+}
+
+/// OCI annotation recording which version a floating `latest` tag points at
+pub const VERSION_ANNOTATION: &str = "org.opencontainers.image.version";
+
+/// Floating tag tracking the newest promoted version of one codename and
+/// network, shaped like the versioned tags (`latest-bullseye-devnet`)
+pub fn latest_tag(codename: &str, network_suffix: &str) -> String {
+    format!("latest-{}{}", docker_codename(codename), network_suffix)
+}
+
+/// The [`VERSION_ANNOTATION`] out of `docker buildx imagetools inspect --raw`
+/// output, which is an image index or manifest with top-level annotations
+pub fn parse_version_annotation(raw: &[u8]) -> Option<String> {
+    let manifest: serde_json::Value = serde_json::from_slice(raw).ok()?;
+    manifest
+        .get("annotations")?
+        .get(VERSION_ANNOTATION)?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Whether `latest` may move to `version`: only forward, by Debian version
+/// ordering. A `latest` that doesn't exist or records no version is moved.
+pub fn should_move_latest(current: Option<&str>, version: &str) -> bool {
+    match current {
+        Some(current) => debian_version_cmp(version, current) == std::cmp::Ordering::Greater,
+        None => true,
+    }
+}
+
+/// Whether a failed manifest inspection's stderr says the image or tag
+/// doesn't exist, as opposed to any other registry error
+fn is_missing_manifest(stderr: &str) -> bool {
+    let stderr = stderr.to_ascii_lowercase();
+    stderr.contains("manifest unknown") || stderr.contains("not found")
+}
+
+/// What `image` currently points at: `None` when the tag doesn't exist,
+/// `Some(None)` when it exists without a recorded version
+async fn resolve_tagged_version(image: &str) -> ManagerResult<Option<Option<String>>> {
+    let _permit = registry_limits::acquire(image).await;
+    let output = inspect_raw_command(image, false)
+        .timed_output("Failed to inspect manifest")
+        .await?;
+
+    if output.status.success() {
+        return Ok(Some(parse_version_annotation(&output.stdout)));
+    }
+    // Anything but a missing tag must not be mistaken for one, or `latest`
+    // could move backward on a registry hiccup
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if is_missing_manifest(&stderr) {
+        Ok(None)
+    } else {
+        Err(ManagerError::CommandFailed(format!(
+            "Inspecting the manifest of {} failed: {}",
+            image,
+            stderr_excerpt(&stderr)
+        )))
+    }
+}
+
+/// Point `registry/name:latest_tag` at the image under `version_tag` if
+/// `version` is newer than what it points at now, recording `version` on it
+/// as [`VERSION_ANNOTATION`]. Returns whether the tag moved.
+pub async fn update_latest_tag(
+    registry: &str,
+    name: &str,
+    version_tag: &str,
+    latest_tag: &str,
+    version: &str,
+    annotations: &[(String, String)],
+) -> ManagerResult<bool> {
+    let latest = format!("{}/{}:{}", registry, name, latest_tag);
+    textln!("    🔝 Checking {} before moving it to {}", latest, version);
+
+    let current = resolve_tagged_version(&latest).await?;
+    let current_version = current.clone().flatten();
+    if !should_move_latest(current_version.as_deref(), version) {
+        textln!(
+            "    ⏸️  Not moving {} backward: it points at {}, {} is not newer",
+            latest,
+            current_version.unwrap_or_default(),
+            version
+        );
+        return Ok(false);
+    }
+    if current == Some(None) {
+        textln!(
+            "    ⚠️  {} records no version, moving it to {}",
+            latest,
+            version
+        );
+    }
+
+    let mut annotations = annotations.to_vec();
+    annotations.retain(|(key, _)| key != VERSION_ANNOTATION);
+    annotations.push((VERSION_ANNOTATION.to_string(), version.to_string()));
+    // Recording the version takes an annotation, which only buildx
+    // imagetools can add
+    retag_docker_image(registry, name, version_tag, latest_tag, &annotations, false).await?;
+    Ok(true)
+}
+
+/// High-level function to promote a Docker image into `target_registry`
+#[allow(clippy::too_many_arguments)]
 pub async fn promote_docker_image(
     name: &str,
     source_version: &str,
     target_version: &str,
-    publish_to_docker_io: bool,
+    target_registry: &str,
     quiet: bool,
-) -> ManagerResult<()> {
+    annotations: &[(String, String)],
+    use_skopeo: bool,
+    platforms: &[String],
+    pin_digest: bool,
+) -> ManagerResult<Option<String>> {
     let config = DockerPromoteConfig {
         name: name.to_string(),
         source_version: source_version.to_string(),
         target_version: target_version.to_string(),
-        publish_to_docker_io,
+        target_registry: Some(target_registry.to_string()),
         quiet,
+        annotations: annotations.to_vec(),
+        use_skopeo,
+        platforms: platforms.to_vec(),
+        pin_digest,
     };
 
     let promoter = DockerPromoter::new(config);
@@ -127,6 +723,14 @@ pub struct DockerRegistryConfig {
     pub source_tag: String,
     /// Target tag
     pub target_tag: String,
+    /// OCI annotations to add/override on the target
+    pub annotations: Vec<(String, String)>,
+    /// Copy with `skopeo copy` instead of docker pull/tag/push
+    pub use_skopeo: bool,
+    /// Platforms of a multi-arch source to copy; all of them when empty
+    pub platforms: Vec<String>,
+    /// Copy from the source tag's digest and check the tag didn't move
+    pub pin_digest: bool,
 }
 
 /// Advanced Docker registry manager for cross-registry promotion
@@ -140,8 +744,9 @@ impl DockerRegistryManager {
         Self { config }
     }
 
-    /// Promote image between different registries
-    pub async fn cross_registry_promote(&self) -> ManagerResult<()> {
+    /// Promote image between different registries, returning the pushed
+    /// digest when it could be determined
+    pub async fn cross_registry_promote(&self) -> ManagerResult<Option<String>> {
         self.validate_config()?;
 
         let source_image = format!(
@@ -154,90 +759,302 @@ impl DockerRegistryManager {
             self.config.target_registry, self.config.image_name, self.config.target_tag
         );
 
-        println!(" 🔄 Cross-registry promotion:");
-        println!("    📥 Source: {}", source_image);
-        println!("    📤 Target: {}", target_image);
+        textln!(" 🔄 Cross-registry promotion:");
+        textln!("    📥 Source: {}", source_image);
+        textln!("    📤 Target: {}", target_image);
+
+        // A missing source otherwise only surfaces as an opaque pull failure
+        // halfway through the copy.
+        if self.is_cross_registry() {
+            let exists = self.manifest_exists(&source_image).await?;
+            check_source_exists(&source_image, exists)?;
+        }
+
+        let raw = self.raw_manifest(&source_image).await?;
+
+        // Copy from the digest the tag points at now, so a tag overwritten
+        // mid-promotion can't slip other bits in
+        let pinned = self.config.pin_digest.then(|| manifest_digest(&raw));
+        let copy_source = match &pinned {
+            Some(digest) => {
+                textln!("    📌 Pinned source digest: {}", digest);
+                format!(
+                    "{}/{}@{}",
+                    self.config.source_registry, self.config.image_name, digest
+                )
+            }
+            None => source_image.clone(),
+        };
+
+        let digest = self
+            .copy_image(&copy_source, &target_image, &parse_manifest_list(&raw)?)
+            .await?;
+
+        if let Some(pinned) = &pinned {
+            let current = manifest_digest(&self.raw_manifest(&source_image).await?);
+            check_digest_unchanged(&source_image, pinned, &current)?;
+        }
+
+        textln!("    ✅ Cross-registry promotion successful");
+        if let Some(digest) = &digest {
+            textln!("    🔏 Promoted digest: {}", digest);
+        }
+        Ok(digest)
+    }
+
+    /// Copy `source` to `target` the way its kind and the configuration
+    /// call for. `entries` are its platforms when it's a manifest list.
+    async fn copy_image(
+        &self,
+        source: &str,
+        target: &str,
+        entries: &Option<Vec<PlatformManifest>>,
+    ) -> ManagerResult<Option<String>> {
+        // docker pull/push would only move the host's platform out of a
+        // manifest list
+        if let Some(entries) = entries {
+            return self.promote_manifest_list(source, target, entries).await;
+        }
+        if !self.config.platforms.is_empty() {
+            textln!(
+                "    ℹ️  {} is a single-platform image, copying it as is",
+                source
+            );
+        }
+
+        // Annotations can't be added by docker tag/push; copy the manifest
+        // registry-side instead, which also avoids a local pull.
+        if !self.config.annotations.is_empty() {
+            self.imagetools_create(&[source.to_string()], target)
+                .await?;
+            return Ok(self.inspect_digest(target).await);
+        }
+
+        // Registry to registry, without a daemon or a local copy
+        if self.config.use_skopeo {
+            return self.skopeo_copy(source, target).await;
+        }
 
         // Pull from source registry
-        self.pull_image(&source_image).await?;
+        self.pull_image(source).await?;
 
         // Tag for target registry
-        self.tag_image(&source_image, &target_image).await?;
+        self.tag_image(source, target).await?;
 
         // Push to target registry
-        self.push_image(&target_image).await?;
+        self.push_image(target).await
+    }
+
+    /// Whether the copy moves the image to a different registry
+    fn is_cross_registry(&self) -> bool {
+        self.config.source_registry != self.config.target_registry
+    }
+
+    /// Whether `docker manifest inspect` finds `image` in its registry
+    async fn manifest_exists(&self, image: &str) -> ManagerResult<bool> {
+        textln!("    🔍 Checking source exists: {}", image);
+
+        let mut cmd = if self.config.use_skopeo {
+            let mut cmd = AsyncCommand::new("skopeo");
+            cmd.args(["inspect", "--raw", &format!("docker://{}", image)]);
+            cmd
+        } else {
+            let mut cmd = AsyncCommand::new(container_runtime());
+            cmd.args(["manifest", "inspect", image]);
+            cmd
+        };
+        let output = cmd
+            .timed_output("Failed to inspect source manifest")
+            .await?;
+        if output.status.success() {
+            return Ok(true);
+        }
+        // Auth failures and registry outages aren't a missing image
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if is_missing_manifest(&stderr) {
+            Ok(false)
+        } else {
+            Err(ManagerError::CommandFailed(format!(
+                "Failed to inspect source manifest {}: {}",
+                image,
+                stderr_excerpt(&stderr)
+            )))
+        }
+    }
+
+    /// Raw manifest or manifest list of `image`; see [`inspect_raw_command`]
+    async fn raw_manifest(&self, image: &str) -> ManagerResult<Vec<u8>> {
+        let mut cmd = inspect_raw_command(image, self.config.use_skopeo);
+        let _permit = registry_limits::acquire(image).await;
+        let output = cmd
+            .checked_output(
+                "Failed to inspect manifest",
+                &format!("Inspecting the manifest of {} failed", image),
+            )
+            .await?;
+        Ok(output.stdout)
+    }
+
+    /// Copy a multi-arch image with every platform it has, or only the
+    /// configured ones, and check the target is still a manifest list
+    async fn promote_manifest_list(
+        &self,
+        source: &str,
+        target: &str,
+        entries: &[PlatformManifest],
+    ) -> ManagerResult<Option<String>> {
+        let selected = select_platforms(source, entries, &self.config.platforms)?;
+        let platforms: Vec<&str> = selected.iter().map(|e| e.platform.as_str()).collect();
+        textln!("    🧩 Manifest list: {}", platforms.join(", "));
+
+        let digest = if self.config.use_skopeo
+            && self.config.platforms.is_empty()
+            && self.config.annotations.is_empty()
+        {
+            self.skopeo_copy(source, target).await?
+        } else {
+            // A subset is assembled into a new list from the platform images
+            let sources = if self.config.platforms.is_empty() {
+                vec![source.to_string()]
+            } else {
+                selected
+                    .iter()
+                    .map(|entry| {
+                        format!(
+                            "{}/{}@{}",
+                            self.config.source_registry, self.config.image_name, entry.digest
+                        )
+                    })
+                    .collect()
+            };
+            self.imagetools_create(&sources, target).await?;
+            self.inspect_digest(target).await
+        };
+
+        let raw = self.raw_manifest(target).await?;
+        check_manifest_list(target, &raw, &selected)?;
+        Ok(digest)
+    }
+
+    /// Best-effort registry-side digest lookup for `image`
+    async fn inspect_digest(&self, image: &str) -> Option<String> {
+        resolve_digest(image).await
+    }
+
+    /// Arguments for `docker buildx imagetools create`, copying `sources`
+    /// (several are merged into one manifest list) to `target` with the
+    /// configured annotations
+    fn imagetools_create_args(&self, sources: &[String], target: &str) -> Vec<String> {
+        let mut args = vec![
+            "buildx".to_string(),
+            "imagetools".to_string(),
+            "create".to_string(),
+        ];
+        for (key, value) in &self.config.annotations {
+            args.push("--annotation".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+        args.push("--tag".to_string());
+        args.push(target.to_string());
+        args.extend(sources.iter().cloned());
+        args
+    }
+
+    /// Copy and annotate an image with `docker buildx imagetools create`
+    async fn imagetools_create(&self, sources: &[String], target: &str) -> ManagerResult<()> {
+        for (key, value) in &self.config.annotations {
+            textln!("    📝 Annotation: {}={}", key, value);
+        }
+
+        let images: Vec<&str> = sources
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(target))
+            .collect();
+        let _permits = registry_limits::acquire_all(&images).await;
+        let mut cmd = AsyncCommand::new(container_runtime());
+        cmd.args(self.imagetools_create_args(sources, target));
+
+        cmd.checked_output(
+            "Failed to run docker buildx imagetools",
+            "docker buildx imagetools create failed",
+        )
+        .await?;
 
-        println!("    ✅ Cross-registry promotion successful");
         Ok(())
     }
 
+    /// Arguments for `skopeo copy`, copying every platform of `source` to
+    /// `target` and writing the target's manifest digest to `digest_file`
+    fn skopeo_copy_args(&self, source: &str, target: &str, digest_file: &Path) -> Vec<String> {
+        vec![
+            "copy".to_string(),
+            "--all".to_string(),
+            "--digestfile".to_string(),
+            digest_file.display().to_string(),
+            format!("docker://{}", source),
+            format!("docker://{}", target),
+        ]
+    }
+
+    /// Copy an image between registries with `skopeo copy`, returning the
+    /// digest it wrote
+    async fn skopeo_copy(&self, source: &str, target: &str) -> ManagerResult<Option<String>> {
+        textln!("    📦 Copying with skopeo: {} -> {}", source, target);
+
+        let digest_dir = tempfile::tempdir()?;
+        let digest_file = digest_dir.path().join("digest");
+
+        let _permits = registry_limits::acquire_all(&[source, target]).await;
+        AsyncCommand::new("skopeo")
+            .args(self.skopeo_copy_args(source, target, &digest_file))
+            .checked_output("Failed to run skopeo", "skopeo copy failed")
+            .await?;
+
+        let digest = std::fs::read_to_string(&digest_file).unwrap_or_default();
+        Ok(parse_digest(&format!("Digest: {}", digest.trim())))
+    }
+
     /// Pull Docker image
     async fn pull_image(&self, image: &str) -> ManagerResult<()> {
-        println!("    📥 Pulling: {}", image);
+        textln!("    📥 Pulling: {}", image);
 
-        let mut cmd = AsyncCommand::new("docker");
+        let _permit = registry_limits::acquire(image).await;
+        let mut cmd = AsyncCommand::new(container_runtime());
         cmd.arg("pull").arg(image);
 
-        let output = cmd
-            .output()
-            .await
-            .map_err(|e| ManagerError::CommandFailed(format!("Failed to pull image: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "Docker pull failed: {}",
-                stderr
-            )));
-        }
+        cmd.checked_output("Failed to pull image", "Docker pull failed")
+            .await?;
 
         Ok(())
     }
 
     /// Tag Docker image
     async fn tag_image(&self, source: &str, target: &str) -> ManagerResult<()> {
-        println!("    🏷️  Tagging: {} -> {}", source, target);
+        textln!("    🏷️  Tagging: {} -> {}", source, target);
 
-        let mut cmd = AsyncCommand::new("docker");
+        let mut cmd = AsyncCommand::new(container_runtime());
         cmd.arg("tag").arg(source).arg(target);
 
-        let output = cmd
-            .output()
-            .await
-            .map_err(|e| ManagerError::CommandFailed(format!("Failed to tag image: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "Docker tag failed: {}",
-                stderr
-            )));
-        }
+        cmd.checked_output("Failed to tag image", "Docker tag failed")
+            .await?;
 
         Ok(())
     }
 
-    /// Push Docker image
-    async fn push_image(&self, image: &str) -> ManagerResult<()> {
-        println!("    📤 Pushing: {}", image);
+    /// Push Docker image, returning the digest docker reports
+    async fn push_image(&self, image: &str) -> ManagerResult<Option<String>> {
+        textln!("    📤 Pushing: {}", image);
 
-        let mut cmd = AsyncCommand::new("docker");
+        let _permit = registry_limits::acquire(image).await;
+        let mut cmd = AsyncCommand::new(container_runtime());
         cmd.arg("push").arg(image);
 
         let output = cmd
-            .output()
-            .await
-            .map_err(|e| ManagerError::CommandFailed(format!("Failed to push image: {}", e)))?;
+            .checked_output("Failed to push image", "Docker push failed")
+            .await?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "Docker push failed: {}",
-                stderr
-            )));
-        }
-
-        Ok(())
+        Ok(parse_digest(&String::from_utf8_lossy(&output.stdout)))
     }
 
     /// Validate configuration
@@ -281,8 +1098,12 @@ mod tests {
             name: "mina-daemon".to_string(),
             source_version: "1.0.0-abc123".to_string(),
             target_version: "1.0.0".to_string(),
-            publish_to_docker_io: false,
+            target_registry: None,
             quiet: false,
+            annotations: vec![],
+            use_skopeo: false,
+            platforms: vec![],
+            pin_digest: false,
         };
 
         let promoter = DockerPromoter::new(config);
@@ -295,14 +1116,50 @@ mod tests {
             name: "".to_string(),
             source_version: "1.0.0-abc123".to_string(),
             target_version: "1.0.0".to_string(),
-            publish_to_docker_io: false,
+            target_registry: None,
             quiet: false,
+            annotations: vec![],
+            use_skopeo: false,
+            platforms: vec![],
+            pin_digest: false,
         };
 
         let promoter = DockerPromoter::new(config);
         assert!(promoter.validate_config().is_err());
     }
 
+    #[test]
+    fn test_latest_only_moves_forward() {
+        assert!(should_move_latest(None, "3.0.0"));
+        assert!(should_move_latest(Some("3.0.0"), "3.0.1"));
+        assert!(should_move_latest(Some("3.9.0"), "3.10.0"));
+        assert!(should_move_latest(Some("3.1.0~rc1"), "3.1.0"));
+
+        assert!(!should_move_latest(Some("3.0.1"), "3.0.1"));
+        assert!(!should_move_latest(Some("3.0.1"), "3.0.0"));
+        assert!(!should_move_latest(Some("3.10.0"), "3.9.0"));
+        assert!(!should_move_latest(Some("3.1.0"), "3.1.0~rc1"));
+
+        assert_eq!(latest_tag("bullseye", "-devnet"), "latest-bullseye-devnet");
+        assert_eq!(latest_tag("focal", ""), "latest-focal");
+    }
+
+    #[test]
+    fn test_parse_version_annotation() {
+        let index = br#"{
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.index.v1+json",
+            "manifests": [],
+            "annotations": {"org.opencontainers.image.version": "3.0.1"}
+        }"#;
+        assert_eq!(parse_version_annotation(index), Some("3.0.1".to_string()));
+
+        let unversioned = br#"{"schemaVersion": 2, "annotations": {"other": "x"}}"#;
+        assert_eq!(parse_version_annotation(unversioned), None);
+        assert_eq!(parse_version_annotation(br#"{"schemaVersion": 2}"#), None);
+        assert_eq!(parse_version_annotation(b"not json"), None);
+    }
+
     #[test]
     fn test_registry_config_validation() {
         let config = DockerRegistryConfig {
@@ -311,9 +1168,451 @@ mod tests {
             image_name: "mina-daemon".to_string(),
             source_tag: "1.0.0-dev".to_string(),
             target_tag: "1.0.0".to_string(),
+            annotations: vec![],
+            use_skopeo: false,
+            platforms: vec![],
+            pin_digest: false,
         };
 
         let manager = DockerRegistryManager::new(config);
         assert!(manager.validate_config().is_ok());
     }
+
+    #[test]
+    fn test_source_existence_check() {
+        let config = |target: &str| DockerRegistryConfig {
+            source_registry: GCR_REGISTRY.to_string(),
+            target_registry: target.to_string(),
+            image_name: "mina-daemon".to_string(),
+            source_tag: "3.0.1-bullseye-devnet".to_string(),
+            target_tag: "3.0.1-bullseye-devnet".to_string(),
+            annotations: vec![],
+            use_skopeo: false,
+            platforms: vec![],
+            pin_digest: false,
+        };
+        assert!(DockerRegistryManager::new(config("docker.io/minaprotocol")).is_cross_registry());
+        assert!(!DockerRegistryManager::new(config(GCR_REGISTRY)).is_cross_registry());
+
+        let source = "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet";
+        assert!(check_source_exists(source, true).is_ok());
+        match check_source_exists(source, false) {
+            Err(ManagerError::ArtifactNotFound(msg)) => assert!(msg.contains(source)),
+            other => panic!("expected ArtifactNotFound, got {:?}", other),
+        }
+
+        assert!(is_missing_manifest(
+            "manifest unknown: manifest tagged by \"3.0.1\" is not found"
+        ));
+        assert!(is_missing_manifest(
+            "ERROR: docker.io/minaprotocol/mina-daemon:3.0.1: not found"
+        ));
+        assert!(is_missing_manifest(
+            "Error: MANIFEST_UNKNOWN: Manifest Unknown"
+        ));
+        assert!(!is_missing_manifest(
+            "unauthorized: authentication required"
+        ));
+        assert!(!is_missing_manifest(
+            "Get \"https://gcr.io/v2/\": dial tcp: i/o timeout"
+        ));
+    }
+
+    #[test]
+    fn test_parse_digest() {
+        let push = "\
+The push refers to repository [docker.io/minaprotocol/mina-daemon]
+5f70bf18a086: Layer already exists
+a1b2c3d4e5f6: Pushed
+3.0.1-bullseye-devnet: digest: sha256:4c1b0c0bd5e8a3d2a8f0e6b1f7c9d2e3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9 size: 1577
+";
+        assert_eq!(
+            parse_digest(push).as_deref(),
+            Some("sha256:4c1b0c0bd5e8a3d2a8f0e6b1f7c9d2e3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9")
+        );
+
+        let inspect = "\
+Name:      gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet
+MediaType: application/vnd.oci.image.index.v1+json
+Digest:    sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef
+";
+        assert_eq!(
+            parse_digest(inspect).as_deref(),
+            Some("sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")
+        );
+
+        assert_eq!(parse_digest("a1b2c3d4e5f6: Pushed\n"), None);
+        assert_eq!(parse_digest("latest: digest: sha256:abc size: 1"), None);
+    }
+
+    /// Records the steps taken, failing the ones listed in `fail`
+    #[derive(Default)]
+    struct RecordingPromotion {
+        fail: Vec<&'static str>,
+        steps: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl RecordingPromotion {
+        fn step(&self, name: &'static str, tag: &str) -> ManagerResult<()> {
+            self.steps.lock().unwrap().push(format!("{} {}", name, tag));
+            if self.fail.contains(&name) {
+                Err(ManagerError::CommandFailed(format!("{} failed", name)))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn steps(&self) -> Vec<String> {
+            self.steps.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl StagedPromotion for RecordingPromotion {
+        async fn push(&self, staging_tag: &str) -> ManagerResult<()> {
+            self.step("push", staging_tag)
+        }
+        async fn verify(&self, staging_tag: &str) -> ManagerResult<()> {
+            self.step("verify", staging_tag)
+        }
+        async fn commit(&self, staging_tag: &str) -> ManagerResult<Option<String>> {
+            self.step("commit", staging_tag)
+                .map(|_| Some("sha256:feed".to_string()))
+        }
+        async fn cleanup(&self, staging_tag: &str) -> ManagerResult<()> {
+            self.step("cleanup", staging_tag)
+        }
+    }
+
+    #[test]
+    fn test_staging_tag() {
+        let staging = staging_tag("3.0.1-bullseye-devnet");
+        assert_eq!(
+            staging,
+            format!("3.0.1-bullseye-devnet-unverified-{}", std::process::id())
+        );
+        assert!(staging.ends_with(&staging_suffix()));
+        assert_eq!(
+            delete_tag_args("docker.io/minaprotocol/mina-daemon:x"),
+            vec!["tag", "delete", "docker.io/minaprotocol/mina-daemon:x"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_promote_verified_commits_after_verification() {
+        let staging = staging_tag("3.0.1-bullseye-devnet");
+        let promotion = RecordingPromotion::default();
+        let digest = promote_verified(&promotion, "3.0.1-bullseye-devnet")
+            .await
+            .unwrap();
+        assert_eq!(digest.as_deref(), Some("sha256:feed"));
+        assert_eq!(
+            promotion.steps(),
+            ["push", "verify", "commit", "cleanup"].map(|step| format!("{} {}", step, staging))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_promote_verified_never_commits_unverified() {
+        let staging = staging_tag("3.0.1-bullseye-devnet");
+
+        let failing = RecordingPromotion {
+            fail: vec!["verify"],
+            ..Default::default()
+        };
+        assert!(promote_verified(&failing, "3.0.1-bullseye-devnet")
+            .await
+            .is_err());
+        assert_eq!(
+            failing.steps(),
+            ["push", "verify", "cleanup"].map(|step| format!("{} {}", step, staging))
+        );
+
+        // A failed push leaves nothing to verify or clean up
+        let unpushed = RecordingPromotion {
+            fail: vec!["push"],
+            ..Default::default()
+        };
+        assert!(promote_verified(&unpushed, "3.0.1-bullseye-devnet")
+            .await
+            .is_err());
+        assert_eq!(unpushed.steps(), vec![format!("push {}", staging)]);
+
+        // Cleanup is best-effort once the final tag is committed
+        let sticky = RecordingPromotion {
+            fail: vec!["cleanup"],
+            ..Default::default()
+        };
+        assert!(promote_verified(&sticky, "3.0.1-bullseye-devnet")
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_parse_annotation() {
+        assert_eq!(
+            parse_annotation("org.opencontainers.image.revision=abc123").unwrap(),
+            (
+                "org.opencontainers.image.revision".to_string(),
+                "abc123".to_string()
+            )
+        );
+        assert_eq!(
+            parse_annotation("note=a=b").unwrap(),
+            ("note".to_string(), "a=b".to_string())
+        );
+        assert_eq!(
+            parse_annotation("empty=").unwrap(),
+            ("empty".to_string(), String::new())
+        );
+        assert!(parse_annotation("no-equals").is_err());
+        assert!(parse_annotation("=value").is_err());
+        assert!(parse_annotation("bad key=value").is_err());
+        assert!(parse_required_label("no-equals")
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid label"));
+    }
+
+    #[test]
+    fn test_imagetools_create_args() {
+        let manager = DockerRegistryManager::new(DockerRegistryConfig {
+            source_registry: "gcr.io/o1labs-192920".to_string(),
+            target_registry: "docker.io/minaprotocol".to_string(),
+            image_name: "mina-daemon".to_string(),
+            source_tag: "1.0.0-dev".to_string(),
+            target_tag: "1.0.0".to_string(),
+            annotations: vec![
+                (
+                    "org.opencontainers.image.revision".to_string(),
+                    "abc123".to_string(),
+                ),
+                (
+                    "org.opencontainers.image.version".to_string(),
+                    "1.0.0".to_string(),
+                ),
+            ],
+            use_skopeo: false,
+            platforms: vec![],
+            pin_digest: false,
+        });
+
+        assert_eq!(
+            manager.imagetools_create_args(
+                &["gcr.io/o1labs-192920/mina-daemon:1.0.0-dev".to_string()],
+                "docker.io/minaprotocol/mina-daemon:1.0.0"
+            ),
+            vec![
+                "buildx",
+                "imagetools",
+                "create",
+                "--annotation",
+                "org.opencontainers.image.revision=abc123",
+                "--annotation",
+                "org.opencontainers.image.version=1.0.0",
+                "--tag",
+                "docker.io/minaprotocol/mina-daemon:1.0.0",
+                "gcr.io/o1labs-192920/mina-daemon:1.0.0-dev",
+            ]
+        );
+    }
+
+    const DAEMON_INDEX: &[u8] = br#"{
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.index.v1+json",
+        "manifests": [
+            {"digest": "sha256:aaa", "platform": {"os": "linux", "architecture": "amd64"}},
+            {"digest": "sha256:bbb", "platform": {"os": "linux", "architecture": "arm64", "variant": "v8"}},
+            {"digest": "sha256:ccc", "platform": {"os": "unknown", "architecture": "unknown"}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_manifest_list() {
+        let entries = parse_manifest_list(DAEMON_INDEX).unwrap().unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                PlatformManifest {
+                    platform: "linux/amd64".to_string(),
+                    digest: "sha256:aaa".to_string(),
+                },
+                PlatformManifest {
+                    platform: "linux/arm64/v8".to_string(),
+                    digest: "sha256:bbb".to_string(),
+                },
+            ]
+        );
+
+        // Docker's manifest list, and an OCI index without a mediaType
+        let docker_list = br#"{"mediaType": "application/vnd.docker.distribution.manifest.list.v2+json",
+            "manifests": [{"digest": "sha256:aaa", "platform": {"os": "linux", "architecture": "amd64"}}]}"#;
+        assert_eq!(parse_manifest_list(docker_list).unwrap().unwrap().len(), 1);
+        assert!(
+            parse_manifest_list(br#"{"schemaVersion": 2, "manifests": []}"#)
+                .unwrap()
+                .is_some()
+        );
+
+        let single =
+            br#"{"mediaType": "application/vnd.oci.image.manifest.v1+json", "layers": []}"#;
+        assert_eq!(parse_manifest_list(single).unwrap(), None);
+        assert!(parse_manifest_list(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_select_platforms() {
+        let image = "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet";
+        let entries = parse_manifest_list(DAEMON_INDEX).unwrap().unwrap();
+
+        assert_eq!(select_platforms(image, &entries, &[]).unwrap(), entries);
+        let arm =
+            select_platforms(image, &entries, &parse_platforms("linux/arm64").unwrap()).unwrap();
+        assert_eq!(arm, vec![entries[1].clone()]);
+        assert!(select_platforms(image, &entries, &["linux/arm64/v7".to_string()]).is_err());
+        let err = select_platforms(image, &entries, &["linux/s390x".to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("has no linux/s390x image (has linux/amd64, linux/arm64/v8)"));
+
+        assert_eq!(
+            parse_platforms(" linux/amd64, linux/arm64/v8 ").unwrap(),
+            vec!["linux/amd64", "linux/arm64/v8"]
+        );
+        for bad in ["linux", "linux/", "/amd64", "linux/arm64/v8/x"] {
+            assert!(parse_platforms(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_check_manifest_list() {
+        let target = "docker.io/minaprotocol/mina-daemon:3.0.1-bullseye-devnet";
+        let entries = parse_manifest_list(DAEMON_INDEX).unwrap().unwrap();
+
+        assert!(check_manifest_list(target, DAEMON_INDEX, &entries).is_ok());
+
+        let amd64_only = br#"{"mediaType": "application/vnd.oci.image.index.v1+json",
+            "manifests": [{"digest": "sha256:aaa", "platform": {"os": "linux", "architecture": "amd64"}}]}"#;
+        let err = check_manifest_list(target, amd64_only, &entries)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is missing linux/arm64/v8 after promotion"));
+
+        let single = br#"{"mediaType": "application/vnd.oci.image.manifest.v1+json"}"#;
+        assert!(check_manifest_list(target, single, &entries)
+            .unwrap_err()
+            .to_string()
+            .contains("is not a manifest list"));
+    }
+
+    #[test]
+    fn test_pinned_digest() {
+        let digest = manifest_digest(DAEMON_INDEX);
+        assert_eq!(digest.len(), "sha256:".len() + 64);
+        assert_eq!(
+            parse_digest(&format!("Digest: {}", digest)),
+            Some(digest.clone())
+        );
+        assert_ne!(manifest_digest(b"{}"), digest);
+        // Known value: sha256 of the empty string
+        assert_eq!(
+            manifest_digest(b""),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let image = "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet";
+        assert!(check_digest_unchanged(image, &digest, &digest).is_ok());
+        let moved = manifest_digest(b"{}");
+        match check_digest_unchanged(image, &digest, &moved) {
+            Err(ManagerError::ValidationError(msg)) => {
+                assert!(msg.contains(&format!("moved from {} to {}", digest, moved)))
+            }
+            other => panic!("expected ValidationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_skopeo_copy_args() {
+        let manager = DockerRegistryManager::new(DockerRegistryConfig {
+            source_registry: GCR_REGISTRY.to_string(),
+            target_registry: "docker.io/minaprotocol".to_string(),
+            image_name: "mina-daemon".to_string(),
+            source_tag: "3.0.1-bullseye-devnet".to_string(),
+            target_tag: "3.0.1-bullseye-devnet".to_string(),
+            annotations: vec![],
+            use_skopeo: true,
+            platforms: vec![],
+            pin_digest: false,
+        });
+
+        assert_eq!(
+            manager.skopeo_copy_args(
+                "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet",
+                "docker.io/minaprotocol/mina-daemon:3.0.1-bullseye-devnet",
+                Path::new("/tmp/digest"),
+            ),
+            vec![
+                "copy",
+                "--all",
+                "--digestfile",
+                "/tmp/digest",
+                "docker://gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet",
+                "docker://docker.io/minaprotocol/mina-daemon:3.0.1-bullseye-devnet",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_required_labels() {
+        let labels = parse_image_labels(
+            br#"{"org.opencontainers.image.revision":"abc123","built-by":"buildkite"}"#,
+        )
+        .unwrap();
+        let required = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let image = "gcr.io/o1labs-192920/mina-daemon:1.0.0-bullseye-devnet";
+
+        assert!(check_required_labels(image, &labels, &[]).is_ok());
+        assert!(check_required_labels(
+            image,
+            &labels,
+            &required(&[
+                ("org.opencontainers.image.revision", "abc123"),
+                ("built-by", "buildkite")
+            ])
+        )
+        .is_ok());
+
+        let err = check_required_labels(
+            image,
+            &labels,
+            &required(&[
+                ("org.opencontainers.image.revision", "def456"),
+                (
+                    "org.opencontainers.image.source",
+                    "https://github.com/MinaProtocol/mina",
+                ),
+                ("built-by", "buildkite"),
+            ]),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains(image));
+        assert!(err.contains("org.opencontainers.image.revision is 'abc123', expected 'def456'"));
+        assert!(err.contains("org.opencontainers.image.source is missing"));
+        assert!(!err.contains("built-by"));
+
+        // An image built without any label
+        let unlabelled = parse_image_labels(b"null\n").unwrap();
+        assert!(unlabelled.is_empty());
+        assert!(
+            check_required_labels(image, &unlabelled, &required(&[("built-by", "buildkite")]))
+                .is_err()
+        );
+        assert!(parse_image_labels(b"not json").is_err());
+    }
 }
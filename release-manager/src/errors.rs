@@ -19,6 +19,9 @@ pub enum ManagerError {
     #[error("Validation error: {0}")]
     ValidationError(String),
 
+    #[error("{operation} timed out after {seconds}s")]
+    Timeout { operation: String, seconds: u64 },
+
     #[error("Network error: {0}")]
     NetworkError(#[from] reqwest::Error),
 
@@ -33,4 +36,42 @@ pub enum ManagerError {
 
     #[error("Unknown artifact: {0}")]
     UnknownArtifact(String),
+
+    #[error("deb-s3 is installed but could not start ({0}). Reinstall the gem and its dependencies with `gem install deb-s3`")]
+    DebS3Broken(String),
+}
+
+/// Exit code of a run that gave up waiting, as `timeout(1)` uses
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+impl ManagerError {
+    /// Process exit code for a run failing with this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ManagerError::Timeout { .. } => TIMEOUT_EXIT_CODE,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_display_and_exit_code() {
+        let timeout = ManagerError::Timeout {
+            operation: "Verification of mina-devnet".to_string(),
+            seconds: 30,
+        };
+        assert_eq!(
+            timeout.to_string(),
+            "Verification of mina-devnet timed out after 30s"
+        );
+        assert_eq!(timeout.exit_code(), TIMEOUT_EXIT_CODE);
+        assert_eq!(
+            ManagerError::CommandFailed("deb-s3".to_string()).exit_code(),
+            1
+        );
+    }
 }
@@ -0,0 +1,521 @@
+use crate::commands::validate::parse_packages_file;
+use crate::debian_publish::versions_to_prune;
+use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::trace::TracedCommand;
+use crate::utils::format_mismatch;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::process::Command as AsyncCommand;
+
+/// Control fields of a `.deb` needed to place it in a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebControl {
+    pub package: String,
+    pub version: String,
+    pub architecture: String,
+    /// Full control stanza as printed by `dpkg-deb -f`
+    pub stanza: String,
+}
+
+impl DebControl {
+    /// Parse the output of `dpkg-deb -f <deb>`
+    pub fn parse(stanza: &str) -> ManagerResult<Self> {
+        let field = |name: &str| {
+            stanza
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("{}:", name)))
+                .map(|v| v.trim().to_string())
+                .ok_or_else(|| {
+                    ManagerError::ValidationError(format!("Control file has no {} field", name))
+                })
+        };
+        Ok(Self {
+            package: field("Package")?,
+            version: field("Version")?,
+            architecture: field("Architecture")?,
+            stanza: stanza.trim_end().to_string(),
+        })
+    }
+}
+
+/// `pool/<component>/<initial>/<package>/`, like a regular Debian mirror
+pub fn pool_dir(component: &str, package: &str) -> PathBuf {
+    let initial = package.chars().next().map(String::from).unwrap_or_default();
+    Path::new("pool")
+        .join(component)
+        .join(initial)
+        .join(package)
+}
+
+/// `dists/<codename>/<component>/binary-<arch>/Packages`
+pub fn packages_index_path(root: &Path, codename: &str, component: &str, arch: &str) -> PathBuf {
+    root.join("dists")
+        .join(codename)
+        .join(component)
+        .join(format!("binary-{}", arch))
+        .join("Packages")
+}
+
+/// Add `stanza` for `package`/`version` to a Packages index, replacing an
+/// existing entry for the same package version.
+pub fn upsert_packages_stanza(index: &str, package: &str, version: &str, stanza: &str) -> String {
+    let mut stanzas: Vec<&str> = split_stanzas(index)
+        .filter(|s| !stanza_is(s, package, |v| v == version))
+        .collect();
+    stanzas.push(stanza.trim_matches('\n'));
+    format!("{}\n", stanzas.join("\n\n"))
+}
+
+fn split_stanzas(index: &str) -> impl Iterator<Item = &str> {
+    index
+        .split("\n\n")
+        .map(|s| s.trim_matches('\n'))
+        .filter(|s| !s.is_empty())
+}
+
+/// Whether `stanza` describes `package` at a version accepted by `version`
+fn stanza_is(stanza: &str, package: &str, version: impl Fn(&str) -> bool) -> bool {
+    parse_packages_file(&format!("{}\n", stanza))
+        .iter()
+        .any(|e| {
+            e.package.as_deref() == Some(package) && e.version.as_deref().is_some_and(&version)
+        })
+}
+
+/// Control fields of the `.deb` at `deb_path`, read with `dpkg-deb -f`
+pub async fn read_deb_control(deb_path: &Path) -> ManagerResult<DebControl> {
+    let mut cmd = AsyncCommand::new("dpkg-deb");
+    cmd.arg("-f").arg(deb_path);
+    let output = cmd
+        .checked_output(
+            "Failed to execute dpkg-deb",
+            &format!("dpkg-deb -f failed for {}", deb_path.display()),
+        )
+        .await?;
+    DebControl::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Publish a `.deb` into a local `file://` style repository at `root`: copy
+/// it into the pool and update the Packages index for its architecture.
+/// Returns the path of the copied package.
+pub async fn publish_to_local_mirror(
+    deb_path: &Path,
+    root: &Path,
+    codename: &str,
+    component: &str,
+) -> ManagerResult<PathBuf> {
+    let control = read_deb_control(deb_path).await?;
+
+    let file_name = deb_path.file_name().ok_or_else(|| {
+        ManagerError::ValidationError(format!("Not a file: {}", deb_path.display()))
+    })?;
+    let relative = pool_dir(component, &control.package).join(file_name);
+    let target = root.join(&relative);
+    tokio::fs::create_dir_all(target.parent().unwrap_or(root)).await?;
+    tokio::fs::copy(deb_path, &target).await?;
+
+    let bytes = tokio::fs::read(&target).await?;
+    let stanza = format!(
+        "{}\nFilename: {}\nSize: {}\nSHA256: {}\n",
+        control.stanza,
+        relative.display(),
+        bytes.len(),
+        hex::encode(Sha256::digest(&bytes))
+    );
+
+    let index_path = packages_index_path(root, codename, component, &control.architecture);
+    tokio::fs::create_dir_all(index_path.parent().unwrap_or(root)).await?;
+    let index = tokio::fs::read_to_string(&index_path)
+        .await
+        .unwrap_or_default();
+    tokio::fs::write(
+        &index_path,
+        upsert_packages_stanza(&index, &control.package, &control.version, &stanza),
+    )
+    .await?;
+
+    textln!(
+        "    📁 Published {} {} to local mirror {}",
+        control.package,
+        control.version,
+        root.display()
+    );
+    Ok(target)
+}
+
+/// Every architecture's Packages index of `codename`/`component`, one after
+/// the other; empty when the mirror has no such component yet
+pub fn read_component_indexes(
+    root: &Path,
+    codename: &str,
+    component: &str,
+) -> ManagerResult<String> {
+    let component_dir = root.join("dists").join(codename).join(component);
+    let arch_dirs = match std::fs::read_dir(&component_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut indexes = String::new();
+    for arch_dir in arch_dirs {
+        if let Ok(index) = std::fs::read_to_string(arch_dir?.path().join("Packages")) {
+            indexes.push_str(&index);
+            indexes.push_str("\n\n");
+        }
+    }
+    Ok(indexes)
+}
+
+/// Keep only the `keep` most recent versions of `package` in every
+/// architecture's Packages index of `codename`/`component`, deleting the pool
+/// files of the others. Returns the pruned versions, newest first.
+pub fn prune_local_mirror_package(
+    root: &Path,
+    codename: &str,
+    component: &str,
+    package: &str,
+    keep: usize,
+) -> ManagerResult<Vec<String>> {
+    let component_dir = root.join("dists").join(codename).join(component);
+    let arch_dirs = match std::fs::read_dir(&component_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut indexes = Vec::new();
+    let mut versions = Vec::new();
+    for arch_dir in arch_dirs {
+        let index_path = arch_dir?.path().join("Packages");
+        let index = match std::fs::read_to_string(&index_path) {
+            Ok(index) => index,
+            Err(_) => continue,
+        };
+        versions.extend(
+            parse_packages_file(&index)
+                .into_iter()
+                .filter(|e| e.package.as_deref() == Some(package))
+                .filter_map(|e| e.version),
+        );
+        indexes.push((index_path, index));
+    }
+
+    let pruned = versions_to_prune(&versions, keep);
+    if pruned.is_empty() {
+        textln!("    🧹 Nothing to prune for {} (keeping {})", package, keep);
+        return Ok(pruned);
+    }
+
+    for (index_path, index) in indexes {
+        let mut kept = Vec::new();
+        for stanza in split_stanzas(&index) {
+            if !stanza_is(stanza, package, |v| pruned.iter().any(|p| p == v)) {
+                kept.push(stanza);
+                continue;
+            }
+            let filename = parse_packages_file(&format!("{}\n", stanza))
+                .into_iter()
+                .find_map(|e| e.filename);
+            if let Some(filename) = filename {
+                match std::fs::remove_file(root.join(&filename)) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        let pruned_index = if kept.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", kept.join("\n\n"))
+        };
+        std::fs::write(&index_path, pruned_index)?;
+    }
+    for version in &pruned {
+        textln!("    🧹 Pruned {} {} from local mirror", package, version);
+    }
+
+    Ok(pruned)
+}
+
+/// Presence-only verification: check that `package` at `version` is listed in
+/// any architecture's Packages index of the local mirror, and that the pool
+/// file it points to exists with the recorded SHA256.
+pub fn verify_local_mirror_package(
+    root: &Path,
+    codename: &str,
+    component: &str,
+    package: &str,
+    version: &str,
+) -> ManagerResult<()> {
+    let component_dir = root.join("dists").join(codename).join(component);
+    let arch_dirs = match std::fs::read_dir(&component_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ManagerError::ArtifactNotFound(format!(
+                "{} has no {}/{} component",
+                root.display(),
+                codename,
+                component
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    for arch_dir in arch_dirs {
+        let index = match std::fs::read_to_string(arch_dir?.path().join("Packages")) {
+            Ok(index) => index,
+            Err(_) => continue,
+        };
+        for entry in parse_packages_file(&index) {
+            if entry.package.as_deref() != Some(package)
+                || entry.version.as_deref() != Some(version)
+            {
+                continue;
+            }
+            let filename = entry.filename.unwrap_or_default();
+            let bytes = std::fs::read(root.join(&filename)).map_err(|e| {
+                ManagerError::ArtifactNotFound(format!(
+                    "{} {} is indexed but {} is unreadable: {}",
+                    package, version, filename, e
+                ))
+            })?;
+            let actual = hex::encode(Sha256::digest(&bytes));
+            if entry.sha256.as_deref() != Some(actual.as_str()) {
+                return Err(ManagerError::ValidationError(format!(
+                    "SHA256 mismatch for {} (expected from index, actual from file):{}",
+                    filename,
+                    format_mismatch(entry.sha256.as_deref().unwrap_or("<missing>"), &actual)
+                )));
+            }
+            textln!(
+                "    ✅ {} {} present in local mirror ({})",
+                package,
+                version,
+                filename
+            );
+            return Ok(());
+        }
+    }
+
+    Err(ManagerError::ArtifactNotFound(format!(
+        "{} {} not found in {} {}/{}",
+        package,
+        version,
+        root.display(),
+        codename,
+        component
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTROL: &str = "Package: mina-devnet\nVersion: 1.0.0\nArchitecture: amd64\nMaintainer: test@example.com\nDescription: fixture\n";
+
+    #[test]
+    fn test_parse_control() {
+        let control = DebControl::parse(CONTROL).unwrap();
+        assert_eq!(control.package, "mina-devnet");
+        assert_eq!(control.version, "1.0.0");
+        assert_eq!(control.architecture, "amd64");
+        assert!(DebControl::parse("Package: x\n").is_err());
+    }
+
+    #[test]
+    fn test_mirror_layout() {
+        assert_eq!(
+            pool_dir("unstable", "mina-devnet"),
+            Path::new("pool/unstable/m/mina-devnet")
+        );
+        assert_eq!(
+            packages_index_path(Path::new("/repo"), "bullseye", "unstable", "amd64"),
+            Path::new("/repo/dists/bullseye/unstable/binary-amd64/Packages")
+        );
+    }
+
+    #[test]
+    fn test_upsert_packages_stanza() {
+        let first = "Package: mina-devnet\nVersion: 1.0.0\nFilename: a.deb\n";
+        let other = "Package: mina-devnet\nVersion: 1.1.0\nFilename: b.deb\n";
+        let replaced = "Package: mina-devnet\nVersion: 1.0.0\nFilename: c.deb\n";
+
+        let index = upsert_packages_stanza("", "mina-devnet", "1.0.0", first);
+        let index = upsert_packages_stanza(&index, "mina-devnet", "1.1.0", other);
+        let index = upsert_packages_stanza(&index, "mina-devnet", "1.0.0", replaced);
+
+        let entries = parse_packages_file(&index);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].filename.as_deref(), Some("b.deb"));
+        assert_eq!(entries[1].filename.as_deref(), Some("c.deb"));
+    }
+
+    #[test]
+    fn test_verify_local_mirror_package_detects_missing_and_tampered() {
+        let root = tempfile::tempdir().unwrap();
+        let pool_file = root
+            .path()
+            .join("pool/unstable/m/mina-devnet/mina-devnet_1.0.0.deb");
+        std::fs::create_dir_all(pool_file.parent().unwrap()).unwrap();
+        std::fs::write(&pool_file, b"deb").unwrap();
+
+        let index_path = packages_index_path(root.path(), "bullseye", "unstable", "amd64");
+        std::fs::create_dir_all(index_path.parent().unwrap()).unwrap();
+        let stanza = format!(
+            "{}Filename: pool/unstable/m/mina-devnet/mina-devnet_1.0.0.deb\nSHA256: {}\n",
+            CONTROL,
+            hex::encode(Sha256::digest(b"deb"))
+        );
+        std::fs::write(&index_path, &stanza).unwrap();
+
+        verify_local_mirror_package(root.path(), "bullseye", "unstable", "mina-devnet", "1.0.0")
+            .unwrap();
+        assert!(matches!(
+            verify_local_mirror_package(
+                root.path(),
+                "bullseye",
+                "unstable",
+                "mina-devnet",
+                "2.0.0"
+            ),
+            Err(ManagerError::ArtifactNotFound(_))
+        ));
+        assert!(matches!(
+            verify_local_mirror_package(root.path(), "focal", "unstable", "mina-devnet", "1.0.0"),
+            Err(ManagerError::ArtifactNotFound(_))
+        ));
+
+        std::fs::write(&pool_file, b"tampered").unwrap();
+        assert!(matches!(
+            verify_local_mirror_package(
+                root.path(),
+                "bullseye",
+                "unstable",
+                "mina-devnet",
+                "1.0.0"
+            ),
+            Err(ManagerError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_prune_local_mirror_package() {
+        let root = tempfile::tempdir().unwrap();
+        let index_path = packages_index_path(root.path(), "bullseye", "unstable", "amd64");
+        std::fs::create_dir_all(index_path.parent().unwrap()).unwrap();
+        let pool = pool_dir("unstable", "mina-devnet");
+        std::fs::create_dir_all(root.path().join(&pool)).unwrap();
+
+        let mut index = String::new();
+        for version in ["1.0.0", "1.2.0", "1.10.0"] {
+            let filename = pool.join(format!("mina-devnet_{}.deb", version));
+            std::fs::write(root.path().join(&filename), b"deb").unwrap();
+            index = upsert_packages_stanza(
+                &index,
+                "mina-devnet",
+                version,
+                &format!(
+                    "Package: mina-devnet\nVersion: {}\nFilename: {}\n",
+                    version,
+                    filename.display()
+                ),
+            );
+        }
+        index = upsert_packages_stanza(
+            &index,
+            "mina-archive-devnet",
+            "1.0.0",
+            "Package: mina-archive-devnet\nVersion: 1.0.0\nFilename: pool/unstable/m/mina-archive-devnet/a.deb\n",
+        );
+        std::fs::write(&index_path, &index).unwrap();
+
+        let pruned =
+            prune_local_mirror_package(root.path(), "bullseye", "unstable", "mina-devnet", 2)
+                .unwrap();
+        assert_eq!(pruned, vec!["1.0.0".to_string()]);
+
+        let remaining = std::fs::read_to_string(&index_path).unwrap();
+        let versions: Vec<_> = parse_packages_file(&remaining)
+            .into_iter()
+            .map(|e| (e.package.unwrap(), e.version.unwrap()))
+            .collect();
+        assert_eq!(versions.len(), 3);
+        assert!(!versions.contains(&("mina-devnet".to_string(), "1.0.0".to_string())));
+        assert!(!root
+            .path()
+            .join(pool.join("mina-devnet_1.0.0.deb"))
+            .exists());
+        assert!(root
+            .path()
+            .join(pool.join("mina-devnet_1.10.0.deb"))
+            .exists());
+
+        assert!(
+            prune_local_mirror_package(root.path(), "focal", "unstable", "mina-devnet", 2)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    /// End to end: build a fixture `.deb`, publish it to a local mirror and
+    /// verify it through the presence-only path.
+    #[cfg(feature = "integration-test")]
+    #[tokio::test]
+    async fn publish_and_verify_against_local_mirror() {
+        assert!(
+            std::process::Command::new("dpkg-deb")
+                .arg("--version")
+                .output()
+                .is_ok(),
+            "publish_and_verify_against_local_mirror needs dpkg-deb on PATH"
+        );
+
+        let tmp = tempfile::tempdir().unwrap();
+        let pkg_root = tmp.path().join("mina-devnet");
+        std::fs::create_dir_all(pkg_root.join("DEBIAN")).unwrap();
+        std::fs::create_dir_all(pkg_root.join("usr/share/doc/mina-devnet")).unwrap();
+        std::fs::write(pkg_root.join("DEBIAN/control"), CONTROL).unwrap();
+        std::fs::write(pkg_root.join("usr/share/doc/mina-devnet/README"), "hello\n").unwrap();
+        let deb_path = tmp.path().join("mina-devnet_1.0.0.deb");
+        let out = std::process::Command::new("dpkg-deb")
+            .args(["-Zgzip", "--build"])
+            .arg(&pkg_root)
+            .arg(&deb_path)
+            .output()
+            .expect("dpkg-deb");
+        assert!(
+            out.status.success(),
+            "dpkg-deb failed: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+
+        let mirror = tmp.path().join("mirror");
+        let published = publish_to_local_mirror(&deb_path, &mirror, "bullseye", "unstable")
+            .await
+            .unwrap();
+        assert_eq!(
+            published,
+            mirror.join("pool/unstable/m/mina-devnet/mina-devnet_1.0.0.deb")
+        );
+
+        verify_local_mirror_package(&mirror, "bullseye", "unstable", "mina-devnet", "1.0.0")
+            .unwrap();
+        assert!(
+            verify_local_mirror_package(&mirror, "bullseye", "stable", "mina-devnet", "1.0.0")
+                .is_err()
+        );
+
+        // Re-publishing the same version replaces its index entry
+        publish_to_local_mirror(&deb_path, &mirror, "bullseye", "unstable")
+            .await
+            .unwrap();
+        let index = std::fs::read_to_string(packages_index_path(
+            &mirror, "bullseye", "unstable", "amd64",
+        ))
+        .unwrap();
+        assert_eq!(parse_packages_file(&index).len(), 1);
+    }
+}
@@ -1,21 +1,41 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use colored::*;
 use std::env;
+use std::path::Path;
+use std::time::Instant;
 
+mod artifactory;
 mod artifacts;
+mod baseline;
+mod cache_lock;
 mod cli;
+mod command_timeout;
 mod commands;
+mod container_runtime;
 mod debian_publish;
+mod disk_space;
 mod docker_promote;
 mod errors;
+mod local_mirror;
+mod metrics;
+mod output;
 mod process;
+mod registry_limits;
+mod release_signature;
 mod reversion;
+mod run_manifest;
+mod s3;
+mod sbom;
 mod storage;
+mod tool_versions;
+mod trace;
 mod utils;
 mod verification;
 
 use cli::*;
+use container_runtime::{container_runtime, ContainerRuntime};
 use errors::ManagerResult;
+use output::{textln, OutputFormat, Reporter};
 
 #[derive(Parser)]
 #[command(name = "release-manager")]
@@ -31,13 +51,16 @@ Main capabilities:
 - PROMOTE: Promote artifacts from one channel/registry to another (e.g., unstable -> stable)
 - VERIFY: Verify that artifacts are correctly published in target channels/registries
 - FIX: Repair Debian repository manifests when needed
+- DELETE: Remove a published version of debian packages from a channel
+- LIST: Show the versions of artifacts published in a channel/registry
 - PERSIST: Archive artifacts to long-term storage backends
+- NAMES: Print the canonical debian package names, versions and docker tags
 
 Supported artifacts: mina-daemon, mina-archive, mina-rosetta, mina-logproc
 Supported networks: devnet, mainnet
 Supported platforms: Debian (bullseye, focal), Docker (GCR, Docker.io)
 Supported channels: unstable, alpha, beta, stable
-Supported backends: Google Cloud Storage (gs), Hetzner, local filesystem
+Supported backends: Google Cloud Storage (gs), Hetzner, S3, local filesystem
 "#)]
 struct Cli {
     #[command(subcommand)]
@@ -45,6 +68,56 @@ struct Cli {
 
     #[arg(long, env = "RUST_LOG", default_value = "info")]
     log_level: String,
+
+    /// Write Prometheus textfile metrics for this run to the given path
+    #[arg(long, global = true)]
+    metrics_file: Option<String>,
+
+    /// Append a JSON line per external command run (args, exit code, duration) to the given path
+    #[arg(long, global = true)]
+    trace: Option<String>,
+
+    /// Maximum concurrent docker pulls/pushes against gcr.io
+    #[arg(long, global = true, default_value_t = registry_limits::DEFAULT_GCR_CONCURRENCY)]
+    gcr_concurrency: usize,
+
+    /// Maximum concurrent docker pulls/pushes against docker.io
+    #[arg(long, global = true, default_value_t = registry_limits::DEFAULT_DOCKER_IO_CONCURRENCY)]
+    dockerio_concurrency: usize,
+
+    /// Memory limit of verification containers, as for `docker run --memory` (e.g. 4g)
+    #[arg(long, global = true)]
+    container_memory: Option<String>,
+
+    /// CPU limit of verification containers, as for `docker run --cpus` (e.g. 2)
+    #[arg(long, global = true)]
+    container_cpus: Option<String>,
+
+    /// Lines of a failed command's stderr to include in its error; --debug/--trace keep the full output
+    #[arg(long, global = true, default_value_t = utils::DEFAULT_STDERR_LINES)]
+    stderr_lines: usize,
+
+    /// Print a single JSON document describing the run on stdout; text output goes to stderr
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Program that runs containers; detected from PATH when unset, preferring docker
+    #[arg(long, global = true, env = "CONTAINER_RUNTIME", value_enum)]
+    container_runtime: Option<ContainerRuntime>,
+
+    /// Fail unless `<name> --version` reports this version, as name=version (repeatable)
+    #[arg(long, global = true, value_name = "NAME=VERSION")]
+    require_tool_version: Vec<String>,
+
+    /// Tag segment docker images use for a debian codename, as codename=segment (repeatable)
+    #[arg(
+        long,
+        global = true,
+        env = "DOCKER_CODENAME_TAGS",
+        value_delimiter = ',',
+        value_name = "CODENAME=SEGMENT"
+    )]
+    docker_codename_tag: Vec<String>,
 }
 
 #[derive(Subcommand)]
@@ -57,6 +130,11 @@ enum Commands {
     Verify(VerifyArgs),
     /// Fix debian package repository manifests
     Fix(FixArgs),
+    /// Remove one version of debian packages from a channel
+    #[command(alias = "unpublish")]
+    Delete(DeleteArgs),
+    /// List the debian package versions and docker tags published for artifacts
+    List(ListArgs),
     /// Validate a debian channel: list, SHA256-check, optionally repair + re-sign + invalidate CDN
     Validate(ValidateArgs),
     /// Persist artifacts to long-term storage
@@ -67,43 +145,220 @@ enum Commands {
     Reversion(ReversionArgs),
     /// Show release-progress report (what's published per channel/codename/arch)
     Progress(ProgressArgs),
+    /// Print the resolved debian package names, versions and docker tags for the given inputs
+    Names(NamesArgs),
+    /// Print a shell completion script to stdout
+    #[command(hide = true)]
+    Completions(CompletionsArgs),
 }
 
 #[tokio::main]
 async fn main() -> ManagerResult<()> {
     let cli = Cli::parse();
 
+    // The script goes to stdout as-is, without logging or prerequisites
+    if let Commands::Completions(args) = &cli.command {
+        print_completions(args.shell, &mut std::io::stdout());
+        return Ok(());
+    }
+
     // Initialize logger
     env::set_var("RUST_LOG", &cli.log_level);
     env_logger::init();
 
+    registry_limits::configure(cli.gcr_concurrency, cli.dockerio_concurrency);
+    container_runtime::configure(cli.container_runtime);
+    artifacts::configure_codename_tags(artifacts::CodenameTags::parse(&cli.docker_codename_tag)?);
+    utils::configure_stderr_lines(cli.stderr_lines);
+    verification::configure_container_limits(verification::ContainerLimits::new(
+        cli.container_memory.clone(),
+        cli.container_cpus.clone(),
+    )?);
+    if let Some(path) = &cli.trace {
+        trace::configure(path)?;
+    }
+
+    let reporter = Reporter::new(cli.output);
+    check_output_format(&cli.command, reporter.format())?;
+    // The JSON document and `verify --json-stream` records own stdout
+    let json_stdout = reporter.format() == OutputFormat::Json
+        || matches!(&cli.command, Commands::Verify(args) if args.json_stream);
+    if json_stdout {
+        output::reserve_stdout();
+    }
+
+    let (command_label, channel_label) = metric_labels(&cli.command);
+
     // Check required applications based on command
-    check_prerequisites(&cli.command).await?;
+    let prerequisites = match check_prerequisites(&cli.command).await {
+        Ok(()) => tool_versions::check_required_versions(&cli.require_tool_version).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = prerequisites {
+        return finish(command_label, &reporter, Err(e), true);
+    }
+
+    // Keep machine-readable output parseable
+    let json_output = json_stdout
+        || match &cli.command {
+            Commands::Names(args) => args.format == commands::names::NamesFormat::Json,
+            Commands::List(args) => args.json,
+            _ => false,
+        };
+
+    let started = Instant::now();
 
     let result = match cli.command {
-        Commands::Publish(args) => commands::publish::execute(args).await,
-        Commands::Promote(args) => commands::promote::execute(args).await,
-        Commands::Verify(args) => commands::verify::execute(args).await,
+        Commands::Publish(args) => commands::publish::execute(args, &reporter).await,
+        Commands::Promote(args) => commands::promote::execute(args, &reporter).await,
+        Commands::Verify(args) => commands::verify::execute(args, &reporter).await.map(drop),
         Commands::Fix(args) => commands::fix::execute(args).await,
+        Commands::Delete(args) => commands::delete::execute(args, &reporter).await,
+        Commands::List(args) => commands::list::execute(args).await,
         Commands::Validate(args) => commands::validate::execute(args).await,
         Commands::Persist(args) => commands::persist::execute(args).await,
         Commands::Pull(args) => commands::pull::execute(args).await,
         Commands::Reversion(args) => commands::reversion::execute(args).await,
         Commands::Progress(args) => commands::progress::execute(args).await,
+        Commands::Names(args) => commands::names::execute(args).await,
+        Commands::Completions(_) => Ok(()),
     };
 
+    if let Some(path) = &cli.metrics_file {
+        let run = metrics::RunMetrics::collect(
+            command_label,
+            &channel_label,
+            started.elapsed(),
+            result.is_err(),
+        );
+        // Don't let a metrics problem mask the run's own outcome
+        if let Err(e) = metrics::write_metrics_file(Path::new(path), &run) {
+            eprintln!(
+                "{} Failed to write metrics to {}: {}",
+                "⚠️".yellow(),
+                path,
+                e
+            );
+        }
+    }
+
+    finish(command_label, &reporter, result, json_output)
+}
+
+/// `--output json` needs a command that records per-artifact results; the
+/// others would print an empty report, and `list --json` and
+/// `names --format json` already print their own JSON
+fn check_output_format(command: &Commands, format: OutputFormat) -> ManagerResult<()> {
+    let reports = matches!(
+        command,
+        Commands::Publish(_) | Commands::Promote(_) | Commands::Verify(_) | Commands::Delete(_)
+    );
+    if format == OutputFormat::Json && !reports {
+        return Err(errors::ManagerError::ValidationError(format!(
+            "--output json is only supported by publish, promote, verify and delete, not {}",
+            metric_labels(command).0
+        )));
+    }
+    Ok(())
+}
+
+/// Report the run's outcome, also as a JSON document on stdout for
+/// `--output json`, and exit non-zero on failure
+fn finish(
+    operation: &str,
+    reporter: &Reporter,
+    result: ManagerResult<()>,
+    quiet: bool,
+) -> ManagerResult<()> {
+    if reporter.format() == OutputFormat::Json {
+        output::write_report(&mut std::io::stdout(), &reporter.finish(operation, &result))?;
+    }
     match result {
         Ok(_) => {
-            println!("{}", " ✅  Operation completed successfully.".green());
+            if !quiet {
+                textln!("{}", " ✅  Operation completed successfully.".green());
+            }
             Ok(())
         }
         Err(e) => {
             eprintln!("{} {}", "❌".red(), e.to_string().red());
-            std::process::exit(1);
+            std::process::exit(e.exit_code());
         }
     }
 }
 
+/// `command` and `channel` labels for `--metrics-file`
+fn metric_labels(command: &Commands) -> (&'static str, String) {
+    match command {
+        Commands::Publish(args) => ("publish", args.channel.clone()),
+        Commands::Promote(args) => ("promote", args.target_channel.clone().unwrap_or_default()),
+        Commands::Verify(args) => ("verify", args.channel.clone()),
+        Commands::Fix(args) => ("fix", args.channel.clone()),
+        Commands::Delete(args) => ("delete", args.channel.clone()),
+        Commands::List(args) => ("list", args.channel.clone()),
+        Commands::Validate(args) => ("validate", args.channel.clone()),
+        Commands::Persist(_) => ("persist", String::new()),
+        Commands::Pull(_) => ("pull", String::new()),
+        Commands::Reversion(_) => ("reversion", String::new()),
+        Commands::Progress(_) => ("progress", String::new()),
+        Commands::Names(args) => ("names", args.channel.clone()),
+        Commands::Completions(_) => ("completions", String::new()),
+    }
+}
+
+/// Write `shell`'s completion script for the whole CLI to `out`
+fn print_completions(shell: clap_complete::Shell, out: &mut dyn std::io::Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "release-manager", out);
+}
+
+/// podman has no `buildx imagetools`: images are copied with skopeo, and
+/// what only imagetools can do (annotating, assembling a platform subset)
+/// is refused up front
+fn check_podman_copy(
+    command: &str,
+    use_skopeo: bool,
+    imagetools_flags: &[(&str, bool)],
+) -> ManagerResult<()> {
+    if !use_skopeo {
+        return Err(errors::ManagerError::ValidationError(format!(
+            "podman has no buildx imagetools to copy images with; {} with --use-skopeo",
+            command
+        )));
+    }
+    let needs_imagetools: Vec<&str> = imagetools_flags
+        .iter()
+        .filter_map(|(flag, set)| set.then_some(*flag))
+        .collect();
+    if !needs_imagetools.is_empty() {
+        return Err(errors::ManagerError::ValidationError(format!(
+            "{} need docker buildx imagetools, which podman doesn't have; {} with --container-runtime docker",
+            needs_imagetools.join(", "),
+            command
+        )));
+    }
+    Ok(())
+}
+
+fn check_podman_promotion(args: &PromoteArgs) -> ManagerResult<()> {
+    check_podman_copy(
+        "promote",
+        args.use_skopeo,
+        &[
+            ("--update-latest", args.update_latest),
+            ("--annotation", !args.annotations.is_empty()),
+            ("--platforms", args.platforms.is_some()),
+        ],
+    )
+}
+
+fn check_podman_publish(args: &PublishArgs) -> ManagerResult<()> {
+    check_podman_copy(
+        "publish",
+        args.use_skopeo,
+        &[("--platforms", args.platforms.is_some())],
+    )
+}
+
 async fn check_prerequisites(command: &Commands) -> ManagerResult<()> {
     use utils::check_app;
 
@@ -113,25 +368,235 @@ async fn check_prerequisites(command: &Commands) -> ManagerResult<()> {
                 check_app("gsutil").await?;
             }
             if args.verify {
-                check_app("docker").await?;
+                check_app(container_runtime()).await?;
+            }
+            if args.sbom_dir.is_some() && !args.only_debians && !args.dry_run {
+                check_app("oras").await?;
+            }
+            if (args.local_mirror.is_some()
+                || args.debian_publisher == debian_publish::DebianPublisherKind::Artifactory)
+                && !args.only_dockers
+            {
+                check_app("dpkg-deb").await?;
+            }
+            if args.use_skopeo && !args.only_debians && !args.dry_run {
+                check_app("skopeo").await?;
+            }
+            // --only-missing resolves docker tags with skopeo under podman
+            if args.only_missing && !args.only_debians && !container_runtime::has_imagetools() {
+                check_app("skopeo").await?;
+            }
+            if container_runtime::runtime() == ContainerRuntime::Podman
+                && !args.only_debians
+                && !args.dry_run
+            {
+                check_podman_publish(args)?;
             }
         }
         Commands::Promote(args) => {
-            if args.verify {
-                check_app("docker").await?;
+            if args.verify || args.verify_before_commit {
+                check_app(container_runtime()).await?;
+            }
+            if args.verify_before_commit && !args.only_debians && !args.dry_run {
+                check_app("regctl").await?;
+            }
+            if !args.only_dockers && !args.dry_run {
+                check_app("deb-s3").await?;
+                if args.source_version != args.target_version {
+                    check_app("dpkg-deb").await?;
+                }
+            }
+            if args.from_build_id.is_some() && args.backend == "gs" {
+                check_app("gsutil").await?;
+            }
+            if args.use_skopeo && !args.only_debians && !args.dry_run {
+                check_app("skopeo").await?;
+            }
+            if container_runtime::runtime() == ContainerRuntime::Podman
+                && !args.only_debians
+                && !args.dry_run
+            {
+                check_podman_promotion(args)?;
             }
         }
-        Commands::Verify(_) => {
-            check_app("docker").await?;
+        Commands::Verify(args) => {
+            check_app(container_runtime()).await?;
+            // Digests are resolved with skopeo under podman
+            if args.from_manifest.is_some() && !container_runtime::has_imagetools() {
+                check_app("skopeo").await?;
+            }
         }
         Commands::Fix(_) => {
             check_app("deb-s3").await?;
         }
-        Commands::Validate(_) => {
+        Commands::Delete(args) if !args.dry_run => {
             check_app("deb-s3").await?;
         }
+        Commands::Validate(args) => {
+            check_app("deb-s3").await?;
+            if args.check_signing_key {
+                check_app("gpg").await?;
+            }
+        }
         _ => {}
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap_complete::Shell;
+
+    #[test]
+    fn test_completions_for_each_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let mut out = Vec::new();
+            print_completions(shell, &mut out);
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("release-manager"), "{}", shell);
+            assert!(script.contains("verify-cross-consistency"), "{}", shell);
+        }
+    }
+
+    #[test]
+    fn test_keep_versions_rejects_zero() {
+        let parse = |keep: &str| {
+            Cli::try_parse_from([
+                "release-manager",
+                "publish",
+                "--buildkite-build-id",
+                "123",
+                "--source-version",
+                "3.0.1",
+                "--target-version",
+                "3.0.1",
+                "--channel",
+                "unstable",
+                "--keep-versions",
+                keep,
+            ])
+        };
+        let err = parse("0").err().unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(parse("1").is_ok());
+    }
+    #[test]
+    fn test_output_json_only_for_reporting_commands() {
+        let check = |argv: &[&str]| {
+            let cli = Cli::try_parse_from(argv).unwrap();
+            check_output_format(&cli.command, cli.output)
+        };
+        let err = check(&["release-manager", "--output", "json", "list", "--json"]).unwrap_err();
+        assert!(err.to_string().contains(
+            "--output json is only supported by publish, promote, verify and delete, not list"
+        ));
+        assert!(check(&["release-manager", "list", "--json"]).is_ok());
+        assert!(check(&[
+            "release-manager",
+            "--output",
+            "json",
+            "verify",
+            "--version",
+            "3.0.1",
+            "--channel",
+            "unstable",
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn test_verify_concurrency_rejects_zero() {
+        let parse = |concurrency: &str| {
+            Cli::try_parse_from([
+                "release-manager",
+                "verify",
+                "--version",
+                "3.0.1",
+                "--channel",
+                "unstable",
+                "--verify-concurrency",
+                concurrency,
+            ])
+        };
+        let err = parse("0").err().unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(parse("1").is_ok());
+    }
+
+    #[test]
+    fn test_podman_promotion_needs_skopeo_and_no_imagetools_flags() {
+        let promote_args = |extra: &[&str]| {
+            let mut argv = vec![
+                "release-manager",
+                "promote",
+                "--source-version",
+                "3.0.1",
+                "--target-version",
+                "3.0.1",
+                "--only-dockers",
+            ];
+            argv.extend_from_slice(extra);
+            match Cli::try_parse_from(argv).unwrap().command {
+                Commands::Promote(args) => args,
+                _ => unreachable!(),
+            }
+        };
+
+        assert!(check_podman_promotion(&promote_args(&["--use-skopeo"])).is_ok());
+        assert!(
+            check_podman_promotion(&promote_args(&["--use-skopeo", "--verify-before-commit"]))
+                .is_ok()
+        );
+        let err = check_podman_promotion(&promote_args(&[])).unwrap_err();
+        assert!(err.to_string().contains("--use-skopeo"));
+        let err = check_podman_promotion(&promote_args(&[
+            "--use-skopeo",
+            "--update-latest",
+            "--annotation",
+            "a=b",
+        ]))
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("--update-latest, --annotation need docker buildx imagetools"));
+    }
+
+    #[test]
+    fn test_podman_publish_needs_skopeo_and_no_imagetools_flags() {
+        let publish_args = |extra: &[&str]| {
+            let mut argv = vec![
+                "release-manager",
+                "publish",
+                "--source-version",
+                "3.0.1",
+                "--target-version",
+                "3.0.1",
+                "--channel",
+                "unstable",
+                "--buildkite-build-id",
+                "abc",
+                "--only-dockers",
+            ];
+            argv.extend_from_slice(extra);
+            match Cli::try_parse_from(argv).unwrap().command {
+                Commands::Publish(args) => args,
+                _ => unreachable!(),
+            }
+        };
+
+        assert!(check_podman_publish(&publish_args(&["--use-skopeo"])).is_ok());
+        let err = check_podman_publish(&publish_args(&[])).unwrap_err();
+        assert!(err.to_string().contains("publish with --use-skopeo"));
+        let err = check_podman_publish(&publish_args(&[
+            "--use-skopeo",
+            "--platforms",
+            "linux/amd64",
+        ]))
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("--platforms need docker buildx imagetools"));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::errors::ManagerResult;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+static PUBLISHED: AtomicU64 = AtomicU64::new(0);
+static PROMOTED: AtomicU64 = AtomicU64::new(0);
+static VERIFIED: AtomicU64 = AtomicU64::new(0);
+static DOWNLOADED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide counters bumped by the commands as they go
+#[derive(Debug, Clone, Copy)]
+pub enum Counter {
+    Published,
+    Promoted,
+    Verified,
+    DownloadedBytes,
+}
+
+impl Counter {
+    fn cell(self) -> &'static AtomicU64 {
+        match self {
+            Counter::Published => &PUBLISHED,
+            Counter::Promoted => &PROMOTED,
+            Counter::Verified => &VERIFIED,
+            Counter::DownloadedBytes => &DOWNLOADED_BYTES,
+        }
+    }
+}
+
+pub fn add(counter: Counter, value: u64) {
+    counter.cell().fetch_add(value, Ordering::Relaxed);
+}
+
+pub fn record(counter: Counter) {
+    add(counter, 1);
+}
+
+/// Summary of one release-manager run, written for the node_exporter
+/// textfile collector by `--metrics-file`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunMetrics {
+    pub command: String,
+    pub channel: String,
+    pub published: u64,
+    pub promoted: u64,
+    pub verified: u64,
+    pub failures: u64,
+    pub duration: Duration,
+    pub downloaded_bytes: u64,
+}
+
+impl RunMetrics {
+    /// Snapshot the counters at the end of a run
+    pub fn collect(command: &str, channel: &str, duration: Duration, failed: bool) -> Self {
+        Self {
+            command: command.to_string(),
+            channel: channel.to_string(),
+            published: PUBLISHED.load(Ordering::Relaxed),
+            promoted: PROMOTED.load(Ordering::Relaxed),
+            verified: VERIFIED.load(Ordering::Relaxed),
+            failures: u64::from(failed),
+            duration,
+            downloaded_bytes: DOWNLOADED_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Prometheus text exposition format. Metric names are part of the
+    /// dashboards' contract, so don't rename them.
+    pub fn to_textfile(&self) -> String {
+        let labels = format!(
+            "{{command=\"{}\",channel=\"{}\"}}",
+            escape_label(&self.command),
+            escape_label(&self.channel)
+        );
+        let samples = [
+            (
+                "mina_release_published_artifacts",
+                "Artifacts published by the last run",
+                self.published.to_string(),
+            ),
+            (
+                "mina_release_promoted_artifacts",
+                "Artifacts promoted by the last run",
+                self.promoted.to_string(),
+            ),
+            (
+                "mina_release_verified_artifacts",
+                "Artifacts verified by the last run",
+                self.verified.to_string(),
+            ),
+            (
+                "mina_release_failures",
+                "Whether the last run failed (1) or succeeded (0)",
+                self.failures.to_string(),
+            ),
+            (
+                "mina_release_duration_seconds",
+                "Wall-clock duration of the last run",
+                format!("{:.3}", self.duration.as_secs_f64()),
+            ),
+            (
+                "mina_release_downloaded_bytes",
+                "Bytes of debian packages downloaded from storage by the last run",
+                self.downloaded_bytes.to_string(),
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, help, value) in samples {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+            out.push_str(&format!("# TYPE {} gauge\n", name));
+            out.push_str(&format!("{}{} {}\n", name, labels, value));
+        }
+        out
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Write `metrics` to `path` via a temporary file and a rename, so the
+/// textfile collector never scrapes a half-written file.
+pub fn write_metrics_file(path: &Path, metrics: &RunMetrics) -> ManagerResult<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, metrics.to_textfile())?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_textfile_format() {
+        let metrics = RunMetrics {
+            command: "publish".to_string(),
+            channel: "unstable".to_string(),
+            published: 4,
+            promoted: 0,
+            verified: 4,
+            failures: 1,
+            duration: Duration::from_millis(12_345),
+            downloaded_bytes: 123_457_813,
+        };
+        let text = metrics.to_textfile();
+
+        assert!(text.ends_with('\n'));
+        assert_eq!(text.lines().count(), 18);
+        assert!(text.contains(
+            "# HELP mina_release_published_artifacts Artifacts published by the last run\n\
+             # TYPE mina_release_published_artifacts gauge\n\
+             mina_release_published_artifacts{command=\"publish\",channel=\"unstable\"} 4\n"
+        ));
+        assert!(text.contains(
+            "mina_release_duration_seconds{command=\"publish\",channel=\"unstable\"} 12.345\n"
+        ));
+        assert!(text.contains(
+            "mina_release_downloaded_bytes{command=\"publish\",channel=\"unstable\"} 123457813\n"
+        ));
+        assert!(
+            text.contains("mina_release_failures{command=\"publish\",channel=\"unstable\"} 1\n")
+        );
+    }
+
+    #[test]
+    fn test_label_escaping_and_atomic_write() {
+        let metrics = RunMetrics {
+            command: "verify".to_string(),
+            channel: "we\"ird\\".to_string(),
+            published: 0,
+            promoted: 0,
+            verified: 0,
+            failures: 0,
+            duration: Duration::ZERO,
+            downloaded_bytes: 0,
+        };
+        assert!(metrics
+            .to_textfile()
+            .contains("{command=\"verify\",channel=\"we\\\"ird\\\\\"}"));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("release.prom");
+        write_metrics_file(&path, &metrics).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            metrics.to_textfile()
+        );
+        assert!(!dir.path().join("release.prom.tmp").exists());
+    }
+}
@@ -0,0 +1,225 @@
+//! `--output json`: one JSON document describing the run on stdout, with
+//! the usual decorative text moved to stderr.
+//!
+//! Progress and summary text is printed with [`textln!`] rather than
+//! `println!`, so it can step aside while JSON owns stdout. Commands print
+//! their own results (`names`, `list`) with `println!`.
+
+use std::future::Future;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::errors::ManagerResult;
+
+/// How a run reports what it did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Progress and summaries as human-readable text
+    #[default]
+    Text,
+    /// A single JSON document once the run is over
+    Json,
+}
+
+/// Outcome of one unit of work on an artifact
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArtifactResult {
+    pub artifact: String,
+    /// What was done, e.g. `debian bullseye devnet` or a docker tag
+    pub target: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the artifact's SBOM was uploaded or attached (`--sbom-dir`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<String>,
+}
+
+/// The document `--output json` prints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunReport {
+    pub operation: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub results: Vec<ArtifactResult>,
+}
+
+/// Collects per-artifact results while a command runs. Shared by reference,
+/// so parallel units can record into it.
+#[derive(Debug, Default)]
+pub struct Reporter {
+    format: OutputFormat,
+    results: Mutex<Vec<ArtifactResult>>,
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            results: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Record the outcome of `target` on `artifact`
+    pub fn record<T>(&self, artifact: &str, target: &str, outcome: &ManagerResult<T>) {
+        self.push(artifact, target, outcome, None);
+    }
+
+    fn push<T>(
+        &self,
+        artifact: &str,
+        target: &str,
+        outcome: &ManagerResult<T>,
+        sbom: Option<String>,
+    ) {
+        self.results.lock().unwrap().push(ArtifactResult {
+            artifact: artifact.to_string(),
+            target: target.to_string(),
+            success: outcome.is_ok(),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+            sbom,
+        });
+    }
+
+    /// Run `work` and record its outcome
+    pub async fn track<T, F>(&self, artifact: &str, target: &str, work: F) -> ManagerResult<T>
+    where
+        F: Future<Output = ManagerResult<T>>,
+    {
+        let outcome = work.await;
+        self.record(artifact, target, &outcome);
+        outcome
+    }
+
+    /// [`track`](Self::track) for work that may publish an SBOM, recording
+    /// the location it returns
+    pub async fn track_sbom<F>(&self, artifact: &str, target: &str, work: F) -> ManagerResult<()>
+    where
+        F: Future<Output = ManagerResult<Option<String>>>,
+    {
+        let outcome = work.await;
+        let sbom = outcome.as_ref().ok().cloned().flatten();
+        self.push(artifact, target, &outcome, sbom);
+        outcome.map(drop)
+    }
+
+    /// The run's report, given the command's own outcome
+    pub fn finish(&self, operation: &str, outcome: &ManagerResult<()>) -> RunReport {
+        RunReport {
+            operation: operation.to_string(),
+            success: outcome.is_ok(),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+            results: self.results.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Set once JSON owns stdout
+static TEXT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send [`textln!`] output to stderr for the rest of the run, leaving stdout
+/// to the JSON document or records. Tools run by the commands have their
+/// output captured, so nothing else reaches stdout.
+pub fn reserve_stdout() {
+    TEXT_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`textln!`] prints to stderr
+pub fn text_to_stderr() -> bool {
+    TEXT_TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// `println!` for progress and summary text, printed to stderr once
+/// [`reserve_stdout`] has been called
+macro_rules! textln {
+    ($($arg:tt)*) => {
+        if $crate::output::text_to_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// `print!` counterpart of [`textln!`]
+macro_rules! text {
+    ($($arg:tt)*) => {
+        if $crate::output::text_to_stderr() {
+            eprint!($($arg)*)
+        } else {
+            print!($($arg)*)
+        }
+    };
+}
+
+pub(crate) use {text, textln};
+
+/// Write `report` as pretty-printed JSON to `out`
+pub fn write_report(out: &mut dyn Write, report: &RunReport) -> ManagerResult<()> {
+    writeln!(out, "{}", serde_json::to_string_pretty(report)?)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ManagerError;
+
+    #[tokio::test]
+    async fn test_report_collects_results() {
+        let reporter = Reporter::new(OutputFormat::Json);
+        reporter
+            .track("mina-daemon", "debian bullseye devnet", async { Ok(()) })
+            .await
+            .unwrap();
+        let failed: ManagerResult<()> = reporter
+            .track("mina-archive", "debian bullseye devnet", async {
+                Err(ManagerError::ArtifactNotFound(
+                    "mina-archive-devnet".to_string(),
+                ))
+            })
+            .await;
+        assert!(failed.is_err());
+        reporter
+            .track_sbom("mina-logproc", "debian bullseye", async {
+                Ok(Some(
+                    "gs://buildkite_k8s/coda/shared/123/debians/bullseye/sboms/mina-logproc_3.0.1.spdx.json"
+                        .to_string(),
+                ))
+            })
+            .await
+            .unwrap();
+
+        let outcome = Err(ManagerError::ValidationError("1 failure".to_string()));
+        let report = reporter.finish("publish", &outcome);
+        assert!(!report.success);
+        assert_eq!(report.error.as_deref(), Some("Validation error: 1 failure"));
+
+        let mut out = Vec::new();
+        write_report(&mut out, &report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["operation"], "publish");
+        assert_eq!(json["results"][0]["artifact"], "mina-daemon");
+        assert_eq!(json["results"][0]["success"], true);
+        assert!(json["results"][0].get("error").is_none());
+        assert_eq!(json["results"][1]["success"], false);
+        assert_eq!(
+            json["results"][1]["error"],
+            "Artifact not found: mina-archive-devnet"
+        );
+        assert!(json["results"][1].get("sbom").is_none());
+        assert_eq!(
+            json["results"][2]["sbom"],
+            "gs://buildkite_k8s/coda/shared/123/debians/bullseye/sboms/mina-logproc_3.0.1.spdx.json"
+        );
+    }
+}
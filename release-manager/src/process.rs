@@ -5,6 +5,7 @@
 //! The real impl is the default; tests construct a [`MockCommandExecutor`]
 //! and pre-register responses keyed by the program name.
 
+use crate::trace::TracedStdCommand;
 #[cfg(test)]
 use std::collections::HashMap;
 #[cfg(test)]
@@ -84,7 +85,9 @@ pub struct RealExecutor;
 
 impl CommandExecutor for RealExecutor {
     fn run(&self, program: &str, args: &[&str]) -> std::io::Result<CommandOutput> {
-        let out = std::process::Command::new(program).args(args).output()?;
+        let out = std::process::Command::new(program)
+            .args(args)
+            .traced_output()?;
         Ok(CommandOutput {
             status: out.status.code().unwrap_or(-1),
             stdout: String::from_utf8_lossy(&out.stdout).into_owned(),
@@ -182,13 +185,13 @@ impl MockCommandExecutor {
 /// Executor that runs some programs for real and mocks the rest. Useful for
 /// integration tests that want a real `deb-s3` against a MinIO container but
 /// don't want to depend on `dig`, `aws cloudfront`, etc.
-#[cfg(test)]
+#[cfg(all(test, feature = "integration-test"))]
 pub struct MixedExecutor {
     pub mock: MockCommandExecutor,
     pub mocked_programs: std::collections::HashSet<String>,
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "integration-test"))]
 impl MixedExecutor {
     pub fn new(mocked_programs: &[&str]) -> Self {
         Self {
@@ -198,7 +201,7 @@ impl MixedExecutor {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "integration-test"))]
 impl CommandExecutor for MixedExecutor {
     fn run(&self, program: &str, args: &[&str]) -> std::io::Result<CommandOutput> {
         if self.mocked_programs.contains(program) {
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Concurrent pulls/pushes against gcr.io unless `--gcr-concurrency` is set
+pub const DEFAULT_GCR_CONCURRENCY: usize = 8;
+/// docker.io rate-limits pulls per account, so stay well below it by default
+pub const DEFAULT_DOCKER_IO_CONCURRENCY: usize = 2;
+
+/// Per-registry caps on concurrent docker pulls and pushes, one semaphore per
+/// registry host. Registries other than gcr.io and docker.io aren't capped.
+pub struct RegistryLimits {
+    gcr: usize,
+    docker_io: usize,
+    semaphores: Mutex<HashMap<&'static str, Arc<Semaphore>>>,
+}
+
+impl RegistryLimits {
+    pub fn new(gcr: usize, docker_io: usize) -> Self {
+        Self {
+            gcr: gcr.max(1),
+            docker_io: docker_io.max(1),
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit_for(&self, host: &str) -> Option<(&'static str, usize)> {
+        match host {
+            "gcr.io" => Some(("gcr.io", self.gcr)),
+            "docker.io" => Some(("docker.io", self.docker_io)),
+            _ => None,
+        }
+    }
+
+    /// Wait for a slot on `image`'s registry. The slot is held until the
+    /// permit is dropped; `None` for uncapped registries.
+    pub async fn acquire(&self, image: &str) -> Option<OwnedSemaphorePermit> {
+        let (host, limit) = self.limit_for(&registry_host(image))?;
+        let semaphore = self
+            .semaphores
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// Slots on every registry `images` touch, taken in host order so two
+    /// cross-registry copies can't each hold the slot the other waits for
+    pub async fn acquire_all(&self, images: &[&str]) -> Vec<OwnedSemaphorePermit> {
+        let mut hosts: Vec<(String, &str)> = images
+            .iter()
+            .map(|image| (registry_host(image), *image))
+            .collect();
+        hosts.sort();
+        hosts.dedup_by(|a, b| a.0 == b.0);
+
+        let mut permits = Vec::new();
+        for (_, image) in hosts {
+            permits.extend(self.acquire(image).await);
+        }
+        permits
+    }
+}
+
+/// Registry host of an image reference, normalizing docker.io's aliases and
+/// regional gcr.io hosts. References without a registry are docker.io.
+pub fn registry_host(image: &str) -> String {
+    let host = match image.split_once('/') {
+        Some((first, _)) if first.contains(['.', ':']) || first == "localhost" => first,
+        _ => return "docker.io".to_string(),
+    };
+    match host {
+        "index.docker.io" | "registry-1.docker.io" => "docker.io".to_string(),
+        h if h.ends_with(".gcr.io") => "gcr.io".to_string(),
+        h => h.to_string(),
+    }
+}
+
+static LIMITS: OnceLock<RegistryLimits> = OnceLock::new();
+
+/// Set the process-wide limits; only the first call takes effect
+pub fn configure(gcr: usize, docker_io: usize) {
+    let _ = LIMITS.set(RegistryLimits::new(gcr, docker_io));
+}
+
+fn limits() -> &'static RegistryLimits {
+    LIMITS
+        .get_or_init(|| RegistryLimits::new(DEFAULT_GCR_CONCURRENCY, DEFAULT_DOCKER_IO_CONCURRENCY))
+}
+
+/// Wait for a slot on `image`'s registry under the configured limits
+pub async fn acquire(image: &str) -> Option<OwnedSemaphorePermit> {
+    limits().acquire(image).await
+}
+
+/// Wait for slots on every registry `images` touch
+pub async fn acquire_all(images: &[&str]) -> Vec<OwnedSemaphorePermit> {
+    limits().acquire_all(images).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_registry_host() {
+        for (image, host) in [
+            (
+                "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet",
+                "gcr.io",
+            ),
+            ("us.gcr.io/o1labs-192920/mina-daemon:3.0.1", "gcr.io"),
+            ("docker.io/minaprotocol/mina-daemon:3.0.1", "docker.io"),
+            (
+                "index.docker.io/minaprotocol/mina-daemon:3.0.1",
+                "docker.io",
+            ),
+            ("minaprotocol/mina-daemon:3.0.1", "docker.io"),
+            ("arm64v8/debian:bullseye", "docker.io"),
+            ("debian:bullseye", "docker.io"),
+            ("localhost:5000/mina-daemon:3.0.1", "localhost:5000"),
+            ("ghcr.io/minaprotocol/mina-daemon:3.0.1", "ghcr.io"),
+        ] {
+            assert_eq!(registry_host(image), host, "{}", image);
+        }
+    }
+
+    /// Whether a slot on `image`'s registry is free right now
+    async fn free(limits: &RegistryLimits, image: &str) -> bool {
+        tokio::time::timeout(Duration::from_millis(20), limits.acquire(image))
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_per_registry() {
+        let limits = RegistryLimits::new(1, 2);
+        let gcr = "gcr.io/o1labs-192920/mina-daemon:1";
+        let docker_io = "minaprotocol/mina-daemon:1";
+
+        let held = limits.acquire(gcr).await.unwrap();
+        assert!(!free(&limits, "us.gcr.io/o1labs-192920/mina-archive:1").await);
+
+        // A full gcr.io doesn't hold up docker.io, which has its own limit
+        let first = limits.acquire(docker_io).await.unwrap();
+        let second = limits.acquire("docker.io/minaprotocol/x:1").await.unwrap();
+        assert!(!free(&limits, docker_io).await);
+
+        // Other registries aren't capped
+        assert!(limits.acquire("ghcr.io/minaprotocol/x:1").await.is_none());
+
+        drop(held);
+        assert!(free(&limits, gcr).await);
+        drop((first, second));
+        assert!(free(&limits, docker_io).await);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_all_takes_one_slot_per_registry() {
+        let limits = RegistryLimits::new(1, 1);
+        let permits = limits
+            .acquire_all(&[
+                "docker.io/minaprotocol/mina-daemon:1",
+                "gcr.io/o1labs-192920/mina-daemon:1",
+                "gcr.io/o1labs-192920/mina-daemon:2",
+            ])
+            .await;
+        assert_eq!(permits.len(), 2);
+        assert!(!free(&limits, "gcr.io/o1labs-192920/x:1").await);
+        drop(permits);
+        assert!(free(&limits, "gcr.io/o1labs-192920/x:1").await);
+    }
+}
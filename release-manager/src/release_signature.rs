@@ -0,0 +1,363 @@
+use std::fs;
+use std::path::Path;
+
+use crate::errors::{ManagerError, ManagerResult};
+use crate::process::CommandExecutor;
+
+/// Outcome of `gpg --verify` on a Release signature, from its status output
+#[derive(Debug, PartialEq)]
+pub enum SignatureStatus {
+    /// Good signature; fingerprints of the signing (sub)key and its primary key
+    Valid {
+        signing_fpr: String,
+        primary_fpr: String,
+    },
+    /// Good signature by a key that has since expired, which apt rejects
+    ExpiredKey,
+    /// The signature couldn't be checked or doesn't match
+    Invalid(String),
+}
+
+/// How long the key that signed the Release file stays valid
+#[derive(Debug, PartialEq)]
+pub enum KeyExpiry {
+    Never,
+    Valid { days_left: i64 },
+    Expiring { days_left: i64 },
+    Expired,
+}
+
+/// Parse `gpg --status-fd 1 --verify` output. A bad or uncheckable
+/// signature wins over an expired key, which wins over a good signature.
+pub fn parse_gpg_status(status: &str) -> SignatureStatus {
+    let mut valid = None;
+    let mut expired = false;
+    let mut invalid = None;
+
+    for line in status.lines() {
+        let Some(line) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.first().copied() {
+            Some("BADSIG") => invalid = Some("bad signature".to_string()),
+            Some("NO_PUBKEY") => {
+                invalid = Some(format!(
+                    "no public key {}",
+                    fields.get(1).copied().unwrap_or("?")
+                ))
+            }
+            Some("ERRSIG") if invalid.is_none() => {
+                invalid = Some("signature could not be checked".to_string())
+            }
+            Some("EXPKEYSIG") | Some("KEYEXPIRED") => expired = true,
+            Some("VALIDSIG") if fields.len() > 1 => {
+                let signing_fpr = fields[1].to_string();
+                // The primary key fingerprint is the 10th VALIDSIG argument;
+                // older gpg versions leave it out
+                let primary_fpr = fields.get(10).copied().unwrap_or(fields[1]).to_string();
+                valid = Some(SignatureStatus::Valid {
+                    signing_fpr,
+                    primary_fpr,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(reason) = invalid {
+        SignatureStatus::Invalid(reason)
+    } else if expired {
+        SignatureStatus::ExpiredKey
+    } else {
+        valid.unwrap_or_else(|| SignatureStatus::Invalid("no signature found".to_string()))
+    }
+}
+
+/// Expiry (unix seconds, `None` if it never expires) of every key and
+/// subkey in `gpg --with-colons --list-keys` output, by fingerprint
+pub fn parse_key_expiries(listing: &str) -> Vec<(String, Option<i64>)> {
+    let mut expiries = Vec::new();
+    let mut pending = None;
+
+    for line in listing.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first().copied() {
+            Some("pub") | Some("sub") => {
+                pending = Some(fields.get(6).and_then(|e| e.parse::<i64>().ok()));
+            }
+            Some("fpr") => {
+                if let (Some(expiry), Some(fpr)) = (pending.take(), fields.get(9)) {
+                    expiries.push((fpr.to_string(), expiry));
+                }
+            }
+            _ => {}
+        }
+    }
+    expiries
+}
+
+/// Classify `expires_at` against `now`, warning within `warn_days`
+pub fn classify_expiry(expires_at: Option<i64>, now: i64, warn_days: i64) -> KeyExpiry {
+    let Some(expires_at) = expires_at else {
+        return KeyExpiry::Never;
+    };
+    if expires_at <= now {
+        return KeyExpiry::Expired;
+    }
+    let days_left = (expires_at - now) / 86_400;
+    if days_left < warn_days {
+        KeyExpiry::Expiring { days_left }
+    } else {
+        KeyExpiry::Valid { days_left }
+    }
+}
+
+/// Check the signature on `dists/<codename>/InRelease` (or `Release` +
+/// `Release.gpg` when there's no InRelease) against the repository's
+/// `keys/minaprotocol.asc`, and classify the signing key's expiry. Errors
+/// when the signature is missing, bad, or can't be checked.
+pub async fn check_release_signature(
+    exec: &dyn CommandExecutor,
+    http: &reqwest::Client,
+    repo_base: &str,
+    codename: &str,
+    warn_days: i64,
+) -> ManagerResult<KeyExpiry> {
+    let home = std::env::temp_dir().join(format!(
+        "release-manager-gpg-{}-{}",
+        std::process::id(),
+        codename
+    ));
+    fs::create_dir_all(&home)?;
+    let result = check_in_home(exec, http, repo_base, codename, warn_days, &home).await;
+    let _ = fs::remove_dir_all(&home);
+    result
+}
+
+async fn check_in_home(
+    exec: &dyn CommandExecutor,
+    http: &reqwest::Client,
+    repo_base: &str,
+    codename: &str,
+    warn_days: i64,
+    home: &Path,
+) -> ManagerResult<KeyExpiry> {
+    let dists = format!("{}/dists/{}", repo_base, codename);
+    let home_str = home.to_string_lossy().to_string();
+
+    let key = fetch(http, &format!("{}/keys/minaprotocol.asc", repo_base))
+        .await?
+        .ok_or_else(|| {
+            ManagerError::ValidationError(format!(
+                "No signing key at {}/keys/minaprotocol.asc",
+                repo_base
+            ))
+        })?;
+    let key_path = home.join("minaprotocol.asc");
+    fs::write(&key_path, key)?;
+    let key_str = key_path.to_string_lossy().to_string();
+    gpg(
+        exec,
+        &["--homedir", &home_str, "--batch", "--import", &key_str],
+    )?;
+
+    let mut verify_args = vec![
+        "--homedir".to_string(),
+        home_str.clone(),
+        "--batch".to_string(),
+        "--status-fd".to_string(),
+        "1".to_string(),
+        "--verify".to_string(),
+    ];
+    if let Some(inrelease) = fetch(http, &format!("{}/InRelease", dists)).await? {
+        let path = home.join("InRelease");
+        fs::write(&path, inrelease)?;
+        verify_args.push(path.to_string_lossy().to_string());
+    } else {
+        let missing = || {
+            ManagerError::ValidationError(format!(
+                "Neither InRelease nor Release + Release.gpg found under {}",
+                dists
+            ))
+        };
+        let release = fetch(http, &format!("{}/Release", dists))
+            .await?
+            .ok_or_else(missing)?;
+        let signature = fetch(http, &format!("{}/Release.gpg", dists))
+            .await?
+            .ok_or_else(missing)?;
+        let release_path = home.join("Release");
+        let signature_path = home.join("Release.gpg");
+        fs::write(&release_path, release)?;
+        fs::write(&signature_path, signature)?;
+        verify_args.push(signature_path.to_string_lossy().to_string());
+        verify_args.push(release_path.to_string_lossy().to_string());
+    }
+
+    // gpg exits non-zero for bad signatures, so go by the status lines
+    let verify_refs: Vec<&str> = verify_args.iter().map(String::as_str).collect();
+    let status = exec
+        .run("gpg", &verify_refs)
+        .map_err(|e| ManagerError::CommandFailed(format!("gpg --verify: {}", e)))?;
+    let (signing_fpr, primary_fpr) = match parse_gpg_status(&status.stdout) {
+        SignatureStatus::Valid {
+            signing_fpr,
+            primary_fpr,
+        } => (signing_fpr, primary_fpr),
+        SignatureStatus::ExpiredKey => return Ok(KeyExpiry::Expired),
+        SignatureStatus::Invalid(reason) => {
+            return Err(ManagerError::ValidationError(format!(
+                "Release signature for {}: {}",
+                codename, reason
+            )))
+        }
+    };
+
+    let listing = gpg(
+        exec,
+        &[
+            "--homedir",
+            &home_str,
+            "--batch",
+            "--with-colons",
+            "--fixed-list-mode",
+            "--list-keys",
+            &primary_fpr,
+        ],
+    )?;
+    // A signing subkey is only usable while its primary key is too
+    let expires_at = parse_key_expiries(&listing)
+        .into_iter()
+        .filter(|(fpr, _)| *fpr == signing_fpr || *fpr == primary_fpr)
+        .filter_map(|(_, expiry)| expiry)
+        .min();
+
+    Ok(classify_expiry(
+        expires_at,
+        chrono::Utc::now().timestamp(),
+        warn_days,
+    ))
+}
+
+async fn fetch(http: &reqwest::Client, url: &str) -> ManagerResult<Option<Vec<u8>>> {
+    let response =
+        http.get(url).send().await.map_err(|e| {
+            ManagerError::ValidationError(format!("Failed to fetch {}: {}", url, e))
+        })?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| ManagerError::ValidationError(format!("Failed to read {}: {}", url, e)))?;
+    Ok(Some(body.to_vec()))
+}
+
+fn gpg(exec: &dyn CommandExecutor, args: &[&str]) -> ManagerResult<String> {
+    let output = exec
+        .run("gpg", args)
+        .map_err(|e| ManagerError::CommandFailed(format!("gpg: {}", e)))?;
+    if !output.is_success() {
+        return Err(ManagerError::CommandFailed(format!(
+            "gpg {} failed: {}",
+            args.join(" "),
+            output.stderr
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNING_FPR: &str = "1111111111111111111111111111111111111111";
+    const PRIMARY_FPR: &str = "2222222222222222222222222222222222222222";
+
+    fn validsig() -> String {
+        format!(
+            "[GNUPG:] VALIDSIG {} 2024-01-01 1704067200 0 4 0 1 10 00 {}\n",
+            SIGNING_FPR, PRIMARY_FPR
+        )
+    }
+
+    #[test]
+    fn test_parse_gpg_status() {
+        let valid = format!(
+            "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 1111111111111111 Mina <release@minaprotocol.com>\n{}[GNUPG:] TRUST_UNDEFINED 0 pgp\n",
+            validsig()
+        );
+        assert_eq!(
+            parse_gpg_status(&valid),
+            SignatureStatus::Valid {
+                signing_fpr: SIGNING_FPR.to_string(),
+                primary_fpr: PRIMARY_FPR.to_string(),
+            }
+        );
+
+        let expired = format!(
+            "[GNUPG:] KEYEXPIRED 1700000000\n[GNUPG:] EXPKEYSIG 1111111111111111 Mina <release@minaprotocol.com>\n{}",
+            validsig()
+        );
+        assert_eq!(parse_gpg_status(&expired), SignatureStatus::ExpiredKey);
+
+        let bad = "[GNUPG:] BADSIG 1111111111111111 Mina <release@minaprotocol.com>\n";
+        assert_eq!(
+            parse_gpg_status(bad),
+            SignatureStatus::Invalid("bad signature".to_string())
+        );
+
+        let unknown =
+            "[GNUPG:] ERRSIG 3333333333333333 1 10 01 1704067200 9 -\n[GNUPG:] NO_PUBKEY 3333333333333333\n";
+        assert_eq!(
+            parse_gpg_status(unknown),
+            SignatureStatus::Invalid("no public key 3333333333333333".to_string())
+        );
+        assert!(matches!(parse_gpg_status(""), SignatureStatus::Invalid(_)));
+    }
+
+    #[test]
+    fn test_parse_key_expiries() {
+        let listing = format!(
+            "tru::1:1704067200:0:3:1:5\n\
+             pub:u:4096:1:2222222222222222:1600000000:1900000000::u:::scSC::::::23::0:\n\
+             fpr:::::::::{}:\n\
+             uid:u::::1600000000::ABCD::Mina <release@minaprotocol.com>::::::::::0:\n\
+             sub:u:4096:1:1111111111111111:1600000000:1800000000:::::s::::::23:\n\
+             fpr:::::::::{}:\n\
+             sub:u:4096:1:4444444444444444:1600000000::::::e::::::23:\n\
+             fpr:::::::::4444444444444444444444444444444444444444:\n",
+            PRIMARY_FPR, SIGNING_FPR
+        );
+        assert_eq!(
+            parse_key_expiries(&listing),
+            vec![
+                (PRIMARY_FPR.to_string(), Some(1_900_000_000)),
+                (SIGNING_FPR.to_string(), Some(1_800_000_000)),
+                ("4444444444444444444444444444444444444444".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_expiry() {
+        let now = 1_700_000_000;
+        let day = 86_400;
+        assert_eq!(classify_expiry(None, now, 30), KeyExpiry::Never);
+        assert_eq!(
+            classify_expiry(Some(now + 90 * day), now, 30),
+            KeyExpiry::Valid { days_left: 90 }
+        );
+        assert_eq!(
+            classify_expiry(Some(now + 5 * day + 10), now, 30),
+            KeyExpiry::Expiring { days_left: 5 }
+        );
+        assert_eq!(
+            classify_expiry(Some(now - day), now, 30),
+            KeyExpiry::Expired
+        );
+        assert_eq!(classify_expiry(Some(now), now, 30), KeyExpiry::Expired);
+    }
+}
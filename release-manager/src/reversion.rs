@@ -1,7 +1,17 @@
+use crate::disk_space::{check_free_space, reversion_space_estimate};
 use crate::errors::{ManagerError, ManagerResult};
+use crate::output::{text, textln};
+use crate::trace::TracedCommand;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use md5::Md5;
+use regex::Regex;
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::io;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tempfile::TempDir;
 use tokio::process::Command as AsyncCommand;
 
@@ -22,6 +32,248 @@ pub struct ReversionConfig {
     pub new_suite: String,
     /// New package name (if different from original)
     pub new_name: Option<String>,
+    /// Directory for the rebuilt package (defaults to the source's directory)
+    pub output_dir: Option<PathBuf>,
+    /// `dpkg-deb --deb-format` for the rebuilt package (defaults to the source's)
+    pub deb_format: Option<String>,
+    /// Replace an existing output file even when its contents differ
+    pub overwrite: bool,
+    /// Only extract and print the control file changes; build and write nothing
+    pub dry_run: bool,
+}
+
+/// Old-style (pre-ar) debs start with their format version line
+const OLD_DEB_FORMAT: &str = "0.939000";
+
+/// Format version of a .deb from the first bytes of the file: the contents
+/// of the leading `debian-binary` ar member (e.g. `2.0`), or `0.939000` for
+/// the old pre-ar format.
+pub fn parse_deb_format_version(prefix: &[u8]) -> ManagerResult<String> {
+    let invalid = |reason: &str| {
+        ManagerError::ValidationError(format!("Not a readable .deb archive: {}", reason))
+    };
+
+    let Some(rest) = prefix.strip_prefix(b"!<arch>\n") else {
+        return if prefix.starts_with(format!("{}\n", OLD_DEB_FORMAT).as_bytes()) {
+            Ok(OLD_DEB_FORMAT.to_string())
+        } else {
+            Err(invalid("missing ar magic"))
+        };
+    };
+
+    // ar member header: name(16) mtime(12) uid(6) gid(6) mode(8) size(10) magic(2)
+    if rest.len() < 60 {
+        return Err(invalid("truncated member header"));
+    }
+    let (header, body) = rest.split_at(60);
+    let name = String::from_utf8_lossy(&header[..16]);
+    if name.trim_end().trim_end_matches('/') != "debian-binary" {
+        return Err(invalid("first member is not debian-binary"));
+    }
+    let size: usize = String::from_utf8_lossy(&header[48..58])
+        .trim()
+        .parse()
+        .map_err(|_| invalid("bad debian-binary size"))?;
+    let content = body
+        .get(..size)
+        .ok_or_else(|| invalid("truncated debian-binary"))?;
+
+    let version = String::from_utf8_lossy(content).trim().to_string();
+    if version.is_empty() {
+        return Err(invalid("empty debian-binary"));
+    }
+    Ok(version)
+}
+
+/// Read the format version of the .deb at `path` without loading all of it
+pub fn read_deb_format_version(path: &Path) -> ManagerResult<String> {
+    parse_deb_format_version(&read_deb_prefix(path)?)
+}
+
+fn read_deb_prefix(path: &Path) -> ManagerResult<Vec<u8>> {
+    let mut prefix = Vec::new();
+    fs::File::open(path)?.take(256).read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+/// Timestamp of the leading ar member, used as `SOURCE_DATE_EPOCH` so a
+/// package rebuilt from the same source is byte-identical. `None` for
+/// old-format debs.
+fn parse_deb_mtime(prefix: &[u8]) -> Option<u64> {
+    let header = prefix.strip_prefix(b"!<arch>\n")?.get(..60)?;
+    String::from_utf8_lossy(&header[16..28]).trim().parse().ok()
+}
+
+/// Move the freshly built `built` to `target`. An existing `target` is only
+/// replaced when `overwrite` is set; otherwise it's kept if identical and
+/// an error if not, so two sources mapping to one name (e.g. both networks'
+/// archive with `--strip-network`) can't silently lose one of them.
+pub fn place_rebuilt_package(built: &Path, target: &Path, overwrite: bool) -> ManagerResult<()> {
+    if target.exists() && !overwrite {
+        let existing = sha256_file(target)?;
+        let rebuilt = sha256_file(built)?;
+        fs::remove_file(built)?;
+        if existing != rebuilt {
+            return Err(ManagerError::ValidationError(format!(
+                "{} already exists with different contents (sha256 {} vs rebuilt {}); \
+                 refusing to overwrite it",
+                target.display(),
+                existing,
+                rebuilt
+            )));
+        }
+        textln!(
+            "    ♻️  {} already exists with identical contents",
+            target.display()
+        );
+        return Ok(());
+    }
+
+    fs::rename(built, target)?;
+    Ok(())
+}
+
+/// Gzip `data` the way Debian compresses docs (`gzip -9n`): best
+/// compression, no file name and a zero mtime, so rebuilds stay identical.
+pub fn gzip_bytes(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Rewrite an extracted package's `DEBIAN/md5sums` and its control file's
+/// `Installed-Size` for the data tree as it is now, so the rebuilt package
+/// doesn't ship the source package's stale values
+fn regenerate_metadata(extract_dir: &Path) -> ManagerResult<()> {
+    let (files, installed_size) = scan_data_tree(extract_dir)?;
+
+    let mut md5sums = String::new();
+    for file in &files {
+        let digest = digest_file::<Md5>(&extract_dir.join(file))?;
+        md5sums.push_str(&format!("{}  {}\n", digest, file.display()));
+    }
+    fs::write(extract_dir.join("DEBIAN").join("md5sums"), md5sums)?;
+
+    let control_file = extract_dir.join("DEBIAN").join("control");
+    let control = fs::read_to_string(&control_file)?;
+    fs::write(&control_file, set_installed_size(&control, installed_size))?;
+
+    textln!(
+        "    🧮 Regenerated md5sums for {} files, Installed-Size: {}",
+        files.len(),
+        installed_size
+    );
+    Ok(())
+}
+
+/// Hex digest of the file at `path`, read in chunks so large binaries aren't
+/// loaded into memory
+fn digest_file<D: Digest + Write>(path: &Path) -> io::Result<String> {
+    let mut hasher = D::new();
+    io::copy(&mut io::BufReader::new(fs::File::open(path)?), &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Regular files of an extracted package's data tree (everything outside
+/// `DEBIAN/`), relative to `root` and sorted, plus the tree's installed
+/// size in KiB: every regular file rounded up to a whole KiB and one KiB
+/// for each directory and symlink
+fn scan_data_tree(root: &Path) -> io::Result<(Vec<PathBuf>, u64)> {
+    let mut files = Vec::new();
+    let mut kib = 0;
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if dir == root && entry.file_name() == "DEBIAN" {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_file() {
+                kib += entry.metadata()?.len().div_ceil(1024);
+                if let Ok(relative) = entry.path().strip_prefix(root) {
+                    files.push(relative.to_path_buf());
+                }
+            } else {
+                kib += 1;
+                if file_type.is_dir() {
+                    pending.push(entry.path());
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok((files, kib))
+}
+
+/// `control` with its `Installed-Size` set to `kib`, appended if missing
+fn set_installed_size(control: &str, kib: u64) -> String {
+    let field = format!("Installed-Size: {}", kib);
+    let mut found = false;
+    let mut lines: Vec<String> = control
+        .lines()
+        .map(|line| {
+            if line.starts_with("Installed-Size:") {
+                found = true;
+                field.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !found {
+        lines.push(field);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Decompress a gzipped file, e.g. a shipped `changelog.Debian.gz`
+fn gunzip_file(path: &Path) -> io::Result<String> {
+    let mut content = String::new();
+    MultiGzDecoder::new(fs::File::open(path)?).read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Compiled by [`is_debian_version`] on first use
+static DEBIAN_VERSION: OnceLock<Regex> = OnceLock::new();
+
+/// `[epoch:]upstream_version[-debian_revision]` per Debian policy 5.6.12:
+/// an optional numeric epoch; an upstream version starting with a digit and
+/// made of letters, digits and `. + ~`, plus `-` only when a revision
+/// follows; and a non-empty revision of letters, digits and `. + ~` after
+/// the last hyphen
+fn is_debian_version(version: &str) -> bool {
+    DEBIAN_VERSION
+        .get_or_init(|| {
+            Regex::new(r"^(?:[0-9]+:)?[0-9](?:[A-Za-z0-9.+~]*|[A-Za-z0-9.+~-]*-[A-Za-z0-9.+~]+)$")
+                .expect("valid Debian version regex")
+        })
+        .is_match(version)
+}
+
+/// Reject a version dpkg would refuse or that would break the rebuilt
+/// package's file name, which embeds it
+fn validate_debian_version(field: &str, version: &str) -> ManagerResult<()> {
+    if version
+        .chars()
+        .any(|c| c.is_whitespace() || c == '/' || c == '\\')
+    {
+        return Err(ManagerError::ValidationError(format!(
+            "{} '{}' contains whitespace or a path separator, which would end up in the .deb file name",
+            field, version
+        )));
+    }
+    if !is_debian_version(version) {
+        return Err(ManagerError::ValidationError(format!(
+            "{} '{}' is not a valid Debian version: expected [epoch:]upstream_version[-debian_revision], with the upstream version starting with a digit and only letters, digits and . + ~ - in it",
+            field, version
+        )));
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> ManagerResult<String> {
+    Ok(digest_file::<Sha256>(path)?)
 }
 
 /// Debian package reversion functionality
@@ -34,10 +286,10 @@ impl DebianReversioner {
     /// Create a new DebianReversioner with the given configuration
     pub fn new(config: ReversionConfig) -> ManagerResult<Self> {
         let temp_dir = TempDir::new().map_err(|e| {
-            ManagerError::IoError(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to create temp directory: {}", e),
-            ))
+            ManagerError::IoError(io::Error::other(format!(
+                "Failed to create temp directory: {}",
+                e
+            )))
         })?;
 
         Ok(Self { config, temp_dir })
@@ -47,12 +299,13 @@ impl DebianReversioner {
     pub async fn reversion(&self) -> ManagerResult<PathBuf> {
         self.validate_inputs()?;
 
-        println!(" 🔄 Reversioning Debian package:");
-        println!(
+        textln!(" 🔄 Reversioning Debian package:");
+        textln!(
             "    📦 Source: {} v{}",
-            self.config.package_name, self.config.source_version
+            self.config.package_name,
+            self.config.source_version
         );
-        println!(
+        textln!(
             "    🎯 Target: {} v{}",
             self.config
                 .new_name
@@ -60,21 +313,34 @@ impl DebianReversioner {
                 .unwrap_or(&self.config.package_name),
             self.config.new_version
         );
-        println!(
+        textln!(
             "    📂 Suite: {} → {}",
-            self.config.suite, self.config.new_suite
+            self.config.suite,
+            self.config.new_suite
         );
 
+        // Fail now rather than partway through extracting a large package
+        let package_size = fs::metadata(&self.config.deb_path)?.len();
+        check_free_space(
+            self.temp_dir.path(),
+            reversion_space_estimate(package_size),
+            &format!("reversion {}", self.config.deb_path.display()),
+        )?;
+
         // Extract the original package
         let extract_dir = self.extract_package().await?;
 
+        if self.config.dry_run {
+            return self.preview_control_changes(&extract_dir);
+        }
+
         // Modify package metadata
         self.modify_control_files(&extract_dir).await?;
 
         // Rebuild the package with new version
         let new_deb_path = self.rebuild_package(&extract_dir).await?;
 
-        println!(" ✅ Reversion completed: {}", new_deb_path.display());
+        textln!(" ✅ Reversion completed: {}", new_deb_path.display());
 
         Ok(new_deb_path)
     }
@@ -103,6 +369,9 @@ impl DebianReversioner {
             ));
         }
 
+        validate_debian_version("Source version", &self.config.source_version)?;
+        validate_debian_version("New version", &self.config.new_version)?;
+
         if self.config.package_name.is_empty() {
             return Err(ManagerError::ValidationError(
                 "Package name cannot be empty".to_string(),
@@ -117,7 +386,7 @@ impl DebianReversioner {
         let extract_dir = self.temp_dir.path().join("extracted");
         fs::create_dir_all(&extract_dir)?;
 
-        println!(
+        textln!(
             "    📤 Extracting package: {}",
             self.config.deb_path.display()
         );
@@ -125,23 +394,40 @@ impl DebianReversioner {
         let mut cmd = AsyncCommand::new("dpkg-deb");
         cmd.arg("-R").arg(&self.config.deb_path).arg(&extract_dir);
 
-        let output = cmd.output().await.map_err(|e| {
-            ManagerError::CommandFailed(format!("Failed to execute dpkg-deb: {}", e))
-        })?;
+        cmd.checked_output("Failed to execute dpkg-deb", "dpkg-deb extraction failed")
+            .await?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "dpkg-deb extraction failed: {}",
-                stderr
-            )));
+        Ok(extract_dir)
+    }
+
+    /// Print the control file diff a reversion would apply and the path the
+    /// package would be built at, without building or writing anything
+    fn preview_control_changes(&self, extract_dir: &Path) -> ManagerResult<PathBuf> {
+        let control_file = self.control_file(extract_dir)?;
+        let control_content = fs::read_to_string(&control_file)?;
+        let new_content = self.update_control_content(&control_content)?;
+
+        textln!("    🔍 Dry run, control file changes:");
+        let diff = control_diff(&control_content, &new_content);
+        if diff.is_empty() {
+            textln!("      (none)");
+        } else {
+            text!("{}", diff);
+        }
+        if let Some(header) = self.changelog_entry("").lines().next() {
+            textln!("    🔍 Changelog entry: {}", header);
         }
 
-        Ok(extract_dir)
+        let new_deb_path = self.output_path();
+        textln!(
+            " ✅ Dry run completed, would build: {}",
+            new_deb_path.display()
+        );
+        Ok(new_deb_path)
     }
 
-    /// Modify control files with new version and metadata
-    async fn modify_control_files(&self, extract_dir: &Path) -> ManagerResult<()> {
+    /// The extracted package's `DEBIAN/control`, which must exist
+    fn control_file(&self, extract_dir: &Path) -> ManagerResult<PathBuf> {
         let control_file = extract_dir.join("DEBIAN").join("control");
 
         if !control_file.exists() {
@@ -150,8 +436,14 @@ impl DebianReversioner {
                 format!("Control file not found: {}", control_file.display()),
             )));
         }
+        Ok(control_file)
+    }
+
+    /// Modify control files with new version and metadata
+    async fn modify_control_files(&self, extract_dir: &Path) -> ManagerResult<()> {
+        let control_file = self.control_file(extract_dir)?;
 
-        println!("    ✏️  Modifying control file: {}", control_file.display());
+        textln!("    ✏️  Modifying control file: {}", control_file.display());
 
         // Read the control file
         let control_content = fs::read_to_string(&control_file)?;
@@ -162,13 +454,18 @@ impl DebianReversioner {
         // Write the modified control file
         fs::write(&control_file, new_content)?;
 
-        // Update changelog if it exists
-        self.update_changelog(extract_dir).await?;
+        // The changelog entry is where the new suite is recorded
+        self.create_changelog_entry(extract_dir).await?;
+
+        // Last, so the checksums and size cover the rewritten changelog
+        regenerate_metadata(extract_dir)?;
 
         Ok(())
     }
 
-    /// Update the content of the control file
+    /// Update the content of the control file: package name and version.
+    /// A binary control file has no suite; that goes in the changelog entry
+    /// (see [`Self::changelog_entry`]), and the component is picked at upload.
     fn update_control_content(&self, content: &str) -> ManagerResult<String> {
         let mut result = content.to_string();
         let mut modified = false;
@@ -184,8 +481,8 @@ impl DebianReversioner {
         }
 
         // Update version - be more careful with version replacement
-        let version_pattern = format!("Version: ");
-        if let Some(version_line_start) = result.find(&version_pattern) {
+        let version_pattern = "Version: ";
+        if let Some(version_line_start) = result.find(version_pattern) {
             if let Some(version_line_end) = result[version_line_start..].find('\n') {
                 let version_line_end = version_line_start + version_line_end;
                 let version_line = &result[version_line_start..version_line_end];
@@ -199,19 +496,8 @@ impl DebianReversioner {
             }
         }
 
-        // Update distribution/suite in control file if present
-        let distribution_pattern = "Distribution: ";
-        if let Some(dist_start) = result.find(distribution_pattern) {
-            if let Some(dist_end) = result[dist_start..].find('\n') {
-                let dist_end = dist_start + dist_end;
-                let new_dist_line = format!("Distribution: {}", self.config.new_suite);
-                result.replace_range(dist_start..dist_end, &new_dist_line);
-                modified = true;
-            }
-        }
-
         if !modified {
-            println!("    ⚠️  Warning: No modifications made to control file");
+            textln!("    ⚠️  Warning: No modifications made to control file");
         }
 
         // Ensure the control file ends with a newline (required by Debian format)
@@ -232,119 +518,151 @@ impl DebianReversioner {
         Ok(result)
     }
 
-    /// Update changelog file if it exists
-    async fn update_changelog(&self, extract_dir: &Path) -> ManagerResult<()> {
-        let changelog_paths = [
-            extract_dir
-                .join("usr")
-                .join("share")
-                .join("doc")
-                .join(&self.config.package_name)
-                .join("changelog.Debian.gz"),
-            extract_dir
-                .join("usr")
-                .join("share")
-                .join("doc")
-                .join(&self.config.package_name)
-                .join("changelog.gz"),
-        ];
-
-        for changelog_path in &changelog_paths {
-            if changelog_path.exists() {
-                println!("    📝 Updating changelog: {}", changelog_path.display());
-                // For simplicity, we'll create a new changelog entry
-                // In a full implementation, you might want to decompress, modify, and recompress
-                self.create_changelog_entry(extract_dir).await?;
-                break;
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Create a new changelog entry
-    async fn create_changelog_entry(&self, extract_dir: &Path) -> ManagerResult<()> {
+    /// Changelog entry for the reversioned package, dated `date`. Its header,
+    /// `package (version) suite; urgency=medium`, is the only place the
+    /// package records its suite.
+    fn changelog_entry(&self, date: &str) -> String {
         let package_name = self
             .config
             .new_name
             .as_ref()
             .unwrap_or(&self.config.package_name);
-        let doc_dir = extract_dir
-            .join("usr")
-            .join("share")
-            .join("doc")
-            .join(package_name);
-
-        if let Err(e) = fs::create_dir_all(&doc_dir) {
-            println!("    ⚠️  Warning: Could not create doc directory: {}", e);
-            return Ok(());
-        }
-
-        let changelog_content = format!(
+        format!(
             "{} ({}) {}; urgency=medium\n\n  * Reversion from {} to {}\n  * Automated reversion by release-manager\n\n -- Release Manager <release@minaprotocol.com>  {}\n\n",
             package_name,
             self.config.new_version,
             self.config.new_suite,
             self.config.source_version,
             self.config.new_version,
-            chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S +0000")
-        );
+            date
+        )
+    }
 
-        let changelog_file = doc_dir.join("changelog.Debian");
-        if let Err(e) = fs::write(&changelog_file, changelog_content) {
-            println!("    ⚠️  Warning: Could not write changelog: {}", e);
-        }
+    /// Prepend the changelog entry to `usr/share/doc/<package>/changelog.Debian.gz`,
+    /// keeping the entries the package shipped (under its original name when
+    /// renamed), or start a new changelog when it shipped none
+    async fn create_changelog_entry(&self, extract_dir: &Path) -> ManagerResult<()> {
+        let package_name = self
+            .config
+            .new_name
+            .as_ref()
+            .unwrap_or(&self.config.package_name);
+        let docs = extract_dir.join("usr").join("share").join("doc");
+        let doc_dir = docs.join(package_name);
+        let shipped = [package_name, &self.config.package_name]
+            .iter()
+            .map(|name| docs.join(name).join("changelog.Debian.gz"))
+            .find(|path| path.exists());
+        let history = match shipped.as_deref().map(gunzip_file) {
+            Some(Ok(history)) => history,
+            Some(Err(e)) => {
+                textln!(
+                    "    ⚠️  Warning: Could not read the shipped changelog, starting a new one: {}",
+                    e
+                );
+                String::new()
+            }
+            None => String::new(),
+        };
 
-        // Compress the changelog
-        let mut cmd = AsyncCommand::new("gzip");
-        cmd.arg("-f").arg(&changelog_file);
+        if let Err(e) = fs::create_dir_all(&doc_dir) {
+            textln!("    ⚠️  Warning: Could not create doc directory: {}", e);
+            return Ok(());
+        }
 
-        if let Err(e) = cmd.output().await {
-            println!("    ⚠️  Warning: Could not compress changelog: {}", e);
+        // Dated like the rebuild itself, so re-running a reversion stays
+        // byte-identical
+        let date = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|epoch| epoch.parse().ok())
+            .or_else(|| parse_deb_mtime(&read_deb_prefix(&self.config.deb_path).ok()?))
+            .and_then(|epoch| chrono::DateTime::from_timestamp(epoch as i64, 0))
+            .unwrap_or_else(chrono::Utc::now);
+        let changelog_content = self
+            .changelog_entry(&date.format("%a, %d %b %Y %H:%M:%S +0000").to_string())
+            + &history;
+
+        let changelog_file = doc_dir.join("changelog.Debian.gz");
+        textln!("    📝 Writing changelog: {}", changelog_file.display());
+        let written = gzip_bytes(changelog_content.as_bytes())
+            .and_then(|compressed| fs::write(&changelog_file, compressed));
+        if let Err(e) = written {
+            textln!("    ⚠️  Warning: Could not write changelog: {}", e);
         }
 
         Ok(())
     }
 
-    /// Rebuild the package with new metadata
-    async fn rebuild_package(&self, extract_dir: &Path) -> ManagerResult<PathBuf> {
+    /// Where the rebuilt package goes: `output_dir` or the source's directory
+    fn output_path(&self) -> PathBuf {
         let new_package_name = self
             .config
             .new_name
             .as_ref()
             .unwrap_or(&self.config.package_name);
-        let new_deb_filename = format!("{}_{}.deb", new_package_name, self.config.new_version);
-        let new_deb_path = self
-            .config
-            .deb_path
-            .parent()
-            .unwrap_or_else(|| Path::new("."))
-            .join(&new_deb_filename);
-
-        // Remove existing file if it exists
-        if new_deb_path.exists() {
-            fs::remove_file(&new_deb_path)?;
-        }
+        let output_dir = match &self.config.output_dir {
+            Some(dir) => dir.as_path(),
+            None => self
+                .config
+                .deb_path
+                .parent()
+                .unwrap_or_else(|| Path::new(".")),
+        };
+        output_dir.join(format!(
+            "{}_{}.deb",
+            new_package_name, self.config.new_version
+        ))
+    }
 
-        println!("    📦 Building new package: {}", new_deb_path.display());
+    /// `Version:` of the built package's control file, empty when missing
+    async fn read_control_version(&self, deb: &Path) -> ManagerResult<String> {
+        let output = AsyncCommand::new("dpkg-deb")
+            .arg("-f")
+            .arg(deb)
+            .arg("Version")
+            .checked_output("Failed to execute dpkg-deb -f", "dpkg-deb -f failed")
+            .await?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
 
-        let mut cmd = AsyncCommand::new("dpkg-deb");
-        cmd.arg("--build").arg(extract_dir).arg(&new_deb_path);
+    /// Rebuild the package with new metadata
+    async fn rebuild_package(&self, extract_dir: &Path) -> ManagerResult<PathBuf> {
+        let new_deb_path = self.output_path();
+        if let Some(dir) = &self.config.output_dir {
+            fs::create_dir_all(dir)?;
+        }
+        // Built next to the target so placing it is a same-filesystem rename
+        let mut built_path = new_deb_path.clone().into_os_string();
+        built_path.push(".partial");
+        let built_path = PathBuf::from(built_path);
+        if built_path.exists() {
+            fs::remove_file(&built_path)?;
+        }
 
-        let output = cmd.output().await.map_err(|e| {
-            ManagerError::CommandFailed(format!("Failed to execute dpkg-deb build: {}", e))
-        })?;
+        let deb_format = match &self.config.deb_format {
+            Some(format) => format.clone(),
+            None => read_deb_format_version(&self.config.deb_path)?,
+        };
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(format!(
-                "dpkg-deb build failed: {}",
-                stderr
-            )));
+        textln!(
+            "    📦 Building new package: {} (format {})",
+            new_deb_path.display(),
+            deb_format
+        );
+
+        let mut cmd = AsyncCommand::new("dpkg-deb");
+        cmd.args(build_args(extract_dir, &built_path, &deb_format));
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+            if let Some(epoch) = parse_deb_mtime(&read_deb_prefix(&self.config.deb_path)?) {
+                cmd.env("SOURCE_DATE_EPOCH", epoch.to_string());
+            }
         }
 
+        cmd.checked_output("Failed to execute dpkg-deb build", "dpkg-deb build failed")
+            .await?;
+
         // Validate the new package was created
-        if !new_deb_path.exists() {
+        if !built_path.exists() {
             return Err(ManagerError::IoError(io::Error::new(
                 io::ErrorKind::NotFound,
                 format!(
@@ -354,11 +672,87 @@ impl DebianReversioner {
             )));
         }
 
+        // A package whose control Version disagrees with its filename would
+        // be uploaded under the wrong version
+        let declared = self.read_control_version(&built_path).await?;
+        if let Err(e) = check_rebuilt_version(&new_deb_path, &declared, &self.config.new_version) {
+            fs::remove_file(&built_path)?;
+            return Err(e);
+        }
+
+        place_rebuilt_package(&built_path, &new_deb_path, self.config.overwrite)?;
         Ok(new_deb_path)
     }
 }
 
+/// Fail unless the rebuilt package at `deb` declares `expected`, the
+/// version its filename carries. `declared` is its control `Version:` as
+/// `dpkg-deb -f` printed it, empty when the field is missing.
+fn check_rebuilt_version(deb: &Path, declared: &str, expected: &str) -> ManagerResult<()> {
+    let declared = declared.trim();
+    if declared == expected {
+        return Ok(());
+    }
+    Err(ManagerError::ValidationError(format!(
+        "Rebuilt {} declares Version '{}' in its control file, not {}: the source version wasn't found to replace",
+        deb.display(),
+        declared,
+        expected
+    )))
+}
+
+/// Line diff of two control files: removed lines as `-`, added as `+`, in
+/// order, unchanged lines omitted. Empty when they're identical.
+pub fn control_diff(before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    // Longest common subsequence table, suffix-based
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("      -{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("      +{}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// `dpkg-deb` arguments rebuilding `extract_dir` into `deb_path`
+fn build_args(extract_dir: &Path, deb_path: &Path, deb_format: &str) -> Vec<String> {
+    let mut args = vec![format!("--deb-format={}", deb_format)];
+    // dpkg can only read old-style debs whose members are gzipped
+    if deb_format == OLD_DEB_FORMAT {
+        args.push("-Zgzip".to_string());
+    }
+    args.extend([
+        "--build".to_string(),
+        extract_dir.to_string_lossy().to_string(),
+        deb_path.to_string_lossy().to_string(),
+    ]);
+    args
+}
+
 /// High-level function to perform debian package reversion
+#[allow(clippy::too_many_arguments)]
 pub async fn reversion_debian_package(
     deb_path: &Path,
     package_name: &str,
@@ -367,6 +761,10 @@ pub async fn reversion_debian_package(
     suite: &str,
     new_suite: &str,
     new_name: Option<&str>,
+    output_dir: Option<&Path>,
+    deb_format: Option<&str>,
+    overwrite: bool,
+    dry_run: bool,
 ) -> ManagerResult<PathBuf> {
     let config = ReversionConfig {
         deb_path: deb_path.to_path_buf(),
@@ -376,6 +774,10 @@ pub async fn reversion_debian_package(
         suite: suite.to_string(),
         new_suite: new_suite.to_string(),
         new_name: new_name.map(|s| s.to_string()),
+        output_dir: output_dir.map(Path::to_path_buf),
+        deb_format: deb_format.map(|s| s.to_string()),
+        overwrite,
+        dry_run,
     };
 
     let reversioner = DebianReversioner::new(config)?;
@@ -387,6 +789,345 @@ mod tests {
     use super::*;
     use tempfile::NamedTempFile;
 
+    /// The first bytes of a deb as `dpkg-deb -Zgzip --build` writes them
+    fn deb_prefix(format: &str) -> Vec<u8> {
+        let content = format!("{}\n", format);
+        let mut bytes = b"!<arch>\n".to_vec();
+        bytes.extend(
+            format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                "debian-binary",
+                "1792204223",
+                "0",
+                "0",
+                "100644",
+                content.len()
+            )
+            .as_bytes(),
+        );
+        bytes.extend(content.as_bytes());
+        bytes.extend(b"control.tar.gz  1792204223  0     0     100644  197       `\n");
+        bytes
+    }
+
+    #[test]
+    fn test_parse_deb_format_version() {
+        assert_eq!(parse_deb_format_version(&deb_prefix("2.0")).unwrap(), "2.0");
+        assert_eq!(
+            parse_deb_format_version(b"0.939000\n264\n\xfd7zXZ").unwrap(),
+            "0.939000"
+        );
+
+        // GNU ar terminates member names with `/`
+        let mut gnu = deb_prefix("2.0");
+        gnu[8..8 + 14].copy_from_slice(b"debian-binary/");
+        assert_eq!(parse_deb_format_version(&gnu).unwrap(), "2.0");
+
+        assert!(parse_deb_format_version(b"PK\x03\x04").is_err());
+        assert!(parse_deb_format_version(&deb_prefix("2.0")[..40]).is_err());
+    }
+
+    #[test]
+    fn test_read_deb_format_version_from_file() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), deb_prefix("2.0")).unwrap();
+        assert_eq!(read_deb_format_version(file.path()).unwrap(), "2.0");
+        assert_eq!(
+            build_args(Path::new("/tmp/x"), Path::new("/tmp/x.deb"), "2.0"),
+            vec!["--deb-format=2.0", "--build", "/tmp/x", "/tmp/x.deb"]
+        );
+        assert_eq!(
+            build_args(Path::new("/tmp/x"), Path::new("/tmp/x.deb"), "0.939000"),
+            vec![
+                "--deb-format=0.939000",
+                "-Zgzip",
+                "--build",
+                "/tmp/x",
+                "/tmp/x.deb"
+            ]
+        );
+    }
+
+    #[cfg(feature = "integration-test")]
+    #[tokio::test]
+    async fn test_reversion_preserves_or_forces_deb_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        std::fs::create_dir_all(root.join("DEBIAN")).unwrap();
+        std::fs::write(
+            root.join("DEBIAN/control"),
+            "Package: mina-logproc\nVersion: 1.0.0\nArchitecture: all\nMaintainer: O(1) Labs\nDescription: logproc\n",
+        )
+        .unwrap();
+        let source = tmp.path().join("mina-logproc_1.0.0.deb");
+        let out = std::process::Command::new("dpkg-deb")
+            .args(["-Zgzip", "--deb-format=0.939000", "--build"])
+            .arg(&root)
+            .arg(&source)
+            .output()
+            .expect("dpkg-deb");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+
+        let preserved = tmp.path().join("preserved");
+        let rebuilt = reversion_debian_package(
+            &source,
+            "mina-logproc",
+            "1.0.0",
+            "1.0.1",
+            "unstable",
+            "stable",
+            None,
+            Some(&preserved),
+            None,
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(read_deb_format_version(&rebuilt).unwrap(), "0.939000");
+        let readable = std::process::Command::new("dpkg-deb")
+            .args(["-f"])
+            .arg(&rebuilt)
+            .arg("Version")
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&readable.stdout).trim(), "1.0.1");
+
+        let forced = tmp.path().join("forced");
+        let rebuilt = reversion_debian_package(
+            &source,
+            "mina-logproc",
+            "1.0.0",
+            "1.0.1",
+            "unstable",
+            "stable",
+            None,
+            Some(&forced),
+            Some("2.0"),
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(read_deb_format_version(&rebuilt).unwrap(), "2.0");
+
+        // Rebuilding the same source gives the same bytes, so it isn't
+        // mistaken for a collision
+        let again = reversion_debian_package(
+            &source,
+            "mina-logproc",
+            "1.0.0",
+            "1.0.1",
+            "unstable",
+            "stable",
+            None,
+            Some(&forced),
+            Some("2.0"),
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again, rebuilt);
+    }
+
+    #[cfg(feature = "integration-test")]
+    #[tokio::test]
+    async fn test_reversion_dry_run_writes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        std::fs::create_dir_all(root.join("DEBIAN")).unwrap();
+        std::fs::write(
+            root.join("DEBIAN/control"),
+            "Package: mina-logproc\nVersion: 1.0.0\nArchitecture: all\nMaintainer: O(1) Labs\nDescription: logproc\n",
+        )
+        .unwrap();
+        let source = tmp.path().join("mina-logproc_1.0.0.deb");
+        let out = std::process::Command::new("dpkg-deb")
+            .arg("--build")
+            .arg(&root)
+            .arg(&source)
+            .output()
+            .expect("dpkg-deb");
+        assert!(out.status.success());
+
+        let output_dir = tmp.path().join("out");
+        let would_build = reversion_debian_package(
+            &source,
+            "mina-logproc",
+            "1.0.0",
+            "1.0.1",
+            "unstable",
+            "stable",
+            None,
+            Some(&output_dir),
+            None,
+            false,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(would_build, output_dir.join("mina-logproc_1.0.1.deb"));
+        assert!(!output_dir.exists());
+
+        // Without an output directory it would land next to the source
+        let would_build = reversion_debian_package(
+            &source,
+            "mina-logproc",
+            "1.0.0",
+            "1.0.1",
+            "unstable",
+            "stable",
+            None,
+            None,
+            None,
+            false,
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(!would_build.exists());
+        let mut entries: Vec<_> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, ["mina-logproc_1.0.0.deb", "root"]);
+    }
+
+    #[test]
+    fn test_check_rebuilt_version() {
+        let deb = Path::new("/tmp/mina-logproc_1.0.1.deb");
+        assert!(check_rebuilt_version(deb, "1.0.1\n", "1.0.1").is_ok());
+
+        match check_rebuilt_version(deb, "1.0.0\n", "1.0.1") {
+            Err(ManagerError::ValidationError(msg)) => {
+                assert!(msg.contains("declares Version '1.0.0' in its control file, not 1.0.1"))
+            }
+            other => panic!("expected ValidationError, got {:?}", other),
+        }
+        assert!(check_rebuilt_version(deb, "", "1.0.1").is_err());
+    }
+
+    #[cfg(feature = "integration-test")]
+    #[tokio::test]
+    async fn test_reversion_fails_when_version_is_not_replaced() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        std::fs::create_dir_all(root.join("DEBIAN")).unwrap();
+        // The control file's version isn't the one asked to be replaced
+        std::fs::write(
+            root.join("DEBIAN/control"),
+            "Package: mina-logproc\nVersion: 0.9.0\nArchitecture: all\nMaintainer: O(1) Labs\nDescription: logproc\n",
+        )
+        .unwrap();
+        let source = tmp.path().join("mina-logproc_1.0.0.deb");
+        let out = std::process::Command::new("dpkg-deb")
+            .arg("--build")
+            .arg(&root)
+            .arg(&source)
+            .output()
+            .expect("dpkg-deb");
+        assert!(out.status.success());
+
+        let output_dir = tmp.path().join("out");
+        let err = reversion_debian_package(
+            &source,
+            "mina-logproc",
+            "1.0.0",
+            "1.0.1",
+            "unstable",
+            "stable",
+            None,
+            Some(&output_dir),
+            None,
+            false,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ManagerError::ValidationError(_)), "{:?}", err);
+        assert!(std::fs::read_dir(&output_dir).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_control_diff() {
+        let before =
+            "Package: mina-devnet\nVersion: 1.0.0\nArchitecture: amd64\nDistribution: unstable\n";
+        let after =
+            "Package: mina-devnet\nVersion: 1.0.1\nArchitecture: amd64\nDistribution: stable\n";
+        assert_eq!(
+            control_diff(before, after),
+            "      -Version: 1.0.0\n      +Version: 1.0.1\n      -Distribution: unstable\n      +Distribution: stable\n"
+        );
+        assert_eq!(control_diff(before, before), "");
+        assert_eq!(
+            control_diff("Package: a\nDepends: b \n", "Package: a\n"),
+            "      -Depends: b \n"
+        );
+    }
+
+    #[test]
+    fn test_gzip_bytes_round_trips() {
+        let changelog =
+            "mina-devnet (1.0.1) stable; urgency=medium\n\n  * Reversion from 1.0.0 to 1.0.1\n";
+        let compressed = gzip_bytes(changelog.as_bytes()).unwrap();
+
+        // gzip magic, deflate, no FNAME and a zero mtime like `gzip -n`
+        assert_eq!(&compressed[..4], &[0x1f, 0x8b, 0x08, 0x00]);
+        assert_eq!(&compressed[4..8], &[0, 0, 0, 0]);
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, changelog);
+        assert_eq!(gzip_bytes(changelog.as_bytes()).unwrap(), compressed);
+    }
+
+    #[test]
+    fn test_parse_deb_mtime() {
+        assert_eq!(parse_deb_mtime(&deb_prefix("2.0")), Some(1792204223));
+        assert_eq!(parse_deb_mtime(b"0.939000\n264\n"), None);
+        assert_eq!(parse_deb_mtime(b"!<arch>\ndebian-binary"), None);
+    }
+
+    #[test]
+    fn test_place_rebuilt_package_detects_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("mina-archive_1.0.1.deb");
+        let built = dir.path().join("mina-archive_1.0.1.deb.partial");
+
+        // Nothing there yet: moved into place
+        std::fs::write(&built, "devnet archive").unwrap();
+        place_rebuilt_package(&built, &target, false).unwrap();
+        assert!(!built.exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"devnet archive");
+
+        // Identical rebuild: kept
+        std::fs::write(&built, "devnet archive").unwrap();
+        place_rebuilt_package(&built, &target, false).unwrap();
+        assert!(!built.exists());
+
+        // The other network's archive under the same name: refused
+        std::fs::write(&built, "mainnet archive").unwrap();
+        let err = place_rebuilt_package(&built, &target, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("already exists with different contents"));
+        assert!(!built.exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"devnet archive");
+
+        // Unless overwriting is asked for
+        std::fs::write(&built, "mainnet archive").unwrap();
+        place_rebuilt_package(&built, &target, true).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"mainnet archive");
+    }
+
     #[test]
     fn test_reversion_config_validation() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -398,10 +1139,133 @@ mod tests {
             suite: "unstable".to_string(),
             new_suite: "stable".to_string(),
             new_name: None,
+            output_dir: None,
+            deb_format: None,
+            overwrite: false,
+            dry_run: false,
         };
 
-        let reversioner = DebianReversioner::new(config).unwrap();
+        let mut reversioner = DebianReversioner::new(config).unwrap();
         assert!(reversioner.validate_inputs().is_ok());
+
+        reversioner.config.new_version = "1.0 0".to_string();
+        let err = reversioner.validate_inputs().unwrap_err().to_string();
+        assert!(err.contains("New version '1.0 0' contains whitespace"));
+    }
+
+    #[test]
+    fn test_is_debian_version() {
+        for (version, expected) in [
+            // Epoch
+            ("0:1.0", true),
+            ("12:1.0", true),
+            ("1:", false),
+            (":1.0", false),
+            ("a:1.0", false),
+            ("1:2:3", false),
+            // Upstream version
+            ("1", true),
+            ("1.0+dfsg~rc1", true),
+            ("1a", true),
+            ("1.0~", true),
+            ("", false),
+            ("a1.0", false),
+            (".1", false),
+            ("~1", false),
+            ("1.0_1", false),
+            ("1.0!", false),
+            // Revision, after the last hyphen
+            ("1.0-1", true),
+            ("1.0-2-3", true),
+            ("1.0-rc1-0ubuntu1", true),
+            ("1.0-a.b+c~d", true),
+            ("1.0-", false),
+            ("1.0-rc1-", false),
+            ("1.0-1:2", false),
+            ("1.0-1_2", false),
+        ] {
+            assert_eq!(is_debian_version(version), expected, "{:?}", version);
+        }
+    }
+
+    #[test]
+    fn test_validate_debian_version() {
+        for valid in [
+            "1.0.0",
+            "3.0.0-rc1",
+            "1:2.3.4",
+            "2:1.0~beta1+dfsg-3",
+            "3.1.0-alpha1-compatible-abc1234",
+            "0.5-1ubuntu0.1",
+            "10",
+        ] {
+            assert!(
+                validate_debian_version("New version", valid).is_ok(),
+                "{} should be valid",
+                valid
+            );
+        }
+
+        for invalid in [
+            "1.0 0", "1.0\t0", "1.0/0", "..\\1.0", "v1.0.0", "a:1.0", "1.0-", ":1.0", "1.0_1",
+            "1:2:3",
+        ] {
+            assert!(
+                matches!(
+                    validate_debian_version("New version", invalid),
+                    Err(ManagerError::ValidationError(_))
+                ),
+                "{:?} should be invalid",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_regenerate_metadata() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        std::fs::create_dir_all(root.join("DEBIAN")).unwrap();
+        std::fs::create_dir_all(root.join("usr/bin")).unwrap();
+        std::fs::create_dir_all(root.join("usr/share/doc/mina-devnet")).unwrap();
+        std::fs::write(
+            root.join("DEBIAN/control"),
+            "Package: mina-devnet\nVersion: 1.0.1\nInstalled-Size: 1\nDescription: Mina\n daemon\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("DEBIAN/md5sums"),
+            "00000000000000000000000000000000  usr/bin/mina\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("usr/bin/mina"), vec![b'x'; 2000]).unwrap();
+        std::fs::write(
+            root.join("usr/share/doc/mina-devnet/copyright"),
+            "Apache-2.0",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("mina", root.join("usr/bin/mina-daemon")).unwrap();
+
+        regenerate_metadata(root).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(root.join("DEBIAN/md5sums")).unwrap(),
+            format!(
+                "{:x}  usr/bin/mina\n{:x}  usr/share/doc/mina-devnet/copyright\n",
+                Md5::digest(vec![b'x'; 2000]),
+                Md5::digest("Apache-2.0")
+            )
+        );
+        // usr, bin, share, doc and mina-devnet, the symlink, then 2 + 1 KiB
+        assert_eq!(
+            std::fs::read_to_string(root.join("DEBIAN/control")).unwrap(),
+            "Package: mina-devnet\nVersion: 1.0.1\nInstalled-Size: 9\nDescription: Mina\n daemon\n"
+        );
+
+        assert_eq!(
+            set_installed_size("Package: mina\nVersion: 1.0.1\n", 42),
+            "Package: mina\nVersion: 1.0.1\nInstalled-Size: 42\n"
+        );
     }
 
     #[test]
@@ -415,6 +1279,10 @@ mod tests {
             suite: "unstable".to_string(),
             new_suite: "stable".to_string(),
             new_name: Some("new-package".to_string()),
+            output_dir: None,
+            deb_format: None,
+            overwrite: false,
+            dry_run: false,
         };
 
         let reversioner = DebianReversioner::new(config).unwrap();
@@ -442,6 +1310,108 @@ Description: Test package
         );
     }
 
+    #[test]
+    fn test_suite_lands_in_changelog_not_control() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = ReversionConfig {
+            deb_path: temp_file.path().to_path_buf(),
+            package_name: "mina-devnet".to_string(),
+            source_version: "1.0.0".to_string(),
+            new_version: "1.0.1".to_string(),
+            suite: "unstable".to_string(),
+            new_suite: "stable".to_string(),
+            new_name: None,
+            output_dir: None,
+            deb_format: None,
+            overwrite: false,
+            dry_run: false,
+        };
+        let reversioner = DebianReversioner::new(config).unwrap();
+
+        // Binary control files carry no suite, and a stray Distribution
+        // field is left alone rather than passed off as the suite
+        let control = "Package: mina-devnet\nVersion: 1.0.0\nArchitecture: amd64\n";
+        assert_eq!(
+            reversioner.update_control_content(control).unwrap(),
+            "Package: mina-devnet\nVersion: 1.0.1\nArchitecture: amd64\n"
+        );
+        let with_distribution = format!("{}Distribution: unstable\n", control);
+        assert!(reversioner
+            .update_control_content(&with_distribution)
+            .unwrap()
+            .contains("Distribution: unstable\n"));
+
+        let entry = reversioner.changelog_entry("Mon, 01 Jan 2024 00:00:00 +0000");
+        assert!(entry.starts_with("mina-devnet (1.0.1) stable; urgency=medium\n"));
+        assert_eq!(
+            crate::verification::parse_changelog_version(&entry),
+            Some("1.0.1")
+        );
+        assert!(entry.contains(" -- Release Manager <release@minaprotocol.com>  Mon, 01 Jan 2024"));
+    }
+
+    #[tokio::test]
+    async fn test_changelog_entry_prepended_to_shipped_history() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = ReversionConfig {
+            deb_path: temp_file.path().to_path_buf(),
+            package_name: "mina-archive-devnet".to_string(),
+            source_version: "1.0.0".to_string(),
+            new_version: "1.0.1".to_string(),
+            suite: "unstable".to_string(),
+            new_suite: "stable".to_string(),
+            new_name: Some("mina-archive".to_string()),
+            output_dir: None,
+            deb_format: None,
+            overwrite: false,
+            dry_run: false,
+        };
+        let reversioner = DebianReversioner::new(config).unwrap();
+
+        let history = "mina-archive-devnet (1.0.0) unstable; urgency=medium\n\n  * Initial release\n\n -- Mina <ci@minaprotocol.com>  Mon, 01 Jan 2024 00:00:00 +0000\n";
+        let extract = tempfile::tempdir().unwrap();
+        let shipped_dir = extract.path().join("usr/share/doc/mina-archive-devnet");
+        std::fs::create_dir_all(&shipped_dir).unwrap();
+        std::fs::write(
+            shipped_dir.join("changelog.Debian.gz"),
+            gzip_bytes(history.as_bytes()).unwrap(),
+        )
+        .unwrap();
+
+        reversioner
+            .create_changelog_entry(extract.path())
+            .await
+            .unwrap();
+        let changelog = gunzip_file(
+            &extract
+                .path()
+                .join("usr/share/doc/mina-archive/changelog.Debian.gz"),
+        )
+        .unwrap();
+        assert!(changelog.starts_with("mina-archive (1.0.1) stable; urgency=medium\n"));
+        assert!(changelog.ends_with(&format!("+0000\n\n{}", history)));
+        assert_eq!(
+            crate::verification::parse_changelog_version(&changelog),
+            Some("1.0.1")
+        );
+
+        // Without a shipped changelog the entry stands alone
+        let empty = tempfile::tempdir().unwrap();
+        reversioner
+            .create_changelog_entry(empty.path())
+            .await
+            .unwrap();
+        let fresh = gunzip_file(
+            &empty
+                .path()
+                .join("usr/share/doc/mina-archive/changelog.Debian.gz"),
+        )
+        .unwrap();
+        assert!(fresh.starts_with("mina-archive (1.0.1) stable;"));
+        assert!(fresh.ends_with("+0000\n\n"));
+        assert!(!fresh.contains("Initial release"));
+    }
+
     #[test]
     fn test_invalid_deb_path() {
         let config = ReversionConfig {
@@ -452,6 +1422,10 @@ Description: Test package
             suite: "unstable".to_string(),
             new_suite: "stable".to_string(),
             new_name: None,
+            output_dir: None,
+            deb_format: None,
+            overwrite: false,
+            dry_run: false,
         };
 
         let reversioner = DebianReversioner::new(config).unwrap();
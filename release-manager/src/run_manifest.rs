@@ -0,0 +1,241 @@
+//! Run manifests: every artifact coordinate a publish/promote run targeted,
+//! written by `--emit-manifest` and read back by `verify --from-manifest`.
+
+use crate::commands::names::{NamesOptions, ResolvedNames};
+use crate::errors::ManagerResult;
+use crate::output::textln;
+use serde::{Deserialize, Serialize};
+
+/// Debian architecture of builds without an explicit arch
+const DEFAULT_DEBIAN_ARCH: &str = "amd64";
+
+/// Every artifact coordinate a publish/promote run targeted, written by
+/// `--emit-manifest` for the CI steps that come after (announcements,
+/// scanners, ...), and read back by `verify --from-manifest`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// `publish` or `promote`
+    pub command: String,
+    pub dry_run: bool,
+    pub debians: Vec<DebianCoordinate>,
+    pub dockers: Vec<DockerCoordinate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebianCoordinate {
+    pub artifact: String,
+    pub network: Option<String>,
+    pub package: String,
+    pub version: String,
+    pub channel: String,
+    pub codename: String,
+    pub arch: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DockerCoordinate {
+    pub artifact: String,
+    pub network: Option<String>,
+    pub codename: String,
+    /// Full reference, e.g. `gcr.io/o1labs-192920/mina-archive:3.0.1-bullseye-devnet`
+    pub reference: String,
+    pub tag: String,
+    /// Digest the registry reported when the image was pushed; absent for
+    /// dry runs or when none was reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl RunManifest {
+    pub fn new(command: &str, dry_run: bool) -> Self {
+        Self {
+            command: command.to_string(),
+            dry_run,
+            debians: Vec::new(),
+            dockers: Vec::new(),
+        }
+    }
+
+    /// Add the coordinates of `names`, resolved with `options`. `debians`
+    /// and `dockers` select which kinds the run targets (`--only-*`).
+    pub fn add(
+        &mut self,
+        names: &[ResolvedNames],
+        options: &NamesOptions,
+        debians: bool,
+        dockers: bool,
+    ) {
+        for entry in names {
+            if debians {
+                self.debians.push(DebianCoordinate {
+                    artifact: entry.artifact.clone(),
+                    network: entry.network.clone(),
+                    package: entry.debian_package.clone(),
+                    version: options.version.to_string(),
+                    channel: entry.channel.clone(),
+                    codename: entry.codename.clone(),
+                    arch: options.arch.unwrap_or(DEFAULT_DEBIAN_ARCH).to_string(),
+                });
+            }
+            if let Some(reference) = entry.docker_tag.as_ref().filter(|_| dockers) {
+                let tag = reference.rsplit_once(':').map(|(_, tag)| tag).unwrap_or("");
+                self.dockers.push(DockerCoordinate {
+                    artifact: entry.artifact.clone(),
+                    network: entry.network.clone(),
+                    codename: entry.codename.clone(),
+                    reference: reference.clone(),
+                    tag: tag.to_string(),
+                    digest: None,
+                });
+            }
+        }
+    }
+
+    /// Publish and promote can ship the archive debian without its network
+    /// (`--strip-network-from-archive`)
+    pub fn strip_network_from_archive(&mut self) {
+        for debian in &mut self.debians {
+            if debian.artifact == "mina-archive" {
+                debian.package = "mina-archive".to_string();
+            }
+        }
+    }
+
+    /// Attach the pushed digests, given as `(reference, digest)` pairs, to
+    /// their docker coordinates
+    pub fn record_digests<'a>(&mut self, digests: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        for (reference, digest) in digests {
+            for docker in self.dockers.iter_mut().filter(|d| d.reference == reference) {
+                docker.digest = Some(digest.to_string());
+            }
+        }
+    }
+
+    /// Read a manifest written by [`RunManifest::write`]
+    pub fn load(path: &str) -> ManagerResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write the manifest as pretty-printed JSON to `path`
+    pub fn write(&self, path: &str) -> ManagerResult<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        textln!(" 🧾 Manifest written to {}", path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::Artifact;
+    use crate::commands::names::resolve_names;
+
+    fn options(version: &str) -> NamesOptions<'_> {
+        NamesOptions {
+            version,
+            channel: "unstable",
+            profile: None,
+            arch: None,
+            docker_io: false,
+            target_registry: None,
+            daemon_debian_name: None,
+        }
+    }
+
+    #[test]
+    fn test_run_manifest_shape() {
+        let networks = ["devnet".to_string()];
+        let codenames = ["bullseye".to_string()];
+        let opts = options("3.0.1");
+        let mut manifest = RunManifest::new("publish", true);
+        manifest.add(
+            &resolve_names(&[Artifact::MinaArchive], &networks, &codenames, &opts),
+            &opts,
+            true,
+            true,
+        );
+        // --only-debians: the logproc has no docker anyway, the daemon's is left out
+        manifest.add(
+            &resolve_names(
+                &[Artifact::MinaLogproc, Artifact::MinaDaemon],
+                &networks,
+                &codenames,
+                &opts,
+            ),
+            &opts,
+            true,
+            false,
+        );
+        manifest.strip_network_from_archive();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        manifest.write(path.to_str().unwrap()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(json["command"], "publish");
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(
+            json["debians"][0],
+            serde_json::json!({
+                "artifact": "mina-archive",
+                "network": "devnet",
+                "package": "mina-archive",
+                "version": "3.0.1",
+                "channel": "unstable",
+                "codename": "bullseye",
+                "arch": "amd64",
+            })
+        );
+        assert_eq!(json["debians"][1]["package"], "mina-logproc");
+        assert_eq!(json["debians"][1]["network"], serde_json::Value::Null);
+        assert_eq!(json["debians"][2]["package"], "mina-devnet");
+        assert_eq!(
+            json["dockers"],
+            serde_json::json!([{
+                "artifact": "mina-archive",
+                "network": "devnet",
+                "codename": "bullseye",
+                "reference": "gcr.io/o1labs-192920/mina-archive:3.0.1-bullseye-devnet",
+                "tag": "3.0.1-bullseye-devnet",
+            }])
+        );
+    }
+
+    #[test]
+    fn test_run_manifest_digests_round_trip() {
+        let opts = options("3.0.1");
+        let mut manifest = RunManifest::new("promote", false);
+        manifest.add(
+            &resolve_names(
+                &[Artifact::MinaDaemon],
+                &["devnet".to_string(), "mainnet".to_string()],
+                &["bullseye".to_string()],
+                &opts,
+            ),
+            &opts,
+            false,
+            true,
+        );
+        let digest = format!("sha256:{}", "a".repeat(64));
+        manifest.record_digests([(
+            "gcr.io/o1labs-192920/mina-daemon:3.0.1-bullseye-devnet",
+            digest.as_str(),
+        )]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        manifest.write(path.to_str().unwrap()).unwrap();
+        let loaded = RunManifest::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded, manifest);
+        assert!(loaded.debians.is_empty());
+        assert_eq!(loaded.dockers[0].digest.as_deref(), Some(digest.as_str()));
+        // No digest reported for mainnet, so none is written
+        assert_eq!(loaded.dockers[1].digest, None);
+        assert!(!std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"digest\": null"));
+    }
+}
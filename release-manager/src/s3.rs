@@ -0,0 +1,501 @@
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::ServerSideEncryption;
+use aws_sdk_s3::Client;
+use md5::{Digest, Md5};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+use crate::errors::{ManagerError, ManagerResult};
+use crate::storage::{expand_local_glob_non_empty, glob_matches};
+
+/// Objects of one S3 bucket, for [`crate::storage::StorageBackend::S3`].
+/// Paths are `s3://<bucket>/<key>` URLs or keys in the default bucket; the
+/// last path component may contain `*`/`?` like the other backends.
+pub struct S3Store {
+    client: Client,
+    bucket: String,
+}
+
+impl S3Store {
+    pub fn new(client: Client, bucket: &str) -> Self {
+        Self {
+            client,
+            bucket: bucket.to_string(),
+        }
+    }
+
+    /// Client for `bucket` in `region`. Credentials, and an `AWS_ENDPOINT_URL`
+    /// override, come from the usual AWS environment and config files.
+    pub async fn connect(region: &str, bucket: &str) -> Self {
+        let config = aws_config::from_env()
+            .region(aws_config::Region::new(region.to_string()))
+            .load()
+            .await;
+        Self::new(Client::new(&config), bucket)
+    }
+
+    /// `(bucket, key, size)` of every object matching `path`, sorted by key
+    async fn objects(&self, path: &str) -> ManagerResult<Vec<(String, String, u64)>> {
+        let (bucket, pattern) = split_s3_path(path, &self.bucket);
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&bucket)
+            .prefix(glob_prefix(&pattern))
+            .into_paginator()
+            .send();
+
+        let mut objects = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| s3_error("list", path, e))?;
+            for object in page.contents() {
+                let Some(key) = object.key() else {
+                    continue;
+                };
+                if key_matches(&pattern, key) {
+                    let size = object.size().unwrap_or(0).max(0) as u64;
+                    objects.push((bucket.clone(), key.to_string(), size));
+                }
+            }
+        }
+        objects.sort();
+        Ok(objects)
+    }
+
+    /// Objects matching `path`, failing like `gsutil` does when there are none
+    async fn objects_non_empty(&self, path: &str) -> ManagerResult<Vec<(String, String, u64)>> {
+        let objects = self.objects(path).await?;
+        if objects.is_empty() {
+            return Err(ManagerError::StorageError(format!(
+                "No S3 objects match {}",
+                path
+            )));
+        }
+        Ok(objects)
+    }
+
+    pub async fn list(&self, path: &str) -> ManagerResult<Vec<String>> {
+        Ok(self
+            .objects_non_empty(path)
+            .await?
+            .into_iter()
+            .map(|(bucket, key, _)| format!("s3://{}/{}", bucket, key))
+            .collect())
+    }
+
+    pub async fn exists(&self, path: &str) -> ManagerResult<bool> {
+        Ok(!self.objects(path).await?.is_empty())
+    }
+
+    pub async fn sizes(&self, path: &str) -> ManagerResult<Vec<(String, u64)>> {
+        Ok(self
+            .objects(path)
+            .await?
+            .into_iter()
+            .map(|(bucket, key, size)| (format!("s3://{}/{}", bucket, key), size))
+            .collect())
+    }
+
+    /// MD5 of the first object matching `path`. Taken from the ETag when it
+    /// is one (see [`head_md5`]); other objects are streamed and hashed.
+    pub async fn md5(&self, path: &str) -> ManagerResult<String> {
+        let (bucket, key, _) = self.objects_non_empty(path).await?.remove(0);
+        let head = self
+            .client
+            .head_object()
+            .bucket(&bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| s3_error("head", path, e))?;
+        if let Some(md5) = head_md5(&head) {
+            return Ok(md5);
+        }
+
+        let mut body = self.get(&bucket, &key, path).await?;
+        let mut hasher = Md5::new();
+        while let Some(chunk) = body
+            .try_next()
+            .await
+            .map_err(|e| ManagerError::StorageError(format!("S3 read {}: {}", path, e)))?
+        {
+            hasher.update(&chunk);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Download every object matching `remote_path` into the directory
+    /// `local_path`, or to `local_path` itself when it isn't a directory
+    pub async fn download(&self, remote_path: &str, local_path: &str) -> ManagerResult<()> {
+        let local = Path::new(local_path);
+        for (bucket, key, _) in self.objects_non_empty(remote_path).await? {
+            let target = if local.is_dir() {
+                local.join(key.rsplit('/').next().unwrap_or(&key))
+            } else {
+                local.to_path_buf()
+            };
+
+            let mut body = self.get(&bucket, &key, remote_path).await?;
+            let mut file = tokio::fs::File::create(&target).await?;
+            while let Some(chunk) = body.try_next().await.map_err(|e| {
+                ManagerError::StorageError(format!("S3 read {}: {}", remote_path, e))
+            })? {
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Upload every file matching `local_path` to `remote_path`, keeping the
+    /// file name when `remote_path` ends in `/` or several files match
+    pub async fn upload(&self, local_path: &str, remote_path: &str) -> ManagerResult<()> {
+        let files = expand_local_glob_non_empty(local_path)?;
+        let (bucket, mut prefix) = split_s3_path(remote_path, &self.bucket);
+        if files.len() > 1 && !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        for file in &files {
+            let key = if prefix.is_empty() || prefix.ends_with('/') {
+                let file_name = file
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                format!("{}{}", prefix, file_name)
+            } else {
+                prefix.clone()
+            };
+
+            let body = ByteStream::from_path(file).await.map_err(|e| {
+                ManagerError::StorageError(format!("Failed to read {}: {}", file.display(), e))
+            })?;
+            self.client
+                .put_object()
+                .bucket(&bucket)
+                .key(&key)
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| s3_error("upload", remote_path, e))?;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, bucket: &str, key: &str, path: &str) -> ManagerResult<ByteStream> {
+        Ok(self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| s3_error("download", path, e))?
+            .body)
+    }
+}
+
+fn s3_error(operation: &str, path: &str, e: impl std::error::Error) -> ManagerError {
+    ManagerError::StorageError(format!(
+        "S3 {} {}: {}",
+        operation,
+        path,
+        DisplayErrorContext(e)
+    ))
+}
+
+/// Split `s3://<bucket>/<key>` into bucket and key. Anything else is a key
+/// in `default_bucket`.
+pub fn split_s3_path(path: &str, default_bucket: &str) -> (String, String) {
+    match path.strip_prefix("s3://") {
+        Some(rest) => {
+            let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+            (bucket.to_string(), key.to_string())
+        }
+        None => (
+            default_bucket.to_string(),
+            path.trim_start_matches('/').to_string(),
+        ),
+    }
+}
+
+/// Longest literal prefix of a key pattern, used to narrow the listing
+fn glob_prefix(pattern: &str) -> &str {
+    pattern
+        .find(['*', '?'])
+        .map_or(pattern, |wildcard| &pattern[..wildcard])
+}
+
+/// Whether `key` matches `pattern` component by component. A pattern
+/// without wildcards also matches the keys directly under it, like listing
+/// a directory.
+pub fn key_matches(pattern: &str, key: &str) -> bool {
+    if !pattern.contains(['*', '?']) && key == pattern {
+        return true;
+    }
+    let directory;
+    let pattern = if pattern.contains(['*', '?']) {
+        pattern
+    } else {
+        directory = format!("{}/*", pattern.trim_end_matches('/'));
+        &directory
+    };
+
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let key: Vec<&str> = key.split('/').collect();
+    pattern.len() == key.len()
+        && pattern
+            .iter()
+            .zip(&key)
+            .all(|(pattern, name)| glob_matches(pattern, name))
+}
+
+/// The object's MD5 as its ETag reports it. Only plaintext and SSE-S3
+/// objects have their MD5 as ETag: SSE-KMS and SSE-C objects have an opaque
+/// one that merely looks like an MD5, so those give `None`.
+fn head_md5(head: &HeadObjectOutput) -> Option<String> {
+    let md5_etag = matches!(
+        head.server_side_encryption(),
+        None | Some(ServerSideEncryption::Aes256)
+    ) && head.sse_customer_algorithm().is_none();
+    head.e_tag().filter(|_| md5_etag).and_then(etag_md5)
+}
+
+/// The MD5 an ETag holds, or `None` for a multipart ETag (`<hash>-<parts>`)
+pub fn etag_md5(etag: &str) -> Option<String> {
+    let etag = etag.trim_matches('"');
+    (etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| etag.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_s3_path() {
+        assert_eq!(
+            split_s3_path("s3://mina-builds/123/debians/bullseye/", "default"),
+            (
+                "mina-builds".to_string(),
+                "123/debians/bullseye/".to_string()
+            )
+        );
+        assert_eq!(
+            split_s3_path("s3://mina-builds", "default"),
+            ("mina-builds".to_string(), String::new())
+        );
+        assert_eq!(
+            split_s3_path("/123/sboms/a.json", "default"),
+            ("default".to_string(), "123/sboms/a.json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_key_matches() {
+        let glob = "123/debians/bullseye/mina-devnet_*";
+        assert_eq!(glob_prefix(glob), "123/debians/bullseye/mina-devnet_");
+        assert!(key_matches(
+            glob,
+            "123/debians/bullseye/mina-devnet_1.0.0.deb"
+        ));
+        assert!(!key_matches(
+            glob,
+            "123/debians/bullseye/mina-devnet-hardfork_1.0.0.deb"
+        ));
+        assert!(!key_matches(
+            glob,
+            "123/debians/bullseye/mina-devnet_1/nested.deb"
+        ));
+
+        // Directories list their direct children only
+        let dir = "123/debians/bullseye/";
+        assert!(key_matches(
+            dir,
+            "123/debians/bullseye/mina-devnet_1.0.0.deb"
+        ));
+        assert!(!key_matches(
+            dir,
+            "123/debians/bullseye/old/mina-devnet_1.0.0.deb"
+        ));
+        assert!(key_matches("123/a.deb", "123/a.deb"));
+        assert!(!key_matches("123/a.deb", "123/a.deb.sig"));
+    }
+
+    #[test]
+    fn test_etag_md5() {
+        assert_eq!(
+            etag_md5("\"D41D8CD98F00B204E9800998ECF8427E\""),
+            Some("d41d8cd98f00b204e9800998ecf8427e".to_string())
+        );
+        assert_eq!(etag_md5("\"9b2cf535f27731c974343645a3985328-12\""), None);
+        assert_eq!(etag_md5(""), None);
+    }
+
+    #[test]
+    fn test_head_md5_ignores_encrypted_etags() {
+        let etag = "\"d41d8cd98f00b204e9800998ecf8427e\"";
+        let md5 = Some("d41d8cd98f00b204e9800998ecf8427e".to_string());
+        let head = || HeadObjectOutput::builder().e_tag(etag);
+
+        assert_eq!(head_md5(&head().build()), md5);
+        assert_eq!(
+            head_md5(
+                &head()
+                    .server_side_encryption(ServerSideEncryption::Aes256)
+                    .build()
+            ),
+            md5
+        );
+        for sse in [
+            ServerSideEncryption::AwsKms,
+            ServerSideEncryption::AwsKmsDsse,
+        ] {
+            assert_eq!(head_md5(&head().server_side_encryption(sse).build()), None);
+        }
+        assert_eq!(
+            head_md5(&head().sse_customer_algorithm("AES256").build()),
+            None
+        );
+    }
+
+    /// Round trip through a MinIO container, including a multipart upload
+    /// whose ETag isn't an MD5. Needs Docker:
+    /// `cargo test --features integration-test s3_round_trip`.
+    #[cfg(feature = "integration-test")]
+    #[tokio::test]
+    async fn s3_round_trip_against_minio() {
+        use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+        use testcontainers_modules::minio::MinIO;
+        use testcontainers_modules::testcontainers::runners::AsyncRunner;
+
+        let docker_up = std::process::Command::new("docker")
+            .arg("info")
+            .output()
+            .is_ok_and(|out| out.status.success());
+        if !docker_up {
+            eprintln!("skipping s3_round_trip_against_minio: docker isn't available");
+            return;
+        }
+
+        let container = MinIO::default()
+            .start()
+            .await
+            .expect("minio container start");
+        let port = container.get_host_port_ipv4(9000).await.unwrap();
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .endpoint_url(format!("http://127.0.0.1:{}", port))
+            .credentials_provider(aws_sdk_s3::config::Credentials::new(
+                "minioadmin",
+                "minioadmin",
+                None,
+                None,
+                "test",
+            ))
+            .force_path_style(true)
+            .build();
+        let client = Client::from_conf(config);
+        client
+            .create_bucket()
+            .bucket("builds")
+            .send()
+            .await
+            .unwrap();
+        let store = S3Store::new(client.clone(), "builds");
+
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("mina-devnet_1.0.0.deb");
+        std::fs::write(&deb, b"fake deb").unwrap();
+        store
+            .upload(deb.to_str().unwrap(), "s3://builds/123/debians/bullseye/")
+            .await
+            .unwrap();
+
+        let glob = "s3://builds/123/debians/bullseye/mina-devnet_*";
+        assert_eq!(
+            store.list(glob).await.unwrap(),
+            vec!["s3://builds/123/debians/bullseye/mina-devnet_1.0.0.deb"]
+        );
+        assert_eq!(
+            store.md5(glob).await.unwrap(),
+            format!("{:x}", Md5::digest(b"fake deb"))
+        );
+
+        let out = tempfile::tempdir().unwrap();
+        store
+            .download(glob, out.path().to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(out.path().join("mina-devnet_1.0.0.deb")).unwrap(),
+            b"fake deb"
+        );
+
+        // A glob uploads every match, like persist does
+        std::fs::write(dir.path().join("mina-archive-devnet_1.0.0.deb"), b"archive").unwrap();
+        store
+            .upload(
+                &format!("{}/*.deb", dir.path().display()),
+                "s3://builds/124/debians/bullseye",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .list("s3://builds/124/debians/bullseye/*.deb")
+                .await
+                .unwrap(),
+            vec![
+                "s3://builds/124/debians/bullseye/mina-archive-devnet_1.0.0.deb",
+                "s3://builds/124/debians/bullseye/mina-devnet_1.0.0.deb",
+            ]
+        );
+
+        // Multipart ETags fall back to hashing the content
+        let part = vec![7u8; 5 * 1024 * 1024];
+        let upload = client
+            .create_multipart_upload()
+            .bucket("builds")
+            .key("big.deb")
+            .send()
+            .await
+            .unwrap();
+        let upload_id = upload.upload_id().unwrap();
+        let uploaded = client
+            .upload_part()
+            .bucket("builds")
+            .key("big.deb")
+            .upload_id(upload_id)
+            .part_number(1)
+            .body(ByteStream::from(part.clone()))
+            .send()
+            .await
+            .unwrap();
+        client
+            .complete_multipart_upload()
+            .bucket("builds")
+            .key("big.deb")
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .parts(
+                        CompletedPart::builder()
+                            .part_number(1)
+                            .e_tag(uploaded.e_tag().unwrap())
+                            .build(),
+                    )
+                    .build(),
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            store.md5("big.deb").await.unwrap(),
+            format!("{:x}", Md5::digest(&part))
+        );
+    }
+}
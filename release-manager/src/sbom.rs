@@ -0,0 +1,158 @@
+use crate::errors::ManagerResult;
+use crate::output::textln;
+use crate::storage::{StorageClient, StorageOperations};
+use crate::trace::TracedCommand;
+use std::path::{Path, PathBuf};
+use tokio::process::Command as AsyncCommand;
+
+/// Media type used both for the OCI referrer artifact and the attached file.
+pub const SBOM_MEDIA_TYPE: &str = "application/spdx+json";
+
+/// SBOM file name expected in `--sbom-dir` for a given artifact and version,
+/// e.g. `mina-devnet_3.0.0.spdx.json`.
+pub fn sbom_file_name(artifact: &str, version: &str) -> String {
+    format!("{}_{}.spdx.json", artifact, version)
+}
+
+/// Look up the SBOM for `artifact`/`version` in `sbom_dir`. Returns `None`
+/// when the file is absent so callers can skip silently.
+pub fn find_sbom(sbom_dir: &Path, artifact: &str, version: &str) -> Option<PathBuf> {
+    let candidate = sbom_dir.join(sbom_file_name(artifact, version));
+    if candidate.is_file() {
+        Some(candidate)
+    } else {
+        None
+    }
+}
+
+/// Storage location for a debian SBOM: a `sboms/` folder in the codename's
+/// debian directory (as laid out by the storage path template), out of
+/// reach of the `<package>_*` globs that find the packages.
+pub fn sbom_remote_path(debian_directory: &str, artifact: &str, version: &str) -> String {
+    format!(
+        "{}sboms/{}",
+        debian_directory,
+        sbom_file_name(artifact, version)
+    )
+}
+
+/// Arguments for `oras attach`, which pushes the SBOM as an OCI referrer of
+/// `image` without touching the image itself.
+pub fn oras_attach_args(image: &str, sbom: &Path) -> Vec<String> {
+    vec![
+        "attach".to_string(),
+        "--artifact-type".to_string(),
+        SBOM_MEDIA_TYPE.to_string(),
+        image.to_string(),
+        format!("{}:{}", sbom.display(), SBOM_MEDIA_TYPE),
+    ]
+}
+
+/// Upload a debian SBOM through the storage backend. Returns the remote
+/// location so it can be reported.
+pub async fn publish_debian_sbom(
+    storage: &StorageClient,
+    sbom: &Path,
+    buildkite_build_id: &str,
+    codename: &str,
+    artifact: &str,
+    version: &str,
+) -> ManagerResult<String> {
+    let remote_path = sbom_remote_path(
+        &storage.debian_directory(buildkite_build_id, codename)?,
+        artifact,
+        version,
+    );
+    textln!(
+        "    📜 Uploading SBOM {} -> {}",
+        sbom.display(),
+        remote_path
+    );
+    storage
+        .upload(&sbom.to_string_lossy(), &remote_path)
+        .await?;
+    Ok(remote_path)
+}
+
+/// Attach an SBOM to a pushed docker image as an OCI referrer via `oras`.
+/// Returns the image reference the SBOM was attached to.
+pub async fn attach_docker_sbom(image: &str, sbom: &Path) -> ManagerResult<String> {
+    textln!("    📜 Attaching SBOM {} to {}", sbom.display(), image);
+
+    let mut cmd = AsyncCommand::new("oras");
+    cmd.args(oras_attach_args(image, sbom));
+
+    cmd.checked_output(
+        "Failed to execute oras",
+        &format!("oras attach failed for {}", image),
+    )
+    .await?;
+
+    Ok(image.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DebianPathTemplate;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_sbom_matches_artifact_and_version() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("mina-devnet_3.0.0.spdx.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("mina-mainnet_3.0.0.spdx.json"), "{}").unwrap();
+
+        assert_eq!(
+            find_sbom(dir.path(), "mina-devnet", "3.0.0"),
+            Some(dir.path().join("mina-devnet_3.0.0.spdx.json"))
+        );
+        assert_eq!(find_sbom(dir.path(), "mina-devnet", "3.0.1"), None);
+        assert_eq!(find_sbom(dir.path(), "mina-archive-devnet", "3.0.0"), None);
+    }
+
+    #[test]
+    fn test_find_sbom_ignores_directories() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("mina-devnet_3.0.0.spdx.json")).unwrap();
+        assert_eq!(find_sbom(dir.path(), "mina-devnet", "3.0.0"), None);
+    }
+
+    #[test]
+    fn test_sbom_remote_path() {
+        let root = "gs://buildkite_k8s/coda/shared";
+        let default = DebianPathTemplate::default().directory(root, "12345", "bullseye");
+        assert_eq!(
+            sbom_remote_path(&default, "mina-devnet", "3.0.0"),
+            "gs://buildkite_k8s/coda/shared/12345/debians/bullseye/sboms/mina-devnet_3.0.0.spdx.json"
+        );
+
+        // Custom layouts keep the SBOM with the build's debians
+        let custom =
+            DebianPathTemplate::parse("{root}/mina/{codename}/builds/{build_id}/{artifact}")
+                .unwrap()
+                .directory(root, "12345", "bullseye");
+        assert_eq!(
+            sbom_remote_path(&custom, "mina-devnet", "3.0.0"),
+            "gs://buildkite_k8s/coda/shared/mina/bullseye/builds/12345/sboms/mina-devnet_3.0.0.spdx.json"
+        );
+    }
+
+    #[test]
+    fn test_oras_attach_args() {
+        let args = oras_attach_args(
+            "gcr.io/o1labs-192920/mina-daemon:3.0.0-bullseye-devnet",
+            Path::new("/sboms/mina-daemon_3.0.0-bullseye-devnet.spdx.json"),
+        );
+        assert_eq!(
+            args,
+            vec![
+                "attach",
+                "--artifact-type",
+                "application/spdx+json",
+                "gcr.io/o1labs-192920/mina-daemon:3.0.0-bullseye-devnet",
+                "/sboms/mina-daemon_3.0.0-bullseye-devnet.spdx.json:application/spdx+json",
+            ]
+        );
+    }
+}
@@ -1,7 +1,15 @@
+use crate::disk_space::{check_free_space, reversion_space_estimate};
 use crate::errors::{ManagerError, ManagerResult};
+use crate::metrics::{self, Counter};
+use crate::output::textln;
+use crate::s3::S3Store;
+use crate::trace::TracedCommand;
+use crate::utils::stderr_excerpt;
 use async_trait::async_trait;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::process::Command;
+use tokio::sync::OnceCell;
 
 #[derive(Debug, Clone)]
 pub enum StorageBackend {
@@ -12,6 +20,10 @@ pub enum StorageBackend {
         host: String,
         key_path: String,
     },
+    S3 {
+        region: String,
+        bucket: String,
+    },
 }
 
 impl StorageBackend {
@@ -33,47 +45,431 @@ impl StorageBackend {
                     key_path,
                 })
             }
+            "s3" => {
+                let bucket = std::env::var("S3_BUCKET").map_err(|_| {
+                    ManagerError::MissingParameter(
+                        "S3_BUCKET must be set for the s3 backend".to_string(),
+                    )
+                })?;
+                let region = std::env::var("S3_REGION").unwrap_or_else(|_| "us-west-2".to_string());
+
+                Ok(StorageBackend::S3 { region, bucket })
+            }
             _ => Err(ManagerError::UnsupportedBackend(backend.to_string())),
         }
     }
 
-    pub fn root_path(&self) -> &str {
+    /// Whether stored objects are plain files on this machine that tools
+    /// like deb-s3 can read without downloading them first.
+    pub fn is_locally_addressable(&self) -> bool {
+        matches!(self, StorageBackend::Local)
+    }
+
+    pub fn root_path(&self) -> String {
         match self {
-            StorageBackend::Local => "/var/storagebox/",
-            StorageBackend::Gs => "gs://buildkite_k8s/coda/shared",
+            StorageBackend::Local => "/var/storagebox/".to_string(),
+            StorageBackend::Gs => "gs://buildkite_k8s/coda/shared".to_string(),
             StorageBackend::Hetzner { .. } => {
-                "/home/o1labs-generic/pvc-4d294645-6466-4260-b933-1b909ff9c3a1"
+                "/home/o1labs-generic/pvc-4d294645-6466-4260-b933-1b909ff9c3a1".to_string()
+            }
+            StorageBackend::S3 { bucket, .. } => format!("s3://{}", bucket),
+        }
+    }
+}
+
+/// Wildcards understood by [`expand_local_glob`] and passed through unescaped
+/// by [`escape_remote_glob`].
+const GLOB_CHARS: &[char] = &['*', '?'];
+
+/// Match a single path component against a `*`/`?` pattern.
+pub(crate) fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Expand a local path whose last component may contain `*`/`?` without
+/// going through a shell, so spaces and other special characters in the
+/// directory part are taken literally. A path without wildcards expands to
+/// itself when it exists, or to its entries when it is a directory (like
+/// `ls`). Results are sorted.
+pub fn expand_local_glob(pattern: &str) -> ManagerResult<Vec<PathBuf>> {
+    let path = Path::new(pattern);
+    let file_pattern = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (dir, file_pattern) = if file_pattern.contains(GLOB_CHARS) {
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        (dir.to_path_buf(), Some(file_pattern))
+    } else if path.is_dir() {
+        (path.to_path_buf(), None)
+    } else if path.exists() {
+        return Ok(vec![path.to_path_buf()]);
+    } else {
+        return Ok(Vec::new());
+    };
+
+    let mut matches = Vec::new();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(matches),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if file_pattern
+            .as_deref()
+            .is_none_or(|pattern| glob_matches(pattern, &name))
+        {
+            matches.push(entry.path());
+        }
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Quote a path for a remote shell while leaving `*`/`?` unquoted so the
+/// remote side still expands them. Only needed where a shell is genuinely
+/// involved (Hetzner over ssh); local and gsutil paths go through argv.
+pub fn escape_remote_glob(path: &str) -> String {
+    let mut escaped = String::new();
+    let mut literal = String::new();
+    for c in path.chars() {
+        if GLOB_CHARS.contains(&c) {
+            if !literal.is_empty() {
+                escaped.push_str(&shell_escape::escape(std::mem::take(&mut literal).into()));
+            }
+            escaped.push(c);
+        } else {
+            literal.push(c);
+        }
+    }
+    if !literal.is_empty() {
+        escaped.push_str(&shell_escape::escape(literal.into()));
+    }
+    escaped
+}
+
+/// `ssh` invocation for rsync's `--rsh`. rsync splits this string itself,
+/// so the key path needs quoting.
+fn hetzner_rsh(key_path: &str) -> String {
+    format!("ssh -p 23 -i {}", shell_escape::escape(key_path.into()))
+}
+
+/// Local paths matched by `pattern`, failing like `ls` would if none match.
+pub(crate) fn expand_local_glob_non_empty(pattern: &str) -> ManagerResult<Vec<PathBuf>> {
+    let paths = expand_local_glob(pattern)?;
+    if paths.is_empty() {
+        return Err(ManagerError::CommandFailed(format!(
+            "No such file or directory: {}",
+            pattern
+        )));
+    }
+    Ok(paths)
+}
+
+/// Parse `gsutil ls -l` output into `(url, size)` pairs, skipping the
+/// trailing `TOTAL:` line.
+fn parse_gsutil_ls_sizes(output: &str) -> Vec<(String, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [size, _date, url] => Some((url.to_string(), size.parse().ok()?)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Parse `ls -l` output into `(name, size)` pairs. The name is everything
+/// after the date columns so names with spaces survive.
+fn parse_ls_l_sizes(output: &str) -> Vec<(String, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 9 || !fields[0].starts_with('-') {
+                return None;
             }
+            let size = fields[4].parse().ok()?;
+            Some((fields[8..].join(" "), size))
+        })
+        .collect()
+}
+
+/// Refuse to download `remote_path` if any object it matches is larger than
+/// `max_size` bytes. `None` means no limit.
+pub async fn check_download_size<S: StorageOperations + Sync + ?Sized>(
+    storage: &S,
+    remote_path: &str,
+    max_size: Option<u64>,
+) -> ManagerResult<()> {
+    let Some(max_size) = max_size else {
+        return Ok(());
+    };
+
+    for (object, size) in storage.sizes(remote_path).await? {
+        if size > max_size {
+            return Err(ManagerError::ValidationError(format!(
+                "Refusing to download {}: {} bytes exceeds --max-download-size of {} bytes",
+                object, size, max_size
+            )));
         }
     }
+
+    Ok(())
+}
+
+/// Refuse to download `remote_path` into `cache_dir` unless its filesystem
+/// has room for the objects and their reversion
+pub async fn check_download_space<S: StorageOperations + Sync + ?Sized>(
+    storage: &S,
+    remote_path: &str,
+    cache_dir: &Path,
+) -> ManagerResult<()> {
+    let size = storage
+        .sizes(remote_path)
+        .await?
+        .iter()
+        .map(|(_, size)| size)
+        .sum();
+    check_free_space(
+        cache_dir,
+        reversion_space_estimate(size),
+        &format!("download and reversion {}", remote_path),
+    )
+}
+
+/// Where a build's debians live: `{root}` is the backend root, and a
+/// package's files are `<expanded template>_*`.
+pub const DEFAULT_DEBIAN_PATH_TEMPLATE: &str = "{root}/{build_id}/debians/{codename}/{artifact}";
+
+/// Environment variable overriding [`DEFAULT_DEBIAN_PATH_TEMPLATE`]
+pub const DEBIAN_PATH_TEMPLATE_ENV: &str = "DEBIAN_STORAGE_PATH_TEMPLATE";
+
+const DEBIAN_PATH_PLACEHOLDERS: [&str; 4] = ["{root}", "{build_id}", "{codename}", "{artifact}"];
+const REQUIRED_DEBIAN_PATH_PLACEHOLDERS: [&str; 3] = ["{build_id}", "{codename}", "{artifact}"];
+
+/// Storage layout of a build's debians, shared by publish, promote, persist
+/// and pull so they all agree on where packages are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebianPathTemplate(String);
+
+impl DebianPathTemplate {
+    /// Validate `template`: `{build_id}`, `{codename}` and `{artifact}` are
+    /// required, it must end in `/{artifact}` (packages are stored as
+    /// `<package>_<version>.deb`), and no other placeholders are allowed.
+    pub fn parse(template: &str) -> ManagerResult<Self> {
+        let invalid = |reason: String| {
+            ManagerError::ValidationError(format!(
+                "Invalid debian path template '{}': {}",
+                template, reason
+            ))
+        };
+
+        for placeholder in REQUIRED_DEBIAN_PATH_PLACEHOLDERS {
+            if !template.contains(placeholder) {
+                return Err(invalid(format!("missing {}", placeholder)));
+            }
+        }
+        if !template.ends_with("/{artifact}") {
+            return Err(invalid("it must end with /{artifact}".to_string()));
+        }
+        let leftover = DEBIAN_PATH_PLACEHOLDERS
+            .iter()
+            .fold(template.to_string(), |rest, p| rest.replace(p, ""));
+        if leftover.contains(['{', '}']) {
+            return Err(invalid(format!(
+                "unknown placeholder, expected only {}",
+                DEBIAN_PATH_PLACEHOLDERS.join(", ")
+            )));
+        }
+
+        Ok(Self(template.to_string()))
+    }
+
+    /// The template from [`DEBIAN_PATH_TEMPLATE_ENV`], or the default layout
+    pub fn from_env() -> ManagerResult<Self> {
+        match std::env::var(DEBIAN_PATH_TEMPLATE_ENV) {
+            Ok(template) => Self::parse(&template),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    fn expand(&self, root: &str, build_id: &str, codename: &str, artifact: &str) -> String {
+        self.0
+            .replace("{root}", root.trim_end_matches('/'))
+            .replace("{build_id}", build_id)
+            .replace("{codename}", codename)
+            .replace("{artifact}", artifact)
+    }
+
+    /// Glob matching every stored file of `artifact` (`<path>_*`)
+    pub fn package_glob(
+        &self,
+        root: &str,
+        build_id: &str,
+        codename: &str,
+        artifact: &str,
+    ) -> String {
+        format!("{}_*", self.expand(root, build_id, codename, artifact))
+    }
+
+    /// Directory holding a build's debians for `codename`, ending in `/`
+    pub fn directory(&self, root: &str, build_id: &str, codename: &str) -> String {
+        self.expand(root, build_id, codename, "")
+    }
+}
+
+impl Default for DebianPathTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_DEBIAN_PATH_TEMPLATE.to_string())
+    }
 }
 
 #[async_trait]
 pub trait StorageOperations {
     async fn list(&self, path: &str) -> ManagerResult<Vec<String>>;
     async fn md5(&self, path: &str) -> ManagerResult<String>;
+    /// Whether anything is stored at `path`, which may be a glob
+    async fn exists(&self, path: &str) -> ManagerResult<bool>;
+    /// Size in bytes of every object matching `path`
+    async fn sizes(&self, path: &str) -> ManagerResult<Vec<(String, u64)>>;
     async fn download(&self, remote_path: &str, local_path: &str) -> ManagerResult<()>;
     async fn upload(&self, local_path: &str, remote_path: &str) -> ManagerResult<()>;
 }
 
+/// Times a failed storage command is retried unless the client says otherwise
+pub const DEFAULT_STORAGE_RETRIES: u32 = 3;
+/// Wait before the first retry; doubles after each attempt
+pub const DEFAULT_STORAGE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// stderr of a command that failed because the path doesn't exist, which
+/// retrying won't change (and [`locate_debian`] relies on to skip builds)
+const MISSING_OBJECT_MARKERS: &[&str] = &[
+    "matched no objects",
+    "No URLs matched",
+    "No such file or directory",
+];
+
 pub struct StorageClient {
     pub backend: StorageBackend,
+    /// Retries of a storage command that exits non-zero
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub retry_delay: Duration,
+    /// S3 client of an `s3` backend, connected on first use
+    s3: OnceCell<S3Store>,
 }
 
 impl StorageClient {
     pub fn new(backend: StorageBackend) -> Self {
-        Self { backend }
+        Self {
+            backend,
+            retries: DEFAULT_STORAGE_RETRIES,
+            retry_delay: DEFAULT_STORAGE_RETRY_DELAY,
+            s3: OnceCell::new(),
+        }
     }
 
+    /// The S3 client for `bucket` in `region`, built once per storage client
+    async fn s3(&self, region: &str, bucket: &str) -> &S3Store {
+        self.s3
+            .get_or_init(|| S3Store::connect(region, bucket))
+            .await
+    }
+
+    /// Glob of `artifact`'s stored debians for a build, laid out per
+    /// [`DebianPathTemplate::from_env`]
+    pub fn debian_glob(
+        &self,
+        build_id: &str,
+        codename: &str,
+        artifact: &str,
+    ) -> ManagerResult<String> {
+        Ok(DebianPathTemplate::from_env()?.package_glob(
+            &self.backend.root_path(),
+            build_id,
+            codename,
+            artifact,
+        ))
+    }
+
+    /// Directory of a build's stored debians for `codename`, ending in `/`
+    pub fn debian_directory(&self, build_id: &str, codename: &str) -> ManagerResult<String> {
+        Ok(
+            DebianPathTemplate::from_env()?.directory(
+                &self.backend.root_path(),
+                build_id,
+                codename,
+            ),
+        )
+    }
+
+    /// Run `cmd`, retrying non-zero exits with exponential backoff. Failures
+    /// to start the command, and missing paths, aren't retried.
     async fn run_command(&self, cmd: &mut Command) -> ManagerResult<String> {
-        let output = cmd.output().await?;
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        let context = format!(
+            "Failed to run {}",
+            cmd.as_std().get_program().to_string_lossy()
+        );
+        loop {
+            let output = cmd.timed_output(&context).await?;
+            if output.status.success() {
+                return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+            }
 
-        if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ManagerError::CommandFailed(stderr.to_string()));
-        }
+            let missing = MISSING_OBJECT_MARKERS
+                .iter()
+                .any(|marker| stderr.contains(marker));
+            if missing || attempt >= self.retries {
+                return Err(ManagerError::CommandFailed(stderr_excerpt(&stderr)));
+            }
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            attempt += 1;
+            textln!(
+                "    🔁 {} failed ({}), retry {}/{} in {:?}",
+                cmd.as_std().get_program().to_string_lossy(),
+                stderr_excerpt(&stderr),
+                attempt,
+                self.retries,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
     }
 }
 
@@ -82,9 +478,10 @@ impl StorageOperations for StorageClient {
     async fn list(&self, path: &str) -> ManagerResult<Vec<String>> {
         let output = match &self.backend {
             StorageBackend::Local => {
-                let mut cmd = Command::new("ls");
-                cmd.arg(path);
-                self.run_command(&mut cmd).await?
+                return Ok(expand_local_glob(path)?
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect());
             }
             StorageBackend::Gs => {
                 let mut cmd = Command::new("gsutil");
@@ -103,10 +500,13 @@ impl StorageOperations for StorageClient {
                     "-i",
                     key_path,
                     &format!("{}@{}", user, host),
-                    &format!("ls {}", shell_escape::escape(path.into())),
+                    &format!("ls {}", escape_remote_glob(path)),
                 ]);
                 self.run_command(&mut cmd).await?
             }
+            StorageBackend::S3 { region, bucket } => {
+                return self.s3(region, bucket).await.list(path).await;
+            }
         };
 
         Ok(output.lines().map(|s| s.to_string()).collect())
@@ -116,7 +516,7 @@ impl StorageOperations for StorageClient {
         let output = match &self.backend {
             StorageBackend::Local => {
                 let mut cmd = Command::new("md5sum");
-                cmd.arg(path);
+                cmd.args(expand_local_glob_non_empty(path)?);
                 let result = self.run_command(&mut cmd).await?;
                 result.split_whitespace().next().unwrap_or("").to_string()
             }
@@ -149,21 +549,120 @@ impl StorageOperations for StorageClient {
                     "-i",
                     key_path,
                     &format!("{}@{}", user, host),
-                    &format!("md5sum {}", shell_escape::escape(path.into())),
+                    &format!("md5sum {}", escape_remote_glob(path)),
                 ]);
                 let result = self.run_command(&mut cmd).await?;
                 result.split_whitespace().next().unwrap_or("").to_string()
             }
+            StorageBackend::S3 { region, bucket } => {
+                self.s3(region, bucket).await.md5(path).await?
+            }
         };
 
         Ok(output)
     }
 
+    async fn exists(&self, path: &str) -> ManagerResult<bool> {
+        let mut cmd = match &self.backend {
+            StorageBackend::Local => {
+                return Ok(if path.contains(GLOB_CHARS) {
+                    !expand_local_glob(path)?.is_empty()
+                } else {
+                    Path::new(path).exists()
+                });
+            }
+            // Quiet stat exits 1 without output for a missing object
+            StorageBackend::Gs => {
+                let mut cmd = Command::new("gsutil");
+                cmd.args(["-q", "stat", path]);
+                cmd
+            }
+            // `test -e` takes a single path; a glob matching several would
+            // be a usage error, so globs are checked with `ls -d`
+            StorageBackend::Hetzner {
+                user,
+                host,
+                key_path,
+            } => {
+                let check = if path.contains(GLOB_CHARS) {
+                    format!("ls -d {}", escape_remote_glob(path))
+                } else {
+                    format!("test -e {}", escape_remote_glob(path))
+                };
+                let mut cmd = Command::new("ssh");
+                cmd.args([
+                    "-p",
+                    "23",
+                    "-i",
+                    key_path,
+                    &format!("{}@{}", user, host),
+                    &check,
+                ]);
+                cmd
+            }
+            StorageBackend::S3 { region, bucket } => {
+                return self.s3(region, bucket).await.exists(path).await;
+            }
+        };
+
+        let output = cmd.timed_output("Failed to check storage path").await?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match output.status.code() {
+            Some(0) => Ok(true),
+            // ssh's own failures, or gsutil failing for another reason
+            Some(255) => Err(ManagerError::StorageError(stderr.trim().to_string())),
+            _ if matches!(self.backend, StorageBackend::Gs) && !stderr.trim().is_empty() => {
+                Err(ManagerError::StorageError(stderr.trim().to_string()))
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn sizes(&self, path: &str) -> ManagerResult<Vec<(String, u64)>> {
+        match &self.backend {
+            StorageBackend::Local => expand_local_glob(path)?
+                .into_iter()
+                .filter(|p| p.is_file())
+                .map(|p| {
+                    let size = std::fs::metadata(&p)?.len();
+                    Ok((p.to_string_lossy().to_string(), size))
+                })
+                .collect(),
+            StorageBackend::Gs => {
+                let mut cmd = Command::new("gsutil");
+                cmd.args(["ls", "-l", path]);
+                let result = self.run_command(&mut cmd).await?;
+                Ok(parse_gsutil_ls_sizes(&result))
+            }
+            StorageBackend::Hetzner {
+                user,
+                host,
+                key_path,
+            } => {
+                let mut cmd = Command::new("ssh");
+                cmd.args([
+                    "-p",
+                    "23",
+                    "-i",
+                    key_path,
+                    &format!("{}@{}", user, host),
+                    &format!("ls -l {}", escape_remote_glob(path)),
+                ]);
+                let result = self.run_command(&mut cmd).await?;
+                Ok(parse_ls_l_sizes(&result))
+            }
+            StorageBackend::S3 { region, bucket } => {
+                self.s3(region, bucket).await.sizes(path).await
+            }
+        }
+    }
+
     async fn download(&self, remote_path: &str, local_path: &str) -> ManagerResult<()> {
         match &self.backend {
             StorageBackend::Local => {
                 let mut cmd = Command::new("cp");
-                cmd.args([remote_path, local_path]);
+                cmd.args(expand_local_glob_non_empty(remote_path)?)
+                    .arg(local_path);
                 self.run_command(&mut cmd).await?;
             }
             StorageBackend::Gs => {
@@ -177,7 +676,7 @@ impl StorageOperations for StorageClient {
                 key_path,
             } => {
                 // First list files to get actual file names
-                let list_cmd = format!("ls {}", shell_escape::escape(remote_path.into()));
+                let list_cmd = format!("ls {}", escape_remote_glob(remote_path));
                 let mut ssh_cmd = Command::new("ssh");
                 ssh_cmd.args([
                     "-p",
@@ -196,8 +695,9 @@ impl StorageOperations for StorageClient {
                         let mut rsync_cmd = Command::new("rsync");
                         rsync_cmd.args([
                             "-avz",
+                            "--protect-args",
                             "--rsh",
-                            &format!("ssh -p 23 -i {}", key_path),
+                            &hetzner_rsh(key_path),
                             &format!("{}@{}:{}", user, host, file),
                             local_path,
                         ]);
@@ -205,6 +705,12 @@ impl StorageOperations for StorageClient {
                     }
                 }
             }
+            StorageBackend::S3 { region, bucket } => {
+                self.s3(region, bucket)
+                    .await
+                    .download(remote_path, local_path)
+                    .await?;
+            }
         }
 
         Ok(())
@@ -214,7 +720,8 @@ impl StorageOperations for StorageClient {
         match &self.backend {
             StorageBackend::Local => {
                 let mut cmd = Command::new("cp");
-                cmd.args([local_path, remote_path]);
+                cmd.args(expand_local_glob_non_empty(local_path)?)
+                    .arg(remote_path);
                 self.run_command(&mut cmd).await?;
             }
             StorageBackend::Gs => {
@@ -228,48 +735,134 @@ impl StorageOperations for StorageClient {
                 key_path,
             } => {
                 let mut cmd = Command::new("rsync");
-                cmd.args([
-                    "-avz",
-                    "-e",
-                    &format!("ssh -p 23 -i {}", key_path),
-                    local_path,
-                    &format!("{}@{}:{}", user, host, remote_path),
-                ]);
+                cmd.args(["-avz", "--protect-args", "-e", &hetzner_rsh(key_path)])
+                    .args(expand_local_glob_non_empty(local_path)?)
+                    .arg(format!("{}@{}:{}", user, host, remote_path));
                 self.run_command(&mut cmd).await?;
             }
+            StorageBackend::S3 { region, bucket } => {
+                self.s3(region, bucket)
+                    .await
+                    .upload(local_path, remote_path)
+                    .await?;
+            }
         }
 
         Ok(())
     }
 }
 
-pub async fn get_cached_debian_or_download(
+/// Path of the stored `<artifact_full_name>_<version>.deb` for a build, for
+/// backends whose objects can be read in place (see
+/// [`StorageBackend::is_locally_addressable`]).
+pub async fn find_stored_debian(
     storage: &StorageClient,
-    artifact: &str,
+    artifact_full_name: &str,
+    version: &str,
     codename: &str,
-    network: Option<&str>,
-    buildkite_build_id: &str,
-    cache_folder: &Path,
-) -> ManagerResult<()> {
-    use crate::artifacts::get_artifact_with_suffix;
+    build_ids: &[String],
+) -> ManagerResult<(PathBuf, String)> {
+    let file_name = format!("{}_{}.deb", artifact_full_name, version);
 
-    let artifact_full_name = get_artifact_with_suffix(artifact, network, None);
-    let remote_path = format!(
-        "{}/{}/debians/{}/{}_*",
-        storage.backend.root_path(),
-        buildkite_build_id,
-        codename,
-        artifact_full_name
-    );
+    let located = locate_debian(
+        storage,
+        build_ids,
+        |build_id| storage.debian_glob(build_id, codename, artifact_full_name),
+        |file| file.rsplit('/').next() == Some(file_name.as_str()),
+    )
+    .await?
+    .ok_or_else(|| {
+        ManagerError::ArtifactNotFound(format!(
+            "No debian package {} found for build {}",
+            file_name,
+            build_ids.join(", ")
+        ))
+    })?;
 
-    // Check if files exist
-    let files = storage.list(&remote_path).await?;
-    if files.is_empty() {
-        return Err(ManagerError::ArtifactNotFound(format!(
-            "No debian package found for {} (build: {})",
-            artifact_full_name, buildkite_build_id
-        )));
+    Ok((PathBuf::from(&located.files[0]), located.build_id))
+}
+
+/// Stored debians of one build, found by [`locate_debian`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedDebian {
+    pub build_id: String,
+    /// Glob the files were listed with
+    pub remote_path: String,
+    /// Listed files accepted by the caller, never empty
+    pub files: Vec<String>,
+}
+
+/// Search `build_ids` in order for stored files under `glob_for(build_id)`
+/// that `wanted` accepts, and return the first build with any. Builds with
+/// nothing under the glob are skipped without listing it.
+pub async fn locate_debian<S: StorageOperations + Sync + ?Sized>(
+    storage: &S,
+    build_ids: &[String],
+    glob_for: impl Fn(&str) -> ManagerResult<String>,
+    wanted: impl Fn(&str) -> bool,
+) -> ManagerResult<Option<LocatedDebian>> {
+    for build_id in build_ids {
+        let remote_path = glob_for(build_id)?;
+        if !storage.exists(&remote_path).await? {
+            continue;
+        }
+        let files: Vec<String> = storage
+            .list(&remote_path)
+            .await?
+            .into_iter()
+            .filter(|file| wanted(file))
+            .collect();
+        if !files.is_empty() {
+            return Ok(Some(LocatedDebian {
+                build_id: build_id.clone(),
+                remote_path,
+                files,
+            }));
+        }
     }
+    Ok(None)
+}
+
+/// Download `artifact_full_name` (the resolved debian package name, see
+/// `get_debian_package_name`) into the cache unless an identical copy is
+/// already there. Objects larger than `max_download_size` bytes are refused.
+/// `build_ids` are searched in order and the first one storing the package
+/// is used; its id is returned.
+pub async fn get_cached_debian_or_download(
+    storage: &StorageClient,
+    artifact_full_name: &str,
+    codename: &str,
+    build_ids: &[String],
+    cache_folder: &Path,
+    max_download_size: Option<u64>,
+) -> ManagerResult<String> {
+    let LocatedDebian {
+        build_id,
+        remote_path,
+        files,
+    } = locate_debian(
+        storage,
+        build_ids,
+        |build_id| storage.debian_glob(build_id, codename, artifact_full_name),
+        |_| true,
+    )
+    .await?
+    .ok_or_else(|| {
+        let searched: Vec<String> = build_ids
+            .iter()
+            .filter_map(|build_id| {
+                storage
+                    .debian_glob(build_id, codename, artifact_full_name)
+                    .ok()
+            })
+            .collect();
+        ManagerError::ArtifactNotFound(format!(
+            "No debian package found for {} (build: {}); nothing stored at {}",
+            artifact_full_name,
+            build_ids.join(", "),
+            searched.join(", ")
+        ))
+    })?;
 
     // Get target hash
     let target_hash = storage.md5(&remote_path).await?;
@@ -278,9 +871,10 @@ pub async fn get_cached_debian_or_download(
     let cache_dir = cache_folder.join(codename);
     tokio::fs::create_dir_all(&cache_dir).await?;
 
-    println!(
+    textln!(
         " 🗂️  Checking cache for {}/{} Debian package",
-        codename, artifact_full_name
+        codename,
+        artifact_full_name
     );
 
     // Check if already cached with correct hash
@@ -295,15 +889,15 @@ pub async fn get_cached_debian_or_download(
                 let mut cmd = Command::new("md5sum");
                 cmd.arg(&file_path);
 
-                if let Ok(output) = cmd.output().await {
+                if let Ok(output) = cmd.timed_output("Failed to run md5sum").await {
                     let hash = String::from_utf8_lossy(&output.stdout);
                     if let Some(local_hash) = hash.split_whitespace().next() {
                         if local_hash == target_hash {
-                            println!(
+                            textln!(
                                 "   🗂️  {} Debian package already cached. Skipping download.",
                                 artifact_full_name
                             );
-                            return Ok(());
+                            return Ok(build_id);
                         }
                     }
                 }
@@ -311,13 +905,525 @@ pub async fn get_cached_debian_or_download(
         }
     }
 
-    println!(
-        "   📂  {} Debian package is not cached. Downloading from {:?}.",
-        artifact_full_name, storage.backend
+    textln!(
+        "   📂  {} Debian package is not cached. Downloading from {:?} (build {}).",
+        artifact_full_name,
+        storage.backend,
+        build_id
     );
+    check_download_size(storage, &remote_path, max_download_size).await?;
+    check_download_space(storage, &remote_path, &cache_dir).await?;
     storage
         .download(&remote_path, cache_dir.to_str().unwrap())
         .await?;
 
-    Ok(())
+    let downloaded: u64 = files
+        .iter()
+        .filter_map(|file| file.rsplit('/').next())
+        .filter_map(|name| std::fs::metadata(cache_dir.join(name)).ok())
+        .map(|meta| meta.len())
+        .sum();
+    metrics::add(Counter::DownloadedBytes, downloaded);
+
+    Ok(build_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Backend that only reports fixed object sizes
+    struct SizedMock(Vec<(String, u64)>);
+
+    #[async_trait]
+    impl StorageOperations for SizedMock {
+        async fn list(&self, _path: &str) -> ManagerResult<Vec<String>> {
+            Ok(self.0.iter().map(|(name, _)| name.clone()).collect())
+        }
+        async fn md5(&self, _path: &str) -> ManagerResult<String> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+        async fn exists(&self, _path: &str) -> ManagerResult<bool> {
+            Ok(!self.0.is_empty())
+        }
+        async fn sizes(&self, _path: &str) -> ManagerResult<Vec<(String, u64)>> {
+            Ok(self.0.clone())
+        }
+        async fn download(&self, _remote_path: &str, _local_path: &str) -> ManagerResult<()> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+        async fn upload(&self, _local_path: &str, _remote_path: &str) -> ManagerResult<()> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+    }
+
+    /// Backend listing fixed files per glob; other globs fail like gsutil
+    struct ListingMock(Vec<(&'static str, Vec<&'static str>)>);
+
+    #[async_trait]
+    impl StorageOperations for ListingMock {
+        async fn list(&self, path: &str) -> ManagerResult<Vec<String>> {
+            self.0
+                .iter()
+                .find(|(glob, _)| *glob == path)
+                .map(|(_, files)| files.iter().map(|f| f.to_string()).collect())
+                .ok_or_else(|| ManagerError::StorageError(format!("No URLs matched: {}", path)))
+        }
+        async fn md5(&self, _path: &str) -> ManagerResult<String> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+        async fn exists(&self, path: &str) -> ManagerResult<bool> {
+            Ok(self
+                .0
+                .iter()
+                .any(|(glob, files)| *glob == path && !files.is_empty()))
+        }
+        async fn sizes(&self, _path: &str) -> ManagerResult<Vec<(String, u64)>> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+        async fn download(&self, _remote_path: &str, _local_path: &str) -> ManagerResult<()> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+        async fn upload(&self, _local_path: &str, _remote_path: &str) -> ManagerResult<()> {
+            Err(ManagerError::StorageError("unexpected call".into()))
+        }
+    }
+
+    fn glob_for(build_id: &str) -> ManagerResult<String> {
+        Ok(DebianPathTemplate::default().package_glob(
+            "gs://bucket",
+            build_id,
+            "noble",
+            "mina-devnet",
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_locate_debian_searches_builds_in_order() {
+        let storage = ListingMock(vec![
+            // The amd64 build has no arm64 package, the arm64 build has both
+            (
+                "gs://bucket/101/debians/noble/mina-devnet_*",
+                vec!["gs://bucket/101/debians/noble/mina-devnet_3.0.1_amd64.deb"],
+            ),
+            (
+                "gs://bucket/102/debians/noble/mina-devnet_*",
+                vec![
+                    "gs://bucket/102/debians/noble/mina-devnet_3.0.1_amd64.deb",
+                    "gs://bucket/102/debians/noble/mina-devnet_3.0.1_arm64.deb",
+                ],
+            ),
+        ]);
+        let builds: Vec<String> = ["100", "101", "102"].map(String::from).to_vec();
+
+        // 100 has nothing stored, so 101 supplies amd64
+        let amd64 = locate_debian(&storage, &builds, glob_for, |f| f.ends_with("_amd64.deb"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(amd64.build_id, "101");
+        assert_eq!(
+            amd64.remote_path,
+            "gs://bucket/101/debians/noble/mina-devnet_*"
+        );
+        assert_eq!(
+            amd64.files,
+            vec!["gs://bucket/101/debians/noble/mina-devnet_3.0.1_amd64.deb"]
+        );
+
+        let arm64 = locate_debian(&storage, &builds, glob_for, |f| f.ends_with("_arm64.deb"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(arm64.build_id, "102");
+        assert_eq!(arm64.files.len(), 1);
+
+        // Order matters: the first build with the package wins
+        let reversed: Vec<String> = ["102", "101"].map(String::from).to_vec();
+        let first = locate_debian(&storage, &reversed, glob_for, |_| true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.build_id, "102");
+    }
+
+    #[tokio::test]
+    async fn test_locate_debian_not_found_in_any_build() {
+        let storage = ListingMock(vec![(
+            "gs://bucket/101/debians/noble/mina-devnet_*",
+            vec!["gs://bucket/101/debians/noble/mina-devnet_3.0.1_amd64.deb"],
+        )]);
+        let builds: Vec<String> = ["100", "101"].map(String::from).to_vec();
+
+        let missing = locate_debian(&storage, &builds, glob_for, |f| f.ends_with("_riscv64.deb"))
+            .await
+            .unwrap();
+        assert_eq!(missing, None);
+        assert_eq!(
+            locate_debian(&storage, &[], glob_for, |_| true)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let cache = TempDir::new().unwrap();
+        let err = get_cached_debian_or_download(
+            &StorageClient::new(StorageBackend::Local),
+            "mina-no-such-package",
+            "noble",
+            &builds,
+            cache.path(),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ManagerError::ArtifactNotFound(_)));
+        assert!(err.to_string().contains("100, 101"), "{}", err);
+        assert!(
+            err.to_string()
+                .contains("/101/debians/noble/mina-no-such-package_*"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exists_local() {
+        let dir = TempDir::new().unwrap();
+        let deb = dir.path().join("mina-devnet_3.0.0.deb");
+        std::fs::write(&deb, "deb").unwrap();
+        let storage = StorageClient::new(StorageBackend::Local);
+        let at = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+
+        assert!(storage.exists(deb.to_str().unwrap()).await.unwrap());
+        assert!(storage.exists(&at("mina-devnet_*")).await.unwrap());
+        assert!(storage.exists(dir.path().to_str().unwrap()).await.unwrap());
+        assert!(!storage.exists(&at("mina-mainnet_*")).await.unwrap());
+        assert!(!storage.exists(&at("mina-devnet_3.0.1.deb")).await.unwrap());
+        assert!(!storage.exists(&at("missing/mina-devnet_*")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_download_size_rejects_oversized_object() {
+        let storage = SizedMock(vec![
+            (
+                "gs://bucket/debians/mina-devnet_1.0.0.deb".to_string(),
+                1_000,
+            ),
+            (
+                "gs://bucket/debians/mina-devnet_huge.deb".to_string(),
+                50_000_000_000,
+            ),
+        ]);
+
+        let err = check_download_size(&storage, "gs://bucket/debians/mina-devnet_*", Some(10_000))
+            .await
+            .unwrap_err();
+        match err {
+            ManagerError::ValidationError(msg) => assert!(msg.contains("mina-devnet_huge.deb")),
+            other => panic!("expected ValidationError, got {:?}", other),
+        }
+
+        // No limit by default, and objects at the limit are fine
+        assert!(check_download_size(&storage, "gs://bucket/debians/*", None)
+            .await
+            .is_ok());
+        assert!(
+            check_download_size(&storage, "gs://bucket/debians/*", Some(50_000_000_000))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_download_space_counts_reversion() {
+        let cache = TempDir::new().unwrap();
+        let small = SizedMock(vec![(
+            "gs://bucket/debians/mina-devnet_1.deb".to_string(),
+            1024,
+        )]);
+        assert!(
+            check_download_space(&small, "gs://bucket/debians/mina-devnet_*", cache.path())
+                .await
+                .is_ok()
+        );
+
+        // Fits on its own but not three times over
+        let available = fs2::available_space(cache.path()).unwrap();
+        let large = SizedMock(vec![(
+            "gs://bucket/debians/mina-archive_1.deb".to_string(),
+            available / 2,
+        )]);
+        let err = check_download_space(&large, "gs://bucket/debians/mina-archive_*", cache.path())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ManagerError::ValidationError(_)));
+        assert!(err
+            .to_string()
+            .contains("to download and reversion gs://bucket/debians/mina-archive_*"));
+    }
+
+    #[test]
+    fn test_parse_size_listings() {
+        let gsutil = "\
+      1024  2024-05-01T10:00:00Z  gs://bucket/debians/bullseye/mina-devnet_1.0.0.deb
+ 123456789  2024-05-02T10:00:00Z  gs://bucket/debians/bullseye/mina-devnet_1.0.1.deb
+TOTAL: 2 objects, 123457813 bytes (117.74 MiB)
+";
+        assert_eq!(
+            parse_gsutil_ls_sizes(gsutil),
+            vec![
+                (
+                    "gs://bucket/debians/bullseye/mina-devnet_1.0.0.deb".to_string(),
+                    1024
+                ),
+                (
+                    "gs://bucket/debians/bullseye/mina-devnet_1.0.1.deb".to_string(),
+                    123456789
+                ),
+            ]
+        );
+
+        let ls = "\
+-rw-r--r-- 1 u434410 u434410 2048 May  1 10:00 /data/my debs/mina-devnet_1.0.0.deb
+drwxr-xr-x 2 u434410 u434410 4096 May  1 10:00 /data/subdir
+";
+        assert_eq!(
+            parse_ls_l_sizes(ls),
+            vec![("/data/my debs/mina-devnet_1.0.0.deb".to_string(), 2048)]
+        );
+    }
+
+    /// Temp dir containing `dir with spaces/` with two debs and a changelog.
+    fn spaced_tree() -> (TempDir, PathBuf) {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("dir with spaces");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("mina-devnet_1.0.0.deb"), "devnet").unwrap();
+        std::fs::write(dir.join("mina-devnet_1.0.1 (rebuild).deb"), "rebuild").unwrap();
+        std::fs::write(dir.join("changelog"), "log").unwrap();
+        (tmp, dir)
+    }
+
+    #[test]
+    fn test_debian_path_template_expansion() {
+        let default = DebianPathTemplate::default();
+        assert_eq!(
+            default.package_glob("/var/storagebox/", "123", "bullseye", "mina-devnet"),
+            "/var/storagebox/123/debians/bullseye/mina-devnet_*"
+        );
+        assert_eq!(
+            default.directory("gs://buildkite_k8s/coda/shared", "123", "focal"),
+            "gs://buildkite_k8s/coda/shared/123/debians/focal/"
+        );
+
+        let mirror =
+            DebianPathTemplate::parse("{root}/mina/{codename}/builds/{build_id}/{artifact}")
+                .unwrap();
+        assert_eq!(
+            mirror.package_glob("/srv", "123", "jammy", "mina-logproc"),
+            "/srv/mina/jammy/builds/123/mina-logproc_*"
+        );
+        assert_eq!(
+            mirror.directory("/srv", "123", "jammy"),
+            "/srv/mina/jammy/builds/123/"
+        );
+
+        let rootless =
+            DebianPathTemplate::parse("/mnt/mirror/{build_id}/{codename}/{artifact}").unwrap();
+        assert_eq!(
+            rootless.package_glob("/var/storagebox/", "9", "focal", "mina-archive"),
+            "/mnt/mirror/9/focal/mina-archive_*"
+        );
+    }
+
+    #[test]
+    fn test_debian_path_template_rejects_invalid() {
+        for template in [
+            "{root}/debians/{codename}/{artifact}",
+            "{root}/{build_id}/debians/{artifact}",
+            "{root}/{build_id}/debians/{codename}",
+            "{root}/{build_id}/{artifact}/{codename}",
+            "{root}/{build_id}/{codename}/pkg-{artifact}",
+            "{root}/{build}/{build_id}/{codename}/{artifact}",
+            "{root}/{build_id}/{codename/{artifact}",
+        ] {
+            assert!(
+                matches!(
+                    DebianPathTemplate::parse(template),
+                    Err(ManagerError::ValidationError(_))
+                ),
+                "{} should be rejected",
+                template
+            );
+        }
+        assert!(DebianPathTemplate::parse(DEFAULT_DEBIAN_PATH_TEMPLATE).is_ok());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("mina-devnet_*", "mina-devnet_1.0.0.deb"));
+        assert!(glob_matches("*.deb", "a b.deb"));
+        assert!(glob_matches("mina-?evnet_*", "mina-devnet_1.0.0.deb"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("mina-devnet_*", "mina-mainnet_1.0.0.deb"));
+        assert!(!glob_matches("*.deb", "changelog"));
+    }
+
+    #[test]
+    fn test_expand_local_glob_with_spaces() {
+        let (_tmp, dir) = spaced_tree();
+        let pattern = format!("{}/mina-devnet_*", dir.display());
+        assert_eq!(
+            expand_local_glob(&pattern).unwrap(),
+            vec![
+                dir.join("mina-devnet_1.0.0.deb"),
+                dir.join("mina-devnet_1.0.1 (rebuild).deb"),
+            ]
+        );
+
+        // Exact path and directory listing, no wildcard involved
+        let exact = dir.join("mina-devnet_1.0.1 (rebuild).deb");
+        assert_eq!(
+            expand_local_glob(&exact.to_string_lossy()).unwrap(),
+            vec![exact.clone()]
+        );
+        assert_eq!(expand_local_glob(&dir.to_string_lossy()).unwrap().len(), 3);
+
+        // Missing directory or no match is empty rather than an error
+        let missing = format!("{}/nope here/*", dir.display());
+        assert!(expand_local_glob(&missing).unwrap().is_empty());
+        let unmatched = format!("{}/mina-mainnet_*", dir.display());
+        assert!(expand_local_glob(&unmatched).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_escape_remote_glob() {
+        assert_eq!(escape_remote_glob("/data/debs/mina_*"), "/data/debs/mina_*");
+        assert_eq!(
+            escape_remote_glob("/data/my debs/mina_*.deb"),
+            "'/data/my debs/mina_'*.deb"
+        );
+        assert_eq!(escape_remote_glob("/data/it's"), "'/data/it'\\''s'");
+        assert_eq!(hetzner_rsh("/keys/my key"), "ssh -p 23 -i '/keys/my key'");
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_with_spaces() {
+        let (tmp, dir) = spaced_tree();
+        let storage = StorageClient::new(StorageBackend::Local);
+        let pattern = format!("{}/mina-devnet_1.0.1*", dir.display());
+
+        let listed = storage.list(&pattern).await.unwrap();
+        assert_eq!(
+            listed,
+            vec![dir
+                .join("mina-devnet_1.0.1 (rebuild).deb")
+                .to_string_lossy()
+                .to_string()]
+        );
+
+        assert_eq!(
+            storage.md5(&pattern).await.unwrap(),
+            "9e67f9a60581ce784117ff2047627d2f"
+        );
+
+        let target = tmp.path().join("cache dir");
+        std::fs::create_dir(&target).unwrap();
+        storage
+            .download(&pattern, &target.to_string_lossy())
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.join("mina-devnet_1.0.1 (rebuild).deb")).unwrap(),
+            "rebuild"
+        );
+
+        let uploaded = tmp.path().join("up load.deb");
+        storage
+            .upload(
+                &target
+                    .join("mina-devnet_1.0.1 (rebuild).deb")
+                    .to_string_lossy(),
+                &uploaded.to_string_lossy(),
+            )
+            .await
+            .unwrap();
+        assert!(uploaded.is_file());
+
+        // Uploads take a glob, like persist's `<dir>/*<artifact>*`
+        let uploads = tmp.path().join("up loads");
+        std::fs::create_dir(&uploads).unwrap();
+        storage
+            .upload(
+                &format!("{}/mina-devnet_*", target.display()),
+                &uploads.to_string_lossy(),
+            )
+            .await
+            .unwrap();
+        assert!(uploads.join("mina-devnet_1.0.1 (rebuild).deb").is_file());
+        assert!(storage
+            .upload(
+                &format!("{}/mina-mainnet_*", target.display()),
+                &uploads.to_string_lossy(),
+            )
+            .await
+            .is_err());
+
+        assert_eq!(
+            storage.sizes(&pattern).await.unwrap(),
+            vec![(listed[0].clone(), "rebuild".len() as u64)]
+        );
+
+        let missing = format!("{}/mina-mainnet_*", dir.display());
+        assert!(storage
+            .download(&missing, &target.to_string_lossy())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_command_retries_with_backoff() {
+        let tmp = TempDir::new().unwrap();
+        let counter = tmp.path().join("attempts");
+        // Fails on the first two runs, succeeds on the third
+        let script = format!(
+            "n=$(cat '{0}' 2>/dev/null || echo 0); n=$((n+1)); echo $n > '{0}'; \
+             [ $n -ge 3 ] && echo ok || {{ echo transient >&2; exit 1; }}",
+            counter.display()
+        );
+        let mut storage = StorageClient::new(StorageBackend::Local);
+        storage.retry_delay = Duration::from_millis(10);
+
+        let started = std::time::Instant::now();
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &script]);
+        assert_eq!(storage.run_command(&mut cmd).await.unwrap(), "ok");
+        assert_eq!(std::fs::read_to_string(&counter).unwrap().trim(), "3");
+        // 10ms then 20ms of backoff
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // Out of retries: the last failure is returned
+        std::fs::remove_file(&counter).unwrap();
+        storage.retries = 1;
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &script]);
+        let err = storage.run_command(&mut cmd).await.unwrap_err();
+        assert!(err.to_string().contains("transient"), "{}", err);
+        assert_eq!(std::fs::read_to_string(&counter).unwrap().trim(), "2");
+
+        // Missing paths and commands that can't start fail straight away
+        storage.retry_delay = Duration::from_secs(5);
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo 'ls: x: No such file or directory' >&2; exit 2"]);
+        let started = std::time::Instant::now();
+        assert!(storage.run_command(&mut cmd).await.is_err());
+        assert!(started.elapsed() < storage.retry_delay);
+        let mut cmd = Command::new("release-manager-no-such-command");
+        assert!(matches!(
+            storage.run_command(&mut cmd).await,
+            Err(ManagerError::CommandFailed(msg)) if msg.contains("release-manager-no-such-command")
+        ));
+    }
 }
@@ -0,0 +1,194 @@
+//! `--require-tool-version name=version`: pin the versions of the external
+//! tools a release runs, since reversioning differs across `dpkg-deb`
+//! releases and deb-s3 across gem versions.
+
+use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::trace::TracedCommand;
+use crate::utils::stderr_excerpt;
+use regex::Regex;
+use std::sync::OnceLock;
+use tokio::process::Command;
+
+/// One `name=version` requirement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolRequirement {
+    pub tool: String,
+    pub version: String,
+}
+
+impl ToolRequirement {
+    /// Parse `name=version`, e.g. `dpkg-deb=1.20.12`
+    pub fn parse(spec: &str) -> ManagerResult<Self> {
+        match spec.split_once('=') {
+            Some((tool, version)) if !tool.trim().is_empty() && !version.trim().is_empty() => {
+                Ok(Self {
+                    tool: tool.trim().to_string(),
+                    version: version.trim().trim_start_matches('v').to_string(),
+                })
+            }
+            _ => Err(ManagerError::ValidationError(format!(
+                "Invalid --require-tool-version '{}', expected name=version",
+                spec
+            ))),
+        }
+    }
+}
+
+/// First version number in a tool's `--version` output, without a leading
+/// `v`: `Docker version 24.0.7, build afdd53b` gives `24.0.7`
+pub fn extract_version(output: &str) -> Option<String> {
+    static VERSION: OnceLock<Regex> = OnceLock::new();
+    let re = VERSION.get_or_init(|| {
+        Regex::new(r"(?:^|[^\w.])v?(\d+(?:\.\d+)+(?:[-+~][0-9A-Za-z.+~-]*)?)").unwrap()
+    });
+    re.captures(output).map(|c| c[1].to_string())
+}
+
+/// Whether `installed` satisfies `required`: the same version, or one within
+/// the required prefix (`1.20` accepts `1.20.12`, not `1.2.0` or `1.200`)
+pub fn version_matches(required: &str, installed: &str) -> bool {
+    installed == required
+        || installed
+            .strip_prefix(required)
+            .is_some_and(|rest| rest.starts_with(['.', '-', '+', '~']))
+}
+
+/// Arguments making `tool` print its version: `--version`, except for tools
+/// such as regctl that only have a `version` subcommand
+fn version_args(tool: &str) -> &'static [&'static str] {
+    match tool.rsplit('/').next().unwrap_or(tool) {
+        "regctl" => &["version"],
+        _ => &["--version"],
+    }
+}
+
+/// Run `<tool> --version` and fail unless it succeeds and reports
+/// `requirement.version`
+pub async fn check_tool_version(requirement: &ToolRequirement) -> ManagerResult<()> {
+    let args = version_args(&requirement.tool);
+    let invocation = format!("{} {}", requirement.tool, args.join(" "));
+    let output = Command::new(&requirement.tool)
+        .args(args)
+        .timed_output(&format!("Failed to run {}", invocation))
+        .await?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(ManagerError::CommandFailed(format!(
+            "`{}` exited with {}: {}",
+            invocation,
+            output.status,
+            stderr_excerpt(&stderr)
+        )));
+    }
+    let reported = format!("{}{}", String::from_utf8_lossy(&output.stdout), stderr);
+
+    let installed = extract_version(&reported).ok_or_else(|| {
+        ManagerError::ValidationError(format!(
+            "Could not find a version in `{}` output: {}",
+            invocation,
+            reported.trim()
+        ))
+    })?;
+    if !version_matches(&requirement.version, &installed) {
+        return Err(ManagerError::ValidationError(format!(
+            "{} {} is installed but {} is required",
+            requirement.tool, installed, requirement.version
+        )));
+    }
+
+    textln!(
+        " 📌 {} {} matches required {}",
+        requirement.tool,
+        installed,
+        requirement.version
+    );
+    Ok(())
+}
+
+/// Check every `--require-tool-version` spec
+pub async fn check_required_versions(specs: &[String]) -> ManagerResult<()> {
+    let requirements = specs
+        .iter()
+        .map(|spec| ToolRequirement::parse(spec))
+        .collect::<ManagerResult<Vec<_>>>()?;
+    for requirement in &requirements {
+        check_tool_version(requirement).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_version_parsing_and_matching() {
+        assert_eq!(
+            ToolRequirement::parse("deb-s3=v0.11.4").unwrap(),
+            ToolRequirement {
+                tool: "deb-s3".to_string(),
+                version: "0.11.4".to_string(),
+            }
+        );
+        for spec in ["dpkg-deb", "=1.0", "dpkg-deb=", "dpkg-deb= "] {
+            assert!(ToolRequirement::parse(spec).is_err(), "{}", spec);
+        }
+
+        for (output, version) in [
+            (
+                "Debian 'dpkg-deb' package management program version 1.20.12 (amd64).\nThis is free software;",
+                "1.20.12",
+            ),
+            ("deb-s3 0.11.4\n", "0.11.4"),
+            ("Docker version 24.0.7, build afdd53b", "24.0.7"),
+            ("podman version 4.3.1", "4.3.1"),
+            ("gsutil version: 5.27", "5.27"),
+            ("skopeo version 1.13.3 commit: 6f5d0c8", "1.13.3"),
+            ("VCSTag:     v0.5.1\nVCSRef: 1a2b3c", "0.5.1"),
+            ("gpg (GnuPG) 2.2.27\nlibgcrypt 1.8.8", "2.2.27"),
+            ("oras version 1.1.0+Homebrew", "1.1.0+Homebrew"),
+            ("aws-cli/2.15.0 Python/3.11.6 Linux/6.1 exe/x86_64", "2.15.0"),
+        ] {
+            assert_eq!(extract_version(output).as_deref(), Some(version), "{}", output);
+        }
+        assert_eq!(extract_version("usage: tool [options]"), None);
+        // A build hash isn't a version
+        assert_eq!(extract_version("build afdd53b"), None);
+
+        assert!(version_matches("1.20.12", "1.20.12"));
+        assert!(version_matches("1.20", "1.20.12"));
+        assert!(version_matches("1.1.0", "1.1.0+Homebrew"));
+        assert!(!version_matches("1.20", "1.200.1"));
+        assert!(!version_matches("1.2", "1.20.12"));
+        assert!(!version_matches("1.20.12", "1.20"));
+    }
+
+    #[test]
+    fn test_version_args() {
+        assert_eq!(version_args("regctl"), ["version"]);
+        assert_eq!(version_args("/usr/local/bin/regctl"), ["version"]);
+        assert_eq!(version_args("deb-s3"), ["--version"]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_version_command_is_an_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tool = tmp.path().join("broken-tool");
+        std::fs::write(
+            &tool,
+            "#!/bin/sh\necho 'broken-tool 1.2.3'\necho 'missing libfoo' >&2\nexit 3\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&tool, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let requirement = ToolRequirement {
+            tool: tool.to_string_lossy().to_string(),
+            version: "1.2.3".to_string(),
+        };
+        let err = check_tool_version(&requirement).await.unwrap_err();
+        assert!(matches!(err, ManagerError::CommandFailed(_)));
+        assert!(err.to_string().contains("missing libfoo"), "{}", err);
+    }
+}
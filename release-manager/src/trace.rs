@@ -0,0 +1,388 @@
+//! `--trace <path>`: append one JSON line per external command (program,
+//! redacted args, exit code, duration) for post-mortems of failed releases.
+//!
+//! Every subprocess goes through [`TracedCommand`] / [`TracedStdCommand`]
+//! instead of calling `output()`/`status()` directly. Without `--trace`
+//! they're plain pass-throughs. Their `timed_*` variants also bound the
+//! run by [`command_timeout`], and `checked_output` turns a non-zero exit
+//! into an error carrying a [`failure_excerpt`] of the command's output.
+
+use crate::command_timeout::command_timeout;
+use crate::errors::{ManagerError, ManagerResult};
+use crate::utils::{stderr_excerpt, within};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::process::{ExitStatus, Output};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Flags whose value is a credential and must never reach the trace file
+const SECRET_FLAGS: &[&str] = &[
+    "--sign",
+    "--access-key-id",
+    "--secret-access-key",
+    "--password",
+    "--passphrase",
+    "--token",
+];
+
+const REDACTED: &str = "<redacted>";
+
+static TRACE_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Start appending records to `path`
+pub fn configure(path: &str) -> ManagerResult<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| {
+            ManagerError::IoError(io::Error::new(
+                e.kind(),
+                format!("Could not open trace file {}: {}", path, e),
+            ))
+        })?;
+    let _ = TRACE_FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// One external command run, as written to the trace file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceRecord {
+    /// RFC 3339 time the command was started
+    pub started_at: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// `None` when the process was killed by a signal, couldn't be spawned
+    /// or was abandoned (e.g. by a timeout)
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Why there's no exit code, if there isn't one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Full stderr of a command that exited unsuccessfully, which errors
+    /// only quote the end of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+impl TraceRecord {
+    pub fn to_json_line(&self) -> String {
+        let mut line = serde_json::to_string(self).expect("trace record serializes");
+        line.push('\n');
+        line
+    }
+}
+
+/// Replace the values of [`SECRET_FLAGS`], both `--flag value` and
+/// `--flag=value`, including inside shell scripts passed as one argument
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut secret_next = false;
+    for arg in args {
+        if secret_next {
+            redacted.push(REDACTED.to_string());
+            secret_next = false;
+        } else if arg.contains(char::is_whitespace) {
+            let words: Vec<String> = arg.split(' ').map(str::to_string).collect();
+            redacted.push(redact_args(&words).join(" "));
+        } else {
+            let (value, takes_next) = redact_word(arg);
+            redacted.push(value);
+            secret_next = takes_next;
+        }
+    }
+    redacted
+}
+
+/// A single word with its inline secret redacted, and whether the next word
+/// is a secret flag's value
+fn redact_word(word: &str) -> (String, bool) {
+    if SECRET_FLAGS.contains(&word) {
+        return (word.to_string(), true);
+    }
+    match word.split_once('=') {
+        Some((flag, _)) if SECRET_FLAGS.contains(&flag) => {
+            (format!("{}={}", flag, REDACTED), false)
+        }
+        _ => (word.to_string(), false),
+    }
+}
+
+/// A command in flight. The record is written on drop, so commands whose
+/// future is dropped before completing still show up.
+struct Invocation {
+    started: Instant,
+    record: TraceRecord,
+}
+
+impl Invocation {
+    fn start(cmd: &std::process::Command) -> Option<Self> {
+        TRACE_FILE.get()?;
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        Some(Self {
+            started: Instant::now(),
+            record: TraceRecord {
+                started_at: chrono::Utc::now().to_rfc3339(),
+                program: cmd.get_program().to_string_lossy().into_owned(),
+                args: redact_args(&args),
+                exit_code: None,
+                duration_ms: 0,
+                error: Some("abandoned before exit".to_string()),
+                stderr: None,
+            },
+        })
+    }
+
+    fn finish(&mut self, result: Result<ExitStatus, &io::Error>) {
+        self.record.error = match result {
+            Ok(status) => {
+                self.record.exit_code = status.code();
+                status
+                    .code()
+                    .is_none()
+                    .then(|| format!("terminated: {}", status))
+            }
+            Err(e) => Some(format!("failed to run: {}", e)),
+        };
+    }
+
+    fn finish_output(&mut self, result: Result<&Output, &io::Error>) {
+        self.finish(result.map(|output| output.status));
+        if let Ok(output) = result {
+            if !output.status.success() && !output.stderr.is_empty() {
+                self.record.stderr = Some(String::from_utf8_lossy(&output.stderr).into_owned());
+            }
+        }
+    }
+}
+
+impl Drop for Invocation {
+    fn drop(&mut self) {
+        self.record.duration_ms = self.started.elapsed().as_millis() as u64;
+        if let Some(file) = TRACE_FILE.get() {
+            let line = self.record.to_json_line();
+            if let Err(e) = file.lock().unwrap().write_all(line.as_bytes()) {
+                eprintln!(" ⚠️  Could not write trace record: {}", e);
+            }
+        }
+    }
+}
+
+/// `program subcommand` and the time limit of `cmd`
+fn time_limit(cmd: &std::process::Command) -> (String, Option<Duration>) {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let args: Vec<String> = cmd
+        .get_args()
+        .take(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let limit = command_timeout(&program, &args);
+    let what = std::iter::once(program).chain(args).collect::<Vec<_>>();
+    (what.join(" "), limit)
+}
+
+/// Traced counterpart of `tokio::process::Command::output`
+#[async_trait]
+pub trait TracedCommand {
+    async fn traced_output(&mut self) -> io::Result<Output>;
+
+    /// [`traced_output`](Self::traced_output), killing the command with
+    /// [`ManagerError::Timeout`] once it runs over its [`command_timeout`].
+    /// A command that can't be started fails as `<context>: <error>`.
+    async fn timed_output(&mut self, context: &str) -> ManagerResult<Output>;
+
+    /// [`timed_output`](Self::timed_output) for a command expected to
+    /// succeed: a non-zero exit fails as `<failure>: <excerpt>`, see
+    /// [`failure_excerpt`]
+    async fn checked_output(&mut self, context: &str, failure: &str) -> ManagerResult<Output>;
+}
+
+/// A failed command's output as it goes into an error message: the last
+/// `--stderr-lines` lines of stderr, and of stdout when it printed anything
+pub fn failure_excerpt(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match (stdout.trim().is_empty(), stderr.trim().is_empty()) {
+        (true, _) => stderr_excerpt(&stderr),
+        (false, true) => stderr_excerpt(&stdout),
+        (false, false) => format!(
+            "Stdout: {}, Stderr: {}",
+            stderr_excerpt(&stdout),
+            stderr_excerpt(&stderr)
+        ),
+    }
+}
+
+#[async_trait]
+impl TracedCommand for tokio::process::Command {
+    async fn traced_output(&mut self) -> io::Result<Output> {
+        let mut invocation = Invocation::start(self.as_std());
+        let result = self.output().await;
+        if let Some(invocation) = &mut invocation {
+            invocation.finish_output(result.as_ref());
+        }
+        result
+    }
+
+    async fn timed_output(&mut self, context: &str) -> ManagerResult<Output> {
+        let (what, limit) = time_limit(self.as_std());
+        self.kill_on_drop(true);
+        match limit {
+            Some(limit) => within(&what, limit, self.traced_output()).await?,
+            None => self.traced_output().await,
+        }
+        .map_err(|e| ManagerError::CommandFailed(format!("{}: {}", context, e)))
+    }
+
+    async fn checked_output(&mut self, context: &str, failure: &str) -> ManagerResult<Output> {
+        let output = self.timed_output(context).await?;
+        if !output.status.success() {
+            return Err(ManagerError::CommandFailed(format!(
+                "{}: {}",
+                failure,
+                failure_excerpt(&output)
+            )));
+        }
+        Ok(output)
+    }
+}
+
+/// Traced counterparts of `std::process::Command::output`/`status`
+pub trait TracedStdCommand {
+    fn traced_output(&mut self) -> io::Result<Output>;
+    fn traced_status(&mut self) -> io::Result<ExitStatus>;
+}
+
+impl TracedStdCommand for std::process::Command {
+    fn traced_output(&mut self) -> io::Result<Output> {
+        let mut invocation = Invocation::start(self);
+        let result = self.output();
+        if let Some(invocation) = &mut invocation {
+            invocation.finish_output(result.as_ref());
+        }
+        result
+    }
+
+    fn traced_status(&mut self) -> io::Result<ExitStatus> {
+        let mut invocation = Invocation::start(self);
+        let result = self.status();
+        if let Some(invocation) = &mut invocation {
+            invocation.finish(result.as_ref().copied());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_trace_record_format() {
+        let record = TraceRecord {
+            started_at: "2024-01-01T00:00:00+00:00".to_string(),
+            program: "deb-s3".to_string(),
+            args: redact_args(&strings(&[
+                "upload",
+                "--bucket",
+                "packages.o1test.net",
+                "--sign",
+                "ABCDEF0123456789",
+                "--secret-access-key=hunter2",
+                "mina-devnet_3.0.1_amd64.deb",
+            ])),
+            exit_code: Some(1),
+            duration_ms: 1520,
+            error: None,
+            stderr: None,
+        };
+        assert_eq!(
+            record.to_json_line(),
+            "{\"started_at\":\"2024-01-01T00:00:00+00:00\",\"program\":\"deb-s3\",\
+             \"args\":[\"upload\",\"--bucket\",\"packages.o1test.net\",\"--sign\",\"<redacted>\",\
+             \"--secret-access-key=<redacted>\",\"mina-devnet_3.0.1_amd64.deb\"],\
+             \"exit_code\":1,\"duration_ms\":1520}\n"
+        );
+
+        let killed = TraceRecord {
+            exit_code: None,
+            error: Some("terminated: signal: 9 (SIGKILL)".to_string()),
+            ..record
+        };
+        let parsed: serde_json::Value = serde_json::from_str(&killed.to_json_line()).unwrap();
+        assert_eq!(parsed["exit_code"], serde_json::Value::Null);
+        assert_eq!(parsed["error"], "terminated: signal: 9 (SIGKILL)");
+
+        let failed = TraceRecord {
+            exit_code: Some(1),
+            stderr: Some("Error: bucket not found\n".to_string()),
+            ..killed
+        };
+        let parsed: serde_json::Value = serde_json::from_str(&failed.to_json_line()).unwrap();
+        assert_eq!(parsed["stderr"], "Error: bucket not found\n");
+    }
+
+    #[test]
+    fn test_redact_args_inside_scripts() {
+        assert_eq!(
+            redact_args(&strings(&[
+                "-c",
+                "deb-s3 upload --sign KEY --access-key-id=AKIA x.deb"
+            ])),
+            strings(&[
+                "-c",
+                "deb-s3 upload --sign <redacted> --access-key-id=<redacted> x.deb"
+            ])
+        );
+        // Nothing to hide
+        let plain = strings(&["pull", "gcr.io/o1labs-192920/mina-daemon:3.0.1"]);
+        assert_eq!(redact_args(&plain), plain);
+    }
+
+    #[test]
+    fn test_failure_excerpt_cuts_both_streams() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let output = |stdout: String, stderr: &str| Output {
+            status: ExitStatus::from_raw(256),
+            stdout: stdout.into_bytes(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+        let apt_log: String = (1..=200)
+            .map(|i| format!("Get:{} http://deb.debian.org/debian bullseye/main\n", i))
+            .collect();
+
+        let excerpt = failure_excerpt(&output(apt_log.clone(), "E: Unable to locate package"));
+        assert!(
+            excerpt.starts_with("Stdout: [... 150 earlier lines truncated"),
+            "{}",
+            excerpt
+        );
+        assert!(!excerpt.contains("Get:150 "), "{}", excerpt);
+        assert!(excerpt.contains("Get:200 "), "{}", excerpt);
+        assert!(
+            excerpt.ends_with(", Stderr: E: Unable to locate package"),
+            "{}",
+            excerpt
+        );
+
+        // Only the stream that says anything
+        assert_eq!(
+            failure_excerpt(&output(String::new(), "manifest unknown\n")),
+            "manifest unknown"
+        );
+        assert!(
+            failure_excerpt(&output(apt_log, "")).starts_with("[... 150 earlier lines truncated")
+        );
+    }
+}
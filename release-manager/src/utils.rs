@@ -1,10 +1,16 @@
 use crate::errors::{ManagerError, ManagerResult};
+use crate::output::textln;
+use crate::trace::{failure_excerpt, TracedCommand};
 use colored::*;
+use std::future::Future;
+use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
 use tokio::process::Command;
 
 pub async fn check_app(app: &str) -> ManagerResult<()> {
-    let output = Command::new("which").arg(app).output().await?;
+    let output = Command::new("which").arg(app).traced_output().await?;
 
     if !output.status.success() {
         eprintln!(
@@ -20,6 +26,52 @@ pub async fn check_app(app: &str) -> ManagerResult<()> {
     Ok(())
 }
 
+/// Await `work`, giving up with [`ManagerError::Timeout`] after `limit`
+pub async fn within<F: Future>(
+    operation: &str,
+    limit: Duration,
+    work: F,
+) -> ManagerResult<F::Output> {
+    tokio::time::timeout(limit, work)
+        .await
+        .map_err(|_| ManagerError::Timeout {
+            operation: operation.to_string(),
+            seconds: limit.as_secs(),
+        })
+}
+
+/// Lines of a failed command's stderr kept in its error unless
+/// `--stderr-lines` is set
+pub const DEFAULT_STDERR_LINES: usize = 50;
+
+static STDERR_LINES: OnceLock<usize> = OnceLock::new();
+
+/// Set the process-wide stderr excerpt length; only the first call takes effect
+pub fn configure_stderr_lines(lines: usize) {
+    let _ = STDERR_LINES.set(lines);
+}
+
+/// The last `limit` lines of `output`, noting how many were cut
+pub fn tail_lines(output: &str, limit: usize) -> String {
+    let output = output.trim_end();
+    let lines: Vec<&str> = output.lines().collect();
+    if lines.len() <= limit {
+        return output.to_string();
+    }
+    let cut = lines.len() - limit;
+    format!(
+        "[... {} earlier lines truncated; run with --debug or --trace for the full output]\n{}",
+        cut,
+        lines[cut..].join("\n")
+    )
+}
+
+/// A failed command's stderr as it goes into an error message: only its
+/// last `--stderr-lines` lines, so huge outputs don't flood CI logs
+pub fn stderr_excerpt(stderr: &str) -> String {
+    tail_lines(stderr, *STDERR_LINES.get().unwrap_or(&DEFAULT_STDERR_LINES))
+}
+
 pub fn get_debian_cache_folder() -> PathBuf {
     if let Ok(cache_folder) = std::env::var("DEBIAN_CACHE_FOLDER") {
         PathBuf::from(cache_folder)
@@ -30,11 +82,10 @@ pub fn get_debian_cache_folder() -> PathBuf {
 }
 
 pub async fn run_command_with_prefix(prefix: &str, mut cmd: Command) -> ManagerResult<String> {
-    let output = cmd.output().await?;
+    let output = cmd.timed_output("Failed to run command").await?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(ManagerError::CommandFailed(stderr.to_string()));
+        return Err(ManagerError::CommandFailed(failure_excerpt(&output)));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -46,7 +97,7 @@ pub async fn run_command_with_prefix(prefix: &str, mut cmd: Command) -> ManagerR
         .collect::<Vec<_>>()
         .join("\n");
 
-    println!("{}", prefixed_output);
+    textln!("{}", prefixed_output);
 
     Ok(stdout.to_string())
 }
@@ -64,14 +115,17 @@ pub async fn run_command_with_debug(mut cmd: Command, debug: bool) -> ManagerRes
         } else {
             format!("{} {}", program, args.join(" "))
         };
-        println!("{} 🔧 Executing: {}", "".clear(), command_line.cyan());
+        textln!("{} 🔧 Executing: {}", "".clear(), command_line.cyan());
     }
 
-    let output = cmd.output().await?;
+    let output = cmd.timed_output("Failed to run command").await?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(ManagerError::CommandFailed(stderr.to_string()));
+        if debug {
+            eprintln!("{}", stderr);
+        }
+        return Err(ManagerError::CommandFailed(failure_excerpt(&output)));
     }
 
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
@@ -90,26 +144,113 @@ pub fn validate_required_args(args: &[(&str, Option<&String>)]) -> ManagerResult
     Ok(())
 }
 
+/// Resolve a `--source-version`/`--target-version` value: `@<path>` reads
+/// the version from a file (e.g. the build's `VERSION`), anything else is
+/// taken as is. Non-empty results must look like a Debian version; empty
+/// ones are left to [`validate_required_args`].
+pub fn resolve_version_arg(name: &str, value: &str) -> ManagerResult<String> {
+    let version = match value.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| {
+                ManagerError::ValidationError(format!(
+                    "Could not read {} from {}: {}",
+                    name, path, e
+                ))
+            })?
+            .trim()
+            .to_string(),
+        None => value.to_string(),
+    };
+
+    if !version.is_empty() && !is_version_shaped(&version) {
+        return Err(ManagerError::ValidationError(format!(
+            "{} '{}' is not a valid version (expected e.g. 3.0.1-alpha1-ab12cd3)",
+            name, version
+        )));
+    }
+    Ok(version)
+}
+
+/// Starts with a digit and only uses characters allowed in Debian versions
+pub(crate) fn is_version_shaped(version: &str) -> bool {
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '~' | ':'))
+}
+
 pub fn validate_backend(backend: &str) -> ManagerResult<()> {
     match backend {
-        "gs" | "hetzner" | "local" => Ok(()),
+        "gs" | "hetzner" | "local" | "s3" => Ok(()),
         _ => Err(ManagerError::UnsupportedBackend(backend.to_string())),
     }
 }
 
 pub fn print_operation_info(title: &str, params: &[(&str, &str)]) {
-    println!();
-    println!(" ℹ️  {} with following parameters:", title);
+    textln!();
+    textln!(" ℹ️  {} with following parameters:", title);
     for (key, value) in params {
-        println!(" - {}: {}", key, value);
+        textln!(" - {}: {}", key, value);
+    }
+    textln!();
+}
+
+/// Render an expected-vs-actual mismatch as a two-line diff for error
+/// messages. Expected is green and actual red when stderr is a terminal and
+/// `NO_COLOR` is unset; plain text otherwise so CI logs stay greppable.
+pub fn format_mismatch(expected: &str, actual: &str) -> String {
+    let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    format_mismatch_with(expected, actual, color)
+}
+
+fn format_mismatch_with(expected: &str, actual: &str, color: bool) -> String {
+    let expected = format!("- expected: {}", expected);
+    let actual = format!("+ actual:   {}", actual);
+    if color {
+        format!("\n    {}\n    {}", expected.green(), actual.red())
+    } else {
+        format!("\n    {}\n    {}", expected, actual)
     }
-    println!();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tail_lines() {
+        assert_eq!(tail_lines("one\ntwo\n", 2), "one\ntwo");
+        assert_eq!(tail_lines("", 2), "");
+
+        let output: String = (1..=5).map(|n| format!("line {}\n", n)).collect();
+        let tail = tail_lines(&output, 2);
+        let lines: Vec<&str> = tail.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("3 earlier lines truncated"), "{}", tail);
+        assert_eq!(&lines[1..], ["line 4", "line 5"]);
+    }
+
+    #[tokio::test]
+    async fn test_within() {
+        let err = within(
+            "deb-s3 upload",
+            Duration::from_millis(50),
+            tokio::time::sleep(Duration::from_secs(5)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ManagerError::Timeout { ref operation, seconds: 0 } if operation == "deb-s3 upload"
+        ));
+        assert_eq!(
+            within("deb-s3 upload", Duration::from_secs(5), async { 7 })
+                .await
+                .unwrap(),
+            7
+        );
+    }
+
     #[test]
     fn test_validate_backend() {
         assert!(validate_backend("gs").is_ok());
@@ -127,4 +268,50 @@ mod tests {
         let args_with_missing = vec![("arg1", Some(&valid_arg)), ("arg2", None)];
         assert!(validate_required_args(&args_with_missing).is_err());
     }
+
+    #[test]
+    fn test_resolve_version_arg() {
+        assert_eq!(
+            resolve_version_arg("target-version", "3.0.1-ab12cd3").unwrap(),
+            "3.0.1-ab12cd3"
+        );
+        assert_eq!(resolve_version_arg("target-version", "").unwrap(), "");
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("VERSION");
+        std::fs::write(&file, "  3.0.1~rc1-bullseye\n").unwrap();
+        assert_eq!(
+            resolve_version_arg("target-version", &format!("@{}", file.display())).unwrap(),
+            "3.0.1~rc1-bullseye"
+        );
+
+        std::fs::write(&file, "version: 3.0.1\n").unwrap();
+        assert!(resolve_version_arg("target-version", &format!("@{}", file.display())).is_err());
+        assert!(resolve_version_arg("source-version", "v3.0.1").is_err());
+    }
+
+    #[test]
+    fn test_resolve_version_arg_missing_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("VERSION");
+        let err = resolve_version_arg("source-version", &format!("@{}", missing.display()))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Could not read source-version from"));
+        assert!(err.contains("VERSION"));
+    }
+
+    #[test]
+    fn test_format_mismatch() {
+        assert_eq!(
+            format_mismatch_with("1.0.0", "0.9.9", false),
+            "\n    - expected: 1.0.0\n    + actual:   0.9.9"
+        );
+
+        // colored may still strip escapes when stdout isn't a terminal, so
+        // only check the content survives
+        let colored = format_mismatch_with("1.0.0", "0.9.9", true);
+        assert!(colored.contains("- expected: 1.0.0"));
+        assert!(colored.contains("+ actual:   0.9.9"));
+    }
 }